@@ -0,0 +1,111 @@
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion};
+use grappling_hook_core::game_state::{GameState, Object};
+use grappling_hook_core::instances::FrameInstances;
+use grappling_hook_core::level::Level;
+
+const TICK: f64 = 1.0 / 60.0;
+
+/// a walled in floor with `count` crates dropped onto it in a grid, already settled for a bit so
+/// the benchmarks see resting contacts and not just everything falling
+fn crate_pile(count: usize) -> GameState {
+    let columns = (count as f64).sqrt().ceil() as usize;
+    let width = columns as f64 * 1.5;
+    let mut objects = vec![
+        Object::movable().at(0.0, -5.0).build(),
+        Object::fixed()
+            .at(-10.0, -10.0)
+            .size(width + 20.0, 5.0)
+            .build(),
+        Object::fixed().at(-10.0, -5.0).size(5.0, 1000.0).build(),
+        Object::fixed()
+            .at(width + 5.0, -5.0)
+            .size(5.0, 1000.0)
+            .build(),
+    ];
+    for i in 0..count.saturating_sub(objects.len()) {
+        objects.push(
+            Object::movable()
+                .at((i % columns) as f64 * 1.5, (i / columns) as f64 * 1.5)
+                .build(),
+        );
+    }
+    let mut state = GameState::from_level(&Level {
+        version: Level::VERSION,
+        objects,
+        player: 0,
+        palette: Default::default(),
+        lighting: None,
+        goal: None,
+        triggers: vec![],
+        intro: None,
+        bounds: None,
+        out_of_bounds: Default::default(),
+        movement: Default::default(),
+        turrets: vec![],
+        scripts: vec![],
+        paths: vec![],
+        patrols: vec![],
+        chunking: None,
+        exits: vec![],
+        entrances: vec![],
+        gravity: None,
+        gravity_zones: vec![],
+        hook_limits: Default::default(),
+        checkpoints: vec![],
+        dual_hooks: false,
+        ziplines: vec![],
+        tutorials: vec![],
+        prefabs: Default::default(),
+        placed: vec![],
+        seed: 0,
+    });
+    for _ in 0..60 {
+        state.update(TICK);
+    }
+    state
+}
+
+fn update(c: &mut Criterion) {
+    let mut group = c.benchmark_group("update");
+    for count in [10, 100, 1000] {
+        let state = crate_pile(count);
+        group.bench_with_input(BenchmarkId::from_parameter(count), &state, |b, state| {
+            b.iter_batched_ref(
+                || state.clone(),
+                |state| state.update(TICK),
+                BatchSize::SmallInput,
+            )
+        });
+    }
+    group.finish();
+}
+
+fn instances(c: &mut Criterion) {
+    let mut group = c.benchmark_group("instances");
+    for count in [10, 100, 1000] {
+        let last_state = crate_pile(count);
+        let mut state = last_state.clone();
+        state.update(TICK);
+        group.bench_with_input(
+            BenchmarkId::from_parameter(count),
+            &(state, last_state),
+            |b, (state, last_state)| {
+                b.iter(|| {
+                    FrameInstances::prepare(
+                        0.5,
+                        state,
+                        last_state,
+                        None,
+                        None,
+                        &Default::default(),
+                        None,
+                    )
+                })
+            },
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, update, instances);
+criterion_main!(benches);
@@ -0,0 +1,176 @@
+//! throws random boxes into a walled in pit and random inputs at the player, checking the
+//! simulation stays sane and plays out the same way twice
+//!
+//! run with `cargo fuzz run simulation` from the core directory
+
+#![no_main]
+
+use grappling_hook_core::game_state::{ButtonState, Direction, Event, GameState, Object};
+use grappling_hook_core::level::Level;
+use libfuzzer_sys::arbitrary::{self, Arbitrary};
+use libfuzzer_sys::fuzz_target;
+
+const TICK: f64 = 1.0 / 60.0;
+/// each box gets a cell of this size to itself, so nothing starts out overlapping
+const CELL: f64 = 3.0;
+const COLUMNS: usize = 6;
+/// as deep as the biggest box is across, sinking this far in means going right through, heavy
+/// boxes landing on much lighter ones can sink a fair way in before getting pushed back out
+const MAX_PENETRATION: f64 = 2.0;
+
+#[derive(Arbitrary, Debug)]
+struct Crate {
+    /// where in its cell it goes, and how big it is, in 256ths
+    x: u8,
+    y: u8,
+    w: u8,
+    h: u8,
+    velocity: (i8, i8),
+    mass: u8,
+    fixed: bool,
+}
+
+#[derive(Arbitrary, Debug)]
+enum Input {
+    Wait,
+    Key { button: u8, pressed: bool },
+    Movement { push: i8 },
+    Hook { x: i8, y: i8, pressed: bool },
+    SecondHook { x: i8, y: i8, pressed: bool },
+}
+
+#[derive(Arbitrary, Debug)]
+struct Scenario {
+    crates: Vec<Crate>,
+    inputs: Vec<Input>,
+    dual_hooks: bool,
+}
+
+fn level(scenario: &Scenario) -> Level {
+    let mut objects = vec![
+        Object::movable().at(1.0, 1.0).build(),
+        Object::fixed().at(-10.0, -10.0).size(40.0, 10.0).build(),
+        Object::fixed().at(-10.0, 0.0).size(10.0, 40.0).build(),
+        Object::fixed().at(20.0, 0.0).size(10.0, 40.0).build(),
+        Object::fixed().at(-10.0, 40.0).size(40.0, 10.0).build(),
+    ];
+    // the first cell is the player's
+    for (i, c) in scenario.crates.iter().take(30).enumerate() {
+        let cell = i + 1;
+        let (w, h) = (0.2 + c.w as f64 / 256.0 * 1.8, 0.2 + c.h as f64 / 256.0 * 1.8);
+        let x = (cell % COLUMNS) as f64 * CELL + 0.5 + c.x as f64 / 256.0 * (CELL - w - 0.5);
+        let y = (cell / COLUMNS) as f64 * CELL + 0.5 + c.y as f64 / 256.0 * (CELL - h - 0.5);
+        let builder = match c.fixed {
+            true => Object::fixed(),
+            false => Object::movable()
+                .velocity(c.velocity.0 as f64 / 8.0, c.velocity.1 as f64 / 8.0)
+                .mass(0.1 + c.mass as f64 / 16.0),
+        };
+        objects.push(builder.at(x, y).size(w, h).build());
+    }
+    Level {
+        version: Level::VERSION,
+        objects,
+        player: 0,
+        palette: Default::default(),
+        lighting: None,
+        goal: None,
+        triggers: vec![],
+        intro: None,
+        bounds: None,
+        out_of_bounds: Default::default(),
+        movement: Default::default(),
+        turrets: vec![],
+        scripts: vec![],
+        paths: vec![],
+        patrols: vec![],
+        chunking: None,
+        exits: vec![],
+        entrances: vec![],
+        gravity: None,
+        gravity_zones: vec![],
+        hook_limits: Default::default(),
+        checkpoints: vec![],
+        dual_hooks: scenario.dual_hooks,
+        ziplines: vec![],
+        tutorials: vec![],
+        prefabs: Default::default(),
+        placed: vec![],
+        seed: 0,
+    }
+}
+
+fn event(input: &Input) -> Option<Event> {
+    let state = |pressed| match pressed {
+        true => ButtonState::Pressed,
+        false => ButtonState::Released,
+    };
+    let target = |x: i8, y: i8| cgmath::point2(x as f64 / 4.0 + 10.0, y as f64 / 4.0 + 20.0);
+    Some(match *input {
+        Input::Wait => return None,
+        Input::Key { button, pressed } => Event::Keyboard {
+            button: match button % 4 {
+                0 => Direction::Left,
+                1 => Direction::Right,
+                2 => Direction::Up,
+                _ => Direction::Down,
+            },
+            state: state(pressed),
+        },
+        Input::Movement { push } => Event::Movement {
+            push: push as f64 / 127.0,
+        },
+        Input::Hook { x, y, pressed } => Event::Hook {
+            state: state(pressed),
+            target: target(x, y),
+        },
+        Input::SecondHook { x, y, pressed } => Event::SecondHook {
+            state: state(pressed),
+            target: target(x, y),
+        },
+    })
+}
+
+/// plays the scenario through, checking every update, and gives back where everything ended up
+fn play(scenario: &Scenario) -> Vec<[u64; 4]> {
+    let mut state = GameState::from_level(&level(scenario));
+    for input in scenario.inputs.iter().take(600) {
+        if let Some(event) = event(input) {
+            state.submit_player_event(event);
+        }
+        state.update(TICK);
+        let stats = state.physics_stats();
+        assert!(
+            stats.max_penetration < MAX_PENETRATION,
+            "objects sank {} into each other",
+            stats.max_penetration
+        );
+        for object in state.iter_objects() {
+            let (pos, velocity) = (object.get_pos(), object.get_velocity());
+            assert!(
+                pos.x.is_finite() && pos.y.is_finite(),
+                "object {} is at {:?}",
+                object.id(),
+                pos
+            );
+            assert!(
+                velocity.x.is_finite() && velocity.y.is_finite(),
+                "object {} is moving at {:?}",
+                object.id(),
+                velocity
+            );
+        }
+    }
+    state
+        .iter_objects()
+        .map(|object| {
+            let (pos, velocity) = (object.get_pos(), object.get_velocity());
+            [pos.x, pos.y, velocity.x, velocity.y].map(f64::to_bits)
+        })
+        .collect()
+}
+
+fuzz_target!(|scenario: Scenario| {
+    let first = play(&scenario);
+    assert_eq!(first, play(&scenario), "the same inputs played out differently");
+});
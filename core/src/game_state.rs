@@ -0,0 +1,5218 @@
+mod broadphase;
+mod builder;
+mod chunks;
+mod gravity;
+mod hook;
+mod input;
+mod mouse_joint;
+mod patrol;
+mod projectile;
+mod rope;
+mod script;
+mod snapshot;
+mod turret;
+mod view;
+
+use std::{
+    cell::RefCell,
+    collections::{BTreeMap, HashMap, HashSet},
+};
+
+use cgmath::prelude::*;
+use serde::{Deserialize, Serialize};
+use stable_vec::StableVec;
+
+use crate::level::{
+    Entrance, Exit, GravityZone, HookLimits, Lesson, Level, Lighting, Movement, OutOfBounds,
+    PaletteCycle, PatrolPath, Region, Swing, Trigger, Tutorial, Zipline,
+};
+use crate::rng::Rng;
+use crate::tween::{Easing, Tween, TweenId, Tweens};
+use broadphase::Broadphase;
+pub use builder::ObjectBuilder;
+use chunks::Chunks;
+use gravity::Gravity;
+use hook::Hook;
+pub use hook::{HookCharge, Trajectory};
+pub use input::Buttons;
+use mouse_joint::MouseJoint;
+use patrol::PatrolController;
+pub use projectile::Projectile;
+use projectile::{Outcome, Projectiles};
+use rope::Rope;
+use script::{Action, ScriptRunner};
+use turret::TurretController;
+pub use view::ObjectView;
+
+#[derive(Clone, Serialize, Deserialize)]
+struct PlayerController {
+    pending_events: Vec<Event>,
+    controlled_object: usize,
+    /// the movement keys, as of the last update
+    #[serde(default)]
+    keys: Buttons<Direction>,
+    /// how far an analog stick is pushed to the right, from -1 to 1, used while neither movement
+    /// key is held
+    #[serde(default)]
+    movement: f64,
+    last_touch_velocity: cgmath::Vector2<f64>,
+    top_speed: f64,
+    acceleration_speed: f64,
+    hook: Hook,
+    /// the fire button is held down, the hook goes out when it's released
+    aiming: bool,
+    /// the hook from the second fire button, only ever out in levels with `dual_hooks` on
+    #[serde(default)]
+    second_hook: Hook,
+    /// the second fire button is held down
+    #[serde(default)]
+    second_aiming: bool,
+    /// whether the level lets players have a second hook out
+    #[serde(default)]
+    dual_hooks: bool,
+    /// which zipline each hook was on as of the last update
+    #[serde(default)]
+    on_ziplines: [Option<usize>; 2],
+    /// how much the level lets the hook get used
+    #[serde(default)]
+    limits: HookLimits,
+    /// what's left of the hook under `limits`
+    #[serde(default)]
+    charge: HookCharge,
+    /// how hard each hook's rope pulled on the player on the last update
+    #[serde(default)]
+    tension: [f64; 2],
+    /// how the ropes hold on to the player
+    #[serde(default)]
+    swing: Swing,
+}
+
+impl PlayerController {
+    /// how fast a player with no-clip on flies around
+    const NO_CLIP_SPEED: f64 = 20.0;
+    /// how many times over both ropes they get pulled tight, with only one out once is exact
+    const DUAL_HOOK_ITERATIONS: usize = 8;
+    /// about how many seconds the tension of a rope takes to catch up with how hard it's pulling
+    const TENSION_RESPONSE: f64 = 0.1;
+
+    fn new(
+        controlled_object: usize,
+        movement: &Movement,
+        limits: &HookLimits,
+        dual_hooks: bool,
+    ) -> Self {
+        Self {
+            pending_events: vec![],
+            controlled_object,
+            keys: Buttons::default(),
+            movement: 0.0,
+            last_touch_velocity: cgmath::vec2(0.0, 0.0),
+            top_speed: movement.run_speed,
+            acceleration_speed: movement.acceleration,
+            hook: Hook::Idle,
+            aiming: false,
+            second_hook: Hook::Idle,
+            second_aiming: false,
+            dual_hooks,
+            on_ziplines: [None; 2],
+            limits: *limits,
+            charge: HookCharge::full(limits),
+            tension: [0.0; 2],
+            swing: movement.swing,
+        }
+    }
+
+    fn fire_hook(&mut self, objects: &StableVec<RefCell<Object>>, target: cgmath::Point2<f64>) {
+        if let Some(object) = objects.get(self.controlled_object) {
+            self.hook = Hook::fire(object.borrow().center(), target);
+        }
+    }
+
+    /// both hooks, the first one first
+    fn hooks(&self) -> [&Hook; 2] {
+        [&self.hook, &self.second_hook]
+    }
+
+    fn hooks_mut(&mut self) -> [&mut Hook; 2] {
+        [&mut self.hook, &mut self.second_hook]
+    }
+
+    /// keeps the player within reach of every hook that's stuck in something, and snaps the ropes
+    /// that had to pull harder than the level allows
+    fn constrain(
+        &mut self,
+        objects: &StableVec<RefCell<Object>>,
+        ziplines: &[Zipline],
+        events: &mut Vec<GameEvent>,
+        dt: f64,
+    ) {
+        let owner = self.controlled_object;
+        // how much speed each rope took away, which over the tick is how hard it pulled
+        let mut stopped = [0.0; 2];
+        if let Hook::Idle = self.second_hook {
+            stopped[0] = self.hook.constrain(owner, objects, ziplines, &self.swing);
+        } else {
+            // pulling tight along one rope can leave the other slack or stretched, so they take
+            // turns until both are about right
+            for _ in 0..Self::DUAL_HOOK_ITERATIONS {
+                let swing = self.swing;
+                for (stopped, hook) in stopped.iter_mut().zip(self.hooks_mut()) {
+                    *stopped += hook.constrain(owner, objects, ziplines, &swing);
+                }
+            }
+        }
+        let mass = objects
+            .get(owner)
+            .and_then(|object| object.borrow().can_be_pushed())
+            .unwrap_or(0.0);
+        // a rope that goes taut stops a fall within a single tick, which is only a jerk, so
+        // what counts is how hard it pulls over a little while
+        let ease = (dt / Self::TENSION_RESPONSE).min(1.0);
+        for (tension, stopped) in self.tension.iter_mut().zip(stopped) {
+            *tension += (mass * stopped / dt - *tension) * ease;
+        }
+        let max_tension = match self.limits.max_tension {
+            Some(max_tension) => max_tension,
+            None => return,
+        };
+        let from = objects.get(owner).map(|object| object.borrow().center());
+        let hooks = [&mut self.hook, &mut self.second_hook];
+        for (tension, hook) in self.tension.iter_mut().zip(hooks) {
+            if *tension <= max_tension {
+                continue;
+            }
+            if let (Some(from), Some(to)) = (from, hook.position(objects)) {
+                events.push(GameEvent::RopeSnapped {
+                    object: owner,
+                    from,
+                    to,
+                });
+            }
+            *hook = Hook::Idle;
+            *tension = 0.0;
+        }
+    }
+
+    /// speeds the player up for letting go of the hook stuck at `anchor`, more so the closer to
+    /// the bottom of the swing it was
+    fn let_go(
+        &self,
+        anchor: cgmath::Point2<f64>,
+        objects: &StableVec<RefCell<Object>>,
+        events: &mut Vec<GameEvent>,
+        gravity: &Gravity,
+    ) {
+        let object = match objects.get(self.controlled_object) {
+            Some(object) => object,
+            None => return,
+        };
+        let mut object = object.borrow_mut();
+        let center = object.center();
+        let down = gravity.down_at(center);
+        let velocity = match &mut object.ty {
+            ObjectType::Movable { velocity, .. } => velocity,
+            _ => return,
+        };
+        *velocity *= self.swing.release_boost;
+        let from_anchor = center - anchor;
+        if self.swing.release_bonus <= 0.0
+            || from_anchor.magnitude2() == 0.0
+            || down.magnitude2() == 0.0
+        {
+            return;
+        }
+        let off_bottom = cgmath::Deg::from(from_anchor.angle(down)).0.abs();
+        let timing = 1.0 - off_bottom / self.swing.release_window;
+        let radial = from_anchor.normalize();
+        let along = *velocity - radial * velocity.dot(radial);
+        if timing <= 0.0 || along.magnitude2() == 0.0 {
+            return;
+        }
+        let speed = self.swing.release_bonus * timing;
+        *velocity += along.normalize_to(speed);
+        events.push(GameEvent::ReleaseBoost {
+            object: self.controlled_object,
+            speed,
+        });
+    }
+
+    /// which zipline each hook is on, if any
+    fn ziplines(&self) -> [Option<usize>; 2] {
+        self.hooks().map(|hook| match hook {
+            Hook::Zipline { zipline, .. } => Some(*zipline),
+            _ => None,
+        })
+    }
+
+    fn update(
+        &mut self,
+        objects: &StableVec<RefCell<Object>>,
+        ziplines: &[Zipline],
+        events: &mut Vec<GameEvent>,
+        gravity: &Gravity,
+        dt: f64,
+    ) {
+        let mut fire_at = None;
+        let mut fire_second_at = None;
+        // where the hook the player let go of was stuck, if it was holding them
+        let mut let_go = None;
+        // what went down or came up on the last update is old news now
+        self.keys.end_tick();
+        for event in self.pending_events.drain(..) {
+            match event {
+                Event::Keyboard { button, state } => {
+                    self.keys.set(button, state);
+                }
+                Event::Movement { push } => {
+                    self.movement = push.clamp(-1.0, 1.0);
+                }
+                Event::Hook {
+                    state: ButtonState::Pressed,
+                    ..
+                } => {
+                    if let Hook::Idle = self.hook {
+                        self.aiming = true;
+                    } else {
+                        // pressing while the hook is out lets go of it
+                        if self.hook.is_attached() {
+                            let_go = let_go.or(self.hook.position(objects));
+                        }
+                        self.hook = Hook::Idle;
+                    }
+                }
+                Event::Hook {
+                    state: ButtonState::Released,
+                    target,
+                } => {
+                    // letting go while it can't be fired doesn't use anything up
+                    if self.aiming && self.charge.ready() {
+                        fire_at = Some(target);
+                    }
+                    self.aiming = false;
+                }
+                Event::SecondHook { .. } if !self.dual_hooks => {}
+                Event::SecondHook {
+                    state: ButtonState::Pressed,
+                    ..
+                } => {
+                    if let Hook::Idle = self.second_hook {
+                        self.second_aiming = true;
+                    } else {
+                        if self.second_hook.is_attached() {
+                            let_go = let_go.or(self.second_hook.position(objects));
+                        }
+                        self.second_hook = Hook::Idle;
+                    }
+                }
+                Event::SecondHook {
+                    state: ButtonState::Released,
+                    target,
+                } => {
+                    if self.second_aiming && self.charge.ready() {
+                        fire_second_at = Some(target);
+                    }
+                    self.second_aiming = false;
+                }
+            }
+        }
+        if let Some(anchor) = let_go {
+            self.let_go(anchor, objects, events, gravity);
+        }
+        if let Some(target) = fire_at {
+            self.fire_hook(objects, target);
+            self.charge.fired(&self.limits);
+            events.push(GameEvent::HookFired {
+                object: self.controlled_object,
+            });
+        }
+        // fired after the first one, so firing both at once costs the same as it would one by one
+        if let (Some(target), Some(object)) = (fire_second_at, objects.get(self.controlled_object))
+        {
+            if self.charge.ready() {
+                self.second_hook = Hook::fire(object.borrow().center(), target);
+                self.charge.fired(&self.limits);
+                events.push(GameEvent::HookFired {
+                    object: self.controlled_object,
+                });
+            }
+        }
+        let owner = self.controlled_object;
+        for hook in self.hooks_mut() {
+            if hook.update(owner, objects, ziplines, gravity, dt) {
+                events.push(GameEvent::HookAttached { object: owner });
+            }
+        }
+        let hanging = self.hooks().iter().any(|hook| hook.is_attached());
+        let standing = objects.get(self.controlled_object).is_some_and(|object| {
+            let object = object.borrow();
+            object.is_on_ground(gravity.down_at(object.center()))
+        });
+        if self.charge.update(&self.limits, hanging, standing, dt) {
+            self.hook = Hook::Idle;
+            self.second_hook = Hook::Idle;
+            events.push(GameEvent::HookTired {
+                object: self.controlled_object,
+            });
+        }
+        // checked against the last update rather than the start of this one, so letting go from
+        // outside of here, like respawning, still counts
+        let on_ziplines = self.ziplines();
+        for (before, now) in self.on_ziplines.into_iter().zip(on_ziplines) {
+            if before == now {
+                continue;
+            }
+            if let Some(zipline) = before {
+                events.push(GameEvent::ZiplineDetached {
+                    object: owner,
+                    zipline,
+                });
+            }
+            if let Some(zipline) = now {
+                events.push(GameEvent::ZiplineAttached {
+                    object: owner,
+                    zipline,
+                });
+            }
+        }
+        self.on_ziplines = on_ziplines;
+        let controlled = self.controlled_object;
+        let object = objects.get(controlled);
+        if let Some(object) = object {
+            let mut object = object.borrow_mut();
+            if let Object {
+                ty: ObjectType::Movable { velocity, .. },
+                no_clip: true,
+                ..
+            } = &mut *object
+            {
+                let held = |direction| self.keys.held(direction) as i32;
+                let axis = |negative, positive| {
+                    (held(positive) - held(negative)) as f64 * Self::NO_CLIP_SPEED
+                };
+                *velocity = cgmath::vec2(
+                    axis(Direction::Left, Direction::Right),
+                    axis(Direction::Down, Direction::Up),
+                );
+            } else if let Object {
+                ty: ObjectType::Movable { velocity, .. },
+                touching,
+                pos,
+                size,
+                ..
+            } = &mut *object
+            {
+                let center = *pos + *size / 2.0;
+                let pull = gravity.at(center);
+                // ground and walls are the sides gravity pulls towards and the ones across from
+                // that, where nothing pulls it keeps the level's idea of down to run along
+                let down = match gravity.down_at(center) {
+                    down if down.magnitude2() > 0.0 => down,
+                    _ => gravity.down,
+                };
+                let (right, _) = gravity::axes(down);
+                let touching_sides = touching.iter().fold(HashSet::new(), |mut acc, x| {
+                    acc.insert(*x.1);
+                    acc
+                });
+                let against = |direction: cgmath::Vector2<f64>| {
+                    touching_sides
+                        .iter()
+                        .any(|side| side.to_vector().dot(direction) > 0.5)
+                };
+                let average_touch_velocity = if !touching.is_empty() {
+                    (|| {
+                        let mut weights = 0.0;
+                        let mut sum = cgmath::vec2(0.0, 0.0);
+                        for index in touching.keys() {
+                            let other = &objects[*index].borrow();
+                            let contribution = other.surface_friction;
+                            if contribution == 0.0 {
+                                //fucking glue or smth
+                                return other.get_velocity();
+                            }
+                            let contribution = 1.0 / contribution;
+                            sum += other.get_velocity() * contribution;
+                            weights += contribution;
+                        }
+                        sum / weights
+                    })()
+                } else {
+                    self.last_touch_velocity
+                };
+                self.last_touch_velocity = average_touch_velocity;
+                // running happens along the ground, whichever way that is
+                let mut local = gravity::to_local(*velocity, down);
+                let touch = gravity::to_local(average_touch_velocity, down);
+
+                let (left_held, right_held) = (
+                    self.keys.held(Direction::Left),
+                    self.keys.held(Direction::Right),
+                );
+                // the keys are all or nothing, a stick speeds up slower the less it's pushed
+                let push = match (left_held, right_held) {
+                    (true, false) => -1.0,
+                    (false, true) => 1.0,
+                    _ => self.movement,
+                };
+                if push != 0.0 {
+                    if push < 0.0 {
+                        local.x += self.acceleration_speed * push * dt;
+                        if local.x < touch.x - self.top_speed {
+                            local.x = touch.x - self.top_speed;
+                        }
+                    } else {
+                        local.x += self.acceleration_speed * push * dt;
+                        if local.x > touch.x + self.top_speed {
+                            local.x = touch.x + self.top_speed;
+                        }
+                    }
+                } else {
+                    let target = touch.x - local.x;
+                    let mut difference = self.acceleration_speed * dt;
+                    if difference > target.abs() {
+                        difference = target.abs()
+                    }
+                    local.x += difference * target.signum();
+                }
+                // holding jump down doesn't jump again on landing, it has to be pressed again
+                if self.keys.pressed(Direction::Up) && !touching.is_empty() {
+                    let mut velocity_offset = cgmath::vec2(0.0, 10.0);
+                    if against(-right) {
+                        velocity_offset.x += 10.0;
+                    } else if against(right) {
+                        velocity_offset.x -= 10.0;
+                    }
+                    local += velocity_offset;
+                }
+                *velocity = gravity::from_local(local, down);
+                if against(down) {
+                    *velocity -= pull * dt;
+                }
+            }
+        }
+    }
+}
+
+// there's only ever a few of them, so players being bigger than the rest doesn't cost anything
+#[derive(Clone, Serialize, Deserialize)]
+#[allow(clippy::enum_variant_names, clippy::large_enum_variant)]
+enum Controller {
+    PlayerController(PlayerController),
+    TurretController(TurretController),
+    PatrolController(PatrolController),
+}
+
+impl Controller {
+    #[allow(clippy::too_many_arguments)]
+    fn update(
+        &mut self,
+        objects: &StableVec<RefCell<Object>>,
+        ziplines: &[Zipline],
+        players: &[usize],
+        projectiles: &mut Projectiles,
+        events: &mut Vec<GameEvent>,
+        gravity: &Gravity,
+        rng: &mut Rng,
+        dt: f64,
+    ) {
+        match self {
+            Self::PlayerController(c) => c.update(objects, ziplines, events, gravity, dt),
+            Self::TurretController(c) => c.update(objects, players, projectiles, events, rng, dt),
+            Self::PatrolController(c) => c.update(objects, events, gravity, dt),
+        }
+    }
+
+    /// runs after everything has moved, to pull objects back where they are tied down
+    fn constrain(
+        &mut self,
+        objects: &StableVec<RefCell<Object>>,
+        ziplines: &[Zipline],
+        events: &mut Vec<GameEvent>,
+        dt: f64,
+    ) {
+        match self {
+            Self::PlayerController(c) => c.constrain(objects, ziplines, events, dt),
+            Self::TurretController(_) | Self::PatrolController(_) => {}
+        }
+    }
+
+    fn as_player(&self) -> Option<&PlayerController> {
+        match self {
+            Self::PlayerController(c) => Some(c),
+            _ => None,
+        }
+    }
+
+    fn as_player_mut(&mut self) -> Option<&mut PlayerController> {
+        match self {
+            Self::PlayerController(c) => Some(c),
+            _ => None,
+        }
+    }
+}
+
+/// what kind of object something is, and the state that goes with it
+#[derive(Clone, Serialize, Deserialize)]
+pub enum ObjectType {
+    /// never moves and can't be pushed
+    Static,
+    /// falls, gets pushed around and collides with everything
+    Movable {
+        /// in units per second
+        velocity: cgmath::Vector2<f64>,
+        /// heavier objects get pushed less by lighter ones
+        mass: f64,
+    },
+    /// never moves, but carries whatever is on top of it along as if it did
+    Treadmill {
+        /// the speed things on top of it get carried at
+        fake_velocity: cgmath::Vector2<f64>,
+    },
+}
+
+/// makes an object blow up when something hits it hard enough, like a barrel of something nasty
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct Explosive {
+    /// how far out things get pushed away
+    pub radius: f64,
+    /// the impulse things right next to it get, fading out to nothing at `radius`
+    pub strength: f64,
+    /// hitting or getting hit by something at least this fast sets it off
+    pub trigger_speed: f64,
+}
+
+/// a ribbon left behind along where an object has just been, fading out as it gets older
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Ribbon {
+    /// how wide it is right behind the object, it narrows to nothing at the far end
+    pub width: f64,
+    /// how many seconds each bit of it lasts before it's faded out completely
+    pub fade: f64,
+    /// picked in sRGB like every other colour, the alpha is what the newest bit starts out at
+    pub color: [f32; 4],
+}
+
+/// a value in an object's metadata, written in level files as it is, `true`, `3.5` or `"red"`
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum MetaValue {
+    /// `true` or `false`
+    Flag(bool),
+    /// any number, written with or without a point
+    Number(f64),
+    /// anything in quotes
+    Text(String),
+}
+
+impl MetaValue {
+    /// the flag, if it is one
+    pub fn as_flag(&self) -> Option<bool> {
+        match self {
+            MetaValue::Flag(flag) => Some(*flag),
+            _ => None,
+        }
+    }
+    /// the number, if it is one
+    pub fn as_number(&self) -> Option<f64> {
+        match self {
+            MetaValue::Number(number) => Some(*number),
+            _ => None,
+        }
+    }
+    /// the text, if it is some
+    pub fn as_text(&self) -> Option<&str> {
+        match self {
+            MetaValue::Text(text) => Some(text),
+            _ => None,
+        }
+    }
+}
+
+impl From<bool> for MetaValue {
+    fn from(flag: bool) -> Self {
+        MetaValue::Flag(flag)
+    }
+}
+
+impl From<f64> for MetaValue {
+    fn from(number: f64) -> Self {
+        MetaValue::Number(number)
+    }
+}
+
+impl From<&str> for MetaValue {
+    fn from(text: &str) -> Self {
+        MetaValue::Text(text.to_owned())
+    }
+}
+
+impl From<String> for MetaValue {
+    fn from(text: String) -> Self {
+        MetaValue::Text(text)
+    }
+}
+
+/// an axis aligned box in the world, `pos` is its bottom left corner
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Object {
+    ty: ObjectType,
+    pos: cgmath::Point2<f64>,
+    size: cgmath::Vector2<f64>,
+    #[serde(default = "default_surface_friction")]
+    surface_friction: f64,
+    #[serde(skip)]
+    touching: HashMap<usize, Direction>,
+    /// how many updates in a row this object has been stuck between things on opposite sides
+    #[serde(skip)]
+    squeezed_for: u32,
+    /// seconds left until this object goes away by itself, for debris and the like
+    #[serde(default)]
+    lifetime: Option<f64>,
+    /// the update this object was spawned on, so a reused slot isn't mistaken for what was in it before
+    #[serde(skip)]
+    spawned_at: u64,
+    /// goes straight through everything and ignores gravity, for flying around levels while debugging
+    #[serde(skip)]
+    no_clip: bool,
+    /// the name of the material it gets drawn with, out of the ones the renderer knows about,
+    /// none is the plain look
+    #[serde(default)]
+    material: Option<String>,
+    /// the object this one is attached to, by index, it gets carried along with it
+    #[serde(default)]
+    parent: Option<usize>,
+    /// where this is relative to `parent`, worked out from where they both start
+    #[serde(skip, default = "no_offset")]
+    offset_from_parent: cgmath::Vector2<f64>,
+    /// the fastest this can go, in place of the limit the level sets for everything
+    #[serde(default)]
+    max_speed: Option<f64>,
+    /// blows up when hit hard enough
+    #[serde(default)]
+    explosive: Option<Explosive>,
+    /// how much damage it can take before it's destroyed, None for things that can't be hurt
+    #[serde(default)]
+    health: Option<f64>,
+    /// the ribbon it leaves behind while it moves, if it has one
+    #[serde(default)]
+    ribbon: Option<Ribbon>,
+    /// names for finding it by, which stay the same however the objects around it get moved
+    /// around, several objects can share one
+    #[serde(default)]
+    tags: Vec<String>,
+    /// anything else the level wants to say about it, for whatever reads it
+    #[serde(default)]
+    metadata: BTreeMap<String, MetaValue>,
+}
+
+fn straight_down() -> cgmath::Vector2<f64> {
+    cgmath::vec2(0.0, -1.0)
+}
+
+fn default_surface_friction() -> f64 {
+    1.0
+}
+
+fn no_offset() -> cgmath::Vector2<f64> {
+    cgmath::vec2(0.0, 0.0)
+}
+
+impl Object {
+    /// a box at `pos` with the default friction
+    pub fn new(ty: ObjectType, pos: cgmath::Point2<f64>, size: cgmath::Vector2<f64>) -> Self {
+        Self {
+            ty,
+            pos,
+            size,
+            surface_friction: default_surface_friction(),
+            touching: HashMap::new(),
+            squeezed_for: 0,
+            lifetime: None,
+            spawned_at: 0,
+            no_clip: false,
+            material: None,
+            parent: None,
+            offset_from_parent: no_offset(),
+            max_speed: None,
+            explosive: None,
+            health: None,
+            ribbon: None,
+            tags: vec![],
+            metadata: BTreeMap::new(),
+        }
+    }
+    /// makes the object blow up when something hits it hard enough
+    pub fn with_explosive(self, explosive: Explosive) -> Self {
+        Self {
+            explosive: Some(explosive),
+            ..self
+        }
+    }
+    /// lets the object get hurt, destroying it after it takes `health` damage
+    pub fn with_health(self, health: f64) -> Self {
+        Self {
+            health: Some(health),
+            ..self
+        }
+    }
+    /// makes the object leave a ribbon behind while it moves
+    pub fn with_ribbon(self, ribbon: Ribbon) -> Self {
+        Self {
+            ribbon: Some(ribbon),
+            ..self
+        }
+    }
+    /// makes the object disappear by itself after `seconds`
+    pub fn with_lifetime(self, seconds: f64) -> Self {
+        Self {
+            lifetime: Some(seconds),
+            ..self
+        }
+    }
+    /// adds `tag` to the names it can be found by
+    pub fn with_tag(mut self, tag: impl Into<String>) -> Self {
+        let tag = tag.into();
+        if !self.tags.contains(&tag) {
+            self.tags.push(tag);
+        }
+        self
+    }
+    /// the update this object was spawned on, 0 for things that were there from the start
+    pub fn spawned_at(&self) -> u64 {
+        self.spawned_at
+    }
+    /// the one static box covering both of these, if they are static boxes that line up edge to edge
+    pub fn merged_with(&self, other: &Object) -> Option<Object> {
+        const EPSILON: f64 = 1e-9;
+        if !matches!(
+            (&self.ty, &other.ty),
+            (ObjectType::Static, ObjectType::Static)
+        ) || self.surface_friction != other.surface_friction
+            || self.material != other.material
+            || self.parent.is_some()
+            || other.parent.is_some()
+            || self.health.is_some()
+            || other.health.is_some()
+            || self.ribbon != other.ribbon
+            || !self.tags.is_empty()
+            || !other.tags.is_empty()
+            || !self.metadata.is_empty()
+            || !other.metadata.is_empty()
+        {
+            return None;
+        }
+        let close = |a: f64, b: f64| (a - b).abs() < EPSILON;
+        // whether the spans along one axis touch or overlap, and if so the span covering both
+        let join = |pos1: f64, size1: f64, pos2: f64, size2: f64| {
+            if pos1 <= pos2 + size2 + EPSILON && pos2 <= pos1 + size1 + EPSILON {
+                let start = pos1.min(pos2);
+                Some((start, (pos1 + size1).max(pos2 + size2) - start))
+            } else {
+                None
+            }
+        };
+        let (pos, size) = if close(self.pos.y, other.pos.y) && close(self.size.y, other.size.y) {
+            let (x, width) = join(self.pos.x, self.size.x, other.pos.x, other.size.x)?;
+            (
+                cgmath::point2(x, self.pos.y),
+                cgmath::vec2(width, self.size.y),
+            )
+        } else if close(self.pos.x, other.pos.x) && close(self.size.x, other.size.x) {
+            let (y, height) = join(self.pos.y, self.size.y, other.pos.y, other.size.y)?;
+            (
+                cgmath::point2(self.pos.x, y),
+                cgmath::vec2(self.size.x, height),
+            )
+        } else {
+            return None;
+        };
+        Some(Object {
+            pos,
+            size,
+            ..self.clone()
+        })
+    }
+    /// what kind of object this is
+    pub fn get_type(&self) -> &ObjectType {
+        &self.ty
+    }
+    /// the name of the material it gets drawn with, if it isn't drawn plain
+    pub fn get_material(&self) -> Option<&str> {
+        self.material.as_deref()
+    }
+    /// how this object blows up, if it does
+    pub fn get_explosive(&self) -> Option<Explosive> {
+        self.explosive
+    }
+    /// the ribbon it leaves behind while it moves, if it has one
+    pub fn get_ribbon(&self) -> Option<Ribbon> {
+        self.ribbon
+    }
+    /// how much more damage it can take, if it can be hurt at all
+    pub fn get_health(&self) -> Option<f64> {
+        self.health
+    }
+    /// the names it can be found by
+    pub fn get_tags(&self) -> &[String] {
+        &self.tags
+    }
+    /// whether it can be found by `tag`
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.iter().any(|own| own == tag)
+    }
+    /// the metadata under `key`, if it has any
+    pub fn get_metadata(&self, key: &str) -> Option<&MetaValue> {
+        self.metadata.get(key)
+    }
+    /// the object this one is attached to
+    pub fn get_parent(&self) -> Option<usize> {
+        self.parent
+    }
+    /// for when the objects in a level get moved around, doesn't change the offset
+    pub(crate) fn set_parent(&mut self, parent: Option<usize>) {
+        self.parent = parent;
+    }
+    /// draws it with the material called `material` from now on, or plain with None
+    pub(crate) fn set_material(&mut self, material: Option<String>) {
+        self.material = material;
+    }
+    /// the objects this one is resting against, and which side of this one they are on
+    pub fn get_touching(&self) -> &HashMap<usize, Direction> {
+        &self.touching
+    }
+    /// the bottom left corner
+    pub fn get_pos(&self) -> &cgmath::Point2<f64> {
+        &self.pos
+    }
+    /// the width and height
+    pub fn get_size(&self) -> &cgmath::Vector2<f64> {
+        &self.size
+    }
+    /// the middle of the box
+    pub fn center(&self) -> cgmath::Point2<f64> {
+        self.pos + self.size / 2.0
+    }
+    /// moves the bottom left corner to `pos`, without changing how it's moving
+    pub fn set_pos(&mut self, pos: cgmath::Point2<f64>) {
+        self.pos = pos;
+    }
+    /// changes the width and height, keeping the bottom left corner where it is
+    pub fn set_size(&mut self, size: cgmath::Vector2<f64>) {
+        self.size = size;
+    }
+    /// how fast it moves, or how fast a treadmill carries things, does nothing for static objects
+    pub fn set_velocity(&mut self, new_velocity: cgmath::Vector2<f64>) {
+        match &mut self.ty {
+            ObjectType::Static => {}
+            ObjectType::Movable { velocity, .. } => *velocity = new_velocity,
+            ObjectType::Treadmill { fake_velocity } => *fake_velocity = new_velocity,
+        }
+    }
+    /// how grippy the surface is, 0 sticks to whatever touches it
+    pub fn get_surface_friction(&self) -> f64 {
+        self.surface_friction
+    }
+    /// changes how grippy the surface is, see `get_surface_friction`
+    pub fn set_surface_friction(&mut self, surface_friction: f64) {
+        self.surface_friction = surface_friction;
+    }
+    /// the fastest this object is allowed to go, None if it goes by the level's limit
+    pub fn get_max_speed(&self) -> Option<f64> {
+        self.max_speed
+    }
+    /// changes how fast this object is allowed to go, see `get_max_speed`
+    pub fn set_max_speed(&mut self, max_speed: Option<f64>) {
+        self.max_speed = max_speed;
+    }
+    /// puts the object somewhere else and stops it
+    pub fn teleport(&mut self, pos: cgmath::Point2<f64>) {
+        self.pos = pos;
+        if let ObjectType::Movable { velocity, .. } = &mut self.ty {
+            *velocity = cgmath::vec2(0.0, 0.0);
+        }
+    }
+    /// whether this object is going through everything, see `GameState::set_no_clip`
+    pub fn is_no_clip(&self) -> bool {
+        self.no_clip
+    }
+    /// whether something is holding this object up against falling towards `down`, see
+    /// `GameState::down_at`
+    pub fn is_on_ground(&self, down: cgmath::Vector2<f64>) -> bool {
+        self.touching
+            .values()
+            .any(|side| side.to_vector().dot(down) > 0.5)
+    }
+    /// 0 for things that can't be pushed around
+    fn inverse_mass(&self) -> f64 {
+        self.can_be_pushed().map_or(0.0, |mass| 1.0 / mass)
+    }
+
+    fn apply_push(&mut self, push: cgmath::Vector2<f64>) {
+        if let ObjectType::Movable { velocity, .. } = &mut self.ty {
+            *velocity += push
+        }
+    }
+
+    /// how fast it is moving, or for treadmills how fast things on them get carried
+    pub fn get_velocity(&self) -> cgmath::Vector2<f64> {
+        match &self.ty {
+            ObjectType::Static => cgmath::vec2(0.0, 0.0),
+            ObjectType::Movable { velocity, .. } => *velocity,
+            ObjectType::Treadmill { fake_velocity } => *fake_velocity,
+        }
+    }
+
+    fn can_be_pushed(&self) -> Option<f64> {
+        match self.ty {
+            ObjectType::Static => None,
+            ObjectType::Movable { mass, .. } => Some(mass),
+            ObjectType::Treadmill { .. } => None,
+        }
+    }
+}
+
+/// one of the four sides of a box, also used for the movement keys
+#[derive(Clone, Copy, Hash, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum Direction {
+    /// towards negative x
+    Left,
+    /// towards positive x
+    Right,
+    /// towards positive y
+    Up,
+    /// towards negative y
+    Down,
+}
+
+impl Direction {
+    /// one unit towards this side
+    pub fn to_vector(self) -> cgmath::Vector2<f64> {
+        match self {
+            Direction::Left => cgmath::vec2(-1.0, 0.0),
+            Direction::Right => cgmath::vec2(1.0, 0.0),
+            Direction::Up => cgmath::vec2(0.0, 1.0),
+            Direction::Down => cgmath::vec2(0.0, -1.0),
+        }
+    }
+    fn invert(&self) -> Self {
+        match self {
+            Direction::Left => Direction::Right,
+            Direction::Right => Direction::Left,
+            Direction::Up => Direction::Down,
+            Direction::Down => Direction::Up,
+        }
+    }
+    fn from_vector(vec: &cgmath::Vector2<f64>) -> Self {
+        if vec.x.abs() > vec.y.abs() {
+            if vec.x > 0.0 {
+                Direction::Right
+            } else {
+                Direction::Left
+            }
+        } else if vec.y > 0.0 {
+            Direction::Up
+        } else {
+            Direction::Down
+        }
+    }
+}
+
+/// what a player is holding down and what their hook is doing, for showing it on screen
+#[derive(Clone, Debug, Default)]
+pub struct PlayerInput {
+    /// the movement keys held down, in the order left, up, right, down, up is jumping
+    pub held: Vec<Direction>,
+    /// the fire button is down, the hook goes out once it's let go
+    pub aiming: bool,
+    /// where the hook is at
+    pub hook: HookState,
+}
+
+/// what a player's hook is doing, angles are in radians counterclockwise from the right, from
+/// the player to the hook
+// the fields are explained above
+#[allow(missing_docs)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum HookState {
+    /// it's ready to fire
+    #[default]
+    Idle,
+    /// on its way out, `distance` from the player
+    Flying { angle: f64, distance: f64 },
+    /// stuck to something, on a rope `length` long
+    Attached { angle: f64, length: f64 },
+}
+
+/// whether a button went down or came back up
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ButtonState {
+    /// the button went down
+    Pressed,
+    /// the button came back up
+    Released,
+}
+
+/// player input, handed to the game with `GameState::submit_player_event`
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub enum Event {
+    /// one of the movement keys
+    Keyboard {
+        /// which way the key moves the player
+        button: Direction,
+        /// whether it went down or came up
+        state: ButtonState,
+    },
+    /// an analog stick moving, `push` is how far to the right it is, from -1 to 1
+    ///
+    /// it only counts while neither the left nor the right key is held
+    Movement {
+        /// how hard the player runs, negative to the left
+        push: f64,
+    },
+    /// the fire button, `target` is where in the world the player is aiming
+    Hook {
+        /// whether it went down or came up
+        state: ButtonState,
+        /// where the hook should go
+        target: cgmath::Point2<f64>,
+    },
+    /// the fire button for the second hook, which does nothing in levels without `dual_hooks`
+    SecondHook {
+        /// whether it went down or came up
+        state: ButtonState,
+        /// where the hook should go
+        target: cgmath::Point2<f64>,
+    },
+}
+
+impl Event {
+    /// whether this is the player doing what `lesson` teaches
+    fn does(&self, lesson: Lesson) -> bool {
+        let pressed = |direction| matches!(*self, Event::Keyboard { button, state: ButtonState::Pressed } if button == direction);
+        match lesson {
+            Lesson::Move => {
+                pressed(Direction::Left)
+                    || pressed(Direction::Right)
+                    || matches!(*self, Event::Movement { push } if push != 0.0)
+            }
+            Lesson::Jump => pressed(Direction::Up),
+            Lesson::Reel => pressed(Direction::Down),
+            // the hook goes out when the button comes back up
+            Lesson::FireHook => matches!(
+                self,
+                Event::Hook {
+                    state: ButtonState::Released,
+                    ..
+                }
+            ),
+            Lesson::FireSecondHook => matches!(
+                self,
+                Event::SecondHook {
+                    state: ButtonState::Released,
+                    ..
+                }
+            ),
+        }
+    }
+}
+
+/// things that happened during an update, for everything outside the simulation to react to
+// the fields are explained on each variant
+#[allow(missing_docs)]
+#[derive(Clone, Copy, Debug)]
+pub enum GameEvent {
+    /// two objects collided, `speed` is how fast they were moving towards each other
+    Impact { objects: (usize, usize), speed: f64 },
+    /// `object` came down onto something after being in the air
+    Landed { object: usize },
+    /// the hook got thrown out by whoever is holding `object`
+    HookFired { object: usize },
+    /// the hook thrown by `object` stuck into something
+    HookAttached { object: usize },
+    /// the player controlling `object` ran out of stamina and let go of the hook
+    HookTired { object: usize },
+    /// the rope from `object` to its hook pulled harder than the level's `max_tension` and
+    /// snapped, it ran from `from` to `to`
+    RopeSnapped {
+        object: usize,
+        from: cgmath::Point2<f64>,
+        to: cgmath::Point2<f64>,
+    },
+    /// the player controlling `object` let go of the hook near the bottom of a swing and got
+    /// `speed` added along it for the timing
+    ReleaseBoost { object: usize, speed: f64 },
+    /// a hook thrown by `object` caught on the zipline with index `zipline`
+    ZiplineAttached { object: usize, zipline: usize },
+    /// a hook thrown by `object` came off the zipline with index `zipline`
+    ZiplineDetached { object: usize, zipline: usize },
+    /// `object` fell out of the world and got removed
+    ObjectDestroyed { object: usize },
+    /// the view object walked into the region of the tutorial with index `tutorial` for the first
+    /// time, so its prompt is showing
+    TutorialShown { tutorial: usize },
+    /// the player did what the tutorial with index `tutorial` asked, so its prompt went away
+    TutorialDone { tutorial: usize },
+    /// `object` entered the trigger region with index `trigger`
+    TriggerFired { trigger: usize, object: usize },
+    /// `object` got squeezed between things that couldn't get out of each others way
+    Crushed { object: usize },
+    /// the projectile in slot `projectile` hit `object` at `at`, and went away
+    ProjectileHit {
+        projectile: usize,
+        object: usize,
+        at: cgmath::Point2<f64>,
+        damage: f64,
+    },
+    /// `object` took `amount` damage, leaving it with `health`
+    Damaged {
+        object: usize,
+        amount: f64,
+        health: f64,
+    },
+    /// `object` ran out of health, it got destroyed unless it's a player
+    Killed { object: usize },
+    /// the script running `object` moved on to the phase with index `phase`
+    PhaseChanged { object: usize, phase: usize },
+    /// `object` got to the point with index `point` along the path it's following
+    WaypointReached { object: usize, point: usize },
+    /// the turret on `object` shot the projectile in slot `projectile`
+    TurretFired { object: usize, projectile: usize },
+    /// something blew up at `center`, pushing everything within `radius` away
+    Explosion {
+        center: cgmath::Point2<f64>,
+        radius: f64,
+    },
+    /// the player controlling `object` left the level's bounds, and either died or got put back
+    /// at the start
+    OutOfBounds { object: usize, killed: bool },
+    /// a move started with `GameState::move_object` got where it was going
+    TweenFinished { id: TweenId, object: usize },
+    /// the player controlling `object` walked into the exit with index `exit`
+    ExitReached { exit: usize, object: usize },
+}
+
+/// everything going on in a running level, stepped forward with `update`, it can be saved as a
+/// whole to pick up from later, apart from the events nobody has taken yet
+#[derive(Clone, Serialize, Deserialize)]
+pub struct GameState {
+    controllers: Vec<Controller>,
+    /// every object in the world, indices stay the same for as long as the object is around
+    #[serde(with = "snapshot::objects")]
+    objects: StableVec<RefCell<Object>>,
+    /// the object the camera follows
+    pub view_object: usize,
+    #[serde(skip)]
+    events: Vec<GameEvent>,
+    /// the colours of the level
+    pub palette: PaletteCycle,
+    /// the lights of the level, if it is lit
+    pub lighting: Option<Lighting>,
+    /// how long the level has been running, in seconds
+    pub time: f64,
+    /// how many updates have happened since the level started
+    pub tick: u64,
+    /// how fast things fall outside of `gravity_zones`, in units per second squared
+    pub gravity: f64,
+    /// which way things fall outside of `gravity_zones`, one unit long
+    #[serde(default = "straight_down")]
+    pub down: cgmath::Vector2<f64>,
+    /// the parts of the level with a gravity of their own
+    #[serde(default)]
+    pub gravity_zones: Vec<GravityZone>,
+    /// where the player has to get to
+    pub goal: Option<Region>,
+    /// the tick the player reached the goal on
+    pub completed_at: Option<u64>,
+    /// the tick the player got crushed on
+    pub died_at: Option<u64>,
+    /// the named regions that fire `GameEvent::TriggerFired`
+    pub triggers: Vec<Trigger>,
+    /// which objects are inside each trigger, so they only fire on the way in
+    inside_triggers: HashSet<(usize, usize)>,
+    /// the ways out to other levels
+    #[serde(default)]
+    pub exits: Vec<Exit>,
+    /// which players are inside each exit, so coming into a level standing in one doesn't send
+    /// them straight back out
+    #[serde(default)]
+    inside_exits: HashSet<(usize, usize)>,
+    /// the named spawn points players can be put at
+    #[serde(default)]
+    pub entrances: Vec<Entrance>,
+    /// where everything is, for finding what's near something, built again once things have moved
+    #[serde(skip)]
+    broadphase: RefCell<Broadphase>,
+    /// anything that leaves this is gone for good, apart from players
+    bounds: Region,
+    /// what happens to players that leave `bounds`
+    out_of_bounds: OutOfBounds,
+    /// where players get put back when they leave the bounds, where the player started
+    respawn_point: cgmath::Point2<f64>,
+    /// how fast players run and how fast things can go
+    movement: Movement,
+    /// how much players are allowed to use the hook
+    #[serde(default)]
+    pub hook_limits: HookLimits,
+    /// the areas that fill a player's hook back up
+    #[serde(default)]
+    pub checkpoints: Vec<Region>,
+    /// whether players get a second hook
+    #[serde(default)]
+    pub dual_hooks: bool,
+    /// the cables the hook can slide along
+    #[serde(default)]
+    pub ziplines: Vec<Zipline>,
+    /// the prompts showing players which buttons to press
+    #[serde(default)]
+    pub tutorials: Vec<Tutorial>,
+    /// the tutorial whose prompt is showing, if any
+    #[serde(default)]
+    pub tutorial: Option<usize>,
+    /// the tutorials that have been shown, so they only come up the once
+    #[serde(default)]
+    shown_tutorials: HashSet<usize>,
+    /// the normal and impulse of every contact from the last update, to warm start the solver
+    contact_impulses: HashMap<(usize, usize), (cgmath::Vector2<f64>, f64)>,
+    /// slots in `objects` that got emptied, handed out again before growing the list, so spawning
+    /// and despawning lots of short lived things doesn't keep making it longer
+    free_slots: Vec<usize>,
+    /// the object being dragged around with the mouse, if any
+    mouse_joint: Option<MouseJoint>,
+    /// objects being moved along a set path, like doors sliding open
+    position_tweens: Tweens<usize, cgmath::Point2<f64>>,
+    /// explosives that got set off and are about to blow up
+    lit_explosives: Vec<usize>,
+    /// shots flying through the level, kept apart from `objects` since there can be lots of them
+    projectiles: Projectiles,
+    /// the objects being run by a script, like bosses
+    scripts: Vec<ScriptRunner>,
+    /// the routes things in the level follow, kept around to show them while editing
+    pub paths: Vec<PatrolPath>,
+    /// ropes tying objects together, like the one between players in co-op
+    ropes: Vec<Rope>,
+    /// how the solver did on the last update
+    #[serde(skip)]
+    physics_stats: PhysicsStats,
+    /// the objects out of the world in a chunked level, None if the level isn't
+    #[serde(default)]
+    chunks: Option<Chunks>,
+    /// the random numbers for everything in the world, started from the level's seed
+    #[serde(default)]
+    rng: Rng,
+    /// the help players are getting
+    #[serde(default)]
+    assist_mode: AssistMode,
+}
+
+/// how the collision solver did on one update, for comparing tuning and solver changes
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct PhysicsStats {
+    /// how many pairs of objects were pushing into each other
+    pub contacts: usize,
+    /// how many passes over the contacts it took for the impulses to stop changing, the solver
+    /// makes all of its passes either way
+    pub solver_iterations: usize,
+    /// how far the deepest pair of objects had sunk into each other, before being pushed apart
+    pub max_penetration: f64,
+    /// how far every pair had sunk into each other, added up
+    pub total_penetration: f64,
+}
+
+/// help for players who want it, which makes levels easier, so runs with any of it on get
+/// flagged as assisted
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct AssistMode {
+    /// how fast the game runs compared to normal, from `MIN_GAME_SPEED` to 1, the simulation
+    /// steps the same either way so this is up to whoever runs it to slow down by
+    #[serde(default = "full_speed")]
+    pub game_speed: f64,
+    /// the hook has no cooldown and can be fired as often as players like
+    #[serde(default)]
+    pub infinite_hooks: bool,
+    /// players don't get hurt, and get put back at the start instead of dying
+    #[serde(default)]
+    pub no_death: bool,
+}
+
+fn full_speed() -> f64 {
+    1.0
+}
+
+impl Default for AssistMode {
+    fn default() -> Self {
+        Self {
+            game_speed: full_speed(),
+            infinite_hooks: false,
+            no_death: false,
+        }
+    }
+}
+
+impl AssistMode {
+    /// the slowest `game_speed` goes
+    pub const MIN_GAME_SPEED: f64 = 0.5;
+
+    /// whether any of it is on
+    pub fn assisted(&self) -> bool {
+        self.game_speed < 1.0 || self.infinite_hooks || self.no_death
+    }
+}
+
+/// a pair of objects that were pushing into each other on the last update
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ContactInfo {
+    /// the two objects, the lower index first
+    pub objects: (usize, usize),
+    /// the way the first object got pushed to get out of the second
+    pub normal: cgmath::Vector2<f64>,
+    /// how hard the solver ended up pushing them apart
+    pub impulse: f64,
+}
+
+impl GameState {
+    /// how fast things fall unless someone changes it
+    pub const DEFAULT_GRAVITY: f64 = 15.0;
+    /// how far the hook can get from whoever threw it before it gets reeled back in
+    pub const HOOK_RANGE: f64 = hook::MAX_LENGTH;
+    /// how far something has to be stuck inside its neighbours on both sides put together to be getting crushed
+    const CRUSH_DEPTH: f64 = 0.1;
+    /// how many updates in a row it has to stay like that, so a single bad frame doesn't kill anyone
+    const CRUSH_TICKS: u32 = 5;
+    /// how far objects are allowed to sink into each other before getting pushed apart
+    const PENETRATION_SLOP: f64 = 0.01;
+    /// how much of the remaining overlap gets fixed per update
+    const POSITION_CORRECTION: f64 = 0.8;
+    /// how long the pieces of a broken crate stick around for, in seconds
+    const DEBRIS_LIFETIME: f64 = 3.0;
+    /// how much faster or slower than the rest each piece of a broken crate can fly off, so they
+    /// don't all go the same way every time
+    const DEBRIS_SCATTER: f64 = 1.0;
+    /// how many passes over the contacts the solver makes, more means stacks settle faster
+    const SOLVER_ITERATIONS: usize = 8;
+    /// an impulse changing by less than this counts as settled, for `PhysicsStats::solver_iterations`
+    const SOLVER_TOLERANCE: f64 = 1.0e-6;
+    /// anything further out than this has gone wrong, no level comes anywhere near this big
+    const MAX_COORDINATE: f64 = 1.0e6;
+    /// speeds past this get cut down to it, nothing that's working right goes this fast
+    const MAX_SPEED: f64 = 1.0e4;
+    /// how many of a broken object's contacts get logged along with it
+    const LOGGED_CONTACTS: usize = 5;
+    /// things with health get hurt by hitting something faster than this, by how much faster
+    const HURT_SPEED: f64 = 12.0;
+}
+
+/// two objects pushing into each other, `normal` is the way object 1 has to go to get out
+#[derive(Clone, Copy, Debug)]
+struct Contact {
+    objects: (usize, usize),
+    normal: cgmath::Vector2<f64>,
+    /// how much impulse the solver has put into this contact so far
+    impulse: f64,
+    /// how far the objects were inside each other when they were found touching
+    depth: f64,
+}
+
+impl GameState {
+    /// a fresh start of `level`, with the player controlling `level.player`
+    pub fn from_level(level: &Level) -> Self {
+        let mut state = Self {
+            controllers: std::iter::once(Controller::PlayerController(PlayerController::new(
+                level.player,
+                &level.movement,
+                &level.hook_limits,
+                level.dual_hooks,
+            )))
+            .chain(
+                level
+                    .turrets
+                    .iter()
+                    .map(|turret| Controller::TurretController(TurretController::new(*turret))),
+            )
+            .chain(level.patrols.iter().filter_map(|patrol| {
+                let path = level.path(&patrol.path)?.clone();
+                Some(Controller::PatrolController(PatrolController::new(
+                    patrol.object,
+                    path,
+                )))
+            }))
+            .collect(),
+            objects: level
+                .objects
+                .iter()
+                .cloned()
+                .map(RefCell::new)
+                .collect::<Vec<_>>()
+                .into(),
+            view_object: level.player,
+            events: vec![],
+            palette: level.palette.clone(),
+            lighting: level.lighting.clone(),
+            time: 0.0,
+            tick: 0,
+            gravity: level
+                .gravity
+                .map_or(Self::DEFAULT_GRAVITY, |gravity| gravity.magnitude()),
+            down: level
+                .gravity
+                .filter(|gravity| gravity.magnitude2() > 0.0)
+                .map_or_else(straight_down, |gravity| gravity.normalize()),
+            gravity_zones: level.gravity_zones.clone(),
+            goal: level.goal,
+            completed_at: None,
+            died_at: None,
+            triggers: level.triggers.clone(),
+            inside_triggers: HashSet::new(),
+            exits: level.exits.clone(),
+            inside_exits: HashSet::new(),
+            entrances: level.entrances.clone(),
+            broadphase: Default::default(),
+            bounds: level.bounds(),
+            out_of_bounds: level.out_of_bounds,
+            respawn_point: level.objects[level.player].pos,
+            movement: level.movement,
+            hook_limits: level.hook_limits,
+            checkpoints: level.checkpoints.clone(),
+            dual_hooks: level.dual_hooks,
+            ziplines: level.ziplines.clone(),
+            tutorials: level.tutorials.clone(),
+            tutorial: None,
+            shown_tutorials: HashSet::new(),
+            lit_explosives: vec![],
+            projectiles: Projectiles::default(),
+            scripts: vec![],
+            paths: level.paths.clone(),
+            ropes: vec![],
+            physics_stats: PhysicsStats::default(),
+            contact_impulses: HashMap::new(),
+            free_slots: vec![],
+            mouse_joint: None,
+            position_tweens: Tweens::default(),
+            chunks: level.chunking.map(Chunks::new),
+            rng: Rng::new(level.seed),
+            assist_mode: AssistMode::default(),
+        };
+        for (_, object) in &state.objects {
+            let mut object = object.borrow_mut();
+            let parent = object.parent.and_then(|parent| level.objects.get(parent));
+            match parent {
+                Some(parent) => object.offset_from_parent = object.pos - parent.pos,
+                None => object.parent = None,
+            }
+        }
+        state.scripts = level
+            .scripts
+            .iter()
+            .map(|script| ScriptRunner::new(script.clone(), &state.objects))
+            .collect();
+        state.inside_exits = state.exits_around_players().collect();
+        state.stream_chunks();
+        state
+    }
+    /// moves the world forward by `dt` seconds, meant to be called with the same `dt` every time
+    pub fn update(&mut self, dt: f64) {
+        let first_event = self.events.len();
+        self.time += dt;
+        self.tick += 1;
+        self.stream_chunks();
+        let players = self.player_indices();
+        // spelled out instead of `gravity_field` so it leaves the controllers free to change
+        // before the controllers take the input
+        self.check_lessons();
+        let gravity = Gravity {
+            strength: self.gravity,
+            down: self.down,
+            zones: &self.gravity_zones,
+        };
+        for controller in &mut self.controllers {
+            controller.update(
+                &self.objects,
+                &self.ziplines,
+                &players,
+                &mut self.projectiles,
+                &mut self.events,
+                &gravity,
+                &mut self.rng,
+                dt,
+            );
+        }
+        if let Some(mouse_joint) = &self.mouse_joint {
+            mouse_joint.update(&self.objects, dt);
+        }
+        self.apply_tweens(dt);
+        // once before anything moves, so a broken speed can't carry something off or spread to
+        // what it touches, and once after the solver in case it's what broke something
+        self.check_sanity();
+        let players = self.player_indices();
+        let gravity = self.gravity_field();
+        for (index, object) in &self.objects {
+            let mut object = object.borrow_mut();
+            let object = &mut *object;
+            let center = object.center();
+            if let ObjectType::Movable { velocity, .. } = &mut object.ty {
+                if !object.no_clip {
+                    *velocity += gravity.at(center) * dt;
+                }
+                if let Some(max_speed) = object.max_speed.or(self.movement.max_speed) {
+                    if velocity.magnitude() > max_speed {
+                        *velocity = velocity.normalize_to(max_speed);
+                    }
+                }
+                let down = gravity.down_at(center);
+                if let (true, Some(terminal_velocity), true) = (
+                    players.contains(&index),
+                    self.movement.terminal_velocity,
+                    down.magnitude2() > 0.0,
+                ) {
+                    let mut local = gravity::to_local(*velocity, down);
+                    local.y = local.y.max(-terminal_velocity);
+                    *velocity = gravity::from_local(local, down);
+                }
+                object.pos += *velocity * dt;
+            }
+        }
+        for controller in &mut self.controllers {
+            controller.constrain(&self.objects, &self.ziplines, &mut self.events, dt);
+        }
+        for rope in &self.ropes {
+            rope.constrain(&self.objects);
+        }
+
+        self.check_whats_still_touching();
+
+        self.collision_detection();
+        self.finish_tweens();
+        // after the tweens are done, so a move that starts right as the last one ends doesn't cut
+        // it short
+        self.run_scripts(&players, dt);
+        self.follow_parents();
+        self.check_sanity();
+        self.set_off_explosives(first_event);
+        self.hurt_from_impacts(first_event);
+        self.update_projectiles(dt);
+
+        self.check_crushing();
+        self.check_bounds();
+        self.expire_objects(dt);
+        self.check_triggers();
+        self.check_checkpoints();
+        self.check_tutorials();
+
+        if self.completed_at.is_none() {
+            let player_center = self.player().map(|o| o.center());
+            if let (Some(goal), Some(center)) = (&self.goal, player_center) {
+                if goal.contains(center) {
+                    self.completed_at = Some(self.tick);
+                }
+            }
+        }
+        self.check_exits();
+        self.invalidate_broadphase();
+    }
+    /// hands input to the player, it takes effect on the next `update`
+    pub fn submit_player_event(&mut self, event: Event) {
+        for controller in self
+            .controllers
+            .iter_mut()
+            .filter_map(Controller::as_player_mut)
+        {
+            controller.pending_events.push(event);
+        }
+    }
+    /// hands input to just the player controlling `object`, for when there is more than one
+    pub fn submit_event_for(&mut self, object: usize, event: Event) {
+        for controller in self
+            .controllers
+            .iter_mut()
+            .filter_map(Controller::as_player_mut)
+        {
+            if controller.controlled_object == object {
+                controller.pending_events.push(event);
+            }
+        }
+    }
+    /// lets another player control `object`
+    pub fn add_player(&mut self, object: usize) {
+        self.controllers
+            .push(Controller::PlayerController(PlayerController::new(
+                object,
+                &self.movement,
+                &self.player_hook_limits(),
+                self.dual_hooks,
+            )));
+    }
+    /// ties `a` and `b` together with a rope `length` long, so they can't get further apart
+    /// than that
+    pub fn tie(&mut self, a: usize, b: usize, length: f64) {
+        if a != b {
+            self.ropes.push(Rope {
+                objects: (a, b),
+                length,
+            });
+        }
+    }
+    /// adds a second player right next to the first one, tied to them with a rope `rope_length`
+    /// long, returning the object the new player controls
+    pub fn start_coop(&mut self, rope_length: f64) -> Option<usize> {
+        let first = *self.player_indices().first()?;
+        let mut partner = self.objects.get(first)?.borrow().clone();
+        partner.teleport(partner.pos + cgmath::vec2(partner.size.x + 0.5, 0.0));
+        partner.touching.clear();
+        let partner = self.spawn_object(partner);
+        self.add_player(partner);
+        self.tie(first, partner, rope_length);
+        Some(partner)
+    }
+    /// takes away control of `object`, the object itself stays where it is
+    pub fn remove_player(&mut self, object: usize) {
+        self.controllers.retain(|controller| {
+            !matches!(controller, Controller::PlayerController(c) if c.controlled_object == object)
+        });
+    }
+    /// what's left of the hook of the player controlling `object`, None if nobody controls it
+    pub fn hook_charge(&self, object: usize) -> Option<HookCharge> {
+        self.controllers
+            .iter()
+            .filter_map(Controller::as_player)
+            .find(|controller| controller.controlled_object == object)
+            .map(|controller| controller.charge)
+    }
+
+    /// how swinging from the hook feels
+    pub fn swing(&self) -> Swing {
+        self.movement.swing
+    }
+
+    /// changes how swinging from the hook feels, for every player right away
+    pub fn set_swing(&mut self, swing: Swing) {
+        self.movement.swing = swing;
+        for controller in self
+            .controllers
+            .iter_mut()
+            .filter_map(Controller::as_player_mut)
+        {
+            controller.swing = swing;
+        }
+    }
+
+    /// how hard each of the hooks of the player controlling `object` pulled on them on the last
+    /// update, the first hook first, None if nobody controls it
+    pub fn hook_tension(&self, object: usize) -> Option<[f64; 2]> {
+        self.controllers
+            .iter()
+            .filter_map(Controller::as_player)
+            .find(|controller| controller.controlled_object == object)
+            .map(|controller| controller.tension)
+    }
+
+    /// what the player controlling `object` is pressing, None if nobody controls it
+    pub fn player_input(&self, object: usize) -> Option<PlayerInput> {
+        let controller = self
+            .controllers
+            .iter()
+            .filter_map(Controller::as_player)
+            .find(|controller| controller.controlled_object == object)?;
+        let held = [
+            Direction::Left,
+            Direction::Up,
+            Direction::Right,
+            Direction::Down,
+        ]
+        .into_iter()
+        .filter(|direction| controller.keys.held(*direction))
+        .collect();
+        let from = self.objects.get(object)?.borrow().center();
+        let to_hook = controller
+            .hook
+            .position(&self.objects)
+            .map(|hook| hook - from);
+        let hook = match (&controller.hook, to_hook) {
+            (Hook::Flying { .. }, Some(to_hook)) => HookState::Flying {
+                angle: to_hook.y.atan2(to_hook.x),
+                distance: to_hook.magnitude(),
+            },
+            (Hook::Attached { length, .. } | Hook::Zipline { length, .. }, Some(to_hook)) => {
+                HookState::Attached {
+                    angle: to_hook.y.atan2(to_hook.x),
+                    length: *length,
+                }
+            }
+            _ => HookState::Idle,
+        };
+        Some(PlayerInput {
+            held,
+            aiming: controller.aiming,
+            hook,
+        })
+    }
+    /// whether the player is holding down the fire button, about to throw the hook
+    pub fn is_aiming(&self) -> bool {
+        self.controllers
+            .iter()
+            .filter_map(Controller::as_player)
+            .any(|controller| controller.aiming)
+    }
+    /// whether the player is hanging from the hook
+    pub fn is_swinging(&self) -> bool {
+        self.controllers
+            .iter()
+            .filter_map(Controller::as_player)
+            .flat_map(PlayerController::hooks)
+            .any(Hook::is_attached)
+    }
+    fn gravity_field(&self) -> Gravity<'_> {
+        Gravity {
+            strength: self.gravity,
+            down: self.down,
+            zones: &self.gravity_zones,
+        }
+    }
+    /// the pull on something with its middle at `point`, in units per second squared
+    pub fn gravity_at(&self, point: cgmath::Point2<f64>) -> cgmath::Vector2<f64> {
+        self.gravity_field().at(point)
+    }
+    /// which way is down for something with its middle at `point`, one unit long, or zero where
+    /// nothing pulls at all, it's what standing on the ground goes by
+    pub fn down_at(&self, point: cgmath::Point2<f64>) -> cgmath::Vector2<f64> {
+        self.gravity_field().down_at(point)
+    }
+    /// the ropes tying objects together, from one end to the other
+    pub fn rope_lines(&self) -> Vec<(cgmath::Point2<f64>, cgmath::Point2<f64>)> {
+        self.ropes
+            .iter()
+            .filter_map(|rope| rope.ends(&self.objects))
+            .collect()
+    }
+    /// the ropes currently out, from the object holding them to the hook at the end
+    pub fn hook_lines(&self) -> Vec<(cgmath::Point2<f64>, cgmath::Point2<f64>)> {
+        self.hook_ropes()
+            .into_iter()
+            .map(|(from, to, _)| (from, to))
+            .collect()
+    }
+    /// like `hook_lines`, along with how hard each rope pulled on the last update
+    pub fn hook_ropes(&self) -> Vec<(cgmath::Point2<f64>, cgmath::Point2<f64>, f64)> {
+        self.controllers
+            .iter()
+            .filter_map(Controller::as_player)
+            .flat_map(|controller| {
+                let holder = self.objects.get(controller.controlled_object);
+                let from = holder.map(|holder| holder.borrow().center());
+                controller
+                    .hooks()
+                    .into_iter()
+                    .zip(controller.tension)
+                    .filter_map(move |(hook, tension)| {
+                        Some((from?, hook.position(&self.objects)?, tension))
+                    })
+            })
+            .collect()
+    }
+    /// where each hook that's holding on to something is stuck, by the object that threw it and
+    /// which of its hooks it is, like `flying_hooks`
+    pub fn hook_anchors(&self) -> Vec<(usize, usize, cgmath::Point2<f64>)> {
+        self.controllers
+            .iter()
+            .filter_map(Controller::as_player)
+            .flat_map(|controller| {
+                let object = controller.controlled_object;
+                controller
+                    .hooks()
+                    .into_iter()
+                    .enumerate()
+                    .filter(|(_, hook)| hook.is_attached())
+                    .filter_map(move |(which, hook)| {
+                        Some((object, which, hook.position(&self.objects)?))
+                    })
+            })
+            .collect()
+    }
+    /// where each hook that's still flying is, by the object that threw it and which of its
+    /// hooks it is, 0 for the first and 1 for the second
+    pub fn flying_hooks(&self) -> Vec<(usize, usize, cgmath::Point2<f64>)> {
+        self.controllers
+            .iter()
+            .filter_map(Controller::as_player)
+            .flat_map(|controller| {
+                let object = controller.controlled_object;
+                controller
+                    .hooks()
+                    .into_iter()
+                    .enumerate()
+                    .filter_map(move |(which, hook)| match hook {
+                        Hook::Flying { pos, .. } => Some((object, which, *pos)),
+                        _ => None,
+                    })
+            })
+            .collect()
+    }
+    /// simulates what would happen if the player fired the hook at `target` right now
+    ///
+    /// runs on a scratch copy of the state, so it doesn't disturb the real one
+    pub fn predict_hook(&self, target: cgmath::Point2<f64>, dt: f64) -> Trajectory {
+        const FLIGHT_STEPS: usize = 90;
+        const SWING_STEPS: usize = 90;
+        let mut scratch = self.clone();
+        for controller in scratch
+            .controllers
+            .iter_mut()
+            .filter_map(Controller::as_player_mut)
+        {
+            controller.pending_events.clear();
+            controller.fire_hook(&scratch.objects, target);
+        }
+        let mut trajectory = Trajectory::default();
+        for _ in 0..FLIGHT_STEPS + SWING_STEPS {
+            scratch.update(dt);
+            let controller = match scratch.controllers.iter().find_map(Controller::as_player) {
+                Some(controller) => controller,
+                None => break,
+            };
+            match &controller.hook {
+                Hook::Idle => break,
+                Hook::Flying { pos, .. } => {
+                    if trajectory.hook_path.len() >= FLIGHT_STEPS {
+                        break;
+                    }
+                    trajectory.hook_path.push(*pos)
+                }
+                Hook::Attached { .. } | Hook::Zipline { .. } => {
+                    if trajectory.swing_path.len() >= SWING_STEPS {
+                        break;
+                    }
+                    if let Some(object) = scratch.objects.get(controller.controlled_object) {
+                        trajectory.swing_path.push(object.borrow().center());
+                    }
+                }
+            }
+        }
+        trajectory
+    }
+    /// a hash of where everything is and how it's moving, for telling whether two runs went exactly
+    /// the same way, it doesn't depend on the rust version so it can be written down and compared later
+    pub fn checksum(&self) -> u64 {
+        // fnv-1a
+        let mut hash = 0xcbf2_9ce4_8422_2325u64;
+        let mut add = |value: u64| {
+            for byte in value.to_le_bytes() {
+                hash ^= byte as u64;
+                hash = hash.wrapping_mul(0x100_0000_01b3);
+            }
+        };
+        add(self.tick);
+        for (index, object) in &self.objects {
+            let object = object.borrow();
+            let velocity = object.get_velocity();
+            add(index as u64);
+            for value in [
+                object.pos.x,
+                object.pos.y,
+                object.size.x,
+                object.size.y,
+                velocity.x,
+                velocity.y,
+            ] {
+                add(value.to_bits());
+            }
+        }
+        hash
+    }
+    /// takes all the events that happened since the last call
+    pub fn drain_events(&mut self) -> impl Iterator<Item = GameEvent> + '_ {
+        self.events.drain(..)
+    }
+    /// the broadphase, built again first if objects might have moved since it last was
+    fn broadphase(&self) -> std::cell::Ref<'_, Broadphase> {
+        if !self.broadphase.borrow().up_to_date {
+            self.broadphase.borrow_mut().build(&self.objects);
+        }
+        self.broadphase.borrow()
+    }
+
+    /// for after objects have moved, so the broadphase gets built again before it's used next
+    fn invalidate_broadphase(&mut self) {
+        self.broadphase.get_mut().up_to_date = false;
+    }
+
+    fn collision_detection(&mut self) {
+        let mut events = vec![];
+        let mut contacts = vec![];
+        self.invalidate_broadphase();
+        // in the same order as going through every pair would be, so the solver comes out the same
+        let pairs = self.broadphase().pairs(&self.objects);
+        for (object1, object2) in pairs {
+            self.handle_collision(object1, object2, &mut events, &mut contacts);
+        }
+        self.events.extend(events);
+        let solver_iterations = self.solve_contacts(&mut contacts);
+        self.physics_stats = PhysicsStats {
+            contacts: contacts.len(),
+            solver_iterations,
+            max_penetration: contacts.iter().map(|c| c.depth).fold(0.0, f64::max),
+            total_penetration: contacts.iter().map(|c| c.depth).sum(),
+        };
+    }
+
+    /// the help players are getting
+    pub fn assist_mode(&self) -> AssistMode {
+        self.assist_mode
+    }
+
+    /// changes the help players are getting, their hooks fill back up if it changes how much
+    /// they get
+    pub fn set_assist_mode(&mut self, assist_mode: AssistMode) {
+        let refill = assist_mode.infinite_hooks != self.assist_mode.infinite_hooks;
+        self.assist_mode = assist_mode;
+        let limits = self.player_hook_limits();
+        for controller in self
+            .controllers
+            .iter_mut()
+            .filter_map(Controller::as_player_mut)
+        {
+            controller.limits = limits;
+            if refill {
+                controller.charge = HookCharge::full(&limits);
+            }
+        }
+    }
+
+    /// how much players are allowed to use the hook, the level's `hook_limits` unless the assist
+    /// mode lifts them
+    pub fn player_hook_limits(&self) -> HookLimits {
+        match self.assist_mode.infinite_hooks {
+            true => HookLimits {
+                cooldown: None,
+                uses: None,
+                ..self.hook_limits
+            },
+            false => self.hook_limits,
+        }
+    }
+
+    /// the random numbers for anything in the world that needs them, everything that changes
+    /// how the game plays out has to get them from here for replays and online games to play out
+    /// the same
+    pub fn rng(&mut self) -> &mut Rng {
+        &mut self.rng
+    }
+
+    /// how the solver did on the last update
+    pub fn physics_stats(&self) -> PhysicsStats {
+        self.physics_stats
+    }
+
+    /// every contact from the last update, in order of the objects in them
+    pub fn contacts(&self) -> Vec<ContactInfo> {
+        let mut contacts = self
+            .contact_impulses
+            .iter()
+            .map(|(&objects, &(normal, impulse))| ContactInfo {
+                objects,
+                normal,
+                impulse,
+            })
+            .collect::<Vec<_>>();
+        contacts.sort_by_key(|contact| contact.objects);
+        contacts
+    }
+
+    /// stops objects from moving into each other, by working out the impulse every contact needs
+    /// all at once, so a stack of objects ends up holding each other up instead of fighting,
+    /// returns how many passes it took to settle
+    fn solve_contacts(&mut self, contacts: &mut [Contact]) -> usize {
+        // starting from last update's impulses means resting contacts are already nearly solved
+        for contact in contacts.iter_mut() {
+            if let Some((normal, impulse)) = self.contact_impulses.get(&contact.objects) {
+                if *normal == contact.normal {
+                    contact.impulse = *impulse;
+                    self.apply_contact_impulse(contact, contact.impulse);
+                }
+            }
+        }
+        let mut settled_after = 0;
+        for iteration in 0..Self::SOLVER_ITERATIONS {
+            let mut largest_change: f64 = 0.0;
+            for contact in contacts.iter_mut() {
+                let (object1, object2) = match (
+                    self.objects.get(contact.objects.0),
+                    self.objects.get(contact.objects.1),
+                ) {
+                    (Some(object1), Some(object2)) => (object1.borrow(), object2.borrow()),
+                    _ => continue,
+                };
+                let inverse_mass = object1.inverse_mass() + object2.inverse_mass();
+                if inverse_mass == 0.0 {
+                    continue;
+                }
+                let approach = cgmath::dot(
+                    object1.get_velocity() - object2.get_velocity(),
+                    contact.normal,
+                );
+                drop((object1, object2));
+                // the total impulse can never pull the objects together, only push them apart
+                let total = (contact.impulse - approach / inverse_mass).max(0.0);
+                let change = total - contact.impulse;
+                contact.impulse = total;
+                self.apply_contact_impulse(contact, change);
+                largest_change = largest_change.max(change.abs());
+            }
+            if largest_change > Self::SOLVER_TOLERANCE {
+                settled_after = iteration + 1;
+            }
+        }
+        self.contact_impulses = contacts
+            .iter()
+            .map(|contact| (contact.objects, (contact.normal, contact.impulse)))
+            .collect();
+        settled_after
+    }
+
+    fn apply_contact_impulse(&self, contact: &Contact, impulse: f64) {
+        if let (Some(object1), Some(object2)) = (
+            self.objects.get(contact.objects.0),
+            self.objects.get(contact.objects.1),
+        ) {
+            let mut object1 = object1.borrow_mut();
+            let mut object2 = object2.borrow_mut();
+            let push1 = contact.normal * impulse * object1.inverse_mass();
+            let push2 = -contact.normal * impulse * object2.inverse_mass();
+            object1.apply_push(push1);
+            object2.apply_push(push2);
+        }
+    }
+
+    fn handle_collision(
+        &self,
+        object1_index: usize,
+        object2_index: usize,
+        events: &mut Vec<GameEvent>,
+        contacts: &mut Vec<Contact>,
+    ) {
+        if object1_index == object2_index {
+            return; //shouldn't happen, but just in case, since it would otherwise cause a panic
+        }
+        if let (Some(object1), Some(object2)) = (
+            self.objects.get(object1_index),
+            self.objects.get(object2_index),
+        ) {
+            let mut object1 = object1.borrow_mut();
+            let mut object2 = object2.borrow_mut();
+            if object1.no_clip || object2.no_clip {
+                return;
+            }
+            if object1.can_be_pushed().is_some() || object2.can_be_pushed().is_some() {
+                let offset = check_collision(
+                    object1.get_pos(),
+                    object1.get_size(),
+                    object2.get_pos(),
+                    object2.get_size(),
+                );
+                if let Some(offset) = offset {
+                    let direction = Direction::from_vector(&offset);
+                    let down = (
+                        self.down_at(object1.center()),
+                        self.down_at(object2.center()),
+                    );
+                    let on_ground = |object: &Object, down| object.is_on_ground(down);
+                    let was_on_ground = (on_ground(&object1, down.0), on_ground(&object2, down.1));
+                    object1.touching.insert(object2_index, direction.invert());
+                    object2.touching.insert(object1_index, direction);
+                    if !was_on_ground.0 && on_ground(&object1, down.0) {
+                        events.push(GameEvent::Landed {
+                            object: object1_index,
+                        });
+                    }
+                    if !was_on_ground.1 && on_ground(&object2, down.1) {
+                        events.push(GameEvent::Landed {
+                            object: object2_index,
+                        });
+                    }
+                    let relative_velocity = object1.get_velocity() - object2.get_velocity();
+                    let impact_speed = if offset.x != 0.0 {
+                        relative_velocity.x.abs()
+                    } else {
+                        relative_velocity.y.abs()
+                    };
+                    contacts.push(Contact {
+                        objects: (object1_index, object2_index),
+                        normal: if offset.x != 0.0 {
+                            cgmath::vec2(offset.x.signum(), 0.0)
+                        } else {
+                            cgmath::vec2(0.0, offset.y.signum())
+                        },
+                        impulse: 0.0,
+                        depth: offset.x.abs().max(offset.y.abs()),
+                    });
+                    let total = object1.surface_friction * object2.surface_friction;
+                    // objects sitting inside the slop without pushing into each other don't rub
+                    let pressing =
+                        cgmath::dot(object1.get_velocity() - object2.get_velocity(), offset) < 0.0;
+                    let velocity_offset = if !pressing {
+                        cgmath::vec2(0.0, 0.0)
+                    } else if offset.x == 0.0 {
+                        cgmath::vec2(
+                            (object1.get_velocity().x - object2.get_velocity().x) / total,
+                            0.0,
+                        )
+                    } else if offset.y == 0.0 {
+                        cgmath::vec2(
+                            0.0,
+                            (object1.get_velocity().y - object2.get_velocity().y) / total,
+                        )
+                    } else {
+                        cgmath::vec2(0.0, 0.0)
+                    };
+                    // only push out part of the way, leaving a little overlap alone, so resting
+                    // objects settle slightly inside what they rest on instead of bouncing in and out
+                    let depth = offset.x.abs().max(offset.y.abs());
+                    let offset = if depth > 0.0 {
+                        offset
+                            * ((depth - Self::PENETRATION_SLOP).max(0.0) / depth)
+                            * Self::POSITION_CORRECTION
+                    } else {
+                        offset
+                    };
+                    match (object1.can_be_pushed(), object2.can_be_pushed()) {
+                        (Some(mass1), Some(mass2)) => {
+                            // the lighter object is the one that gets moved more
+                            let ratio = mass2 / (mass1 + mass2);
+                            let offset1 = offset * ratio;
+                            object1.pos += offset1;
+                            object2.pos -= offset - offset1;
+                            object1.apply_push(-velocity_offset * ratio);
+                            object2.apply_push(velocity_offset * (1.0 - ratio));
+                        }
+                        (Some(_), None) => {
+                            object1.pos += offset;
+                            object1.apply_push(-velocity_offset);
+                        }
+                        (None, Some(_)) => {
+                            object2.pos -= offset;
+                            object2.apply_push(velocity_offset);
+                        }
+                        (None, None) => unreachable!(),
+                    }
+                    events.push(GameEvent::Impact {
+                        objects: (object1_index, object2_index),
+                        speed: impact_speed,
+                    });
+                }
+            }
+        }
+    }
+
+    fn player_indices(&self) -> Vec<usize> {
+        self.controllers
+            .iter()
+            .filter_map(Controller::as_player)
+            .map(|controller| controller.controlled_object)
+            .collect()
+    }
+
+    /// the object drawn on top at `point`, if there is one there
+    pub fn object_at(&self, point: cgmath::Point2<f64>) -> Option<usize> {
+        // later objects get drawn over earlier ones
+        self.objects
+            .iter()
+            .rev()
+            .find(|(_, object)| {
+                let object = object.borrow();
+                point.x >= object.pos.x
+                    && point.x <= object.pos.x + object.size.x
+                    && point.y >= object.pos.y
+                    && point.y <= object.pos.y + object.size.y
+            })
+            .map(|(index, _)| index)
+    }
+
+    /// attaches `child` to `parent` where it is right now, so it gets carried along from then on,
+    /// returns false if either isn't there or `parent` is already attached to `child`
+    pub fn attach(&mut self, child: usize, parent: usize) -> bool {
+        if self.objects.get(child).is_none()
+            || self.objects.get(parent).is_none()
+            || self.ancestors(parent).any(|a| a == child)
+        {
+            return false;
+        }
+        let parent_pos = self.objects[parent].borrow().pos;
+        let mut child = self.objects[child].borrow_mut();
+        child.parent = Some(parent);
+        child.offset_from_parent = child.pos - parent_pos;
+        true
+    }
+
+    /// lets go of whatever `child` is attached to, it keeps the speed it was being carried at
+    pub fn detach(&mut self, child: usize) {
+        if let Some(child) = self.objects.get(child) {
+            child.borrow_mut().parent = None;
+        }
+    }
+
+    /// `index` and then everything it's attached to, up to the one that isn't attached to anything
+    fn ancestors(&self, index: usize) -> impl Iterator<Item = usize> + '_ {
+        let mut next = self.objects.get(index).map(|_| index);
+        std::iter::from_fn(move || {
+            let current = next?;
+            next = self
+                .objects
+                .get(current)
+                .and_then(|object| object.borrow().parent)
+                .filter(|&parent| self.objects.get(parent).is_some());
+            Some(current)
+        })
+        // a cycle can only come from a level that skipped `Level::load`, this keeps it from hanging
+        .take(self.objects.num_elements())
+    }
+
+    /// puts everything attached to something where it belongs relative to what it's attached to
+    fn follow_parents(&mut self) {
+        for (index, object) in &self.objects {
+            if object.borrow().parent.is_none() {
+                continue;
+            }
+            // the offsets all add up, so there's no need to go through them in order
+            let mut offset = cgmath::vec2(0.0, 0.0);
+            let mut root = index;
+            for ancestor in self.ancestors(index) {
+                root = ancestor;
+                let ancestor = self.objects[ancestor].borrow();
+                if ancestor.parent.is_some() {
+                    offset += ancestor.offset_from_parent;
+                }
+            }
+            if root == index {
+                continue;
+            }
+            let (root_pos, root_velocity) = {
+                let root = self.objects[root].borrow();
+                let velocity = match root.ty {
+                    ObjectType::Movable { velocity, .. } => velocity,
+                    _ => cgmath::vec2(0.0, 0.0),
+                };
+                (root.pos, velocity)
+            };
+            let mut object = object.borrow_mut();
+            object.pos = root_pos + offset;
+            if let ObjectType::Movable { velocity, .. } = &mut object.ty {
+                *velocity = root_velocity;
+            }
+        }
+    }
+
+    /// moves `object` to `to` over `duration` seconds, instead of wherever it was going, returns
+    /// None if there is no such object
+    pub fn move_object(
+        &mut self,
+        object: usize,
+        to: cgmath::Point2<f64>,
+        duration: f64,
+        easing: Easing,
+    ) -> Option<TweenId> {
+        let from = self.objects.get(object)?.borrow().pos;
+        Some(self.position_tweens.start(
+            object,
+            Tween {
+                from,
+                to,
+                start: self.time,
+                duration,
+                easing,
+            },
+        ))
+    }
+
+    /// stops a move started with `move_object` where it is, movable objects keep whatever speed
+    /// they had, returns whether it was still going
+    pub fn cancel_tween(&mut self, id: TweenId) -> bool {
+        self.position_tweens.cancel(id)
+    }
+
+    /// puts the objects being moved where they should be by now, movable ones get the speed that
+    /// takes them there instead so they still push things out of the way
+    fn apply_tweens(&mut self, dt: f64) {
+        let gravity = self.gravity_field();
+        for (index, pos) in self.position_tweens.values_at(self.time) {
+            let mut object = self.objects[index].borrow_mut();
+            let object = &mut *object;
+            let center = object.center();
+            match &mut object.ty {
+                ObjectType::Movable { velocity, .. } => {
+                    *velocity = (pos - object.pos) / dt;
+                    // gravity is about to get added on again
+                    if !object.no_clip {
+                        *velocity -= gravity.at(center) * dt;
+                    }
+                }
+                _ => object.pos = pos,
+            }
+        }
+    }
+
+    /// lets everyone know about the moves that are done, and stops the movable ones there
+    fn finish_tweens(&mut self) {
+        for (id, index, _) in self.position_tweens.finish(self.time) {
+            if let ObjectType::Movable { velocity, .. } = &mut self.objects[index].borrow_mut().ty {
+                *velocity = cgmath::vec2(0.0, 0.0);
+            }
+            self.events
+                .push(GameEvent::TweenFinished { id, object: index });
+        }
+    }
+
+    /// starts dragging the movable object at `point` towards the cursor, returns whether there was one
+    pub fn grab(&mut self, point: cgmath::Point2<f64>) -> bool {
+        let index = match self.object_at(point) {
+            Some(index) => index,
+            None => return false,
+        };
+        let object = self.objects[index].borrow();
+        if !matches!(object.ty, ObjectType::Movable { .. }) {
+            return false;
+        }
+        self.mouse_joint = Some(MouseJoint {
+            object: index,
+            offset: point - object.pos,
+            target: point,
+        });
+        true
+    }
+
+    /// moves the point the grabbed object gets pulled towards
+    pub fn drag_to(&mut self, target: cgmath::Point2<f64>) {
+        if let Some(mouse_joint) = &mut self.mouse_joint {
+            mouse_joint.target = target;
+        }
+    }
+
+    /// lets go of the grabbed object, it keeps whatever speed it had
+    pub fn release_grab(&mut self) {
+        self.mouse_joint = None;
+    }
+
+    /// the spring from the grabbed object to the cursor, if something is grabbed
+    pub fn grab_line(&self) -> Option<(cgmath::Point2<f64>, cgmath::Point2<f64>)> {
+        let mouse_joint = self.mouse_joint.as_ref()?;
+        Some((mouse_joint.anchor(&self.objects)?, mouse_joint.target))
+    }
+
+    /// lets `object` fly through everything, or puts it back to normal
+    pub fn set_no_clip(&mut self, index: usize, no_clip: bool) {
+        if let Some(object) = self.objects.get(index) {
+            let mut object = object.borrow_mut();
+            object.no_clip = no_clip;
+            object.touching.clear();
+        }
+        for (_, object) in &self.objects {
+            object.borrow_mut().touching.remove(&index);
+        }
+        self.contact_impulses
+            .retain(|(a, b), _| *a != index && *b != index);
+    }
+
+    /// adds an object to the world, reusing an empty slot if there is one, returns its index
+    pub fn spawn_object(&mut self, mut object: Object) -> usize {
+        object.spawned_at = self.tick;
+        self.invalidate_broadphase();
+        let object = RefCell::new(object);
+        match self.free_slots.pop() {
+            Some(slot) => {
+                self.objects.insert(slot, object);
+                slot
+            }
+            None => {
+                // past the slots kept for objects out of the world too
+                let slot = self.objects.next_push_index().max(self.stored_end());
+                self.objects.reserve_for(slot);
+                self.objects.insert(slot, object);
+                slot
+            }
+        }
+    }
+
+    /// takes an object out of the world without anything else noticing, its slot gets reused,
+    /// including ones waiting out of the world in a far chunk
+    pub fn despawn_object(&mut self, index: usize) {
+        if self.objects.remove(index).is_none() {
+            self.despawn_stored(index);
+            return;
+        }
+        self.forget_contacts(index);
+        for controller in self
+            .controllers
+            .iter_mut()
+            .filter_map(Controller::as_player_mut)
+        {
+            for hook in controller.hooks_mut() {
+                if matches!(hook, Hook::Attached { object, .. } if *object == index) {
+                    *hook = Hook::Idle;
+                }
+            }
+        }
+        self.controllers.retain(|controller| match controller {
+            Controller::TurretController(c) => c.turret.object != index,
+            Controller::PatrolController(c) => c.object != index,
+            Controller::PlayerController(_) => true,
+        });
+        self.scripts.retain(|runner| runner.script.object != index);
+        self.ropes
+            .retain(|rope| rope.objects.0 != index && rope.objects.1 != index);
+        if matches!(&self.mouse_joint, Some(joint) if joint.object == index) {
+            self.mouse_joint = None;
+        }
+        self.position_tweens.cancel_key(index);
+        self.lit_explosives.retain(|lit| *lit != index);
+        self.projectiles.forget_owner(index);
+        for (_, object) in &self.objects {
+            let mut object = object.borrow_mut();
+            if object.parent == Some(index) {
+                object.parent = None;
+            }
+        }
+        self.free_slots.push(index);
+    }
+
+    /// forgets everything the rest of the world knows about touching object `index`, once it's gone
+    fn forget_contacts(&mut self, index: usize) {
+        for (_, object) in &self.objects {
+            object.borrow_mut().touching.remove(&index);
+        }
+        self.inside_triggers.retain(|(_, object)| *object != index);
+        self.contact_impulses
+            .retain(|(a, b), _| *a != index && *b != index);
+    }
+
+    /// where the view object's hook would hit going straight towards `target`, as far as it
+    /// reaches, None if it would miss everything
+    pub fn aim_point(&self, target: cgmath::Point2<f64>) -> Option<cgmath::Point2<f64>> {
+        let from = self.object(self.view_object)?.center();
+        let to_target = target - from;
+        if to_target.magnitude2() == 0.0 {
+            return None;
+        }
+        let reach = from + to_target.normalize_to(Self::HOOK_RANGE);
+        self.raycast(from, reach, Some(self.view_object))
+            .map(|(_, hit)| hit)
+    }
+
+    /// the first object in the way going in a straight line from `from` to `to`, and where it
+    /// gets hit, leaving out `ignore`, like whatever is doing the looking
+    pub fn raycast(
+        &self,
+        from: cgmath::Point2<f64>,
+        to: cgmath::Point2<f64>,
+        ignore: Option<usize>,
+    ) -> Option<(usize, cgmath::Point2<f64>)> {
+        let (object, fraction) =
+            first_hit(&self.objects, from, to - from, 0.0, |index, object| {
+                Some(index) == ignore || object.no_clip
+            })?;
+        Some((object, from + (to - from) * fraction))
+    }
+
+    /// where every turret is and which way it's pointing, in radians counterclockwise from the
+    /// right
+    pub fn turret_aims(&self) -> Vec<(cgmath::Point2<f64>, f64)> {
+        self.controllers
+            .iter()
+            .filter_map(|controller| match controller {
+                Controller::TurretController(c) => {
+                    let object = self.objects.get(c.turret.object)?;
+                    let center = object.borrow().center();
+                    Some((center, c.aim))
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// fires a projectile, returning the slot it's flying in
+    pub fn spawn_projectile(&mut self, projectile: Projectile) -> usize {
+        self.projectiles.spawn(projectile)
+    }
+
+    /// every projectile in flight, along with its slot
+    pub fn projectiles(&self) -> impl Iterator<Item = (usize, &Projectile)> {
+        self.projectiles.iter()
+    }
+
+    /// the projectile flying in `slot`, if there is one
+    pub fn projectile(&self, slot: usize) -> Option<&Projectile> {
+        self.projectiles.get(slot)
+    }
+
+    fn update_projectiles(&mut self, dt: f64) {
+        // spelled out instead of `gravity_field` so it leaves the projectiles free to change
+        let gravity = Gravity {
+            strength: self.gravity,
+            down: self.down,
+            zones: &self.gravity_zones,
+        };
+        for outcome in self.projectiles.update(&self.objects, &gravity, dt) {
+            let Outcome::Hit {
+                projectile,
+                object,
+                at,
+                damage,
+            } = outcome;
+            self.events.push(GameEvent::ProjectileHit {
+                projectile,
+                object,
+                at,
+                damage,
+            });
+            self.damage_object(object, damage);
+        }
+    }
+
+    fn run_scripts(&mut self, players: &[usize], dt: f64) {
+        let mut actions = vec![];
+        for runner in &mut self.scripts {
+            let object = runner.script.object;
+            actions.extend(
+                runner
+                    .update(&self.objects, players, dt)
+                    .into_iter()
+                    .map(|action| (object, action)),
+            );
+        }
+        for (object, action) in actions {
+            match action {
+                Action::Fire(projectile) => {
+                    self.projectiles.spawn(projectile);
+                }
+                Action::Move {
+                    to,
+                    seconds,
+                    easing,
+                } => {
+                    self.move_object(object, to, seconds, easing);
+                }
+                Action::PhaseChanged(phase) => {
+                    self.events.push(GameEvent::PhaseChanged { object, phase })
+                }
+            }
+        }
+    }
+
+    /// takes `amount` off the health of object `index`, once it runs out players die and
+    /// anything else gets destroyed, things without health don't mind
+    pub fn damage_object(&mut self, index: usize, amount: f64) {
+        if self.assist_mode.no_death && self.player_indices().contains(&index) {
+            return;
+        }
+        let health = match self.objects.get(index) {
+            Some(object) => match &mut object.borrow_mut().health {
+                Some(health) if *health > 0.0 => {
+                    *health = (*health - amount).max(0.0);
+                    *health
+                }
+                _ => return,
+            },
+            None => return,
+        };
+        self.events.push(GameEvent::Damaged {
+            object: index,
+            amount,
+            health,
+        });
+        if health > 0.0 {
+            return;
+        }
+        self.events.push(GameEvent::Killed { object: index });
+        if self.player_indices().contains(&index) {
+            self.died_at.get_or_insert(self.tick);
+        } else {
+            self.destroy_object(index);
+        }
+    }
+
+    /// hurts the things with health that hit something hard during this update
+    fn hurt_from_impacts(&mut self, first_event: usize) {
+        let hurt = self.events[first_event..]
+            .iter()
+            .filter_map(|event| match *event {
+                GameEvent::Impact {
+                    objects: (a, b),
+                    speed,
+                } if speed > Self::HURT_SPEED => Some((a, b, speed - Self::HURT_SPEED)),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        for (a, b, damage) in hurt {
+            self.damage_object(a, damage);
+            self.damage_object(b, damage);
+        }
+    }
+
+    /// how much health each scripted object has left, out of what it started with, for health bars
+    pub fn script_health(&self) -> Vec<(usize, f64)> {
+        self.scripts
+            .iter()
+            .filter_map(|runner| {
+                let full = runner.full_health?;
+                let object = self.objects.get(runner.script.object)?;
+                let health = object.borrow().health?;
+                Some((runner.script.object, health / full))
+            })
+            .collect()
+    }
+
+    /// changes the velocity of a movable object by `impulse` over its mass, anything else can't be
+    /// pushed and stays put
+    pub fn apply_impulse(&mut self, index: usize, impulse: cgmath::Vector2<f64>) {
+        if let Some(object) = self.objects.get(index) {
+            if let ObjectType::Movable { velocity, mass } = &mut object.borrow_mut().ty {
+                *velocity += impulse / *mass;
+            }
+        }
+    }
+
+    /// pushes every movable object within `radius` of `center` straight away from it, with an
+    /// impulse of `strength` right at the middle fading out to nothing at the edge, explosives it
+    /// reaches go off as well
+    pub fn apply_explosion(&mut self, center: cgmath::Point2<f64>, radius: f64, strength: f64) {
+        let mut pushed = vec![];
+        let mut hurt = vec![];
+        let reach = Region {
+            pos: center - cgmath::vec2(radius, radius),
+            size: cgmath::vec2(radius, radius) * 2.0,
+        };
+        let nearby = self.query_region(&reach).collect::<Vec<_>>();
+        for index in nearby {
+            let object = self.objects[index].borrow();
+            if object.no_clip {
+                continue;
+            }
+            let offset = object.center() - center;
+            let distance = offset.magnitude();
+            if distance >= radius {
+                continue;
+            }
+            // right on top of the middle there is no telling which way is away, so up it is
+            let direction = if distance > 1e-9 {
+                offset / distance
+            } else {
+                cgmath::vec2(0.0, 1.0)
+            };
+            pushed.push((index, direction * strength * (1.0 - distance / radius)));
+            if object.health.is_some() {
+                hurt.push((index, strength * (1.0 - distance / radius)));
+            }
+            if object.explosive.is_some() {
+                self.lit_explosives.push(index);
+            }
+        }
+        for (index, impulse) in pushed {
+            self.apply_impulse(index, impulse);
+        }
+        self.events.push(GameEvent::Explosion { center, radius });
+        for (index, damage) in hurt {
+            self.damage_object(index, damage);
+        }
+    }
+
+    /// blows up the explosives that hit something hard enough during this update, along with any
+    /// others they set off
+    fn set_off_explosives(&mut self, first_event: usize) {
+        // scripts and parents moved things around since the collisions
+        self.invalidate_broadphase();
+        for event in &self.events[first_event..] {
+            if let GameEvent::Impact {
+                objects: (a, b),
+                speed,
+            } = *event
+            {
+                for index in [a, b] {
+                    let explosive = self
+                        .objects
+                        .get(index)
+                        .and_then(|object| object.borrow().explosive);
+                    if explosive.is_some_and(|explosive| speed >= explosive.trigger_speed) {
+                        self.lit_explosives.push(index);
+                    }
+                }
+            }
+        }
+        while let Some(index) = self.lit_explosives.pop() {
+            // it might have gone off already from being set off twice
+            let (center, explosive) = match self.objects.get(index) {
+                Some(object) => {
+                    let object = object.borrow();
+                    match object.explosive {
+                        Some(explosive) => (object.center(), explosive),
+                        None => continue,
+                    }
+                }
+                None => continue,
+            };
+            self.destroy_object(index);
+            self.apply_explosion(center, explosive.radius, explosive.strength);
+        }
+    }
+
+    /// removes an object for good, letting everyone know unless it was just debris
+    fn destroy_object(&mut self, index: usize) {
+        let debris = self
+            .objects
+            .get(index)
+            .is_some_and(|object| object.borrow().lifetime.is_some());
+        self.despawn_object(index);
+        if !debris {
+            self.events
+                .push(GameEvent::ObjectDestroyed { object: index });
+        }
+    }
+
+    /// counts down the lifetime of short lived objects, despawning the ones that ran out
+    fn expire_objects(&mut self, dt: f64) {
+        let mut expired = vec![];
+        for (index, object) in &self.objects {
+            let mut object = object.borrow_mut();
+            if let Some(lifetime) = &mut object.lifetime {
+                *lifetime -= dt;
+                if *lifetime <= 0.0 {
+                    expired.push(index);
+                }
+            }
+        }
+        for index in expired {
+            self.despawn_object(index);
+        }
+    }
+
+    /// replaces a crate with four smaller pieces flying apart, which go away after a bit
+    fn break_apart(&mut self, index: usize) {
+        let rng = &mut self.rng;
+        let pieces = match self.objects.get(index) {
+            Some(object) => {
+                let object = object.borrow();
+                match object.ty {
+                    // debris just goes away, otherwise it would keep breaking into smaller bits
+                    ObjectType::Movable { velocity, mass } if object.lifetime.is_none() => {
+                        let size = object.size / 2.0;
+                        [(0.0, 0.0), (1.0, 0.0), (0.0, 1.0), (1.0, 1.0)]
+                            .iter()
+                            .map(|&(x, y)| {
+                                let outwards = cgmath::vec2(x * 2.0 - 1.0, y * 2.0 - 1.0) * 3.0
+                                    + cgmath::vec2(rng.range(-1.0, 1.0), rng.range(-1.0, 1.0))
+                                        * Self::DEBRIS_SCATTER;
+                                let pos = object.pos + cgmath::vec2(size.x * x, size.y * y);
+                                let velocity = velocity + outwards;
+                                let piece = Object::movable()
+                                    .at(pos.x, pos.y)
+                                    .size(size.x, size.y)
+                                    .mass(mass / 4.0)
+                                    .velocity(velocity.x, velocity.y)
+                                    .friction(object.surface_friction)
+                                    .lifetime(Self::DEBRIS_LIFETIME);
+                                match &object.material {
+                                    Some(material) => piece.material(material.clone()),
+                                    None => piece,
+                                }
+                                .build()
+                            })
+                            .collect::<Vec<_>>()
+                    }
+                    _ => vec![],
+                }
+            }
+            None => return,
+        };
+        self.destroy_object(index);
+        for piece in pieces {
+            self.spawn_object(piece);
+        }
+    }
+
+    /// removes the movable things that left the level, and kills or respawns the players that did
+    fn check_bounds(&mut self) {
+        let players = self.player_indices();
+        let outside = self
+            .objects
+            .iter()
+            .filter(|(index, object)| {
+                let object = object.borrow();
+                (players.contains(index) || matches!(object.ty, ObjectType::Movable { .. }))
+                    && !self.bounds.contains(object.center())
+            })
+            .map(|(index, _)| index)
+            .collect::<Vec<_>>();
+        for index in outside {
+            if !players.contains(&index) {
+                self.destroy_object(index);
+                continue;
+            }
+            let killed = self.out_of_bounds == OutOfBounds::Kill && !self.assist_mode.no_death;
+            self.events.push(GameEvent::OutOfBounds {
+                object: index,
+                killed,
+            });
+            match killed {
+                true => {
+                    self.died_at.get_or_insert(self.tick);
+                }
+                false => self.respawn_player(index),
+            }
+        }
+    }
+
+    /// puts a player back at the start, letting go of the hook
+    fn respawn_player(&mut self, index: usize) {
+        let mut object = self.objects[index].borrow_mut();
+        object.teleport(self.respawn_point);
+        object.touching.clear();
+        for controller in self
+            .controllers
+            .iter_mut()
+            .filter_map(Controller::as_player_mut)
+        {
+            if controller.controlled_object == index {
+                controller.hook = Hook::Idle;
+                controller.aiming = false;
+                controller.second_hook = Hook::Idle;
+                controller.second_aiming = false;
+            }
+        }
+    }
+
+    /// finds objects that ended up somewhere or going at a speed that makes no sense, speeds that
+    /// are just too high get capped, anything that isn't a number or is way off gets removed, or
+    /// put back at the start if it's a player
+    fn check_sanity(&mut self) {
+        let mut broken = vec![];
+        for (index, object) in &self.objects {
+            let mut object = object.borrow_mut();
+            let pos = object.pos;
+            let mut sane = pos.x.is_finite()
+                && pos.y.is_finite()
+                && pos.x.abs() < Self::MAX_COORDINATE
+                && pos.y.abs() < Self::MAX_COORDINATE;
+            if let ObjectType::Movable { velocity, .. } = &mut object.ty {
+                if !velocity.x.is_finite() || !velocity.y.is_finite() {
+                    sane = false;
+                } else if velocity.magnitude() > Self::MAX_SPEED {
+                    log::warn!(
+                        "object {} was going {:?} on tick {}, slowing it down, {}",
+                        index,
+                        velocity,
+                        self.tick,
+                        self.describe_contacts(index)
+                    );
+                    *velocity = velocity.normalize_to(Self::MAX_SPEED);
+                }
+            }
+            if !sane {
+                broken.push(index);
+            }
+        }
+        let players = self.player_indices();
+        for index in broken {
+            {
+                let object = self.objects[index].borrow();
+                log::warn!(
+                    "object {} ended up at {:?} going {:?} on tick {}, {}",
+                    index,
+                    object.pos,
+                    object.get_velocity(),
+                    self.tick,
+                    self.describe_contacts(index)
+                );
+            }
+            if players.contains(&index) {
+                self.respawn_player(index);
+            } else {
+                self.destroy_object(index);
+            }
+        }
+    }
+
+    /// the contacts `index` was in on the last update, for working out how it went wrong
+    fn describe_contacts(&self, index: usize) -> String {
+        let contacts = self
+            .contact_impulses
+            .iter()
+            .filter(|((a, b), _)| *a == index || *b == index)
+            .take(Self::LOGGED_CONTACTS)
+            .map(|((a, b), (normal, impulse))| {
+                format!(
+                    "{} and {} along {:?} with impulse {}",
+                    a, b, normal, impulse
+                )
+            })
+            .collect::<Vec<_>>();
+        if contacts.is_empty() {
+            "it wasn't touching anything".to_owned()
+        } else {
+            format!("its last contacts were {}", contacts.join(", "))
+        }
+    }
+
+    /// finds movable objects that collision resolution couldn't push out from between two others,
+    /// crushed players die and anything else breaks
+    fn check_crushing(&mut self) {
+        let mut crushed = vec![];
+        for (index, object) in &self.objects {
+            let mut object = object.borrow_mut();
+            if object.can_be_pushed().is_none() {
+                continue;
+            }
+            let mut depths = HashMap::new();
+            for (other_index, direction) in &object.touching {
+                let other = match self.objects.get(*other_index) {
+                    Some(other) => other.borrow(),
+                    None => continue,
+                };
+                if let Some(offset) =
+                    check_collision(&object.pos, &object.size, &other.pos, &other.size)
+                {
+                    let depth = offset.x.abs().max(offset.y.abs());
+                    let deepest = depths.entry(*direction).or_insert(0.0);
+                    *deepest = depth.max(*deepest);
+                }
+            }
+            // pushed into from both sides further than resting contacts sink in, with no way out
+            let squeezed_between = |a, b| {
+                let depth = |direction| depths.get(&direction).copied().unwrap_or(0.0);
+                depth(a) > Self::PENETRATION_SLOP
+                    && depth(b) > Self::PENETRATION_SLOP
+                    && depth(a) + depth(b) > Self::CRUSH_DEPTH
+            };
+            let squeezed = squeezed_between(Direction::Left, Direction::Right)
+                || squeezed_between(Direction::Up, Direction::Down);
+            object.squeezed_for = if squeezed { object.squeezed_for + 1 } else { 0 };
+            if object.squeezed_for == Self::CRUSH_TICKS {
+                crushed.push(index);
+            }
+        }
+        let players = self.player_indices();
+        for index in crushed {
+            self.events.push(GameEvent::Crushed { object: index });
+            if players.contains(&index) && self.assist_mode.no_death {
+                self.respawn_player(index);
+            } else if players.contains(&index) {
+                self.died_at.get_or_insert(self.tick);
+            } else {
+                self.break_apart(index);
+            }
+        }
+    }
+
+    fn check_triggers(&mut self) {
+        // players that went out of bounds got put back
+        self.invalidate_broadphase();
+        for trigger_index in 0..self.triggers.len() {
+            let (region, tag) = (
+                self.triggers[trigger_index].region,
+                self.triggers[trigger_index].tag.as_deref(),
+            );
+            let inside = self
+                .query_region(&region)
+                .filter(|index| {
+                    let object = self.objects[*index].borrow();
+                    region.contains(object.center()) && tag.is_none_or(|tag| object.has_tag(tag))
+                })
+                .collect::<Vec<_>>();
+            self.inside_triggers.retain(|&(trigger, index)| {
+                trigger != trigger_index || inside.binary_search(&index).is_ok()
+            });
+            for index in inside {
+                if self.inside_triggers.insert((trigger_index, index)) {
+                    self.events.push(GameEvent::TriggerFired {
+                        trigger: trigger_index,
+                        object: index,
+                    });
+                }
+            }
+        }
+    }
+
+    fn check_checkpoints(&mut self) {
+        for controller in self
+            .controllers
+            .iter_mut()
+            .filter_map(Controller::as_player_mut)
+        {
+            let center = match self.objects.get(controller.controlled_object) {
+                Some(object) => object.borrow().center(),
+                None => continue,
+            };
+            if self
+                .checkpoints
+                .iter()
+                .any(|checkpoint| checkpoint.contains(center))
+            {
+                controller.charge.refill(&controller.limits);
+            }
+        }
+    }
+
+    /// brings up the prompt of each tutorial the view object gets into for the first time, one
+    /// coming up puts away whatever was showing before
+    fn check_tutorials(&mut self) {
+        let center = match self.objects.get(self.view_object) {
+            Some(object) => object.borrow().center(),
+            None => return,
+        };
+        for (index, tutorial) in self.tutorials.iter().enumerate() {
+            if tutorial.region.contains(center) && self.shown_tutorials.insert(index) {
+                self.tutorial = Some(index);
+                self.events
+                    .push(GameEvent::TutorialShown { tutorial: index });
+            }
+        }
+    }
+
+    /// puts away the prompt that's showing once the view object's player does what it says
+    fn check_lessons(&mut self) {
+        let index = match self.tutorial {
+            Some(index) => index,
+            None => return,
+        };
+        let lesson = self.tutorials[index].lesson;
+        let view_object = self.view_object;
+        let done = self
+            .controllers
+            .iter()
+            .filter_map(Controller::as_player)
+            .filter(|controller| controller.controlled_object == view_object)
+            .flat_map(|controller| &controller.pending_events)
+            .any(|event| event.does(lesson));
+        if done {
+            self.tutorial = None;
+            self.events
+                .push(GameEvent::TutorialDone { tutorial: index });
+        }
+    }
+
+    /// each exit with a player in it, as (exit, player)
+    fn exits_around_players(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        self.player_indices().into_iter().flat_map(move |player| {
+            let center = self
+                .objects
+                .get(player)
+                .map(|object| object.borrow().center());
+            self.exits
+                .iter()
+                .enumerate()
+                .filter(move |(_, exit)| center.is_some_and(|center| exit.region.contains(center)))
+                .map(move |(exit, _)| (exit, player))
+        })
+    }
+
+    fn check_exits(&mut self) {
+        let inside = self.exits_around_players().collect::<HashSet<_>>();
+        let mut entered = inside
+            .difference(&self.inside_exits)
+            .copied()
+            .collect::<Vec<_>>();
+        entered.sort_unstable();
+        for (exit, object) in entered {
+            self.events.push(GameEvent::ExitReached { exit, object });
+        }
+        self.inside_exits = inside;
+    }
+
+    /// puts the players at `pos` and has them come back there when they leave the bounds, for
+    /// coming into the level through one of its entrances
+    pub fn enter_at(&mut self, pos: cgmath::Point2<f64>) {
+        for player in self.player_indices() {
+            if let Some(object) = self.objects.get_mut(player) {
+                object.get_mut().teleport(pos);
+            }
+        }
+        self.respawn_point = pos;
+        self.inside_exits = self.exits_around_players().collect();
+        self.invalidate_broadphase();
+    }
+
+    /// puts the players at the spawn point called `name` like `enter_at`, false if there isn't
+    /// one
+    pub fn spawn_player_at(&mut self, name: &str) -> bool {
+        let entrance = self.entrances.iter().find(|entrance| entrance.name == name);
+        match entrance.map(|entrance| entrance.pos) {
+            Some(pos) => {
+                self.enter_at(pos);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn check_whats_still_touching(&mut self) {
+        for (index, object) in &self.objects {
+            let mut object = object.borrow_mut();
+            let touching = object.touching.clone();
+            object.touching.clear();
+            for (other_index, _) in touching {
+                if index == other_index {
+                    continue;
+                }
+                let other_object = self.objects.get(other_index);
+                if let Some(other) = other_object {
+                    let other = other.borrow();
+                    const CHECK_SIZE: f64 = 0.01;
+                    let effective_pos = other.pos.map(|a| a - CHECK_SIZE);
+                    let effective_size = other.size.map(|a| a + CHECK_SIZE * 2.0);
+                    if let Some(offset) =
+                        check_collision(&object.pos, &object.size, &effective_pos, &effective_size)
+                    {
+                        let direction = Direction::from_vector(&offset);
+                        object.touching.insert(other_index, direction.invert());
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// how far along going from `from` by `delta` a circle of `radius` first touches the box at `pos`
+/// with `size`, from 0 to 1, or None if it doesn't on the way, the corners get treated as square
+/// which makes them a tiny bit easier to hit
+fn sweep(
+    from: cgmath::Point2<f64>,
+    delta: cgmath::Vector2<f64>,
+    radius: f64,
+    pos: cgmath::Point2<f64>,
+    size: cgmath::Vector2<f64>,
+) -> Option<f64> {
+    let (mut enter, mut exit) = (0.0_f64, 1.0_f64);
+    for (from, delta, low, high) in [
+        (from.x, delta.x, pos.x - radius, pos.x + size.x + radius),
+        (from.y, delta.y, pos.y - radius, pos.y + size.y + radius),
+    ] {
+        if delta.abs() < 1e-12 {
+            // not moving along this axis, so it has to already be between the sides
+            if from < low || from > high {
+                return None;
+            }
+            continue;
+        }
+        let (a, b) = ((low - from) / delta, (high - from) / delta);
+        enter = enter.max(a.min(b));
+        exit = exit.min(a.max(b));
+        if enter > exit {
+            return None;
+        }
+    }
+    Some(enter)
+}
+
+/// the first object something `radius` big runs into going from `from` by `delta`, and how far
+/// along the way it got, leaving out the objects `skip` says to
+fn first_hit(
+    objects: &StableVec<RefCell<Object>>,
+    from: cgmath::Point2<f64>,
+    delta: cgmath::Vector2<f64>,
+    radius: f64,
+    skip: impl Fn(usize, &Object) -> bool,
+) -> Option<(usize, f64)> {
+    objects
+        .iter()
+        .filter_map(|(index, object)| {
+            let object = object.borrow();
+            if skip(index, &object) {
+                return None;
+            }
+            Some((index, sweep(from, delta, radius, object.pos, object.size)?))
+        })
+        .min_by(|(_, a), (_, b)| a.total_cmp(b))
+}
+
+/// how far the first box has to move to get out of the second one, along whichever axis needs the
+/// smallest move, None if they don't overlap
+///
+/// ties, like when the centers line up exactly, always go right or up so the result is never zero
+fn check_collision(
+    pos1: &cgmath::Point2<f64>,
+    size1: &cgmath::Vector2<f64>,
+    pos2: &cgmath::Point2<f64>,
+    size2: &cgmath::Vector2<f64>,
+) -> Option<cgmath::Vector2<f64>> {
+    if pos1.x < pos2.x + size2.x
+        && pos1.x + size1.x > pos2.x
+        && pos1.y < pos2.y + size2.y
+        && pos1.y + size1.y > pos2.y
+    {
+        // the smaller of the two ways out along one axis, both of which are more than zero
+        let shortest = |forwards: f64, backwards: f64| {
+            if forwards <= backwards {
+                forwards
+            } else {
+                -backwards
+            }
+        };
+        let offset_x = shortest(pos2.x + size2.x - pos1.x, pos1.x + size1.x - pos2.x);
+        let offset_y = shortest(pos2.y + size2.y - pos1.y, pos1.y + size1.y - pos2.y);
+        if offset_x.abs() <= offset_y.abs() {
+            Some(cgmath::vec2(offset_x, 0.0))
+        } else {
+            Some(cgmath::vec2(0.0, offset_y))
+        }
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use itertools::Itertools;
+    use proptest::prelude::*;
+
+    /// a pair of boxes that overlap, as (pos1, size1, pos2, size2)
+    fn overlapping_boxes() -> impl Strategy<
+        Value = (
+            cgmath::Point2<f64>,
+            cgmath::Vector2<f64>,
+            cgmath::Point2<f64>,
+            cgmath::Vector2<f64>,
+        ),
+    > {
+        (
+            (-50.0..50.0, -50.0..50.0),
+            (0.1..10.0, 0.1..10.0),
+            (0.1..10.0, 0.1..10.0),
+            (0.001..0.999, 0.001..0.999),
+        )
+            .prop_map(|((x, y), (w1, h1), (w2, h2), (tx, ty))| {
+                let pos1 = cgmath::point2(x, y);
+                let size1 = cgmath::vec2(w1, h1);
+                let size2 = cgmath::vec2(w2, h2);
+                // anywhere from just touching the left/bottom edge to just touching the right/top one
+                let pos2 = cgmath::point2(x - w2 + tx * (w1 + w2), y - h2 + ty * (h1 + h2));
+                (pos1, size1, pos2, size2)
+            })
+    }
+
+    /// how far two boxes still overlap, anything this small is just rounding from moving them apart
+    const TOLERANCE: f64 = 1e-9;
+    const TICK: f64 = 1.0 / 60.0;
+
+    fn object(ty: ObjectType, (x, y): (f64, f64), (w, h): (f64, f64)) -> Object {
+        Object::builder(ty).at(x, y).size(w, h).build()
+    }
+
+    fn movable((vx, vy): (f64, f64), mass: f64) -> ObjectType {
+        ObjectType::Movable {
+            velocity: cgmath::vec2(vx, vy),
+            mass,
+        }
+    }
+
+    fn state_from(objects: Vec<Object>) -> GameState {
+        GameState::from_level(&level_from(objects))
+    }
+
+    /// a level with nothing but `objects` in it, the first one being the player
+    fn level_from(objects: Vec<Object>) -> Level {
+        Level {
+            version: Level::VERSION,
+            objects,
+            player: 0,
+            palette: Default::default(),
+            lighting: None,
+            goal: None,
+            triggers: vec![],
+            intro: None,
+            bounds: None,
+            out_of_bounds: Default::default(),
+            movement: Default::default(),
+            turrets: vec![],
+            scripts: vec![],
+            paths: vec![],
+            patrols: vec![],
+            chunking: None,
+            exits: vec![],
+            entrances: vec![],
+            gravity: None,
+            gravity_zones: vec![],
+            hook_limits: Default::default(),
+            checkpoints: vec![],
+            dual_hooks: false,
+            ziplines: vec![],
+            tutorials: vec![],
+            prefabs: Default::default(),
+            placed: vec![],
+            seed: 0,
+        }
+    }
+
+    /// a crate to drop in the pit, as (offset in its cell, size, velocity, mass)
+    type Crate = ((f64, f64), (f64, f64), (f64, f64), f64);
+
+    /// a bunch of crates for a walled in pit, each in its own cell of a grid so nothing starts out
+    /// overlapping
+    fn crate_pit() -> impl Strategy<Value = Vec<Crate>> {
+        prop::collection::vec(
+            (
+                (0.0..1.0, 0.0..1.0),
+                (0.2..2.0, 0.2..2.0),
+                (-15.0..15.0, -15.0..15.0),
+                0.1..10.0,
+            ),
+            1..12,
+        )
+    }
+
+    fn pit_state(crates: &[Crate]) -> GameState {
+        const CELL: f64 = 3.0;
+        let mut objects = vec![
+            // the player, parked well out of the way
+            object(ObjectType::Static, (500.0, 500.0), (1.0, 1.0)),
+            object(ObjectType::Static, (-10.0, -10.0), (40.0, 10.0)),
+            object(ObjectType::Static, (-10.0, 0.0), (10.0, 40.0)),
+            object(ObjectType::Static, (20.0, 0.0), (10.0, 40.0)),
+        ];
+        for (i, ((ox, oy), (w, h), velocity, mass)) in crates.iter().enumerate() {
+            let cell = ((i % 6) as f64 * CELL + 1.0, (i / 6) as f64 * CELL + 1.0);
+            let pos = (
+                cell.0 + ox * (CELL - w - 0.5),
+                cell.1 + oy * (CELL - h - 0.5),
+            );
+            objects.push(object(movable(*velocity, *mass), pos, (*w, *h)));
+        }
+        state_from(objects)
+    }
+
+    fn remaining_overlap(
+        pos1: cgmath::Point2<f64>,
+        size1: cgmath::Vector2<f64>,
+        pos2: cgmath::Point2<f64>,
+        size2: cgmath::Vector2<f64>,
+    ) -> f64 {
+        check_collision(&pos1, &size1, &pos2, &size2).map_or(0.0, |o| o.x.abs().max(o.y.abs()))
+    }
+
+    #[test]
+    fn no_clip_goes_through_the_floor() {
+        let mut state = state_from(vec![
+            object(movable((0.0, 0.0), 1.0), (0.0, 1.0), (1.0, 1.0)),
+            object(ObjectType::Static, (-5.0, 0.0), (10.0, 1.0)),
+        ]);
+        state.set_no_clip(0, true);
+        state.submit_player_event(Event::Keyboard {
+            button: Direction::Down,
+            state: ButtonState::Pressed,
+        });
+        for _ in 0..60 {
+            state.update(TICK);
+        }
+        let player = state.objects[0].borrow();
+        assert!(player.get_pos().y < -5.0);
+        assert!(player.touching.is_empty());
+    }
+
+    #[test]
+    fn physics_stats_count_the_contacts_of_the_last_update() {
+        let mut state = state_from(vec![
+            object(movable((0.0, 0.0), 1.0), (0.0, 1.5), (1.0, 1.0)),
+            object(ObjectType::Static, (-5.0, 0.0), (10.0, 1.0)),
+        ]);
+        state.update(TICK);
+        assert_eq!(state.physics_stats(), PhysicsStats::default());
+        for _ in 0..60 {
+            state.update(TICK);
+        }
+        let stats = state.physics_stats();
+        assert_eq!(stats.contacts, 1);
+        assert!(stats.max_penetration > 0.0 && stats.max_penetration < 0.05);
+        assert_eq!(stats.total_penetration, stats.max_penetration);
+        assert!(stats.solver_iterations <= GameState::SOLVER_ITERATIONS);
+    }
+
+    #[test]
+    fn contacts_list_what_pushed_on_what() {
+        let mut state = state_from(vec![
+            object(ObjectType::Static, (-5.0, 0.0), (10.0, 1.0)),
+            object(movable((0.0, 0.0), 2.0), (3.0, 1.5), (1.0, 1.0)),
+            object(movable((0.0, 0.0), 1.0), (-2.0, 1.5), (1.0, 1.0)),
+        ]);
+        assert!(state.contacts().is_empty());
+        for _ in 0..60 {
+            state.update(TICK);
+        }
+        let contacts = state.contacts();
+        let pairs = contacts.iter().map(|c| c.objects).collect::<Vec<_>>();
+        assert_eq!(pairs, vec![(0, 1), (0, 2)]);
+        // resting, each is held up against a tick's worth of its own weight
+        let gravity = GameState::DEFAULT_GRAVITY * TICK;
+        for (contact, mass) in contacts.iter().zip([2.0, 1.0]) {
+            assert_eq!(contact.normal, cgmath::vec2(0.0, -1.0));
+            assert!(
+                (contact.impulse - mass * gravity).abs() < 1e-6,
+                "{:?}",
+                contact
+            );
+        }
+    }
+
+    #[test]
+    fn a_stack_of_crates_comes_to_rest() {
+        let mut objects = vec![
+            // the player, parked well out of the way
+            object(ObjectType::Static, (500.0, 500.0), (1.0, 1.0)),
+            object(ObjectType::Static, (-5.0, 0.0), (10.0, 1.0)),
+        ];
+        // heavier ones on top of lighter ones too, dropped from a little apart
+        for (i, mass) in [1.0, 4.0, 0.5, 2.0].into_iter().enumerate() {
+            let y = 1.1 + i as f64 * 1.1;
+            objects.push(object(movable((0.0, 0.0), mass), (0.0, y), (1.0, 1.0)));
+        }
+        let mut state = state_from(objects);
+        for _ in 0..180 {
+            state.update(TICK);
+        }
+        let positions = |state: &GameState| {
+            (2..6)
+                .map(|i| *state.objects[i].borrow().get_pos())
+                .collect::<Vec<_>>()
+        };
+        let mut last = positions(&state);
+        for _ in 0..60 {
+            state.update(TICK);
+            let now = positions(&state);
+            for (i, (before, after)) in last.iter().zip(&now).enumerate() {
+                let velocity = state.objects[i + 2].borrow().get_velocity();
+                assert!(
+                    velocity.magnitude() < 1e-6,
+                    "crate {} moves at {:?}",
+                    i,
+                    velocity
+                );
+                let drift = (after - before).magnitude();
+                assert!(drift < 1e-6, "crate {} drifted {} in a tick", i, drift);
+            }
+            last = now;
+        }
+        // still in a straight stack, each sunk only a little into the one below
+        let mut below = 1.0;
+        for pos in last {
+            assert_eq!(pos.x, 0.0);
+            assert!(
+                pos.y < below && below - pos.y < 0.05,
+                "{} on {}",
+                pos.y,
+                below
+            );
+            below = pos.y + 1.0;
+        }
+    }
+
+    #[test]
+    fn picking_finds_the_object_on_top() {
+        let state = state_from(vec![
+            object(ObjectType::Static, (0.0, 0.0), (10.0, 10.0)),
+            object(ObjectType::Static, (2.0, 2.0), (1.0, 1.0)),
+        ]);
+        assert_eq!(state.object_at(cgmath::point2(2.5, 2.5)), Some(1));
+        assert_eq!(state.object_at(cgmath::point2(5.0, 5.0)), Some(0));
+        assert_eq!(state.object_at(cgmath::point2(-1.0, 5.0)), None);
+    }
+
+    #[test]
+    fn grabbing_drags_things_to_the_cursor() {
+        let mut state = state_from(vec![
+            object(ObjectType::Static, (-5.0, 0.0), (10.0, 1.0)),
+            object(movable((0.0, 0.0), 1.0), (0.0, 1.0), (1.0, 1.0)),
+        ]);
+        assert!(!state.grab(cgmath::point2(-4.0, 0.5)));
+        assert!(state.grab(cgmath::point2(0.5, 1.5)));
+        state.drag_to(cgmath::point2(3.5, 6.5));
+        for _ in 0..120 {
+            state.update(TICK);
+        }
+        let pos = *state.objects[1].borrow().get_pos();
+        assert!((pos.x - 3.0).abs() < 0.1 && (pos.y - 6.0).abs() < 0.1);
+        state.despawn_object(1);
+        assert!(state.grab_line().is_none());
+    }
+
+    #[test]
+    fn attached_objects_get_carried_along() {
+        let mut lamp = object(ObjectType::Static, (0.5, 3.0), (0.2, 0.2));
+        lamp.parent = Some(1);
+        let mut state = state_from(vec![
+            object(ObjectType::Static, (-5.0, -20.0), (10.0, 1.0)),
+            object(movable((2.0, 0.0), 1.0), (0.0, 0.0), (1.0, 1.0)),
+            lamp,
+            object(movable((0.0, 0.0), 1.0), (20.0, 0.0), (1.0, 1.0)),
+        ]);
+        for _ in 0..30 {
+            state.update(TICK);
+        }
+        let platform = *state.objects[1].borrow().get_pos();
+        assert_eq!(
+            *state.objects[2].borrow().get_pos(),
+            platform + cgmath::vec2(0.5, 3.0)
+        );
+        assert!(state.attach(3, 2));
+        assert!(!state.attach(1, 3), "1 is already above 3");
+        state.despawn_object(1);
+        assert_eq!(state.objects[2].borrow().get_parent(), None);
+        assert_eq!(state.objects[3].borrow().get_parent(), Some(2));
+    }
+
+    #[test]
+    fn tweened_objects_end_up_where_they_were_sent() {
+        let mut state = state_from(vec![
+            object(ObjectType::Static, (-50.0, -20.0), (100.0, 1.0)),
+            object(ObjectType::Static, (10.0, 0.0), (1.0, 3.0)),
+            object(movable((0.0, 0.0), 1.0), (-10.0, 5.0), (1.0, 1.0)),
+        ]);
+        let door = state
+            .move_object(1, cgmath::point2(10.0, 4.0), 0.5, Easing::QuadInOut)
+            .unwrap();
+        let lift = state
+            .move_object(2, cgmath::point2(-10.0, 10.0), 0.5, Easing::Linear)
+            .unwrap();
+        for _ in 0..15 {
+            state.update(TICK);
+        }
+        let halfway = state.objects[1].borrow().get_pos().y;
+        assert!(halfway > 1.0 && halfway < 3.0, "{}", halfway);
+        assert!(state.cancel_tween(lift));
+        let stopped = *state.objects[2].borrow().get_pos();
+        for _ in 0..120 {
+            state.update(TICK);
+        }
+        assert_eq!(
+            *state.objects[1].borrow().get_pos(),
+            cgmath::point2(10.0, 4.0)
+        );
+        assert!(
+            state.objects[2].borrow().get_pos().y < stopped.y,
+            "it falls once let go"
+        );
+        let finished = state
+            .drain_events()
+            .filter_map(|event| match event {
+                GameEvent::TweenFinished { id, object } => Some((id, object)),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(finished, vec![(door, 1)]);
+    }
+
+    #[test]
+    fn leaving_the_bounds_kills_or_respawns_the_player() {
+        let level = |out_of_bounds| Level {
+            bounds: Some(Region {
+                pos: cgmath::point2(-10.0, -5.0),
+                size: cgmath::vec2(20.0, 20.0),
+            }),
+            out_of_bounds,
+            ..level_from(vec![
+                object(movable((0.0, 0.0), 1.0), (0.0, 0.0), (1.0, 1.0)),
+                object(movable((30.0, 0.0), 1.0), (5.0, 0.0), (1.0, 1.0)),
+                object(ObjectType::Static, (-20.0, -10.0), (1.0, 1.0)),
+            ])
+        };
+        let mut state = GameState::from_level(&level(OutOfBounds::Kill));
+        for _ in 0..60 {
+            state.update(TICK);
+        }
+        assert!(
+            state.objects.get(1).is_none(),
+            "the box flew out and is gone"
+        );
+        assert!(
+            state.objects.get(2).is_some(),
+            "statics stay wherever they are"
+        );
+        assert!(state.died_at.is_some());
+
+        let mut state = GameState::from_level(&level(OutOfBounds::Respawn));
+        for _ in 0..60 {
+            state.update(TICK);
+        }
+        assert!(state.died_at.is_none());
+        assert!(state.objects[0].borrow().get_pos().y > -5.0);
+        assert!(state.drain_events().any(|event| matches!(
+            event,
+            GameEvent::OutOfBounds {
+                object: 0,
+                killed: false
+            }
+        )));
+    }
+
+    #[test]
+    fn broken_objects_get_removed_before_they_spread() {
+        let mut state = state_from(vec![
+            object(movable((0.0, 0.0), 1.0), (0.0, 0.0), (1.0, 1.0)),
+            object(movable((f64::NAN, 0.0), 1.0), (5.0, 0.0), (1.0, 1.0)),
+            object(movable((0.0, 0.0), 1.0), (5.0, 0.9), (1.0, 1.0)),
+            object(movable((0.0, 1.0e9), 1.0), (-20.0, 0.0), (1.0, 1.0)),
+            object(ObjectType::Static, (-50.0, -2.0), (100.0, 1.0)),
+        ]);
+        state.update(TICK);
+        assert!(state.objects.get(1).is_none());
+        let above = state.objects[2].borrow();
+        assert!(above.get_pos().x.is_finite() && above.get_pos().y.is_finite());
+        let velocity = state.objects[3].borrow().get_velocity();
+        assert!(velocity.magnitude() <= GameState::MAX_SPEED + 1.0);
+    }
+
+    #[test]
+    fn speeds_get_capped() {
+        let mut capped = object(movable((20.0, 0.0), 1.0), (0.0, 50.0), (1.0, 1.0));
+        capped.set_max_speed(Some(2.0));
+        let mut state = GameState::from_level(&Level {
+            movement: Movement {
+                terminal_velocity: Some(5.0),
+                max_speed: Some(8.0),
+                ..Default::default()
+            },
+            ..level_from(vec![
+                object(movable((0.0, 0.0), 1.0), (0.0, 0.0), (1.0, 1.0)),
+                capped,
+                object(movable((0.0, 20.0), 1.0), (10.0, 50.0), (1.0, 1.0)),
+            ])
+        });
+        for _ in 0..60 {
+            state.update(TICK);
+        }
+        assert_eq!(state.objects[0].borrow().get_velocity().y, -5.0);
+        assert!(state.objects[1].borrow().get_velocity().magnitude() <= 2.0 + 1e-9);
+        assert!(state.objects[2].borrow().get_velocity().magnitude() <= 8.0 + 1e-9);
+    }
+
+    #[test]
+    fn turrets_turn_to_shoot_players_they_can_see() {
+        let run = |wall: bool| {
+            let mut objects = vec![
+                object(movable((0.0, 0.0), 1.0), (0.0, 8.0), (1.0, 1.0)),
+                object(ObjectType::Static, (0.0, 0.0), (1.0, 1.0)),
+            ];
+            if wall {
+                objects.push(object(ObjectType::Static, (-2.0, 4.0), (5.0, 0.5)));
+            }
+            let mut state = GameState::from_level(&Level {
+                turrets: vec![crate::level::Turret {
+                    object: 1,
+                    range: 20.0,
+                    turn_speed: 2.0,
+                    fire_interval: 1.0,
+                    shot_speed: 15.0,
+                    damage: 3.0,
+                    spread: 0.0,
+                }],
+                ..level_from(objects)
+            });
+            state.gravity = 0.0;
+            let mut fired = vec![];
+            let mut hits = vec![];
+            for _ in 0..90 {
+                state.update(TICK);
+                let tick = state.tick;
+                for event in state.drain_events() {
+                    match event {
+                        GameEvent::TurretFired { object, .. } => fired.push((tick, object)),
+                        GameEvent::ProjectileHit { object, damage, .. } => {
+                            hits.push((object, damage))
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            (fired, hits)
+        };
+        let (fired, hits) = run(false);
+        // the player is straight up and the turret starts out facing right, so it has to turn first
+        let (tick, turret) = fired[0];
+        assert_eq!(turret, 1);
+        assert!(tick > 30, "fired on tick {} before lining up", tick);
+        assert_eq!(hits, vec![(0, 3.0)]);
+        assert_eq!(run(true), (vec![], vec![]));
+    }
+
+    #[test]
+    fn scripts_change_phase_as_they_get_hurt() {
+        use crate::level::{Attack, Phase, Script};
+        let mut state = GameState::from_level(&Level {
+            scripts: vec![Script {
+                object: 1,
+                phases: vec![
+                    Phase {
+                        below_health: 0.0,
+                        attacks: vec![
+                            Attack::Ring {
+                                count: 4,
+                                speed: 10.0,
+                                damage: 1.0,
+                            },
+                            Attack::Wait { seconds: 10.0 },
+                        ],
+                    },
+                    Phase {
+                        below_health: 5.0,
+                        attacks: vec![Attack::Move {
+                            to: cgmath::point2(0.0, 10.0),
+                            seconds: 0.5,
+                            easing: Easing::Linear,
+                        }],
+                    },
+                ],
+            }],
+            ..level_from(vec![
+                object(movable((0.0, 0.0), 1.0), (10.0, 0.0), (1.0, 1.0)),
+                object(ObjectType::Static, (0.0, 5.0), (2.0, 2.0)).with_health(10.0),
+            ])
+        });
+        state.gravity = 0.0;
+        state.update(TICK);
+        assert_eq!(state.projectiles().count(), 4);
+        state.damage_object(1, 6.0);
+        assert_eq!(state.script_health(), vec![(1, 0.4)]);
+        state.update(TICK);
+        assert!(state.drain_events().any(|event| matches!(
+            event,
+            GameEvent::PhaseChanged {
+                object: 1,
+                phase: 1
+            }
+        )));
+        for _ in 0..40 {
+            state.update(TICK);
+        }
+        assert_eq!(
+            *state.objects[1].borrow().get_pos(),
+            cgmath::point2(0.0, 10.0)
+        );
+        state.damage_object(1, 6.0);
+        assert!(state.objects.get(1).is_none());
+        assert!(state.script_health().is_empty());
+        assert!(state
+            .drain_events()
+            .any(|event| matches!(event, GameEvent::Killed { object: 1 })));
+    }
+
+    #[test]
+    fn patrols_go_back_and_forth_along_their_path() {
+        use crate::level::{Patrol, Waypoint};
+        let point = |x, y, wait| Waypoint {
+            pos: cgmath::point2(x, y),
+            wait,
+            speed: None,
+        };
+        let mut state = GameState::from_level(&Level {
+            paths: vec![
+                PatrolPath {
+                    name: "across".to_owned(),
+                    points: vec![
+                        point(0.0, 0.0, 0.0),
+                        point(4.0, 0.0, 0.5),
+                        point(4.0, 4.0, 0.0),
+                    ],
+                    speed: 8.0,
+                    looped: false,
+                },
+                PatrolPath {
+                    name: "up".to_owned(),
+                    points: vec![point(0.0, 12.0, 0.0), point(0.0, 10.0, 0.0)],
+                    speed: 4.0,
+                    looped: true,
+                },
+            ],
+            patrols: vec![
+                Patrol {
+                    object: 1,
+                    path: "across".to_owned(),
+                },
+                Patrol {
+                    object: 2,
+                    path: "up".to_owned(),
+                },
+            ],
+            ..level_from(vec![
+                object(movable((0.0, 0.0), 1.0), (50.0, 0.0), (1.0, 1.0)),
+                object(ObjectType::Static, (0.0, 0.0), (2.0, 0.5)),
+                object(movable((0.0, 0.0), 5.0), (0.0, 10.0), (2.0, 0.5)),
+                object(ObjectType::Static, (-50.0, -1.0), (150.0, 1.0)),
+            ])
+        });
+        let mut reached = vec![];
+        for _ in 0..200 {
+            state.update(TICK);
+            let tick = state.tick;
+            for event in state.drain_events() {
+                if let GameEvent::WaypointReached { object: 1, point } = event {
+                    reached.push((tick, point));
+                }
+            }
+        }
+        let points = reached.iter().map(|(_, point)| *point).collect::<Vec<_>>();
+        assert_eq!(points[..5], [0, 1, 2, 1, 0]);
+        // the half a second wait at the second point holds it up on the way there and back
+        let (there, back) = (reached[1].0, reached[2].0);
+        assert!(
+            back - there >= 30 + 29,
+            "went on after {} ticks",
+            back - there
+        );
+        // the movable one floats along its path instead of falling off it
+        let platform = *state.objects[2].borrow().get_pos();
+        assert!(platform.x.abs() < 1e-9 && (10.0..=12.0).contains(&platform.y));
+    }
+
+    #[test]
+    fn player_input_shows_held_keys_and_the_hook() {
+        let mut state = state_from(vec![
+            object(movable((0.0, 0.0), 1.0), (0.0, 0.0), (1.0, 1.0)),
+            object(ObjectType::Static, (-50.0, -1.0), (100.0, 1.0)),
+            object(ObjectType::Static, (-5.0, 8.0), (10.0, 1.0)),
+        ]);
+        let target = cgmath::point2(0.5, 8.5);
+        state.submit_player_event(Event::Keyboard {
+            button: Direction::Right,
+            state: ButtonState::Pressed,
+        });
+        state.submit_player_event(Event::Hook {
+            state: ButtonState::Pressed,
+            target,
+        });
+        state.update(TICK);
+        let input = state.player_input(0).unwrap();
+        assert_eq!(input.held, vec![Direction::Right]);
+        assert!(input.aiming);
+        assert_eq!(input.hook, HookState::Idle);
+        assert!(state.player_input(1).is_none());
+        state.submit_player_event(Event::Hook {
+            state: ButtonState::Released,
+            target,
+        });
+        state.update(TICK);
+        assert!(matches!(
+            state.player_input(0).unwrap().hook,
+            HookState::Flying { .. }
+        ));
+        assert!(matches!(state.flying_hooks()[..], [(0, 0, _)]));
+        for _ in 0..30 {
+            state.update(TICK);
+        }
+        match state.player_input(0).unwrap().hook {
+            HookState::Attached { angle, length } => {
+                // above, it ran off to the right of where it hooked since
+                assert!(angle.sin() > 0.5, "hooked at {}", angle);
+                assert!((7.0..9.0).contains(&length), "rope is {} long", length);
+            }
+            hook => panic!("the hook should have stuck, it's {:?}", hook),
+        }
+        assert!(state.flying_hooks().is_empty());
+    }
+
+    #[test]
+    fn coop_players_stay_within_rope_length() {
+        let mut state = state_from(vec![
+            object(movable((0.0, 0.0), 1.0), (0.0, 0.0), (1.0, 1.0)),
+            object(ObjectType::Static, (-50.0, -1.0), (100.0, 1.0)),
+        ]);
+        let partner = state.start_coop(4.0).unwrap();
+        assert_eq!(state.player_indices(), vec![0, partner]);
+        state.submit_event_for(
+            partner,
+            Event::Keyboard {
+                button: Direction::Right,
+                state: ButtonState::Pressed,
+            },
+        );
+        for _ in 0..120 {
+            state.update(TICK);
+            let first = state.objects[0].borrow().center();
+            let second = state.objects[partner].borrow().center();
+            assert!(first.distance(second) <= 4.0 + 1e-6);
+        }
+        // only the partner ran off, the first player got dragged along behind
+        let first = state.objects[0].borrow().center();
+        let second = state.objects[partner].borrow().center();
+        assert!(
+            second.x > first.x + 3.0 && first.x > 1.0,
+            "{first:?} {second:?}"
+        );
+    }
+
+    #[test]
+    fn explosions_push_things_away_and_set_off_barrels() {
+        let barrel = Explosive {
+            radius: 4.0,
+            strength: 20.0,
+            trigger_speed: 10.0,
+        };
+        let mut state = state_from(vec![
+            object(movable((0.0, 0.0), 1.0), (-30.0, 0.0), (1.0, 1.0)),
+            object(movable((0.0, 0.0), 1.0), (2.0, 0.0), (1.0, 1.0)),
+            object(movable((0.0, 0.0), 2.0), (-2.0, 0.0), (1.0, 1.0)),
+            object(movable((0.0, 0.0), 1.0), (5.0, 0.0), (1.0, 1.0)).with_explosive(barrel),
+            object(movable((0.0, 0.0), 1.0), (8.0, 0.0), (1.0, 1.0)),
+            object(ObjectType::Static, (-50.0, -1.0), (100.0, 1.0)),
+        ]);
+        state.apply_explosion(cgmath::point2(0.5, 0.5), 4.0, 10.0);
+        let right = state.objects[1].borrow().get_velocity();
+        let left = state.objects[2].borrow().get_velocity();
+        assert!(right.x > 0.0 && left.x < 0.0);
+        assert!(right.x > -left.x, "the heavier one gets pushed less");
+        assert_eq!(state.objects[0].borrow().get_velocity().x, 0.0);
+        // the barrel was out of range of that one, but not of this one
+        assert!(state.objects.get(3).is_some());
+        state.apply_explosion(cgmath::point2(4.0, 0.5), 2.0, 10.0);
+        state.update(TICK);
+        assert!(
+            state.objects.get(3).is_none(),
+            "caught in the blast, it went off"
+        );
+        assert!(state.objects[4].borrow().get_velocity().x > 0.0);
+    }
+
+    #[test]
+    fn debris_flies_the_same_way_for_the_same_seed() {
+        let debris = |seed| {
+            let mut level = level_from(vec![
+                object(movable((0.0, 0.0), 1.0), (-30.0, 0.0), (1.0, 1.0)),
+                object(movable((0.0, 0.0), 1.0), (0.0, 0.0), (2.0, 2.0)),
+            ]);
+            level.seed = seed;
+            let mut state = GameState::from_level(&level);
+            state.break_apart(1);
+            // a snapshot carries on with the same numbers
+            let text = ron::ser::to_string(&state).unwrap();
+            let mut copy: GameState = ron::from_str(&text).unwrap();
+            assert_eq!(copy.rng().next_u64(), state.rng().next_u64());
+            state
+                .iter_objects()
+                .skip(1)
+                .map(|object| object.get_velocity())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(debris(3).len(), 4);
+        assert_eq!(debris(3), debris(3));
+        assert_ne!(debris(3), debris(4));
+    }
+
+    #[test]
+    fn fast_projectiles_hit_thin_walls_and_free_their_slot() {
+        let mut state = state_from(vec![
+            object(ObjectType::Static, (500.0, 500.0), (1.0, 1.0)),
+            object(ObjectType::Static, (10.0, -5.0), (0.1, 10.0)),
+            object(ObjectType::Static, (-50.0, -1.0), (100.0, 1.0)),
+        ]);
+        let shot = |owner| Projectile {
+            pos: cgmath::point2(0.0, 1.0),
+            // far enough per tick to go right past the wall without the sweep
+            velocity: cgmath::vec2(600.0, 0.0),
+            radius: 0.1,
+            gravity_scale: 0.0,
+            damage: 2.0,
+            lifetime: 1.0,
+            owner,
+        };
+        let first = state.spawn_projectile(shot(None));
+        state.update(TICK);
+        let hit = state.drain_events().find_map(|event| match event {
+            GameEvent::ProjectileHit {
+                projectile,
+                object,
+                at,
+                damage,
+            } => Some((projectile, object, at, damage)),
+            _ => None,
+        });
+        let (projectile, object, at, damage) = hit.expect("it should have hit the wall");
+        assert_eq!((projectile, object, damage), (first, 1, 2.0));
+        assert!((at.x - 9.9).abs() < 1e-9);
+        assert!(state.projectile(first).is_none());
+        // shots don't hit whatever fired them, and give their slot back once they fizzle out
+        let second = state.spawn_projectile(Projectile {
+            lifetime: 0.1,
+            velocity: cgmath::vec2(0.0, 0.0),
+            ..shot(Some(2))
+        });
+        assert_eq!(first, second);
+        for _ in 0..10 {
+            state.update(TICK);
+        }
+        assert!(state.projectile(second).is_none());
+        assert!(!state
+            .drain_events()
+            .any(|event| matches!(event, GameEvent::ProjectileHit { .. })));
+        assert_eq!(state.spawn_projectile(shot(None)), first);
+    }
+
+    #[test]
+    fn builders_fill_in_what_they_are_given() {
+        let built = Object::movable()
+            .at(2.0, 1.0)
+            .size(1.5, 0.5)
+            .mass(2.0)
+            .velocity(3.0, 0.0)
+            .friction(0.5)
+            .material("stone")
+            .build();
+        assert_eq!(built.center(), cgmath::point2(2.75, 1.25));
+        assert!(matches!(built.get_type(), ObjectType::Movable { mass, .. } if *mass == 2.0));
+        assert_eq!(built.get_velocity(), cgmath::vec2(3.0, 0.0));
+        assert_eq!(built.get_surface_friction(), 0.5);
+        assert_eq!(built.get_material(), Some("stone"));
+        assert!(built.get_touching().is_empty());
+        // the rest is what `new` gives, and mass means nothing to things that can't be pushed
+        let wall = Object::fixed().mass(5.0).build();
+        assert!(matches!(wall.get_type(), ObjectType::Static));
+        assert_eq!(*wall.get_size(), cgmath::vec2(1.0, 1.0));
+        assert_eq!(wall.get_surface_friction(), 1.0);
+        assert_eq!((wall.get_health(), wall.get_parent()), (None, None));
+    }
+
+    #[test]
+    fn queries_find_objects_by_index_and_area() {
+        let mut state = state_from(vec![
+            object(movable((0.0, 0.0), 1.0), (0.0, 0.0), (1.0, 1.0)),
+            object(ObjectType::Static, (3.0, 0.0), (1.0, 1.0)),
+            object(ObjectType::Static, (10.0, 10.0), (2.0, 2.0)),
+        ]);
+        let debris = state.spawn_object(
+            object(movable((0.0, 0.0), 1.0), (3.0, 3.0), (0.5, 0.5)).with_lifetime(0.01),
+        );
+        assert_eq!(state.object_count(), 4);
+        assert_eq!(state.player().map(|player| player.id()), Some(0));
+        assert_eq!(
+            state.object(2).map(|wall| *wall.get_pos()),
+            Some(cgmath::point2(10.0, 10.0))
+        );
+        let region = Region {
+            pos: cgmath::point2(1.0, 0.0),
+            size: cgmath::vec2(2.0, 3.0),
+        };
+        // the edges count, so the wall at x = 3 and the debris resting on the top are in
+        let found = |state: &GameState| {
+            state
+                .objects_in_aabb(&region)
+                .map(|object| object.id())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(found(&state), vec![0, 1, debris]);
+        state.update(TICK);
+        assert!(state.object(debris).is_none());
+        assert!(!found(&state).contains(&debris));
+        assert_eq!(
+            state
+                .iter_objects()
+                .map(|object| object.id())
+                .collect::<Vec<_>>(),
+            vec![0, 1, 2]
+        );
+    }
+
+    #[test]
+    fn despawned_slots_get_reused() {
+        let mut state = state_from(vec![object(ObjectType::Static, (500.0, 500.0), (1.0, 1.0))]);
+        let debris = || object(movable((0.0, 0.0), 1.0), (0.0, 0.0), (0.5, 0.5)).with_lifetime(0.1);
+        let first = state.spawn_object(debris());
+        for _ in 0..10 {
+            state.update(TICK);
+        }
+        assert!(state.objects.get(first).is_none());
+        let second = state.spawn_object(debris());
+        assert_eq!(first, second);
+        assert_eq!(state.objects.next_push_index(), 2);
+    }
+
+    /// where the pendulums in the swing tests hang from
+    const PIVOT: (f64, f64) = (0.0, 10.0);
+
+    /// a 1x1 bob hanging `length` from `PIVOT`, let go from `angle` radians off straight down, on
+    /// either a rope or the player's hook
+    fn pendulum(length: f64, angle: f64, on_hook: bool) -> (GameState, usize) {
+        let bob = (
+            PIVOT.0 + length * angle.sin() - 0.5,
+            PIVOT.1 - length * angle.cos() - 0.5,
+        );
+        let pivot = object(
+            ObjectType::Static,
+            (PIVOT.0 - 0.5, PIVOT.1 - 0.5),
+            (1.0, 1.0),
+        );
+        let bob = object(movable((0.0, 0.0), 1.0), bob, (1.0, 1.0));
+        if on_hook {
+            let mut state = state_from(vec![bob, pivot]);
+            let controller = state.controllers[0].as_player_mut().unwrap();
+            controller.hook = Hook::Attached {
+                object: 1,
+                offset: cgmath::vec2(0.5, 0.5),
+                length,
+            };
+            // with nothing held, air control would slow the swing down towards standing still
+            controller.acceleration_speed = 0.0;
+            (state, 0)
+        } else {
+            let parked = object(ObjectType::Static, (500.0, 500.0), (1.0, 1.0));
+            let mut state = state_from(vec![parked, pivot, bob]);
+            state.tie(1, 2, length);
+            (state, 2)
+        }
+    }
+
+    /// how far the bob is swung out from straight down, in radians, positive to the right
+    fn swing_angle(state: &GameState, bob: usize) -> f64 {
+        let offset = state.objects[bob].borrow().center() - cgmath::point2(PIVOT.0, PIVOT.1);
+        offset.x.atan2(-offset.y)
+    }
+
+    /// kinetic plus potential energy of the bob, per unit of mass
+    fn swing_energy(state: &GameState, bob: usize) -> f64 {
+        let bob = state.objects[bob].borrow();
+        bob.get_velocity().magnitude2() / 2.0 + state.gravity * bob.center().y
+    }
+
+    #[test]
+    fn small_swings_follow_the_analytic_pendulum() {
+        let (length, start) = (5.0, 0.1);
+        let omega = (GameState::DEFAULT_GRAVITY / length).sqrt();
+        let period = 2.0 * std::f64::consts::PI / omega;
+        for on_hook in [false, true] {
+            let (mut state, bob) = pendulum(length, start, on_hook);
+            let mut worst: f64 = 0.0;
+            let mut crossings = vec![];
+            let mut last = start;
+            for tick in 1..=(3.0 * period / TICK).round() as usize {
+                state.update(TICK);
+                let time = tick as f64 * TICK;
+                let angle = swing_angle(&state, bob);
+                if time <= period {
+                    worst = worst.max((angle - start * (omega * time).cos()).abs());
+                }
+                if last > 0.0 && angle <= 0.0 {
+                    crossings.push(time);
+                }
+                last = angle;
+            }
+            // the projection bleeds a little off every swing, so it falls slowly behind the
+            // undamped solution, but the swing itself has to keep the right frequency
+            assert!(
+                worst < 0.01,
+                "on hook {}: off by up to {} rad",
+                on_hook,
+                worst
+            );
+            assert_eq!(crossings.len(), 3, "on hook {}: {:?}", on_hook, crossings);
+            let measured = (crossings[2] - crossings[0]) / 2.0;
+            assert!(
+                (measured - period).abs() < period * 0.01,
+                "on hook {}: swung every {}s instead of {}s",
+                on_hook,
+                measured,
+                period
+            );
+        }
+    }
+
+    #[test]
+    fn swinging_never_gains_energy() {
+        let length = 5.0;
+        for on_hook in [false, true] {
+            let (mut state, bob) = pendulum(length, 1.0, on_hook);
+            let start = swing_energy(&state, bob);
+            // what's left over the bottom of the swing, which is what can actually get lost
+            let lowest = state.gravity * (PIVOT.1 - length);
+            for tick in 0..60 * 20 {
+                state.update(TICK);
+                let energy = swing_energy(&state, bob);
+                assert!(
+                    energy <= start + 1e-9,
+                    "on hook {}: gained {} by tick {}",
+                    on_hook,
+                    energy - start,
+                    tick
+                );
+                let center = state.objects[bob].borrow().center();
+                assert!(center.distance(cgmath::point2(PIVOT.0, PIVOT.1)) <= length + 1e-9);
+            }
+            let kept = (swing_energy(&state, bob) - lowest) / (start - lowest);
+            assert!(
+                kept > 0.25,
+                "on hook {}: only {} of the swing is left after 20s",
+                on_hook,
+                kept
+            );
+        }
+    }
+
+    /// a long chunked level, the player on a floor at the start, a platform every 10 units after
+    /// it and a crate resting on the one at 300, returns the level and the crate's index
+    fn marathon() -> (Level, usize) {
+        use crate::level::Chunking;
+
+        let mut objects = vec![
+            object(movable((0.0, 0.0), 1.0), (0.0, 1.0), (1.0, 1.0)),
+            object(ObjectType::Static, (-10.0, 0.0), (20.0, 1.0)),
+        ];
+        for i in 1..50 {
+            objects.push(object(
+                ObjectType::Static,
+                (i as f64 * 10.0, 0.0),
+                (4.0, 1.0),
+            ));
+        }
+        objects.push(object(movable((0.0, 0.0), 1.0), (301.0, 1.0), (1.0, 1.0)));
+        let crate_index = objects.len() - 1;
+        let mut level = level_from(objects);
+        level.chunking = Some(Chunking {
+            size: 8.0,
+            radius: 16.0,
+        });
+        (level, crate_index)
+    }
+
+    #[test]
+    fn far_chunks_wait_outside_the_world_until_a_player_comes_close() {
+        let (level, crate_index) = marathon();
+        let mut state = GameState::from_level(&level);
+        // the floor and the platforms out to the chunk past the radius
+        assert_eq!(state.object_count(), 5);
+        assert_eq!(state.stored_object_count(), level.objects.len() - 5);
+        assert!(state.object(crate_index).is_none());
+        for _ in 0..120 {
+            state.update(TICK);
+        }
+        // standing on the platform at 300 hasn't let the crate fall through while it was away
+        state.object_mut(0).unwrap().pos = cgmath::point2(302.5, 1.0);
+        state.update(TICK);
+        assert!(state.object(1).is_none());
+        let resting = level.objects[crate_index].pos;
+        for _ in 0..60 {
+            state.update(TICK);
+        }
+        let pos = *state.object(crate_index).unwrap().get_pos();
+        assert!(
+            pos.distance(resting) < 0.05,
+            "the crate ended up at {:?}",
+            pos
+        );
+        assert_eq!(
+            state.object_count() + state.stored_object_count(),
+            level.objects.len()
+        );
+    }
+
+    #[test]
+    fn objects_out_of_the_world_keep_their_slots() {
+        let (level, crate_index) = marathon();
+        let state = GameState::from_level(&level);
+        let text = ron::ser::to_string(&state).unwrap();
+        let mut state: GameState = ron::from_str(&text).unwrap();
+        assert_eq!(state.stored_object_count(), level.objects.len() - 5);
+        let spawned = state.spawn_object(object(ObjectType::Static, (0.0, 5.0), (1.0, 1.0)));
+        assert_eq!(spawned, level.objects.len());
+        state.object_mut(0).unwrap().pos = cgmath::point2(302.5, 1.0);
+        state.update(TICK);
+        // back in the slot it had, without the static spawned after it taking it, which has gone
+        // out of the world itself now the player has left
+        assert!(state
+            .object(crate_index)
+            .is_some_and(|object| matches!(object.ty, ObjectType::Movable { .. })));
+        assert_eq!(
+            state.object_count() + state.stored_object_count(),
+            level.objects.len() + 1
+        );
+    }
+
+    #[test]
+    fn objects_in_far_chunks_can_be_despawned_and_moved() {
+        let (level, crate_index) = marathon();
+        let mut state = GameState::from_level(&level);
+        let platform = 40;
+        assert!(state.object(platform).is_none() && state.object(crate_index).is_none());
+        state.despawn_object(platform);
+        assert_eq!(state.stored_object_count(), level.objects.len() - 6);
+        // somewhere still far off, where it waits to be come back to
+        assert!(state.teleport_object(crate_index, cgmath::point2(201.0, 1.0)));
+        assert!(state.object(crate_index).is_none());
+        assert!(!state.teleport_object(level.objects.len() + 3, cgmath::point2(0.0, 0.0)));
+        state.object_mut(0).unwrap().pos = cgmath::point2(202.5, 1.0);
+        state.update(TICK);
+        let pos = *state.object(crate_index).unwrap().get_pos();
+        assert!(pos.distance(cgmath::point2(201.0, 1.0)) < 0.05, "{:?}", pos);
+        state.object_mut(0).unwrap().pos = cgmath::point2(402.5, 1.0);
+        state.update(TICK);
+        assert!(state.object(platform).is_none());
+        // and right next to a player, where it comes straight back
+        state.teleport_object(1, cgmath::point2(396.0, 0.0));
+        assert!(state.object(1).is_some());
+        assert_eq!(
+            state.object_count() + state.stored_object_count(),
+            level.objects.len() - 1
+        );
+    }
+
+    #[test]
+    fn exits_fire_on_the_way_in_but_not_for_players_put_inside() {
+        use crate::level::Exit;
+
+        let exit = |x| Exit {
+            region: Region {
+                pos: cgmath::point2(x, 0.0),
+                size: cgmath::vec2(2.0, 3.0),
+            },
+            level: "hub".to_owned(),
+            entrance: None,
+        };
+        let mut level = level_from(vec![
+            object(movable((0.0, 0.0), 1.0), (0.0, 0.0), (1.0, 1.0)),
+            object(ObjectType::Static, (-20.0, -1.0), (40.0, 1.0)),
+        ]);
+        level.exits = vec![exit(-0.5), exit(5.0)];
+        let reached = |state: &mut GameState| {
+            state
+                .drain_events()
+                .filter_map(|event| match event {
+                    GameEvent::ExitReached { exit, object } => Some((exit, object)),
+                    _ => None,
+                })
+                .collect::<Vec<_>>()
+        };
+        // starting out in the first one doesn't count
+        let mut state = GameState::from_level(&level);
+        state.update(TICK);
+        assert_eq!(reached(&mut state), vec![]);
+        state
+            .object_mut(0)
+            .unwrap()
+            .teleport(cgmath::point2(5.5, 0.0));
+        state.update(TICK);
+        assert_eq!(reached(&mut state), vec![(1, 0)]);
+        state.update(TICK);
+        assert_eq!(reached(&mut state), vec![]);
+        // and neither does coming in through an entrance in one
+        state.enter_at(cgmath::point2(0.0, 0.0));
+        state.update(TICK);
+        assert_eq!(reached(&mut state), vec![]);
+        assert_eq!(state.respawn_point, cgmath::point2(0.0, 0.0));
+    }
+
+    #[test]
+    fn triggers_with_a_tag_only_fire_for_tagged_objects() {
+        use crate::level::Trigger;
+
+        let trigger = |name: &str, tag: Option<&str>| Trigger {
+            name: name.to_owned(),
+            region: Region {
+                pos: cgmath::point2(-1.0, 0.0),
+                size: cgmath::vec2(10.0, 5.0),
+            },
+            tag: tag.map(str::to_owned),
+        };
+        let mut level = level_from(vec![
+            object(movable((0.0, 0.0), 1.0), (0.0, 0.0), (1.0, 1.0)),
+            object(ObjectType::Static, (-20.0, -1.0), (40.0, 1.0)),
+            object(movable((0.0, 0.0), 1.0), (3.0, 0.0), (1.0, 1.0))
+                .with_tag("key")
+                .with_tag("crate"),
+            object(movable((0.0, 0.0), 1.0), (6.0, 0.0), (1.0, 1.0)).with_tag("crate"),
+        ]);
+        level.triggers = vec![trigger("anything", None), trigger("lock", Some("key"))];
+        let mut state = GameState::from_level(&level);
+        assert_eq!(state.find_by_tag("key"), Some(2));
+        assert_eq!(
+            state.find_all_by_tag("crate").collect::<Vec<_>>(),
+            vec![2, 3]
+        );
+        assert_eq!(state.find_by_tag("door"), None);
+        state.update(TICK);
+        let mut fired = state
+            .drain_events()
+            .filter_map(|event| match event {
+                GameEvent::TriggerFired { trigger, object } => Some((trigger, object)),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        fired.sort();
+        assert_eq!(fired, vec![(0, 0), (0, 2), (0, 3), (1, 2)]);
+    }
+
+    #[test]
+    fn players_get_spawned_at_named_entrances() {
+        use crate::level::Entrance;
+
+        let mut level = level_from(vec![
+            object(movable((0.0, 0.0), 1.0), (0.0, 0.0), (1.0, 1.0)),
+            object(ObjectType::Static, (-20.0, -1.0), (40.0, 1.0)),
+        ]);
+        level.entrances = vec![
+            Entrance {
+                name: "left".to_owned(),
+                pos: cgmath::point2(-10.0, 0.0),
+            },
+            Entrance {
+                name: "right".to_owned(),
+                pos: cgmath::point2(10.0, 0.0),
+            },
+        ];
+        let mut state = GameState::from_level(&level);
+        assert!(state.spawn_player_at("right"));
+        assert_eq!(
+            *state.object(0).unwrap().get_pos(),
+            cgmath::point2(10.0, 0.0)
+        );
+        assert_eq!(state.respawn_point, cgmath::point2(10.0, 0.0));
+        assert!(!state.spawn_player_at("attic"));
+        assert_eq!(
+            *state.object(0).unwrap().get_pos(),
+            cgmath::point2(10.0, 0.0)
+        );
+        // and the entrances survive a snapshot, for going back to them after loading one
+        let mut state: GameState = ron::from_str(&ron::to_string(&state).unwrap()).unwrap();
+        assert!(state.spawn_player_at("left"));
+        assert_eq!(state.respawn_point, cgmath::point2(-10.0, 0.0));
+    }
+
+    #[test]
+    fn buttons_ignore_repeats_and_keep_edges_for_one_update() {
+        let mut buttons = Buttons::default();
+        assert!(buttons.set(Direction::Up, ButtonState::Pressed));
+        assert!(!buttons.set(Direction::Up, ButtonState::Pressed));
+        assert!(buttons.pressed(Direction::Up) && buttons.held(Direction::Up));
+        buttons.end_tick();
+        assert!(!buttons.pressed(Direction::Up) && buttons.held(Direction::Up));
+        // tapped and let go between two updates still counts as a press
+        assert!(buttons.set(Direction::Left, ButtonState::Pressed));
+        assert!(buttons.set(Direction::Left, ButtonState::Released));
+        assert!(buttons.pressed(Direction::Left) && buttons.released(Direction::Left));
+        assert!(!buttons.held(Direction::Left));
+        assert!(!buttons.set(Direction::Right, ButtonState::Released));
+    }
+
+    #[test]
+    fn holding_jump_only_jumps_once() {
+        let mut state = state_from(vec![
+            object(movable((0.0, 0.0), 1.0), (0.0, 0.0), (1.0, 1.0)),
+            object(ObjectType::Static, (-20.0, -1.0), (40.0, 1.0)),
+        ]);
+        state.update(TICK);
+        let mut jumps = 0;
+        let mut was_on_ground = true;
+        for tick in 0..180 {
+            // what a key the OS repeats while it's held down looks like
+            if tick % 5 == 0 {
+                state.submit_player_event(Event::Keyboard {
+                    button: Direction::Up,
+                    state: ButtonState::Pressed,
+                });
+            }
+            state.update(TICK);
+            let player = state.object(0).unwrap();
+            let on_ground = player.is_on_ground(state.down_at(player.center()));
+            jumps += (was_on_ground && !on_ground) as i32;
+            was_on_ground = on_ground;
+        }
+        assert_eq!(jumps, 1);
+        assert!(was_on_ground);
+    }
+
+    #[test]
+    fn half_a_push_speeds_up_half_as_fast() {
+        let speed_after = |events: &[Event]| {
+            let mut state = state_from(vec![
+                object(movable((0.0, 0.0), 1.0), (0.0, 0.0), (1.0, 1.0)),
+                object(ObjectType::Static, (-50.0, -1.0), (100.0, 1.0)),
+            ]);
+            state.update(TICK);
+            for event in events {
+                state.submit_player_event(*event);
+            }
+            for _ in 0..5 {
+                state.update(TICK);
+            }
+            let speed = state.object(0).unwrap().get_velocity().x;
+            speed
+        };
+        let key = speed_after(&[Event::Keyboard {
+            button: Direction::Right,
+            state: ButtonState::Pressed,
+        }]);
+        let half = speed_after(&[Event::Movement { push: 0.5 }]);
+        assert!(key > 0.0);
+        assert!((half - key / 2.0).abs() < 1e-9, "{} {}", half, key);
+        // the key wins over the stick while it's held
+        let both = speed_after(&[
+            Event::Movement { push: 0.5 },
+            Event::Keyboard {
+                button: Direction::Right,
+                state: ButtonState::Pressed,
+            },
+        ]);
+        assert_eq!(both, key);
+        assert!(speed_after(&[Event::Movement { push: -2.0 }]) == -key);
+    }
+
+    /// presses and lets go of the fire button on one update, returns whether the hook went out
+    fn fire_at(state: &mut GameState, target: cgmath::Point2<f64>) -> bool {
+        for button in [ButtonState::Pressed, ButtonState::Released] {
+            state.submit_player_event(Event::Hook {
+                state: button,
+                target,
+            });
+        }
+        state.update(TICK);
+        let fired = state
+            .drain_events()
+            .any(|event| matches!(event, GameEvent::HookFired { .. }));
+        fired
+    }
+
+    #[test]
+    fn hook_cooldown_and_uses_hold_it_back_until_a_checkpoint() {
+        let mut level = level_from(vec![
+            object(movable((0.0, 0.0), 1.0), (0.0, 0.0), (1.0, 1.0)),
+            object(ObjectType::Static, (-50.0, -1.0), (100.0, 1.0)),
+        ]);
+        level.hook_limits = HookLimits {
+            cooldown: Some(0.5),
+            uses: Some(2),
+            ..Default::default()
+        };
+        let mut state = GameState::from_level(&level);
+        let target = cgmath::point2(10.0, 10.0);
+        assert!(fire_at(&mut state, target));
+        // pressing again only lets go of the one that's out
+        assert!(!fire_at(&mut state, target));
+        for _ in 0..30 {
+            state.update(TICK);
+        }
+        assert!(fire_at(&mut state, target));
+        assert_eq!(state.hook_charge(0).unwrap().uses, Some(0));
+        for _ in 0..30 {
+            state.update(TICK);
+        }
+        assert!(!fire_at(&mut state, target));
+        state.checkpoints.push(Region {
+            pos: cgmath::point2(-2.0, -1.0),
+            size: cgmath::vec2(4.0, 4.0),
+        });
+        state.update(TICK);
+        assert_eq!(state.hook_charge(0).unwrap().uses, Some(2));
+        assert!(fire_at(&mut state, target));
+    }
+
+    #[test]
+    fn assist_mode_gives_endless_hooks_and_no_deaths() {
+        let mut level = level_from(vec![
+            object(movable((0.0, 0.0), 1.0), (0.0, 0.0), (1.0, 1.0)).with_health(1.0),
+            object(ObjectType::Static, (-50.0, -1.0), (100.0, 1.0)),
+        ]);
+        level.hook_limits = HookLimits {
+            cooldown: Some(0.5),
+            uses: Some(1),
+            ..Default::default()
+        };
+        let mut state = GameState::from_level(&level);
+        let target = cgmath::point2(10.0, 10.0);
+        assert!(fire_at(&mut state, target));
+        assert!(!fire_at(&mut state, target));
+        assert!(!fire_at(&mut state, target), "out of uses");
+        state.set_assist_mode(AssistMode {
+            infinite_hooks: true,
+            no_death: true,
+            ..Default::default()
+        });
+        for _ in 0..3 {
+            assert!(fire_at(&mut state, target));
+            assert!(!fire_at(&mut state, target));
+        }
+        state.damage_object(0, 5.0);
+        assert_eq!(state.objects[0].borrow().get_health(), Some(1.0));
+
+        level.objects.pop();
+        level.bounds = Some(Region {
+            pos: cgmath::point2(-10.0, -5.0),
+            size: cgmath::vec2(20.0, 20.0),
+        });
+        level.out_of_bounds = OutOfBounds::Kill;
+        let mut state = GameState::from_level(&level);
+        state.set_assist_mode(AssistMode {
+            no_death: true,
+            ..Default::default()
+        });
+        for _ in 0..60 {
+            state.update(TICK);
+        }
+        assert!(state.died_at.is_none(), "fell out and got put back instead");
+        assert!(state.objects[0].borrow().get_pos().y > -5.0);
+    }
+
+    #[test]
+    fn running_out_of_stamina_lets_go_of_the_hook() {
+        let mut level = level_from(vec![
+            object(movable((0.0, 0.0), 1.0), (0.0, 0.0), (1.0, 1.0)),
+            object(ObjectType::Static, (-50.0, -1.0), (100.0, 1.0)),
+            object(ObjectType::Static, (-5.0, 8.0), (10.0, 1.0)),
+        ]);
+        level.hook_limits = HookLimits {
+            stamina: Some(1.0),
+            ..Default::default()
+        };
+        let mut state = GameState::from_level(&level);
+        assert!(fire_at(&mut state, cgmath::point2(0.5, 8.5)));
+        let mut tired_at = None;
+        for tick in 0..120 {
+            state.update(TICK);
+            if state
+                .drain_events()
+                .any(|event| matches!(event, GameEvent::HookTired { object: 0 }))
+            {
+                tired_at = Some(tick);
+                break;
+            }
+        }
+        // it took a few ticks to get there, then a second of hanging
+        let tired_at = tired_at.expect("never let go");
+        assert!((60..70).contains(&tired_at), "{}", tired_at);
+        assert_eq!(state.player_input(0).unwrap().hook, HookState::Idle);
+        assert_eq!(state.hook_charge(0).unwrap().stamina, Some(0.0));
+        // standing back on the ground gets it back, one second for each second stood
+        for _ in 0..180 {
+            state.update(TICK);
+        }
+        assert_eq!(state.hook_charge(0).unwrap().stamina, Some(1.0));
+    }
+
+    #[test]
+    fn dual_hooks_hold_the_player_between_both_anchors() {
+        let mut level = level_from(vec![
+            object(movable((0.0, 0.0), 1.0), (0.0, 0.0), (1.0, 1.0)),
+            object(ObjectType::Static, (-8.0, 8.0), (2.0, 1.0)),
+            object(ObjectType::Static, (7.0, 8.0), (2.0, 1.0)),
+        ]);
+        let second = |state| Event::SecondHook {
+            state,
+            target: cgmath::point2(8.0, 8.5),
+        };
+        let fire_both = |state: &mut GameState| {
+            for button in [ButtonState::Pressed, ButtonState::Released] {
+                state.submit_player_event(Event::Hook {
+                    state: button,
+                    target: cgmath::point2(-7.0, 8.5),
+                });
+                state.submit_player_event(second(button));
+            }
+            for _ in 0..240 {
+                state.update(TICK);
+            }
+        };
+
+        // without the flag the second button does nothing
+        let mut state = GameState::from_level(&level);
+        fire_both(&mut state);
+        assert_eq!(state.hook_lines().len(), 1);
+
+        level.dual_hooks = true;
+        let mut state = GameState::from_level(&level);
+        fire_both(&mut state);
+        let lines = state.hook_lines();
+        assert_eq!(lines.len(), 2);
+        assert!(state.is_swinging());
+        // hanging in the middle, held up by both ropes instead of swinging under either one
+        let player = state.object(0).unwrap().center();
+        assert!(player.x.abs() < 1.0 && player.y > 0.0, "{:?}", player);
+        for (from, to) in lines {
+            assert!(from.distance(to) < 11.0, "{:?} {:?}", from, to);
+        }
+    }
+
+    #[test]
+    fn ropes_pulled_too_hard_snap() {
+        let mut level = level_from(vec![
+            object(movable((0.0, 0.0), 2.0), (0.0, 0.0), (1.0, 1.0)),
+            object(ObjectType::Static, (-0.5, 8.0), (2.0, 1.0)),
+            object(ObjectType::Static, (-21.0, 8.0), (2.0, 1.0)),
+        ]);
+        level.movement.acceleration = 0.0;
+        let weight = 2.0 * GameState::from_level(&level).gravity;
+        level.hook_limits.max_tension = Some(2.0 * weight);
+        let snapped = |state: &mut GameState| {
+            let mut snapped = None;
+            for _ in 0..180 {
+                state.update(TICK);
+                snapped = snapped.or(state.drain_events().find_map(|event| match event {
+                    GameEvent::RopeSnapped { object: 0, to, .. } => Some(to),
+                    _ => None,
+                }));
+            }
+            snapped
+        };
+
+        // catching a short fall holds, and hanging still the rope pulls with the player's weight
+        let mut state = GameState::from_level(&level);
+        assert!(fire_at(&mut state, cgmath::point2(0.5, 8.5)));
+        assert_eq!(snapped(&mut state), None);
+        let [tension, second] = state.hook_tension(0).unwrap();
+        assert!((tension - weight).abs() < 0.01 * weight, "{}", tension);
+        assert_eq!(second, 0.0);
+        assert_eq!(state.hook_ropes()[0].2, tension);
+
+        // swinging through the bottom from far out to the side pulls harder than that
+        let mut state = GameState::from_level(&level);
+        assert!(fire_at(&mut state, cgmath::point2(-20.0, 8.5)));
+        let to = snapped(&mut state).expect("the rope never snapped");
+        assert!((to.x + 20.0).abs() < 1.0, "{:?}", to);
+        assert!(!state.is_swinging());
+        assert_eq!(state.hook_tension(0), Some([0.0; 2]));
+    }
+
+    #[test]
+    fn swing_tuning_changes_how_the_rope_holds_on() {
+        let mut level = level_from(vec![
+            // already falling, so there's something for the rope to stop when it catches
+            object(movable((0.0, -8.0), 1.0), (0.0, 0.0), (1.0, 1.0)),
+            object(ObjectType::Static, (-13.0, 8.0), (2.0, 1.0)),
+        ]);
+        level.movement.acceleration = 0.0;
+        // the fastest it went and the furthest it got from the hook over a second of swinging,
+        // and how fast it was going right after letting go
+        let swing = |level: &Level| {
+            let mut state = GameState::from_level(level);
+            assert!(fire_at(&mut state, cgmath::point2(-12.0, 8.5)));
+            let (mut fastest, mut furthest): (f64, f64) = (0.0, 0.0);
+            for _ in 0..60 {
+                state.update(TICK);
+                let player = state.object(0).unwrap();
+                fastest = fastest.max(player.get_velocity().magnitude());
+                furthest = furthest.max(player.center().distance(cgmath::point2(-12.0, 8.5)));
+            }
+            state.submit_player_event(Event::Hook {
+                state: ButtonState::Pressed,
+                target: cgmath::point2(0.0, 0.0),
+            });
+            state.update(TICK);
+            let released = state.object(0).unwrap().get_velocity().magnitude();
+            (fastest, furthest, released)
+        };
+        let (fastest, furthest, released) = swing(&level);
+
+        level.movement.swing.momentum = 1.0;
+        let (keeping_momentum, ..) = swing(&level);
+        assert!(
+            keeping_momentum > fastest * 1.05,
+            "{} {}",
+            keeping_momentum,
+            fastest
+        );
+
+        level.movement.swing = Swing {
+            stiffness: 0.1,
+            ..Swing::default()
+        };
+        let (_, stretched, _) = swing(&level);
+        assert!(stretched > furthest + 0.1, "{} {}", stretched, furthest);
+
+        level.movement.swing = Swing {
+            release_boost: 2.0,
+            ..Swing::default()
+        };
+        let (_, _, boosted) = swing(&level);
+        assert!(boosted > released * 1.8, "{} {}", boosted, released);
+    }
+
+    #[test]
+    fn letting_go_at_the_bottom_of_a_swing_gives_a_boost() {
+        let mut level = level_from(vec![
+            object(movable((0.0, 0.0), 1.0), (0.0, 0.0), (1.0, 1.0)),
+            object(ObjectType::Static, (-13.0, 8.0), (2.0, 1.0)),
+        ]);
+        level.movement.acceleration = 0.0;
+        level.movement.swing.release_bonus = 5.0;
+        // swings until `let_go` says it's time, then returns how fast it was going before and
+        // after, and the boost it got
+        let swing = |let_go: &dyn Fn(cgmath::Point2<f64>) -> bool| {
+            let mut state = GameState::from_level(&level);
+            assert!(fire_at(&mut state, cgmath::point2(-12.0, 8.5)));
+            for _ in 0..120 {
+                state.update(TICK);
+                if let_go(state.object(0).unwrap().center()) {
+                    break;
+                }
+            }
+            let before = state.object(0).unwrap().get_velocity().magnitude();
+            state.submit_player_event(Event::Hook {
+                state: ButtonState::Pressed,
+                target: cgmath::point2(0.0, 0.0),
+            });
+            state.update(TICK);
+            let after = state.object(0).unwrap().get_velocity().magnitude();
+            let boost = state.drain_events().find_map(|event| match event {
+                GameEvent::ReleaseBoost { object: 0, speed } => Some(speed),
+                _ => None,
+            });
+            (before, after, boost)
+        };
+
+        let (before, after, boost) = swing(&|player| player.x < -11.5);
+        let boost = boost.expect("letting go at the bottom gave no boost");
+        assert!(boost > 4.0 && boost <= 5.0, "{}", boost);
+        assert!(after > before + 3.5, "{} {}", before, after);
+
+        // barely started swinging, nowhere near the bottom
+        let (_, _, boost) = swing(&|player| player.x < -1.0);
+        assert_eq!(boost, None);
+    }
+
+    #[test]
+    fn aiming_finds_where_the_hook_would_stick() {
+        let level = level_from(vec![
+            object(movable((0.0, 0.0), 1.0), (0.0, 0.0), (1.0, 1.0)),
+            object(ObjectType::Static, (-1.0, 8.0), (3.0, 1.0)),
+        ]);
+        let mut state = GameState::from_level(&level);
+        let hit = state.aim_point(cgmath::point2(0.5, 100.0)).unwrap();
+        assert!((hit.y - 8.0).abs() < 1e-9, "{:?}", hit);
+        // nothing over there, and nothing to aim at right where the player is
+        assert_eq!(state.aim_point(cgmath::point2(30.0, 0.5)), None);
+        assert_eq!(state.aim_point(cgmath::point2(0.5, 0.5)), None);
+
+        assert!(state.hook_anchors().is_empty());
+        assert!(fire_at(&mut state, cgmath::point2(0.5, 8.5)));
+        for _ in 0..30 {
+            state.update(TICK);
+        }
+        let anchors = state.hook_anchors();
+        assert_eq!(anchors.len(), 1);
+        let (object, which, anchor) = anchors[0];
+        assert_eq!((object, which), (0, 0));
+        assert!(anchor.y >= 8.0 && anchor.y <= 9.0, "{:?}", anchor);
+    }
+
+    #[test]
+    fn tutorials_show_once_and_go_away_once_done() {
+        let mut level = level_from(vec![
+            object(movable((0.0, 0.0), 1.0), (0.0, 0.0), (1.0, 1.0)),
+            object(ObjectType::Static, (-50.0, -1.0), (100.0, 1.0)),
+        ]);
+        level.tutorials = vec![Tutorial {
+            region: Region {
+                pos: cgmath::point2(3.0, 0.0),
+                size: cgmath::vec2(2.0, 2.0),
+            },
+            lesson: Lesson::Jump,
+            text: "tutorial.jump".to_owned(),
+        }];
+        let mut state = GameState::from_level(&level);
+        let shown = |state: &mut GameState| {
+            state
+                .drain_events()
+                .any(|event| matches!(event, GameEvent::TutorialShown { tutorial: 0 }))
+        };
+        state.update(TICK);
+        assert!(!shown(&mut state));
+        assert_eq!(state.tutorial, None);
+
+        state
+            .object_mut(0)
+            .unwrap()
+            .teleport(cgmath::point2(3.5, 0.0));
+        state.update(TICK);
+        assert!(shown(&mut state));
+        assert_eq!(state.tutorial, Some(0));
+
+        // walking about isn't what it asked for
+        state.submit_player_event(Event::Keyboard {
+            button: Direction::Right,
+            state: ButtonState::Pressed,
+        });
+        state.update(TICK);
+        assert_eq!(state.tutorial, Some(0));
+        state.submit_player_event(Event::Keyboard {
+            button: Direction::Up,
+            state: ButtonState::Pressed,
+        });
+        state.update(TICK);
+        assert_eq!(state.tutorial, None);
+        assert!(state
+            .drain_events()
+            .any(|event| matches!(event, GameEvent::TutorialDone { tutorial: 0 })));
+
+        // leaving and coming back doesn't bring it up again
+        state
+            .object_mut(0)
+            .unwrap()
+            .teleport(cgmath::point2(-10.0, 0.0));
+        state.update(TICK);
+        state
+            .object_mut(0)
+            .unwrap()
+            .teleport(cgmath::point2(3.5, 0.0));
+        state.update(TICK);
+        assert!(!shown(&mut state));
+        assert_eq!(state.tutorial, None);
+    }
+
+    #[test]
+    fn hooks_slide_down_ziplines() {
+        let mut level = level_from(vec![object(
+            movable((0.0, 0.0), 1.0),
+            (-9.0, 2.0),
+            (1.0, 1.0),
+        )]);
+        level.ziplines = vec![Zipline {
+            from: cgmath::point2(-10.0, 10.0),
+            to: cgmath::point2(10.0, 4.0),
+            sag: 1.0,
+        }];
+        // with nothing held, air control would hold it back from sliding
+        level.movement.acceleration = 0.0;
+        let mut state = GameState::from_level(&level);
+        assert!(fire_at(&mut state, cgmath::point2(-8.5, 20.0)));
+        let mut attached = false;
+        for _ in 0..30 {
+            state.update(TICK);
+            attached |= state.drain_events().any(|event| {
+                matches!(
+                    event,
+                    GameEvent::ZiplineAttached {
+                        object: 0,
+                        zipline: 0
+                    }
+                )
+            });
+        }
+        assert!(attached);
+        for _ in 0..120 {
+            state.update(TICK);
+        }
+        // downhill is to the right, and it's still hanging from the cable
+        let player = state.object(0).unwrap().center();
+        assert!(player.x > 0.0, "{:?}", player);
+        let (from, to) = state.hook_lines()[0];
+        let (_, on_cable) = level.ziplines[0].closest(to);
+        assert!(on_cable.distance(to) < 1e-9);
+        assert!(from.y < to.y);
+
+        // pressing again lets go
+        state.submit_player_event(Event::Hook {
+            state: ButtonState::Pressed,
+            target: to,
+        });
+        state.update(TICK);
+        assert!(state.hook_lines().is_empty());
+        assert!(state.drain_events().any(|event| matches!(
+            event,
+            GameEvent::ZiplineDetached {
+                object: 0,
+                zipline: 0
+            }
+        )));
+    }
+
+    #[test]
+    fn level_gravity_can_pull_any_way() {
+        let mut level = level_from(vec![
+            object(movable((0.0, 0.0), 1.0), (0.0, 0.0), (1.0, 1.0)),
+            object(ObjectType::Static, (-20.0, 5.0), (40.0, 1.0)),
+        ]);
+        level.gravity = Some(cgmath::vec2(0.0, 15.0));
+        let mut state = GameState::from_level(&level);
+        for _ in 0..120 {
+            state.update(TICK);
+        }
+        let player = state.object(0).unwrap();
+        // it fell up and is standing on the ceiling
+        assert!(
+            (player.get_pos().y - 4.0).abs() < 0.05,
+            "{:?}",
+            player.get_pos()
+        );
+        assert_eq!(state.down_at(player.center()), cgmath::vec2(0.0, 1.0));
+        assert!(player.is_on_ground(state.down_at(player.center())));
+    }
+
+    #[test]
+    fn gravity_zones_turn_walls_into_floors() {
+        use crate::level::GravityZone;
+
+        let mut level = level_from(vec![
+            object(movable((0.0, 0.0), 1.0), (8.0, 0.0), (1.0, 1.0)),
+            object(ObjectType::Static, (-20.0, -1.0), (40.0, 1.0)),
+            object(ObjectType::Static, (10.0, -1.0), (1.0, 40.0)),
+        ]);
+        level.gravity_zones = vec![GravityZone {
+            region: Region {
+                pos: cgmath::point2(5.0, -1.0),
+                size: cgmath::vec2(5.0, 40.0),
+            },
+            gravity: cgmath::vec2(15.0, 0.0),
+        }];
+        let mut state = GameState::from_level(&level);
+        for _ in 0..60 {
+            state.update(TICK);
+        }
+        let landed = state
+            .drain_events()
+            .any(|event| matches!(event, GameEvent::Landed { object: 0 }));
+        assert!(landed);
+        let player = state.object(0).unwrap();
+        assert!(
+            (player.get_pos().x - 9.0).abs() < 0.05,
+            "{:?}",
+            player.get_pos()
+        );
+        assert!(player.is_on_ground(state.down_at(player.center())));
+        drop(player);
+        // running right along the wall is running up it
+        state.submit_player_event(Event::Keyboard {
+            button: Direction::Right,
+            state: ButtonState::Pressed,
+        });
+        for _ in 0..60 {
+            state.update(TICK);
+        }
+        let player = state.object(0).unwrap();
+        assert!(player.get_pos().y > 2.0, "{:?}", player.get_pos());
+        assert!(
+            (player.get_pos().x - 9.0).abs() < 0.05,
+            "{:?}",
+            player.get_pos()
+        );
+        drop(player);
+        // and jumping is away from it
+        state.submit_player_event(Event::Keyboard {
+            button: Direction::Up,
+            state: ButtonState::Pressed,
+        });
+        state.update(TICK);
+        assert!(state.object(0).unwrap().get_velocity().x < -5.0);
+    }
+
+    proptest! {
+        #[test]
+        fn resolution_separates_along_one_axis((pos1, size1, pos2, size2) in overlapping_boxes()) {
+            let offset = check_collision(&pos1, &size1, &pos2, &size2);
+            prop_assert!(offset.is_some());
+            let offset = offset.unwrap();
+            prop_assert!((offset.x == 0.0) != (offset.y == 0.0), "offset {:?} should use exactly one axis", offset);
+            prop_assert!(remaining_overlap(pos1 + offset, size1, pos2, size2) < TOLERANCE);
+        }
+
+        #[test]
+        fn resolution_takes_the_shortest_way_out((pos1, size1, pos2, size2) in overlapping_boxes()) {
+            let offset = check_collision(&pos1, &size1, &pos2, &size2).unwrap();
+            let shortest = [
+                pos2.x + size2.x - pos1.x,
+                pos1.x + size1.x - pos2.x,
+                pos2.y + size2.y - pos1.y,
+                pos1.y + size1.y - pos2.y,
+            ]
+            .iter()
+            .fold(f64::INFINITY, |a, b| a.min(*b));
+            prop_assert_eq!(offset.x.abs().max(offset.y.abs()), shortest);
+        }
+
+        #[test]
+        fn simulation_never_produces_nan(crates in crate_pit()) {
+            let mut state = pit_state(&crates);
+            for _ in 0..300 {
+                state.update(TICK);
+            }
+            for (_, object) in &state.objects {
+                let object = object.borrow();
+                prop_assert!(object.pos.x.is_finite() && object.pos.y.is_finite());
+                let velocity = object.get_velocity();
+                prop_assert!(velocity.x.is_finite() && velocity.y.is_finite());
+            }
+        }
+
+        #[test]
+        fn the_broadphase_misses_nothing(
+            boxes in prop::collection::vec(((-40.0..40.0, -40.0..40.0), (0.1..8.0, 0.1..8.0)), 1..40),
+            ((x, y), (w, h)) in ((-40.0..40.0, -40.0..40.0), (0.0..30.0, 0.0..30.0)),
+        ) {
+            let mut objects = boxes
+                .iter()
+                .map(|&(pos, size)| object(ObjectType::Static, pos, size))
+                .collect::<Vec<_>>();
+            // long enough to go in with the large objects instead of the cells
+            objects.push(object(ObjectType::Static, (-100.0, -45.0), (200.0, 1.0)));
+            let state = state_from(objects);
+            let touches = |a: &Object, pos: cgmath::Point2<f64>, size: cgmath::Vector2<f64>| {
+                a.pos.x <= pos.x + size.x
+                    && a.pos.x + a.size.x >= pos.x
+                    && a.pos.y <= pos.y + size.y
+                    && a.pos.y + a.size.y >= pos.y
+            };
+            let pairs = state.broadphase().pairs(&state.objects);
+            for (a, b) in state.objects.indices().tuple_combinations() {
+                let (a_object, b_object) = (state.objects[a].borrow(), state.objects[b].borrow());
+                if touches(&a_object, b_object.pos, b_object.size) {
+                    prop_assert!(pairs.binary_search(&(a, b)).is_ok(), "missed {} and {}", a, b);
+                }
+            }
+            let region = Region {
+                pos: cgmath::point2(x, y),
+                size: cgmath::vec2(w, h),
+            };
+            let expected = state
+                .objects
+                .iter()
+                .filter(|(_, object)| touches(&object.borrow(), region.pos, region.size))
+                .map(|(index, _)| index)
+                .collect::<Vec<_>>();
+            prop_assert_eq!(state.query_region(&region).collect::<Vec<_>>(), expected);
+        }
+
+        #[test]
+        fn objects_come_apart(crates in crate_pit()) {
+            let mut state = pit_state(&crates);
+            for _ in 0..300 {
+                state.update(TICK);
+            }
+            // resting contacts sit inside the slop, and stacks squeeze in a little further than that
+            const ALLOWED: f64 = 0.1;
+            for (a, b) in state.objects.indices().tuple_combinations() {
+                let (a, b) = (state.objects[a].borrow(), state.objects[b].borrow());
+                let overlap = remaining_overlap(a.pos, a.size, b.pos, b.size);
+                prop_assert!(overlap < ALLOWED, "objects overlap by {}", overlap);
+            }
+        }
+
+        #[test]
+        fn collisions_conserve_momentum(
+            (mass1, mass2) in (0.1..10.0, 0.1..10.0),
+            (speed1, speed2) in (1.0..20.0f64, 1.0..20.0f64),
+            (h1, h2) in (0.5..3.0, 0.5..3.0),
+        ) {
+            // two boxes flying at each other in the middle of nowhere, both falling just the same
+            let mut state = state_from(vec![
+                object(ObjectType::Static, (500.0, 500.0), (1.0, 1.0)),
+                object(movable((speed1, 0.0), mass1), (-2.0, 0.0), (1.0, h1)),
+                object(movable((-speed2, 0.0), mass2), (1.0, 0.0), (1.0, h2)),
+            ]);
+            let momentum = |state: &GameState| {
+                [1, 2].iter().fold(0.0, |sum, index| {
+                    let object = state.objects[*index].borrow();
+                    sum + object.get_velocity().x * object.can_be_pushed().unwrap()
+                })
+            };
+            let before = momentum(&state);
+            let mut collided = false;
+            for _ in 0..60 {
+                state.update(TICK);
+                collided |= state.drain_events().any(|event| matches!(event, GameEvent::Impact { .. }));
+            }
+            prop_assert!(collided);
+            let after = momentum(&state);
+            prop_assert!(
+                (after - before).abs() <= 1e-6 * before.abs().max(1.0),
+                "momentum went from {} to {}", before, after
+            );
+        }
+
+        #[test]
+        fn aligned_centers_still_resolve(
+            (x, y) in (-50.0..50.0, -50.0..50.0),
+            (w1, h1) in (0.1..10.0, 0.1..10.0),
+            (w2, h2) in (0.1..10.0, 0.1..10.0),
+        ) {
+            let pos1 = cgmath::point2(x - w1 / 2.0, y - h1 / 2.0);
+            let pos2 = cgmath::point2(x - w2 / 2.0, y - h2 / 2.0);
+            let size1 = cgmath::vec2(w1, h1);
+            let size2 = cgmath::vec2(w2, h2);
+            let offset = check_collision(&pos1, &size1, &pos2, &size2).unwrap();
+            prop_assert!(offset.x != 0.0 || offset.y != 0.0);
+            prop_assert!(remaining_overlap(pos1 + offset, size1, pos2, size2) < TOLERANCE);
+        }
+    }
+}
@@ -0,0 +1,115 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+use stable_vec::StableVec;
+
+use super::Object;
+
+/// a grid over the world with the objects overlapping each cell listed in it, so finding what's
+/// near something doesn't mean going through every object
+#[derive(Clone, Debug, Default)]
+pub struct Broadphase {
+    cells: HashMap<(i64, i64), Vec<usize>>,
+    /// objects covering too many cells to be put in all of them, like long floors, they always
+    /// come up as candidates
+    large: Vec<usize>,
+    /// whether it was built from where the objects are now, nothing is in it until it has been
+    pub up_to_date: bool,
+}
+
+impl Broadphase {
+    /// how wide and high a cell is, a few times the size of most things
+    const CELL_SIZE: f64 = 4.0;
+    /// anything covering more cells than this goes in `large` instead
+    const MAX_CELLS: u64 = 64;
+
+    /// the cells from `min` to `max`, both included so things that only touch share a cell, and
+    /// how many that is
+    fn cell_range(
+        min: cgmath::Point2<f64>,
+        max: cgmath::Point2<f64>,
+    ) -> ((i64, i64), (i64, i64), u64) {
+        let cell = |x: f64| (x / Self::CELL_SIZE).floor() as i64;
+        let (from, to) = ((cell(min.x), cell(min.y)), (cell(max.x), cell(max.y)));
+        // anything broken enough to be out at infinity still has to come out as a finite count
+        let span = |from: i64, to: i64| (to.saturating_sub(from) as u64).saturating_add(1);
+        let count = span(from.0, to.0).saturating_mul(span(from.1, to.1));
+        (from, to, count)
+    }
+
+    /// puts every object in the cells it overlaps
+    pub fn build(&mut self, objects: &StableVec<RefCell<Object>>) {
+        // cells that were in use last time probably still are, so they keep their allocations
+        self.cells.retain(|_, cell| {
+            let used = !cell.is_empty();
+            cell.clear();
+            used
+        });
+        self.large.clear();
+        for (index, object) in objects {
+            let object = object.borrow();
+            let (from, to, count) = Self::cell_range(object.pos, object.pos + object.size);
+            if count > Self::MAX_CELLS {
+                self.large.push(index);
+                continue;
+            }
+            for x in from.0..=to.0 {
+                for y in from.1..=to.1 {
+                    self.cells.entry((x, y)).or_default().push(index);
+                }
+            }
+        }
+        self.up_to_date = true;
+    }
+
+    /// every object that might overlap the box from `min` to `max`, in order and without any
+    /// repeats, some of them might not really be in it
+    pub fn query(&self, min: cgmath::Point2<f64>, max: cgmath::Point2<f64>) -> Vec<usize> {
+        let (from, to, count) = Self::cell_range(min, max);
+        let mut found = self.large.clone();
+        if count > self.cells.len() as u64 {
+            // a big enough box is quicker to check against the cells there are than the other way
+            for (&(x, y), cell) in &self.cells {
+                if (from.0..=to.0).contains(&x) && (from.1..=to.1).contains(&y) {
+                    found.extend(cell);
+                }
+            }
+        } else {
+            for x in from.0..=to.0 {
+                for y in from.1..=to.1 {
+                    if let Some(cell) = self.cells.get(&(x, y)) {
+                        found.extend(cell);
+                    }
+                }
+            }
+        }
+        found.sort_unstable();
+        found.dedup();
+        found
+    }
+
+    /// every pair of objects that might be overlapping, each with the lower index first, sorted
+    pub fn pairs(&self, objects: &StableVec<RefCell<Object>>) -> Vec<(usize, usize)> {
+        let mut pairs = vec![];
+        for cell in self.cells.values() {
+            for (i, &a) in cell.iter().enumerate() {
+                for &b in &cell[i + 1..] {
+                    pairs.push((a.min(b), a.max(b)));
+                }
+            }
+        }
+        for &large in &self.large {
+            if let Some(object) = objects.get(large) {
+                let object = object.borrow();
+                for other in self.query(object.pos, object.pos + object.size) {
+                    if other != large {
+                        pairs.push((large.min(other), large.max(other)));
+                    }
+                }
+            }
+        }
+        pairs.sort_unstable();
+        pairs.dedup();
+        pairs
+    }
+}
@@ -0,0 +1,114 @@
+use super::{Explosive, MetaValue, Object, ObjectType, Ribbon};
+
+impl Object {
+    /// starts building an object of type `ty`, a 1x1 box at the origin until told otherwise
+    pub fn builder(ty: ObjectType) -> ObjectBuilder {
+        ObjectBuilder {
+            object: Object::new(ty, cgmath::point2(0.0, 0.0), cgmath::vec2(1.0, 1.0)),
+        }
+    }
+    /// starts building a box that falls and gets pushed around, standing still with a mass of 1
+    pub fn movable() -> ObjectBuilder {
+        Self::builder(ObjectType::Movable {
+            velocity: cgmath::vec2(0.0, 0.0),
+            mass: 1.0,
+        })
+    }
+    /// starts building a box that never moves
+    pub fn fixed() -> ObjectBuilder {
+        Self::builder(ObjectType::Static)
+    }
+    /// starts building a box that stays put but carries whatever is on it along at `velocity`
+    pub fn treadmill(velocity: cgmath::Vector2<f64>) -> ObjectBuilder {
+        Self::builder(ObjectType::Treadmill {
+            fake_velocity: velocity,
+        })
+    }
+}
+
+/// sets up an [`Object`] one property at a time, anything not set is left at what
+/// [`Object::new`] would give it, like
+/// `Object::movable().at(x, y).size(w, h).mass(2.0).friction(0.5).build()`
+#[derive(Clone)]
+pub struct ObjectBuilder {
+    object: Object,
+}
+
+impl ObjectBuilder {
+    /// puts the bottom left corner at (`x`, `y`)
+    pub fn at(mut self, x: f64, y: f64) -> Self {
+        self.object.pos = cgmath::point2(x, y);
+        self
+    }
+    /// makes it `w` wide and `h` high
+    pub fn size(mut self, w: f64, h: f64) -> Self {
+        self.object.size = cgmath::vec2(w, h);
+        self
+    }
+    /// how heavy it is, does nothing for objects that can't be pushed
+    pub fn mass(mut self, mass: f64) -> Self {
+        if let ObjectType::Movable { mass: m, .. } = &mut self.object.ty {
+            *m = mass;
+        }
+        self
+    }
+    /// how fast it starts out moving, see [`Object::set_velocity`]
+    pub fn velocity(mut self, x: f64, y: f64) -> Self {
+        self.object.set_velocity(cgmath::vec2(x, y));
+        self
+    }
+    /// how grippy the surface is, see [`Object::get_surface_friction`]
+    pub fn friction(mut self, surface_friction: f64) -> Self {
+        self.object.surface_friction = surface_friction;
+        self
+    }
+    /// draws it with the material called `name`
+    pub fn material(mut self, name: impl Into<String>) -> Self {
+        self.object.material = Some(name.into());
+        self
+    }
+    /// attaches it to the object at index `parent`, to get carried along with it
+    pub fn parent(mut self, parent: usize) -> Self {
+        self.object.parent = Some(parent);
+        self
+    }
+    /// the fastest it's allowed to go, in place of the level's limit
+    pub fn max_speed(mut self, max_speed: f64) -> Self {
+        self.object.max_speed = Some(max_speed);
+        self
+    }
+    /// see [`Object::with_explosive`]
+    pub fn explosive(mut self, explosive: Explosive) -> Self {
+        self.object.explosive = Some(explosive);
+        self
+    }
+    /// see [`Object::with_health`]
+    pub fn health(mut self, health: f64) -> Self {
+        self.object.health = Some(health);
+        self
+    }
+    /// see [`Object::with_ribbon`]
+    pub fn ribbon(mut self, ribbon: Ribbon) -> Self {
+        self.object.ribbon = Some(ribbon);
+        self
+    }
+    /// see [`Object::with_lifetime`]
+    pub fn lifetime(mut self, seconds: f64) -> Self {
+        self.object.lifetime = Some(seconds);
+        self
+    }
+    /// see [`Object::with_tag`]
+    pub fn tag(mut self, tag: impl Into<String>) -> Self {
+        self.object = self.object.with_tag(tag);
+        self
+    }
+    /// sets the metadata under `key` to `value`
+    pub fn metadata(mut self, key: impl Into<String>, value: impl Into<MetaValue>) -> Self {
+        self.object.metadata.insert(key.into(), value.into());
+        self
+    }
+    /// the finished object
+    pub fn build(self) -> Object {
+        self.object
+    }
+}
@@ -0,0 +1,266 @@
+use std::cell::RefCell;
+use std::collections::{BTreeMap, BTreeSet};
+
+use serde::{Deserialize, Serialize};
+
+use super::{snapshot, Controller, GameState, Hook, Object, ObjectType};
+use crate::level::Chunking;
+
+/// the objects of a chunked level that are out of the world at the moment, see [`Chunking`]
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Chunks {
+    settings: Chunking,
+    /// the chunks things that move get to stay in the world in, things that don't get one more
+    /// chunk all around so nothing moving can get to where the floor is missing
+    near: BTreeSet<(i64, i64)>,
+    /// the objects out of the world, by the slot they go back into
+    #[serde(with = "snapshot::stored")]
+    stored: BTreeMap<usize, Object>,
+    /// which of the stored objects are in each chunk, one in more than one is in all of their lists
+    members: BTreeMap<(i64, i64), Vec<usize>>,
+}
+
+impl Chunks {
+    /// anything covering more chunks than this stays in the world, like a floor running the length
+    /// of the level
+    const MAX_CHUNKS: u64 = 64;
+
+    /// nothing out of the world yet, everything gets sorted out on the first update
+    pub fn new(settings: Chunking) -> Self {
+        Self {
+            settings,
+            near: BTreeSet::new(),
+            stored: BTreeMap::new(),
+            members: BTreeMap::new(),
+        }
+    }
+
+    /// the chunks from `min` to `max`, both included so things that only touch share a chunk, and
+    /// how many that is
+    fn range(
+        &self,
+        min: cgmath::Point2<f64>,
+        max: cgmath::Point2<f64>,
+    ) -> ((i64, i64), (i64, i64), u64) {
+        let chunk = |x: f64| (x / self.settings.size).floor() as i64;
+        let (from, to) = ((chunk(min.x), chunk(min.y)), (chunk(max.x), chunk(max.y)));
+        let span = |from: i64, to: i64| (to.saturating_sub(from) as u64).saturating_add(1);
+        let count = span(from.0, to.0).saturating_mul(span(from.1, to.1));
+        (from, to, count)
+    }
+
+    /// whether `object` belongs in the world with players near the chunks in `near`
+    fn keeps(&self, object: &Object, near: &BTreeSet<(i64, i64)>) -> bool {
+        let (from, to, count) = self.range(object.pos, object.pos + object.size);
+        if count > Self::MAX_CHUNKS {
+            return true;
+        }
+        let moves = matches!(object.ty, ObjectType::Movable { .. });
+        let (from, to) = match moves {
+            true => (from, to),
+            false => ((from.0 - 1, from.1 - 1), (to.0 + 1, to.1 + 1)),
+        };
+        between(from, to).any(|chunk| near.contains(&chunk))
+    }
+
+    /// takes `object` out of the world, to go back into `slot`
+    fn store(&mut self, slot: usize, object: Object) {
+        let (from, to, _) = self.range(object.pos, object.pos + object.size);
+        for chunk in between(from, to) {
+            self.members.entry(chunk).or_default().push(slot);
+        }
+        self.stored.insert(slot, object);
+    }
+
+    /// the object that goes back into `slot`, if there is one
+    fn take(&mut self, slot: usize) -> Option<Object> {
+        let object = self.stored.remove(&slot)?;
+        let (from, to, _) = self.range(object.pos, object.pos + object.size);
+        for chunk in between(from, to) {
+            if let Some(members) = self.members.get_mut(&chunk) {
+                members.retain(|member| *member != slot);
+                if members.is_empty() {
+                    self.members.remove(&chunk);
+                }
+            }
+        }
+        Some(object)
+    }
+}
+
+/// every chunk from `from` to `to`, both included
+fn between(from: (i64, i64), to: (i64, i64)) -> impl Iterator<Item = (i64, i64)> {
+    (from.0..=to.0).flat_map(move |x| (from.1..=to.1).map(move |y| (x, y)))
+}
+
+/// `chunks` along with every chunk next to one of them
+fn grow(chunks: &BTreeSet<(i64, i64)>) -> BTreeSet<(i64, i64)> {
+    chunks
+        .iter()
+        .flat_map(|&(x, y)| between((x - 1, y - 1), (x + 1, y + 1)))
+        .collect()
+}
+
+impl GameState {
+    /// puts back the objects in chunks players have come close to and takes out the ones in
+    /// chunks they've left behind, does nothing for levels that aren't chunked
+    pub(super) fn stream_chunks(&mut self) {
+        let Some(mut chunks) = self.chunks.take() else {
+            return;
+        };
+        let reach = cgmath::vec2(chunks.settings.radius, chunks.settings.radius);
+        let mut near = BTreeSet::new();
+        for index in self.player_indices().into_iter().chain([self.view_object]) {
+            if let Some(object) = self.objects.get(index) {
+                let center = object.borrow().center();
+                let (from, to, _) = chunks.range(center - reach, center + reach);
+                near.extend(between(from, to));
+            }
+        }
+        if near.is_empty() {
+            // with nobody around to follow, everything stays how it was
+            near = chunks.near.clone();
+        }
+        let changed = near != chunks.near;
+        if changed {
+            // only what's in a chunk that just got close can have a reason to come back
+            let entered = grow(&near)
+                .difference(&grow(&chunks.near))
+                .chain(near.difference(&chunks.near))
+                .copied()
+                .collect::<BTreeSet<_>>();
+            let returning = entered
+                .iter()
+                .filter_map(|chunk| chunks.members.get(chunk))
+                .flatten()
+                .copied()
+                .filter(|slot| chunks.keeps(&chunks.stored[slot], &near))
+                .collect::<BTreeSet<_>>();
+            for slot in returning {
+                if let Some(object) = chunks.take(slot) {
+                    self.objects.reserve_for(slot);
+                    self.objects.insert(slot, RefCell::new(object));
+                }
+            }
+        }
+        // things that don't move can only end up too far away by players moving off, but things
+        // that do can get there on their own
+        let leaving = self
+            .objects
+            .iter()
+            .filter(|(_, object)| {
+                let object = object.borrow();
+                (changed || matches!(object.ty, ObjectType::Movable { .. }))
+                    && !chunks.keeps(&object, &near)
+            })
+            .map(|(index, _)| index)
+            .collect::<Vec<_>>();
+        if !leaving.is_empty() {
+            let pinned = self.pinned_objects();
+            for index in leaving.into_iter().filter(|index| !pinned.contains(index)) {
+                if let Some(object) = self.objects.remove(index) {
+                    self.forget_contacts(index);
+                    chunks.store(index, object.into_inner());
+                }
+            }
+            self.invalidate_broadphase();
+        }
+        if changed {
+            self.invalidate_broadphase();
+        }
+        chunks.near = near;
+        self.chunks = Some(chunks);
+    }
+
+    /// the objects that have to stay in the world however far away they are, the players and
+    /// anything something else is holding on to or keeping track of
+    fn pinned_objects(&self) -> BTreeSet<usize> {
+        let mut pinned = self.player_indices().into_iter().collect::<BTreeSet<_>>();
+        pinned.insert(self.view_object);
+        for controller in &self.controllers {
+            match controller {
+                Controller::PlayerController(c) => {
+                    for hook in c.hooks() {
+                        if let Hook::Attached { object, .. } = hook {
+                            pinned.insert(*object);
+                        }
+                    }
+                }
+                Controller::TurretController(c) => {
+                    pinned.insert(c.turret.object);
+                }
+                Controller::PatrolController(c) => {
+                    pinned.insert(c.object);
+                }
+            }
+        }
+        pinned.extend(self.scripts.iter().map(|runner| runner.script.object));
+        pinned.extend(
+            self.ropes
+                .iter()
+                .flat_map(|rope| [rope.objects.0, rope.objects.1]),
+        );
+        pinned.extend(self.mouse_joint.iter().map(|joint| joint.object));
+        pinned.extend(&self.lit_explosives);
+        for (index, object) in &self.objects {
+            if let Some(parent) = object.borrow().parent {
+                pinned.extend([index, parent]);
+            }
+            if self.position_tweens.is_running(index) {
+                pinned.insert(index);
+            }
+        }
+        pinned
+    }
+
+    /// gets rid of the object waiting out of the world to go back into `slot`, returning whether
+    /// there was one, nothing else can be keeping track of it while it's away
+    pub(super) fn despawn_stored(&mut self, slot: usize) -> bool {
+        let taken = self
+            .chunks
+            .as_mut()
+            .is_some_and(|chunks| chunks.take(slot).is_some());
+        if taken {
+            self.free_slots.push(slot);
+        }
+        taken
+    }
+
+    /// moves object `index` to `pos` and stops it, wherever it is, one out of the world in a far
+    /// chunk comes back in if it's been moved near a player, returns whether there was an object
+    pub fn teleport_object(&mut self, index: usize, pos: cgmath::Point2<f64>) -> bool {
+        if let Some(object) = self.object_mut(index) {
+            object.teleport(pos);
+            return true;
+        }
+        let Some(chunks) = &mut self.chunks else {
+            return false;
+        };
+        let Some(mut object) = chunks.take(index) else {
+            return false;
+        };
+        object.teleport(pos);
+        if !chunks.keeps(&object, &chunks.near) {
+            chunks.store(index, object);
+            return true;
+        }
+        self.objects.reserve_for(index);
+        self.objects.insert(index, RefCell::new(object));
+        self.invalidate_broadphase();
+        true
+    }
+
+    /// one past the last slot an object out of the world goes back into, new objects go after it
+    pub(super) fn stored_end(&self) -> usize {
+        self.chunks
+            .as_ref()
+            .and_then(|chunks| chunks.stored.keys().next_back())
+            .map_or(0, |slot| slot + 1)
+    }
+
+    /// how many objects of a chunked level are out of the world, waiting for a player to come
+    /// close
+    pub fn stored_object_count(&self) -> usize {
+        self.chunks.as_ref().map_or(0, |chunks| chunks.stored.len())
+    }
+}
@@ -0,0 +1,68 @@
+use cgmath::prelude::*;
+
+use crate::level::GravityZone;
+
+/// which way and how hard things fall at each point of a level
+#[derive(Clone, Copy)]
+pub(super) struct Gravity<'a> {
+    /// the pull outside of the zones, along `down`
+    pub strength: f64,
+    /// which way things fall outside of the zones, one unit long
+    pub down: cgmath::Vector2<f64>,
+    pub zones: &'a [GravityZone],
+}
+
+impl Gravity<'_> {
+    fn zone_at(&self, point: cgmath::Point2<f64>) -> Option<&GravityZone> {
+        self.zones
+            .iter()
+            .rev()
+            .find(|zone| zone.region.contains(point))
+    }
+
+    /// the pull on something with its middle at `point`
+    pub fn at(&self, point: cgmath::Point2<f64>) -> cgmath::Vector2<f64> {
+        match self.zone_at(point) {
+            Some(zone) => zone.gravity,
+            None => self.down * self.strength,
+        }
+    }
+
+    /// which way is down at `point`, one unit long, or zero where nothing pulls at all
+    ///
+    /// outside of the zones it's `down` even if the strength is zero, so levels without gravity
+    /// still have a ground to stand on
+    pub fn down_at(&self, point: cgmath::Point2<f64>) -> cgmath::Vector2<f64> {
+        match self.zone_at(point) {
+            Some(zone) if zone.gravity.magnitude2() > 0.0 => zone.gravity.normalize(),
+            Some(_) => cgmath::vec2(0.0, 0.0),
+            None => self.down,
+        }
+    }
+}
+
+/// `velocity` as how fast it's going to the right and up for something that falls towards `down`
+///
+/// with `down` pointing straight down it comes out as exactly what went in, so levels with the
+/// usual gravity move the same as they always have
+pub(super) fn to_local(
+    velocity: cgmath::Vector2<f64>,
+    down: cgmath::Vector2<f64>,
+) -> cgmath::Vector2<f64> {
+    let (right, up) = axes(down);
+    cgmath::vec2(velocity.dot(right), velocity.dot(up))
+}
+
+/// turns what `to_local` gives back into a velocity in the world
+pub(super) fn from_local(
+    local: cgmath::Vector2<f64>,
+    down: cgmath::Vector2<f64>,
+) -> cgmath::Vector2<f64> {
+    let (right, up) = axes(down);
+    right * local.x + up * local.y
+}
+
+/// the directions right and up are for something that falls towards `down`
+pub(super) fn axes(down: cgmath::Vector2<f64>) -> (cgmath::Vector2<f64>, cgmath::Vector2<f64>) {
+    (cgmath::vec2(-down.y, down.x), -down)
+}
@@ -0,0 +1,271 @@
+use std::cell::RefCell;
+
+use cgmath::prelude::*;
+use serde::{Deserialize, Serialize};
+use stable_vec::StableVec;
+
+use super::{Gravity, Object, ObjectType};
+use crate::level::{HookLimits, Swing, Zipline};
+
+/// how fast the hook flies out
+const SPEED: f64 = 45.0;
+/// the hook gets pulled down a lot less than everything else, but it still arcs a bit
+const GRAVITY: f64 = 5.0;
+/// the rope can't get longer than this, the hook gets reeled back in if it flies further
+pub const MAX_LENGTH: f64 = 25.0;
+/// how close to a zipline's cable the hook has to get to catch on it
+const CATCH_DISTANCE: f64 = 0.4;
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub enum Hook {
+    #[default]
+    Idle,
+    Flying {
+        pos: cgmath::Point2<f64>,
+        velocity: cgmath::Vector2<f64>,
+    },
+    Attached {
+        object: usize,
+        /// where on the object the hook is stuck, relative to its position, so it moves along with it
+        offset: cgmath::Vector2<f64>,
+        length: f64,
+    },
+    /// caught on the zipline with this index, it slides along to stay as close to whoever threw
+    /// it as it can, which is what running freely along the cable does
+    Zipline {
+        zipline: usize,
+        /// where on the cable it is
+        anchor: cgmath::Point2<f64>,
+        length: f64,
+    },
+}
+
+impl Hook {
+    pub fn fire(from: cgmath::Point2<f64>, target: cgmath::Point2<f64>) -> Self {
+        let direction = target - from;
+        let direction = if direction.magnitude2() > 0.0 {
+            direction.normalize()
+        } else {
+            cgmath::vec2(0.0, 1.0)
+        };
+        Hook::Flying {
+            pos: from,
+            velocity: direction * SPEED,
+        }
+    }
+
+    /// where the end of the rope currently is
+    pub fn position(&self, objects: &StableVec<RefCell<Object>>) -> Option<cgmath::Point2<f64>> {
+        match self {
+            Hook::Idle => None,
+            Hook::Flying { pos, .. } => Some(*pos),
+            Hook::Attached { object, offset, .. } => {
+                objects.get(*object).map(|o| o.borrow().pos + offset)
+            }
+            Hook::Zipline { anchor, .. } => Some(*anchor),
+        }
+    }
+
+    /// whether it's stuck in something that can be hung from
+    pub fn is_attached(&self) -> bool {
+        matches!(self, Hook::Attached { .. } | Hook::Zipline { .. })
+    }
+
+    /// moves a hook on a zipline along to where its owner has pulled it, letting go if the
+    /// zipline or the owner are gone
+    fn slide(&mut self, owner: usize, objects: &StableVec<RefCell<Object>>, ziplines: &[Zipline]) {
+        if let Hook::Zipline {
+            zipline, anchor, ..
+        } = self
+        {
+            match (ziplines.get(*zipline), objects.get(owner)) {
+                (Some(zipline), Some(owner)) => {
+                    *anchor = zipline.closest(owner.borrow().center()).1
+                }
+                _ => *self = Hook::Idle,
+            }
+        }
+    }
+
+    /// moves a flying hook along, sticking it to the first object or zipline it hits, returns whether that happened this update
+    pub fn update(
+        &mut self,
+        owner: usize,
+        objects: &StableVec<RefCell<Object>>,
+        ziplines: &[Zipline],
+        gravity: &Gravity,
+        dt: f64,
+    ) -> bool {
+        match self {
+            Hook::Idle => {}
+            Hook::Flying { pos, velocity } => {
+                *velocity += gravity.down_at(*pos) * GRAVITY * dt;
+                *pos += *velocity * dt;
+                let origin = match objects.get(owner) {
+                    Some(owner) => owner.borrow().center(),
+                    None => {
+                        *self = Hook::Idle;
+                        return false;
+                    }
+                };
+                let hit = objects.iter().find(|(index, object)| {
+                    *index != owner && {
+                        let object = object.borrow();
+                        pos.x >= object.pos.x
+                            && pos.x <= object.pos.x + object.size.x
+                            && pos.y >= object.pos.y
+                            && pos.y <= object.pos.y + object.size.y
+                    }
+                });
+                if let Some((index, object)) = hit {
+                    let length = (*pos - origin).magnitude();
+                    *self = Hook::Attached {
+                        object: index,
+                        offset: *pos - object.borrow().pos,
+                        length,
+                    };
+                    return true;
+                }
+                let caught = ziplines.iter().enumerate().find_map(|(index, zipline)| {
+                    let (_, closest) = zipline.closest(*pos);
+                    (closest.distance(*pos) <= CATCH_DISTANCE).then_some((index, closest))
+                });
+                if let Some((zipline, anchor)) = caught {
+                    *self = Hook::Zipline {
+                        zipline,
+                        anchor,
+                        length: (anchor - origin).magnitude(),
+                    };
+                    return true;
+                } else if (*pos - origin).magnitude() > MAX_LENGTH {
+                    *self = Hook::Idle;
+                }
+            }
+            Hook::Attached { object, .. } => {
+                if objects.get(*object).is_none() {
+                    *self = Hook::Idle;
+                }
+            }
+            Hook::Zipline { .. } => self.slide(owner, objects, ziplines),
+        }
+        false
+    }
+
+    /// keeps the owner from getting further away from the anchor than the rope allows, the way
+    /// `swing` says, returns how much speed away from the anchor the rope took away
+    pub fn constrain(
+        &mut self,
+        owner: usize,
+        objects: &StableVec<RefCell<Object>>,
+        ziplines: &[Zipline],
+        swing: &Swing,
+    ) -> f64 {
+        self.slide(owner, objects, ziplines);
+        if let Hook::Attached { length, .. } | Hook::Zipline { length, .. } = *self {
+            let anchor = match self.position(objects) {
+                Some(anchor) => anchor,
+                None => return 0.0,
+            };
+            if let Some(owner) = objects.get(owner) {
+                let mut owner = owner.borrow_mut();
+                let from_anchor = owner.center() - anchor;
+                let distance = from_anchor.magnitude();
+                if distance > length {
+                    let direction = from_anchor / distance;
+                    owner.pos -= direction * ((distance - length) * swing.stiffness);
+                    if let ObjectType::Movable { velocity, .. } = &mut owner.ty {
+                        let outwards = velocity.dot(direction);
+                        if outwards > 0.0 {
+                            // what isn't soaked up comes back the other way
+                            let caught = outwards * swing.stiffness;
+                            let stopped = caught * (2.0 - swing.damping);
+                            *velocity -= direction * stopped;
+                            if swing.momentum > 0.0 {
+                                let along = *velocity - direction * velocity.dot(direction);
+                                if along.magnitude2() > 0.0 {
+                                    let kept = caught * swing.damping * swing.momentum;
+                                    *velocity += along.normalize_to(kept);
+                                }
+                            }
+                            return stopped;
+                        }
+                    }
+                }
+            }
+        }
+        0.0
+    }
+}
+
+/// what a player has left of their hook, under the level's `HookLimits`
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct HookCharge {
+    /// how long until the hook can be fired again, in seconds
+    pub cooldown: f64,
+    /// how many more times it can be fired, None if there's no limit
+    pub uses: Option<u32>,
+    /// how much longer the player can hang from it, in seconds, None if there's no limit
+    pub stamina: Option<f64>,
+}
+
+impl HookCharge {
+    /// everything there is to have under `limits`
+    pub(super) fn full(limits: &HookLimits) -> Self {
+        Self {
+            cooldown: 0.0,
+            uses: limits.uses,
+            stamina: limits.stamina,
+        }
+    }
+
+    /// whether the hook can go out right now
+    pub fn ready(&self) -> bool {
+        self.cooldown <= 0.0 && self.uses != Some(0)
+    }
+
+    pub(super) fn fired(&mut self, limits: &HookLimits) {
+        self.cooldown = limits.cooldown.unwrap_or(0.0);
+        if let Some(uses) = &mut self.uses {
+            *uses = uses.saturating_sub(1);
+        }
+    }
+
+    /// counts down the cooldown and uses up or gets back stamina, returns true once there's no
+    /// stamina left for hanging on
+    pub(super) fn update(
+        &mut self,
+        limits: &HookLimits,
+        hanging: bool,
+        standing: bool,
+        dt: f64,
+    ) -> bool {
+        self.cooldown = (self.cooldown - dt).max(0.0);
+        let (stamina, max) = match (&mut self.stamina, limits.stamina) {
+            (Some(stamina), Some(max)) => (stamina, max),
+            _ => return false,
+        };
+        if hanging {
+            *stamina = (*stamina - dt).max(0.0);
+            return *stamina == 0.0;
+        }
+        if standing {
+            *stamina = (*stamina + limits.stamina_recovery * dt).min(max);
+        }
+        false
+    }
+
+    /// fills the uses and stamina back up, the cooldown keeps going
+    pub(super) fn refill(&mut self, limits: &HookLimits) {
+        self.uses = limits.uses;
+        self.stamina = limits.stamina;
+    }
+}
+
+/// where a hook fired right now would go, and how the player would swing on it afterwards
+#[derive(Clone, Debug, Default)]
+pub struct Trajectory {
+    /// where the hook flies, up to where it would stick
+    pub hook_path: Vec<cgmath::Point2<f64>>,
+    /// where the player would swing afterwards
+    pub swing_path: Vec<cgmath::Point2<f64>>,
+}
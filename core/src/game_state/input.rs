@@ -0,0 +1,76 @@
+use std::collections::HashSet;
+use std::hash::Hash;
+
+use serde::{Deserialize, Serialize};
+
+use super::ButtonState;
+
+/// which buttons are down, along with which went down or came up since the last update, so a
+/// fresh press can be told apart from a button that's being held
+///
+/// pressing a button that's already down changes nothing, which is what keys the OS repeats
+/// while they're held look like
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Buttons<B: Eq + Hash> {
+    held: HashSet<B>,
+    pressed: HashSet<B>,
+    released: HashSet<B>,
+}
+
+impl<B: Eq + Hash> Default for Buttons<B> {
+    fn default() -> Self {
+        Self {
+            held: HashSet::new(),
+            pressed: HashSet::new(),
+            released: HashSet::new(),
+        }
+    }
+}
+
+impl<B: Copy + Eq + Hash> Buttons<B> {
+    /// `button` went down or came up, returns false if that's what it was doing already
+    pub fn set(&mut self, button: B, state: ButtonState) -> bool {
+        match state {
+            ButtonState::Pressed => {
+                let changed = self.held.insert(button);
+                if changed {
+                    self.pressed.insert(button);
+                }
+                changed
+            }
+            ButtonState::Released => {
+                let changed = self.held.remove(&button);
+                if changed {
+                    self.released.insert(button);
+                }
+                changed
+            }
+        }
+    }
+
+    /// whether `button` went down since the last update, even if it's already come back up
+    pub fn pressed(&self, button: B) -> bool {
+        self.pressed.contains(&button)
+    }
+
+    /// whether `button` is down
+    pub fn held(&self, button: B) -> bool {
+        self.held.contains(&button)
+    }
+
+    /// whether `button` came up since the last update
+    pub fn released(&self, button: B) -> bool {
+        self.released.contains(&button)
+    }
+
+    /// lets go of what changed during this update, what's held stays held
+    pub fn end_tick(&mut self) {
+        self.pressed.clear();
+        self.released.clear();
+    }
+
+    /// lets go of everything, like after the window stops getting key presses
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+}
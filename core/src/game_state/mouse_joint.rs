@@ -0,0 +1,48 @@
+use std::cell::RefCell;
+
+use cgmath::prelude::*;
+use serde::{Deserialize, Serialize};
+use stable_vec::StableVec;
+
+use super::{Object, ObjectType};
+
+/// how hard the spring pulls towards the cursor, per unit of stretch
+const STIFFNESS: f64 = 300.0;
+/// how much of the speed along the way gets eaten up, so it settles instead of wobbling forever
+const DAMPING: f64 = 25.0;
+/// the fastest the joint will move something, so a fast flick doesn't tunnel through walls
+const MAX_SPEED: f64 = 60.0;
+
+/// a spring from a point on an object to the cursor, for dragging things around while debugging
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct MouseJoint {
+    /// the object being dragged
+    pub object: usize,
+    /// where on the object it was grabbed, relative to its position, so it doesn't jump to the cursor
+    pub offset: cgmath::Vector2<f64>,
+    /// where the cursor is in the world
+    pub target: cgmath::Point2<f64>,
+}
+
+impl MouseJoint {
+    /// where the spring is attached on the object right now
+    pub fn anchor(&self, objects: &StableVec<RefCell<Object>>) -> Option<cgmath::Point2<f64>> {
+        Some(*objects.get(self.object)?.borrow().get_pos() + self.offset)
+    }
+
+    /// pulls the object towards the target, before everything moves
+    pub fn update(&self, objects: &StableVec<RefCell<Object>>, dt: f64) {
+        let mut object = match objects.get(self.object) {
+            Some(object) => object.borrow_mut(),
+            None => return,
+        };
+        let anchor = object.pos + self.offset;
+        if let ObjectType::Movable { velocity, .. } = &mut object.ty {
+            let acceleration = (self.target - anchor) * STIFFNESS - *velocity * DAMPING;
+            *velocity += acceleration * dt;
+            if velocity.magnitude() > MAX_SPEED {
+                *velocity = velocity.normalize_to(MAX_SPEED);
+            }
+        }
+    }
+}
@@ -0,0 +1,99 @@
+use std::cell::RefCell;
+
+use cgmath::prelude::*;
+use serde::{Deserialize, Serialize};
+use stable_vec::StableVec;
+
+use super::{GameEvent, Gravity, Object, ObjectType};
+use crate::level::PatrolPath;
+
+/// moves an object along a path, stopping at the points that say to, movable objects get their
+/// velocity set so they carry whatever is on them, anything else just gets moved
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PatrolController {
+    /// the object following the path
+    pub object: usize,
+    path: PatrolPath,
+    /// the point it's on the way to, by index
+    next: usize,
+    /// whether it's on the way back to the start, for paths that aren't looped
+    backwards: bool,
+    /// seconds left until it sets off again
+    wait: f64,
+}
+
+impl PatrolController {
+    pub fn new(object: usize, path: PatrolPath) -> Self {
+        Self {
+            object,
+            path,
+            next: 0,
+            backwards: false,
+            wait: 0.0,
+        }
+    }
+
+    pub fn update(
+        &mut self,
+        objects: &StableVec<RefCell<Object>>,
+        events: &mut Vec<GameEvent>,
+        gravity: &Gravity,
+        dt: f64,
+    ) {
+        let mut object = match objects.get(self.object) {
+            Some(object) => object.borrow_mut(),
+            None => return,
+        };
+        let mut wanted = cgmath::vec2(0.0, 0.0);
+        if self.wait > 0.0 {
+            self.wait -= dt;
+        } else {
+            let point = self.path.points[self.next];
+            let speed = point.speed.unwrap_or(self.path.speed);
+            let to_point = point.pos - object.pos;
+            if to_point.magnitude() <= speed * dt {
+                wanted = to_point / dt;
+                self.wait = point.wait;
+                events.push(GameEvent::WaypointReached {
+                    object: self.object,
+                    point: self.next,
+                });
+                self.advance();
+            } else {
+                wanted = to_point.normalize_to(speed);
+            }
+        }
+        let object = &mut *object;
+        let center = object.center();
+        match &mut object.ty {
+            ObjectType::Movable { velocity, .. } => {
+                *velocity = wanted;
+                // gravity is about to get added on again
+                if !object.no_clip {
+                    *velocity -= gravity.at(center) * dt;
+                }
+            }
+            _ => object.pos += wanted * dt,
+        }
+    }
+
+    /// picks the point to head for after the one it just got to
+    fn advance(&mut self) {
+        let last = self.path.points.len() - 1;
+        if self.path.looped {
+            self.next = if self.next == last { 0 } else { self.next + 1 };
+        } else if self.backwards {
+            if self.next == 0 {
+                self.backwards = false;
+                self.next = last.min(1);
+            } else {
+                self.next -= 1;
+            }
+        } else if self.next == last {
+            self.backwards = true;
+            self.next = last.saturating_sub(1);
+        } else {
+            self.next += 1;
+        }
+    }
+}
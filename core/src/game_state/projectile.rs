@@ -0,0 +1,153 @@
+use std::cell::RefCell;
+
+use serde::{Deserialize, Serialize};
+use stable_vec::StableVec;
+
+use super::{first_hit, Gravity, Object};
+
+/// a small fast shot, moved along its own path instead of going through the collision solver, it
+/// never pushes anything and goes away as soon as it hits something
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct Projectile {
+    /// the middle of the shot
+    pub pos: cgmath::Point2<f64>,
+    /// in units per second
+    pub velocity: cgmath::Vector2<f64>,
+    /// how big it is, it hits things once its edge touches them
+    pub radius: f64,
+    /// how much of the level's gravity pulls it down, 0 for shots that fly straight
+    pub gravity_scale: f64,
+    /// handed to whatever it hits, in `GameEvent::ProjectileHit`
+    pub damage: f64,
+    /// seconds left until it fizzles out without hitting anything
+    pub lifetime: f64,
+    /// the object that fired it, it doesn't hit that one so it can get out of it
+    pub owner: Option<usize>,
+}
+
+/// how a step went for one projectile
+enum Step {
+    Flying,
+    /// it ran into `object` at `at`
+    Hit {
+        object: usize,
+        at: cgmath::Point2<f64>,
+    },
+    Expired,
+}
+
+impl Projectile {
+    /// moves it along by `dt`, stopping where it first touches something on the way
+    fn step(&mut self, objects: &StableVec<RefCell<Object>>, gravity: &Gravity, dt: f64) -> Step {
+        self.lifetime -= dt;
+        if self.lifetime <= 0.0 {
+            return Step::Expired;
+        }
+        self.velocity += gravity.at(self.pos) * self.gravity_scale * dt;
+        let delta = self.velocity * dt;
+        let hit = first_hit(objects, self.pos, delta, self.radius, |index, object| {
+            Some(index) == self.owner || object.no_clip
+        });
+        match hit {
+            Some((object, fraction)) => {
+                self.pos += delta * fraction;
+                Step::Hit {
+                    object,
+                    at: self.pos,
+                }
+            }
+            None => {
+                self.pos += delta;
+                Step::Flying
+            }
+        }
+    }
+}
+
+/// every projectile in flight, slots of ones that are gone get reused so firing lots of them
+/// doesn't keep growing the list
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct Projectiles {
+    #[serde(with = "super::snapshot::slots")]
+    flying: StableVec<Projectile>,
+    free_slots: Vec<usize>,
+}
+
+/// something a projectile did during an update
+pub enum Outcome {
+    /// the projectile in slot `projectile` hit `object` at `at`, and is gone now
+    Hit {
+        projectile: usize,
+        object: usize,
+        at: cgmath::Point2<f64>,
+        damage: f64,
+    },
+}
+
+impl Projectiles {
+    /// puts a projectile in flight, returning the slot it went in
+    pub fn spawn(&mut self, projectile: Projectile) -> usize {
+        match self.free_slots.pop() {
+            Some(slot) => {
+                self.flying.insert(slot, projectile);
+                slot
+            }
+            None => self.flying.push(projectile),
+        }
+    }
+
+    fn remove(&mut self, slot: usize) {
+        if self.flying.remove(slot).is_some() {
+            self.free_slots.push(slot);
+        }
+    }
+
+    /// every projectile still flying, along with its slot
+    pub fn iter(&self) -> impl Iterator<Item = (usize, &Projectile)> {
+        self.flying.iter()
+    }
+
+    /// the projectile in `slot`, if there is one flying in it
+    pub fn get(&self, slot: usize) -> Option<&Projectile> {
+        self.flying.get(slot)
+    }
+
+    /// moves everything along, taking out what hit something or ran out of time
+    pub fn update(
+        &mut self,
+        objects: &StableVec<RefCell<Object>>,
+        gravity: &Gravity,
+        dt: f64,
+    ) -> Vec<Outcome> {
+        let mut outcomes = vec![];
+        let mut gone = vec![];
+        for (slot, projectile) in self.flying.iter_mut() {
+            match projectile.step(objects, gravity, dt) {
+                Step::Flying => {}
+                Step::Hit { object, at } => {
+                    outcomes.push(Outcome::Hit {
+                        projectile: slot,
+                        object,
+                        at,
+                        damage: projectile.damage,
+                    });
+                    gone.push(slot);
+                }
+                Step::Expired => gone.push(slot),
+            }
+        }
+        for slot in gone {
+            self.remove(slot);
+        }
+        outcomes
+    }
+
+    /// forgets about `object` as the owner of anything, for when it goes away
+    pub fn forget_owner(&mut self, object: usize) {
+        for (_, projectile) in self.flying.iter_mut() {
+            if projectile.owner == Some(object) {
+                projectile.owner = None;
+            }
+        }
+    }
+}
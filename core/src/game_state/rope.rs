@@ -0,0 +1,60 @@
+use std::cell::RefCell;
+
+use cgmath::prelude::*;
+use serde::{Deserialize, Serialize};
+use stable_vec::StableVec;
+
+use super::Object;
+
+/// a rope tying the middles of two objects together, it goes slack when they're closer than
+/// `length` and stops them getting any further apart than that
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Rope {
+    /// the objects on either end
+    pub objects: (usize, usize),
+    /// how long it is
+    pub length: f64,
+}
+
+impl Rope {
+    /// where the ends are, if both objects are still around
+    pub fn ends(
+        &self,
+        objects: &StableVec<RefCell<Object>>,
+    ) -> Option<(cgmath::Point2<f64>, cgmath::Point2<f64>)> {
+        let a = objects.get(self.objects.0)?.borrow().center();
+        let b = objects.get(self.objects.1)?.borrow().center();
+        Some((a, b))
+    }
+
+    /// pulls the ends back together if they got too far apart, the lighter one gets pulled more
+    pub fn constrain(&self, objects: &StableVec<RefCell<Object>>) {
+        let (mut a, mut b) = match (objects.get(self.objects.0), objects.get(self.objects.1)) {
+            (Some(a), Some(b)) => (a.borrow_mut(), b.borrow_mut()),
+            _ => return,
+        };
+        let offset = b.center() - a.center();
+        let distance = offset.magnitude();
+        let inverse_mass = a.inverse_mass() + b.inverse_mass();
+        if distance <= self.length || inverse_mass == 0.0 {
+            return;
+        }
+        let direction = offset / distance;
+        let stretch = distance - self.length;
+        let (share_a, share_b) = (
+            a.inverse_mass() / inverse_mass,
+            b.inverse_mass() / inverse_mass,
+        );
+        a.pos += direction * stretch * share_a;
+        b.pos -= direction * stretch * share_b;
+        // and they stop moving apart, without stopping them swinging around each other
+        let separating = (b.get_velocity() - a.get_velocity()).dot(direction);
+        if separating > 0.0 {
+            let impulse = separating / inverse_mass;
+            let push_a = direction * impulse * a.inverse_mass();
+            let push_b = -direction * impulse * b.inverse_mass();
+            a.apply_push(push_a);
+            b.apply_push(push_b);
+        }
+    }
+}
@@ -0,0 +1,161 @@
+use std::cell::RefCell;
+use std::f64::consts::TAU;
+
+use cgmath::prelude::*;
+use serde::{Deserialize, Serialize};
+use stable_vec::StableVec;
+
+use super::{Object, Projectile};
+use crate::level::{Attack, Script};
+use crate::tween::Easing;
+
+/// how big scripted shots are
+const SHOT_RADIUS: f64 = 0.2;
+/// how long scripted shots fly for before fizzling out, in seconds
+const SHOT_LIFETIME: f64 = 4.0;
+
+/// something a script wants done to the world, which the game state takes care of
+pub enum Action {
+    /// shoot this
+    Fire(Projectile),
+    /// slide over to `to`
+    Move {
+        to: cgmath::Point2<f64>,
+        seconds: f64,
+        easing: Easing,
+    },
+    /// it moved on to the phase with this index
+    PhaseChanged(usize),
+}
+
+/// keeps track of where a scripted object is in its script
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ScriptRunner {
+    /// the script it's running
+    pub script: Script,
+    /// the phase it's in, by index
+    pub phase: usize,
+    /// how much health it started out with, for showing how much is left
+    pub full_health: Option<f64>,
+    /// the attack it does next, by index into the phase's attacks
+    attack: usize,
+    /// seconds until it does the next attack
+    wait: f64,
+}
+
+impl ScriptRunner {
+    pub fn new(script: Script, objects: &StableVec<RefCell<Object>>) -> Self {
+        let full_health = objects
+            .get(script.object)
+            .and_then(|object| object.borrow().health);
+        Self {
+            script,
+            phase: 0,
+            full_health,
+            attack: 0,
+            wait: 0.0,
+        }
+    }
+
+    /// moves the script along by `dt`, returning what it did
+    pub fn update(
+        &mut self,
+        objects: &StableVec<RefCell<Object>>,
+        players: &[usize],
+        dt: f64,
+    ) -> Vec<Action> {
+        let mut actions = vec![];
+        let (center, health) = match objects.get(self.script.object) {
+            Some(object) => {
+                let object = object.borrow();
+                (object.center(), object.health)
+            }
+            None => return actions,
+        };
+        if let Some(health) = health {
+            while self
+                .script
+                .phases
+                .get(self.phase + 1)
+                .is_some_and(|next| health <= next.below_health)
+            {
+                self.phase += 1;
+                self.attack = 0;
+                self.wait = 0.0;
+                actions.push(Action::PhaseChanged(self.phase));
+            }
+        }
+        let attacks = &self.script.phases[self.phase].attacks;
+        self.wait -= dt;
+        // going round the whole list at most once, in case none of them take any time
+        for _ in 0..attacks.len() {
+            if self.wait > 0.0 {
+                break;
+            }
+            let attack = attacks[self.attack];
+            self.attack = (self.attack + 1) % attacks.len();
+            match attack {
+                Attack::Wait { seconds } => self.wait += seconds,
+                Attack::Move {
+                    to,
+                    seconds,
+                    easing,
+                } => {
+                    actions.push(Action::Move {
+                        to,
+                        seconds,
+                        easing,
+                    });
+                    self.wait += seconds;
+                }
+                Attack::Aimed {
+                    count,
+                    spread,
+                    speed,
+                    damage,
+                } => {
+                    let target = players
+                        .iter()
+                        .filter_map(|&player| Some(objects.get(player)?.borrow().center()))
+                        .min_by(|a, b| a.distance2(center).total_cmp(&b.distance2(center)));
+                    if let Some(target) = target {
+                        let to_target = target - center;
+                        let aim = to_target.y.atan2(to_target.x);
+                        for shot in 0..count {
+                            // spread evenly over `spread`, centered on the target
+                            let off = if count > 1 {
+                                spread * (shot as f64 / (count - 1) as f64 - 0.5)
+                            } else {
+                                0.0
+                            };
+                            actions.push(self.shot(center, aim + off, speed, damage));
+                        }
+                    }
+                }
+                Attack::Ring {
+                    count,
+                    speed,
+                    damage,
+                } => {
+                    for shot in 0..count {
+                        let angle = TAU * shot as f64 / count as f64;
+                        actions.push(self.shot(center, angle, speed, damage));
+                    }
+                }
+            }
+        }
+        actions
+    }
+
+    fn shot(&self, from: cgmath::Point2<f64>, angle: f64, speed: f64, damage: f64) -> Action {
+        Action::Fire(Projectile {
+            pos: from,
+            velocity: cgmath::vec2(angle.cos(), angle.sin()) * speed,
+            radius: SHOT_RADIUS,
+            gravity_scale: 0.0,
+            damage,
+            lifetime: SHOT_LIFETIME,
+            owner: Some(self.script.object),
+        })
+    }
+}
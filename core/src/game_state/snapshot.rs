@@ -0,0 +1,138 @@
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap};
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use stable_vec::StableVec;
+
+use super::{Direction, Object};
+
+/// for `StableVec`s, written out slot by slot with the empty ones left in, so everything keeps its
+/// index
+pub mod slots {
+    use super::*;
+
+    pub fn serialize<T: Serialize, S: Serializer>(
+        vec: &StableVec<T>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        (0..vec.next_push_index())
+            .map(|index| vec.get(index))
+            .collect::<Vec<_>>()
+            .serialize(serializer)
+    }
+
+    pub fn deserialize<'de, T: Deserialize<'de>, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<StableVec<T>, D::Error> {
+        Ok(from_slots(Vec::deserialize(deserializer)?))
+    }
+}
+
+/// for the objects of a game, which also need what level files leave out, like what they're
+/// touching
+pub mod objects {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(
+        objects: &StableVec<RefCell<Object>>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        (0..objects.next_push_index())
+            .map(|index| {
+                objects
+                    .get(index)
+                    .map(|object| SavedObject::from(object.borrow().clone()))
+            })
+            .collect::<Vec<_>>()
+            .serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<StableVec<RefCell<Object>>, D::Error> {
+        let slots = Vec::<Option<SavedObject>>::deserialize(deserializer)?;
+        Ok(from_slots(
+            slots
+                .into_iter()
+                .map(|slot| slot.map(|saved| RefCell::new(saved.into())))
+                .collect(),
+        ))
+    }
+}
+
+/// for objects kept out of the world by the slot they go back into, which need the same as the
+/// ones in it
+pub mod stored {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(
+        objects: &BTreeMap<usize, Object>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        objects
+            .iter()
+            .map(|(slot, object)| (*slot, SavedObject::from(object.clone())))
+            .collect::<Vec<_>>()
+            .serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<BTreeMap<usize, Object>, D::Error> {
+        let objects = Vec::<(usize, SavedObject)>::deserialize(deserializer)?;
+        Ok(objects
+            .into_iter()
+            .map(|(slot, saved)| (slot, saved.into()))
+            .collect())
+    }
+}
+
+fn from_slots<T>(slots: Vec<Option<T>>) -> StableVec<T> {
+    // empty slots at the end don't get put back, they're all in the game's free slots so they get
+    // filled before anything new goes on the end, or held by objects out of the world in a chunked
+    // level which new things get put after, and the capacity is there for them
+    let mut vec = StableVec::with_capacity(slots.len());
+    for (index, slot) in slots.into_iter().enumerate() {
+        if let Some(item) = slot {
+            vec.insert(index, item);
+        }
+    }
+    vec
+}
+
+/// an object along with the parts of it that only matter while the game is running
+#[derive(Serialize, Deserialize)]
+struct SavedObject {
+    object: Object,
+    touching: HashMap<usize, Direction>,
+    squeezed_for: u32,
+    spawned_at: u64,
+    no_clip: bool,
+    offset_from_parent: cgmath::Vector2<f64>,
+}
+
+impl From<Object> for SavedObject {
+    fn from(object: Object) -> Self {
+        Self {
+            touching: object.touching.clone(),
+            squeezed_for: object.squeezed_for,
+            spawned_at: object.spawned_at,
+            no_clip: object.no_clip,
+            offset_from_parent: object.offset_from_parent,
+            object,
+        }
+    }
+}
+
+impl From<SavedObject> for Object {
+    fn from(saved: SavedObject) -> Self {
+        Self {
+            touching: saved.touching,
+            squeezed_for: saved.squeezed_for,
+            spawned_at: saved.spawned_at,
+            no_clip: saved.no_clip,
+            offset_from_parent: saved.offset_from_parent,
+            ..saved.object
+        }
+    }
+}
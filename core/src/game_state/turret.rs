@@ -0,0 +1,112 @@
+use std::cell::RefCell;
+use std::f64::consts::{PI, TAU};
+
+use cgmath::prelude::*;
+use serde::{Deserialize, Serialize};
+use stable_vec::StableVec;
+
+use super::projectile::Projectiles;
+use super::{first_hit, GameEvent, Object, Projectile};
+use crate::level::Turret;
+use crate::rng::Rng;
+
+/// how far off the target it can be pointing and still take the shot, in radians
+const AIM_TOLERANCE: f64 = 0.02;
+/// how big its shots are
+const SHOT_RADIUS: f64 = 0.15;
+/// how much further than its range its shots get to fly before fizzling out
+const SHOT_REACH: f64 = 1.5;
+
+/// turns a turret towards the closest player it can see, and shoots once it's lined up
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TurretController {
+    /// how the level set it up
+    pub turret: Turret,
+    /// which way it's pointing, in radians counterclockwise from the right
+    pub aim: f64,
+    /// the player it has in its sights, if it can see any
+    pub target: Option<usize>,
+    /// seconds until it can shoot again
+    reload: f64,
+}
+
+impl TurretController {
+    pub fn new(turret: Turret) -> Self {
+        Self {
+            turret,
+            aim: 0.0,
+            target: None,
+            reload: 0.0,
+        }
+    }
+
+    pub fn update(
+        &mut self,
+        objects: &StableVec<RefCell<Object>>,
+        players: &[usize],
+        projectiles: &mut Projectiles,
+        events: &mut Vec<GameEvent>,
+        rng: &mut Rng,
+        dt: f64,
+    ) {
+        self.reload = (self.reload - dt).max(0.0);
+        let center = match objects.get(self.turret.object) {
+            Some(object) => object.borrow().center(),
+            None => return,
+        };
+        self.target = self.closest_visible(objects, players, center);
+        let target = match self.target.and_then(|target| objects.get(target)) {
+            Some(target) => target.borrow().center(),
+            None => return,
+        };
+        let to_target = target - center;
+        let wanted = to_target.y.atan2(to_target.x);
+        // the short way round, from -pi to pi
+        let off = (wanted - self.aim + PI).rem_euclid(TAU) - PI;
+        let turn = self.turret.turn_speed * dt;
+        self.aim = (self.aim + off.clamp(-turn, turn) + PI).rem_euclid(TAU) - PI;
+        if (off.abs() - turn).max(0.0) > AIM_TOLERANCE || self.reload > 0.0 {
+            return;
+        }
+        let angle = self.aim + rng.range(-1.0, 1.0) * self.turret.spread;
+        let direction = cgmath::vec2(angle.cos(), angle.sin());
+        let projectile = projectiles.spawn(Projectile {
+            pos: center,
+            velocity: direction * self.turret.shot_speed,
+            radius: SHOT_RADIUS,
+            gravity_scale: 0.0,
+            damage: self.turret.damage,
+            lifetime: self.turret.range / self.turret.shot_speed * SHOT_REACH,
+            owner: Some(self.turret.object),
+        });
+        events.push(GameEvent::TurretFired {
+            object: self.turret.object,
+            projectile,
+        });
+        self.reload = self.turret.fire_interval;
+    }
+
+    /// the closest player in range that nothing is in the way of
+    fn closest_visible(
+        &self,
+        objects: &StableVec<RefCell<Object>>,
+        players: &[usize],
+        center: cgmath::Point2<f64>,
+    ) -> Option<usize> {
+        players
+            .iter()
+            .filter_map(|&player| {
+                let to_player = objects.get(player)?.borrow().center() - center;
+                let distance = to_player.magnitude();
+                if distance > self.turret.range {
+                    return None;
+                }
+                let (seen, _) = first_hit(objects, center, to_player, 0.0, |index, object| {
+                    index == self.turret.object || object.no_clip
+                })?;
+                (seen == player).then_some((player, distance))
+            })
+            .min_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map(|(player, _)| player)
+    }
+}
@@ -0,0 +1,93 @@
+use std::cell::Ref;
+use std::ops::Deref;
+
+use super::{Controller, GameState, Object};
+use crate::level::Region;
+
+/// a read only look at one of the objects in a game state, along with the index it goes by, the
+/// object stays borrowed for as long as this is around
+pub struct ObjectView<'a> {
+    id: usize,
+    object: Ref<'a, Object>,
+}
+
+impl ObjectView<'_> {
+    /// the index the object goes by, stays the same for as long as it's around
+    pub fn id(&self) -> usize {
+        self.id
+    }
+}
+
+impl Deref for ObjectView<'_> {
+    type Target = Object;
+
+    fn deref(&self) -> &Object {
+        &self.object
+    }
+}
+
+impl GameState {
+    /// object `id`, if it's still around
+    pub fn object(&self, id: usize) -> Option<ObjectView<'_>> {
+        self.objects.get(id).map(|object| ObjectView {
+            id,
+            object: object.borrow(),
+        })
+    }
+    /// object `id` to change, if it's still around, for editors and cheats
+    pub fn object_mut(&mut self, id: usize) -> Option<&mut Object> {
+        self.invalidate_broadphase();
+        self.objects.get_mut(id).map(|object| object.get_mut())
+    }
+    /// every object, in the order they get drawn in
+    pub fn iter_objects(&self) -> impl Iterator<Item = ObjectView<'_>> {
+        self.objects.iter().map(|(id, object)| ObjectView {
+            id,
+            object: object.borrow(),
+        })
+    }
+    /// how many objects there are
+    pub fn object_count(&self) -> usize {
+        self.objects.num_elements()
+    }
+    /// the indices of the objects overlapping `region`, touching its edge counts, in order
+    pub fn query_region(&self, region: &Region) -> impl Iterator<Item = usize> + '_ {
+        let (min, max) = (region.pos, region.pos + region.size);
+        let candidates = self.broadphase().query(min, max);
+        candidates.into_iter().filter(move |&id| {
+            self.objects.get(id).is_some_and(|object| {
+                let (pos, size) = (object.borrow().pos, object.borrow().size);
+                pos.x <= max.x
+                    && pos.x + size.x >= min.x
+                    && pos.y <= max.y
+                    && pos.y + size.y >= min.y
+            })
+        })
+    }
+    /// the objects overlapping `region`, see `query_region`
+    pub fn objects_in_aabb(&self, region: &Region) -> impl Iterator<Item = ObjectView<'_>> + '_ {
+        self.query_region(region).filter_map(|id| self.object(id))
+    }
+    /// the first object tagged `tag`, by index, if any in the world are, objects waiting out
+    /// of the world in a chunked level aren't found until they come back into it
+    pub fn find_by_tag(&self, tag: &str) -> Option<usize> {
+        self.find_all_by_tag(tag).next()
+    }
+    /// every object in the world tagged `tag`, in order
+    pub fn find_all_by_tag<'a>(&'a self, tag: &'a str) -> impl Iterator<Item = usize> + 'a {
+        self.objects
+            .iter()
+            .filter(move |(_, object)| object.borrow().has_tag(tag))
+            .map(|(id, _)| id)
+    }
+    /// the object controlled by the player, if there is one
+    pub fn player(&self) -> Option<ObjectView<'_>> {
+        let id = self
+            .controllers
+            .iter()
+            .filter_map(Controller::as_player)
+            .map(|controller| controller.controlled_object)
+            .find(|id| self.objects.get(*id).is_some())?;
+        self.object(id)
+    }
+}
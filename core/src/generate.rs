@@ -0,0 +1,318 @@
+use cgmath::prelude::*;
+
+use crate::game_state::{GameState, Object};
+use crate::level::{Level, Region};
+use crate::rng::Rng;
+
+/// one swing of a generated course, from the end of one platform across a gap to the next
+#[derive(Clone, Copy, Debug)]
+pub struct Swing {
+    /// where the middle of the player is as they step off the end of the platform
+    pub takeoff: cgmath::Point2<f64>,
+    /// the middle of the block the hook goes into
+    pub anchor: cgmath::Point2<f64>,
+    /// the platform on the other side of the gap
+    pub landing: Region,
+}
+
+impl Swing {
+    /// how far the player's middle is from the anchor the whole way round
+    pub fn rope(&self) -> f64 {
+        self.anchor.distance(self.takeoff)
+    }
+
+    /// where the player's middle is at the top of the swing on the far side
+    pub fn release(&self) -> cgmath::Point2<f64> {
+        cgmath::point2(2.0 * self.anchor.x - self.takeoff.x, self.takeoff.y)
+    }
+
+    /// how high the player's middle is when it's at `x` on the way round
+    fn arc_at(&self, x: f64) -> f64 {
+        let across = (x - self.anchor.x).min(self.rope());
+        self.anchor.y - (self.rope().powi(2) - across.powi(2)).max(0.0).sqrt()
+    }
+
+    /// whether a player can get from the takeoff to the landing by hanging from the anchor and
+    /// letting go at the top of the swing, without any help from running or reeling in
+    pub fn reachable(&self) -> Result<(), String> {
+        let half = Course::PLAYER_SIZE / 2.0;
+        let top = self.landing.pos.y + self.landing.size.y;
+        let (right, release) = (self.landing.pos.x + self.landing.size.x, self.release());
+        if self.rope() > GameState::HOOK_RANGE {
+            return Err(format!(
+                "the anchor at {:?} is {} away, out of the hook's range",
+                self.anchor,
+                self.rope()
+            ));
+        }
+        if self.anchor.y <= self.takeoff.y {
+            return Err(format!(
+                "the anchor at {:?} isn't above the takeoff",
+                self.anchor
+            ));
+        }
+        if release.x < self.landing.pos.x || release.x > right {
+            return Err(format!(
+                "the swing ends at {:?}, which isn't over the landing",
+                release
+            ));
+        }
+        // the lowest the player gets while over the landing, which is the bottom of the swing if
+        // the landing starts before it, or where the player first gets over the landing if not
+        let lowest = self.arc_at((self.landing.pos.x - half).max(self.anchor.x));
+        if lowest - half < top {
+            return Err(format!(
+                "the swing runs into the side of the landing at {:?}",
+                self.landing.pos
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// a level made up on the spot out of platforms with gaps between them, each with a block above
+/// the gap to swing across from
+pub struct Course {
+    /// the course, ready to play
+    pub level: Level,
+    /// how to get across each gap, in order
+    pub swings: Vec<Swing>,
+}
+
+impl Course {
+    const SWINGS: usize = 8;
+    const PLAYER_SIZE: f64 = 1.0;
+    /// ropes any shorter and the player would hit the anchor block, any longer and the hook can
+    /// only just get there from standing still
+    const MIN_ROPE: f64 = 4.0;
+    const MAX_ROPE: f64 = GameState::HOOK_RANGE * 0.6;
+    /// how far from straight up the anchor is, in radians, steep enough that there's a swing
+    /// worth having and shallow enough that the player doesn't hit the edge of the platform
+    const MIN_ANGLE: f64 = 0.45;
+    const MAX_ANGLE: f64 = 1.0;
+    /// how far below the player's path over it the landing goes, for some room to spare
+    const MIN_DROP: f64 = 0.5;
+    const MAX_DROP: f64 = 3.0;
+    const PLATFORM_THICKNESS: f64 = 1.0;
+    const ANCHOR_SIZE: f64 = 1.0;
+    /// tries at a swing before giving up on the seed
+    const ATTEMPTS: usize = 100;
+
+    /// the name generated levels go by, for keeping their stats and ghosts apart
+    pub fn name(seed: u64) -> String {
+        format!("generated-{}", seed)
+    }
+
+    /// a course that comes out the same every time for the same seed
+    pub fn generate(seed: u64) -> color_eyre::Result<Self> {
+        let mut rng = Rng::new(seed);
+        let half = Self::PLAYER_SIZE / 2.0;
+        let start = Region {
+            pos: cgmath::point2(-8.0, -Self::PLATFORM_THICKNESS),
+            size: cgmath::vec2(8.0, Self::PLATFORM_THICKNESS),
+        };
+        let mut objects = vec![
+            Object::movable()
+                .at(start.pos.x + 1.0, 0.0)
+                .size(Self::PLAYER_SIZE, Self::PLAYER_SIZE)
+                .material("player")
+                .build(),
+            platform(&start),
+        ];
+        let mut swings: Vec<Swing> = vec![];
+        let mut last = start;
+        for i in 0..Self::SWINGS {
+            let takeoff = cgmath::point2(
+                last.pos.x + last.size.x + half,
+                last.pos.y + last.size.y + half,
+            );
+            let swing = (0..Self::ATTEMPTS)
+                .map(|_| Self::swing(&mut rng, takeoff))
+                .find(|swing| swing.reachable().is_ok())
+                .ok_or_else(|| {
+                    color_eyre::eyre::eyre!(
+                        "couldn't find a reachable swing {} for seed {}",
+                        i,
+                        seed
+                    )
+                })?;
+            objects.push(
+                Object::fixed()
+                    .at(
+                        swing.anchor.x - Self::ANCHOR_SIZE / 2.0,
+                        swing.anchor.y - Self::ANCHOR_SIZE / 2.0,
+                    )
+                    .size(Self::ANCHOR_SIZE, Self::ANCHOR_SIZE)
+                    .build(),
+            );
+            objects.push(platform(&swing.landing));
+            last = swing.landing;
+            swings.push(swing);
+        }
+        let level = Level {
+            version: Level::VERSION,
+            objects,
+            player: 0,
+            palette: Default::default(),
+            lighting: None,
+            goal: Some(Region {
+                pos: cgmath::point2(last.pos.x + last.size.x - 3.0, last.pos.y + last.size.y),
+                size: cgmath::vec2(3.0, 3.0),
+            }),
+            triggers: vec![],
+            intro: None,
+            bounds: None,
+            out_of_bounds: Default::default(),
+            movement: Default::default(),
+            turrets: vec![],
+            scripts: vec![],
+            paths: vec![],
+            patrols: vec![],
+            chunking: None,
+            exits: vec![],
+            entrances: vec![],
+            gravity: None,
+            gravity_zones: vec![],
+            hook_limits: Default::default(),
+            checkpoints: vec![],
+            dual_hooks: false,
+            ziplines: vec![],
+            tutorials: vec![],
+            prefabs: Default::default(),
+            placed: vec![],
+            seed,
+        };
+        Ok(Self { level, swings })
+    }
+
+    /// a random swing from `takeoff`, built to be reachable, though `Swing::reachable` gets the
+    /// last word
+    fn swing(rng: &mut Rng, takeoff: cgmath::Point2<f64>) -> Swing {
+        let half = Self::PLAYER_SIZE / 2.0;
+        let rope = rng.range(Self::MIN_ROPE, Self::MAX_ROPE);
+        let angle = rng.range(Self::MIN_ANGLE, Self::MAX_ANGLE);
+        let reach = rope * angle.sin();
+        let anchor = takeoff + cgmath::vec2(reach, rope * angle.cos());
+        let mut swing = Swing {
+            takeoff,
+            anchor,
+            landing: Region {
+                pos: cgmath::point2(anchor.x + reach * rng.range(0.2, 0.7), 0.0),
+                size: cgmath::vec2(0.0, Self::PLATFORM_THICKNESS),
+            },
+        };
+        let lowest = swing.arc_at((swing.landing.pos.x - half).max(anchor.x));
+        let top = lowest - half - rng.range(Self::MIN_DROP, Self::MAX_DROP);
+        swing.landing.pos.y = top - Self::PLATFORM_THICKNESS;
+        swing.landing.size.x = swing.release().x - swing.landing.pos.x + rng.range(3.0, 8.0);
+        swing
+    }
+}
+
+fn platform(region: &Region) -> Object {
+    Object::fixed()
+        .at(region.pos.x, region.pos.y)
+        .size(region.size.x, region.size.y)
+        .build()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game_state::{ButtonState, Direction, Event};
+
+    fn run(state: &mut GameState, button: ButtonState) {
+        state.submit_player_event(Event::Keyboard {
+            button: Direction::Right,
+            state: button,
+        });
+    }
+
+    fn click(state: &mut GameState, target: cgmath::Point2<f64>) {
+        for button in [ButtonState::Pressed, ButtonState::Released] {
+            state.submit_player_event(Event::Hook {
+                state: button,
+                target,
+            });
+        }
+    }
+
+    /// runs off the end of each platform with the hook in the anchor, and lets go and stops
+    /// running once over the next one, returning how many swings it got through
+    fn play_through(course: &Course) -> (usize, GameState) {
+        let mut state = GameState::from_level(&course.level);
+        let mut landed = 0;
+        run(&mut state, ButtonState::Pressed);
+        'swings: for swing in &course.swings {
+            let (landing, top) = (swing.landing, swing.landing.pos.y + swing.landing.size.y);
+            let (mut hooked, mut let_go) = (false, false);
+            for _ in 0..600 {
+                state.update(1.0 / 60.0);
+                let player = state.object(0).unwrap().center();
+                if !hooked && player.x >= swing.takeoff.x - 0.8 {
+                    click(&mut state, swing.anchor);
+                    hooked = true;
+                }
+                if hooked && !let_go && player.x >= landing.pos.x + 0.5 && player.y - 0.5 > top {
+                    click(&mut state, swing.anchor);
+                    run(&mut state, ButtonState::Released);
+                    let_go = true;
+                }
+                let over = player.x > landing.pos.x && player.x < landing.pos.x + landing.size.x;
+                if let_go && over && (player.y - 0.5 - top).abs() < 0.05 {
+                    landed += 1;
+                    run(&mut state, ButtonState::Pressed);
+                    continue 'swings;
+                }
+            }
+            break;
+        }
+        (landed, state)
+    }
+
+    #[test]
+    fn every_swing_is_reachable() {
+        for seed in 0..200 {
+            let course = Course::generate(seed).unwrap();
+            assert_eq!(course.swings.len(), Course::SWINGS);
+            for swing in &course.swings {
+                assert_eq!(swing.reachable(), Ok(()), "seed {}", seed);
+            }
+            let problems = course.level.validate();
+            assert!(problems.is_empty(), "seed {}: {:?}", seed, problems);
+        }
+    }
+
+    #[test]
+    fn courses_can_be_played_through() {
+        for seed in 0..10 {
+            let course = Course::generate(seed).unwrap();
+            let (landed, mut state) = play_through(&course);
+            assert_eq!(landed, course.swings.len(), "seed {}", seed);
+            for _ in 0..300 {
+                state.update(1.0 / 60.0);
+            }
+            assert!(state.completed_at.is_some(), "seed {}", seed);
+        }
+    }
+
+    #[test]
+    fn same_seed_same_course() {
+        let course = |seed| ron::to_string(&Course::generate(seed).unwrap().level).unwrap();
+        assert_eq!(course(42), course(42));
+        assert_ne!(course(42), course(43));
+    }
+
+    #[test]
+    fn anchors_out_of_range_are_unreachable() {
+        let swing = Swing {
+            takeoff: cgmath::point2(0.0, 0.0),
+            anchor: cgmath::point2(20.0, 20.0),
+            landing: Region {
+                pos: cgmath::point2(30.0, -5.0),
+                size: cgmath::vec2(20.0, 1.0),
+            },
+        };
+        assert!(swing.reachable().unwrap_err().contains("range"));
+    }
+}
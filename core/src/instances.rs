@@ -0,0 +1,675 @@
+use std::ops::{Add, Mul, Range};
+
+use cgmath::prelude::*;
+use itertools::Itertools;
+
+use crate::game_state::{GameState, ObjectType, Trajectory};
+use crate::level::{PatrolPath, Region};
+
+/// the colour objects get drawn in, before the palette
+pub const OBJECT_COLOR: [f32; 4] = [0.0, 0.0, 1.0, 1.0];
+/// objects that blow up, so they stand out from the rest
+pub const EXPLOSIVE_COLOR: [f32; 4] = [1.0, 0.3, 0.1, 1.0];
+/// the barrels turrets shoot out of
+pub const TURRET_COLOR: [f32; 4] = [0.6, 0.6, 0.65, 1.0];
+/// how long turret barrels stick out from the middle of the turret
+pub const TURRET_BARREL_LENGTH: f32 = 1.0;
+/// what's left of a health bar
+pub const HEALTH_COLOR: [f32; 4] = [0.9, 0.15, 0.1, 0.9];
+/// behind the health bar, showing how much has been lost
+pub const HEALTH_BACK_COLOR: [f32; 4] = [0.1, 0.1, 0.1, 0.7];
+/// shots flying through the level
+pub const PROJECTILE_COLOR: [f32; 4] = [1.0, 0.2, 0.2, 1.0];
+/// the rope between the player and the hook
+pub const ROPE_COLOR: [f32; 4] = [0.8, 0.7, 0.5, 1.0];
+/// what the rope fades to as it gets close to snapping
+pub const STRAINED_ROPE_COLOR: [f32; 4] = [1.0, 0.15, 0.1, 1.0];
+/// the goal region
+pub const GOAL_COLOR: [f32; 4] = [0.2, 1.0, 0.3, 0.3];
+/// the cables of ziplines, and the posts at their ends
+pub const ZIPLINE_COLOR: [f32; 4] = [0.6, 0.6, 0.65, 1.0];
+/// the areas that fill the hook back up
+pub const CHECKPOINT_COLOR: [f32; 4] = [0.3, 0.6, 1.0, 0.25];
+/// the ghost of the best run
+pub const GHOST_COLOR: [f32; 4] = [1.0, 1.0, 1.0, 0.35];
+/// the outline around the object picked for debugging
+pub const SELECTED_COLOR: [f32; 4] = [1.0, 0.9, 0.3, 1.0];
+/// the spring pulling a grabbed object to the cursor
+pub const GRAB_COLOR: [f32; 4] = [1.0, 0.4, 0.8, 0.8];
+/// how wide the stripes scrolling along treadmills are, with gaps just as wide between them
+pub const TREADMILL_STRIPE_WIDTH: f32 = 0.4;
+/// the routes things in the level follow, while editing
+pub const PATROL_PATH_COLOR: [f32; 4] = [0.3, 0.9, 1.0, 0.6];
+/// the marker where a hook is stuck, that the player swings around
+pub const ANCHOR_COLOR: [f32; 4] = [1.0, 0.95, 0.8, 1.0];
+/// the crosshair where the hook would stick if it were fired now
+pub const RETICLE_COLOR: [f32; 4] = [1.0, 1.0, 1.0, 0.8];
+/// how far the arms of the crosshair are from its middle
+const RETICLE_RADIUS: f32 = 0.5;
+/// the dots showing where the hook would fly
+pub const HOOK_PATH_COLOR: [f32; 4] = [1.0, 1.0, 1.0, 0.6];
+/// the dots showing where the player would swing
+pub const SWING_PATH_COLOR: [f32; 4] = [1.0, 0.8, 0.2, 0.4];
+
+/// the colours the kinds of things in a level get drawn in, before the palette, so they can be
+/// swapped for ones that are easier to tell apart
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CategoryColors {
+    /// everything solid, which is everything the hook sticks to
+    pub solid: [f32; 4],
+    /// objects that blow up
+    pub hazard: [f32; 4],
+    /// shots flying through the level
+    pub projectile: [f32; 4],
+    /// the stripes scroll along these on top of the colour
+    pub treadmill: [f32; 4],
+    /// the goal region, and the exits to other levels
+    pub goal: [f32; 4],
+}
+
+impl Default for CategoryColors {
+    fn default() -> Self {
+        Self {
+            solid: OBJECT_COLOR,
+            hazard: EXPLOSIVE_COLOR,
+            projectile: PROJECTILE_COLOR,
+            treadmill: OBJECT_COLOR,
+            goal: GOAL_COLOR,
+        }
+    }
+}
+
+/// how an instance gets mixed with what's already been drawn under it
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Blend {
+    /// covers up everything under it
+    Opaque,
+    /// lets what's under it show through, so it gets drawn after everything opaque
+    Translucent,
+}
+
+/// instances that get drawn together, with the same material and blending
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DrawRun<'a> {
+    /// which instances, by index into `FrameInstances::instances`
+    pub instances: Range<u32>,
+    /// the name of the material, none for the plain look
+    pub material: Option<&'a str>,
+    /// how the instances get mixed in
+    pub blend: Blend,
+}
+
+/// everything that gets drawn as a quad in one frame, worked out on the cpu before being uploaded
+#[derive(Clone, Debug, Default)]
+pub struct FrameInstances {
+    /// the interpolated [x, y, width, height] of every object, in the order of `GameState::iter_objects`
+    pub draw_position: Vec<[f32; 4]>,
+    /// one quad per instance, see `instance`
+    pub instances: Vec<[f32; 16]>,
+    /// instances before this are part of the world and get lit, the rest is drawn on top
+    pub world_count: u32,
+    /// the instance each object is drawn with, in the same order as `draw_position`, None for the
+    /// ones that were out of view
+    pub object_instances: Vec<Option<u32>>,
+    /// where each run of world instances sharing a material starts, and the name of the material,
+    /// none for the plain look, a run goes until the next one starts or the world ends
+    pub materials: Vec<(u32, Option<String>)>,
+}
+
+impl FrameInstances {
+    /// works out the quads for a frame `interpolate` of the way from `last_state` to `state`,
+    /// leaving out objects that aren't anywhere near `view`, they still cast shadows
+    pub fn prepare(
+        interpolate: f64,
+        state: &GameState,
+        last_state: &GameState,
+        ghost: Option<[f64; 4]>,
+        trajectory: Option<&Trajectory>,
+        colors: &CategoryColors,
+        view: Option<&Region>,
+    ) -> Self {
+        let mut draw_position = Vec::with_capacity(state.object_count());
+        for new_object in state.iter_objects() {
+            // something that was just spawned into a reused slot has nothing to interpolate from
+            let last_object = last_state
+                .object(new_object.id())
+                .filter(|o| o.spawned_at() == new_object.spawned_at());
+            if let Some(last_object) = last_object {
+                let pos = lerp(
+                    last_object.get_pos().to_vec(),
+                    new_object.get_pos().to_vec(),
+                    interpolate,
+                );
+                let size = lerp(*last_object.get_size(), *new_object.get_size(), interpolate);
+                draw_position.push([pos.x as f32, pos.y as f32, size.x as f32, size.y as f32]);
+            } else {
+                let pos = new_object.get_pos().to_vec();
+                let size = new_object.get_size();
+                draw_position.push([pos.x as f32, pos.y as f32, size.x as f32, size.y as f32]);
+            }
+        }
+        let mut instances = Vec::with_capacity(
+            draw_position.len() + state.exits.len() + state.checkpoints.len() + 1,
+        );
+        if let Some(goal) = &state.goal {
+            let (pos, size) = (
+                goal.pos.cast::<f32>().unwrap(),
+                goal.size.cast::<f32>().unwrap(),
+            );
+            instances.push(instance([pos.x, pos.y, size.x, size.y], colors.goal));
+        }
+        // exits are goals too, just ones that lead somewhere else
+        for exit in &state.exits {
+            let (pos, size) = (
+                exit.region.pos.cast::<f32>().unwrap(),
+                exit.region.size.cast::<f32>().unwrap(),
+            );
+            instances.push(instance([pos.x, pos.y, size.x, size.y], colors.goal));
+        }
+        for checkpoint in &state.checkpoints {
+            let (pos, size) = (
+                checkpoint.pos.cast::<f32>().unwrap(),
+                checkpoint.size.cast::<f32>().unwrap(),
+            );
+            instances.push(instance([pos.x, pos.y, size.x, size.y], CHECKPOINT_COLOR));
+        }
+        let time = lerp(last_state.time, state.time, interpolate);
+        let mut materials = vec![(0, None)];
+        let visible = view.map(|view| state.query_region(view).collect::<Vec<_>>());
+        let mut object_instances = Vec::with_capacity(draw_position.len());
+        for (&position, object) in draw_position.iter().zip(state.iter_objects()) {
+            if visible
+                .as_ref()
+                .is_some_and(|visible| visible.binary_search(&object.id()).is_err())
+            {
+                object_instances.push(None);
+                continue;
+            }
+            object_instances.push(Some(instances.len() as u32));
+            set_material(
+                &mut materials,
+                instances.len() as u32,
+                object.get_material(),
+            );
+            instances.push(match object.get_type() {
+                ObjectType::Treadmill { fake_velocity } => {
+                    // the pattern repeats every stripe and gap along both axes, so this keeps the
+                    // numbers small enough for f32 no matter how long the level has been running
+                    let period = 2.0 * TREADMILL_STRIPE_WIDTH as f64;
+                    let scroll = (fake_velocity * time).map(|x| x.rem_euclid(period) as f32);
+                    with_stripes(
+                        instance(position, colors.treadmill),
+                        scroll.into(),
+                        TREADMILL_STRIPE_WIDTH,
+                    )
+                }
+                _ if object.get_explosive().is_some() => instance(position, colors.hazard),
+                _ => instance(position, colors.solid),
+            });
+        }
+        set_material(&mut materials, instances.len() as u32, None);
+        for (center, aim) in state.turret_aims() {
+            let (center, aim) = (center.cast::<f32>().unwrap(), aim as f32);
+            // the quad turns around its own middle, so that goes half a barrel out from the turret
+            let middle = center + cgmath::vec2(aim.cos(), aim.sin()) * TURRET_BARREL_LENGTH / 2.0;
+            let (length, width) = (TURRET_BARREL_LENGTH, 0.25);
+            instances.push(with_angle(
+                instance(
+                    [
+                        middle.x - length / 2.0,
+                        middle.y - width / 2.0,
+                        length,
+                        width,
+                    ],
+                    TURRET_COLOR,
+                ),
+                aim,
+            ));
+        }
+        for (object, which, anchor) in state.hook_anchors() {
+            // a hook stuck in something moving moves with it, smoothly like the thing does
+            let last = last_state
+                .hook_anchors()
+                .into_iter()
+                .find(|&(last_object, last_which, _)| (last_object, last_which) == (object, which))
+                .map(|(_, _, last)| last);
+            let anchor = match last {
+                Some(last) => {
+                    cgmath::Point2::from_vec(lerp(last.to_vec(), anchor.to_vec(), interpolate))
+                }
+                None => anchor,
+            };
+            let anchor = anchor.cast::<f32>().unwrap();
+            let size = 0.45;
+            instances.push(with_angle(
+                instance(
+                    [anchor.x - size / 2.0, anchor.y - size / 2.0, size, size],
+                    ANCHOR_COLOR,
+                ),
+                std::f32::consts::FRAC_PI_4,
+            ));
+        }
+        let world_count = instances.len() as u32;
+        if let Some(ghost) = ghost {
+            let [x, y, w, h] = ghost;
+            instances.push(instance(
+                [x as f32, y as f32, w as f32, h as f32],
+                GHOST_COLOR,
+            ));
+        }
+        for (index, health) in state.script_health() {
+            let position = draw_order(state, index);
+            if let Some(&[x, y, w, h]) = position.and_then(|position| draw_position.get(position)) {
+                let (bottom, height) = (y + h + 0.3, 0.2);
+                instances.push(instance([x, bottom, w, height], HEALTH_BACK_COLOR));
+                instances.push(instance(
+                    [x, bottom, w * health as f32, height],
+                    HEALTH_COLOR,
+                ));
+            }
+        }
+        for zipline in &state.ziplines {
+            let points = zipline
+                .points()
+                .into_iter()
+                .map(|point| point.cast::<f32>().unwrap())
+                .collect::<Vec<_>>();
+            for ends in points.windows(2) {
+                push_dotted_line(&mut instances, ends[0], ends[1], 0.2, 0.12, ZIPLINE_COLOR);
+            }
+            for end in [points[0], points[points.len() - 1]] {
+                push_dot(&mut instances, end, 0.5, ZIPLINE_COLOR);
+            }
+        }
+        for (from, to) in state.rope_lines() {
+            let (from, to) = (from.cast::<f32>().unwrap(), to.cast::<f32>().unwrap());
+            push_dotted_line(&mut instances, from, to, 0.3, 0.15, ROPE_COLOR);
+        }
+        for (from, to, tension) in state.hook_ropes() {
+            let (from, to) = (from.cast::<f32>().unwrap(), to.cast::<f32>().unwrap());
+            // ropes that can snap thin out and redden as they get close to it
+            let strain = match state.hook_limits.max_tension {
+                Some(max_tension) => (tension / max_tension).clamp(0.0, 1.0) as f32,
+                None => 0.0,
+            };
+            let mut color = ROPE_COLOR;
+            for (channel, strained) in color.iter_mut().zip(STRAINED_ROPE_COLOR) {
+                *channel += (strained - *channel) * strain;
+            }
+            let (spacing, size) = (0.4 * (1.0 + strain), 0.15 * (1.0 - 0.4 * strain));
+            push_dotted_line(&mut instances, from, to, spacing, size, color);
+            push_dot(&mut instances, to, 0.4, ROPE_COLOR);
+        }
+        for (slot, projectile) in state.projectiles() {
+            // a slot that got reused since the last tick would have it jump across the level
+            let pos = match last_state
+                .projectile(slot)
+                .filter(|last| last.owner == projectile.owner)
+            {
+                Some(last) => lerp(last.pos.to_vec(), projectile.pos.to_vec(), interpolate),
+                None => projectile.pos.to_vec(),
+            };
+            push_dot(
+                &mut instances,
+                cgmath::point2(pos.x as f32, pos.y as f32),
+                2.0 * projectile.radius as f32,
+                colors.projectile,
+            );
+        }
+        if let Some((from, to)) = state.grab_line() {
+            let (from, to) = (from.cast::<f32>().unwrap(), to.cast::<f32>().unwrap());
+            push_dotted_line(&mut instances, from, to, 0.2, 0.2, GRAB_COLOR);
+            push_dot(&mut instances, from, 0.3, GRAB_COLOR);
+        }
+        if let Some(trajectory) = trajectory {
+            for point in trajectory.hook_path.iter().step_by(2) {
+                push_dot(&mut instances, point.cast().unwrap(), 0.15, HOOK_PATH_COLOR);
+            }
+            for point in trajectory.swing_path.iter().step_by(3) {
+                push_dot(
+                    &mut instances,
+                    point.cast().unwrap(),
+                    0.25,
+                    SWING_PATH_COLOR,
+                );
+            }
+        }
+        Self {
+            draw_position,
+            instances,
+            world_count,
+            object_instances,
+            materials,
+        }
+    }
+
+    /// how many objects were left out for being out of view
+    pub fn culled(&self) -> usize {
+        self.object_instances
+            .iter()
+            .filter(|instance| instance.is_none())
+            .count()
+    }
+
+    /// where object `index` gets drawn, `state` has to be the one this got prepared from
+    pub fn draw_position_of(&self, state: &GameState, index: usize) -> Option<[f32; 4]> {
+        let position = draw_order(state, index)?;
+        self.draw_position.get(position).copied()
+    }
+
+    /// the instance object `index` is drawn with, if it's in view
+    fn instance_of(&mut self, state: &GameState, index: usize) -> Option<&mut [f32; 16]> {
+        let instance = (*self.object_instances.get(draw_order(state, index)?)?)?;
+        self.instances.get_mut(instance as usize)
+    }
+
+    /// draws a frame around object `index` on top of everything, `state` has to be the one this
+    /// got prepared from
+    pub fn outline(&mut self, state: &GameState, index: usize, thickness: f32, color: [f32; 4]) {
+        let [x, y, w, h] = match self.draw_position_of(state, index) {
+            Some(position) => position,
+            None => return,
+        };
+        let t = thickness;
+        self.instances.extend([
+            instance([x - t, y - t, w + 2.0 * t, t], color),
+            instance([x - t, y + h, w + 2.0 * t, t], color),
+            instance([x - t, y, t, h], color),
+            instance([x + w, y, t, h], color),
+        ]);
+    }
+
+    /// draws object `index` with `frame` out of its material's sprite atlas, as [u, v, width, height]
+    /// in texture coordinates, `state` has to be the one this got prepared from
+    pub fn set_sprite(&mut self, state: &GameState, index: usize, frame: [f32; 4]) {
+        if let Some(instance) = self.instance_of(state, index) {
+            instance[11..15].copy_from_slice(&frame);
+        }
+    }
+
+    /// leaves object `index` out of the picture, for when something else gets drawn in its place,
+    /// it still casts shadows
+    pub fn hide(&mut self, state: &GameState, index: usize) {
+        if let Some(instance) = self.instance_of(state, index) {
+            instance[2..4].copy_from_slice(&[0.0, 0.0]);
+        }
+    }
+
+    /// draws the routes of `paths` on top of everything, with a bigger dot on each point
+    pub fn paths(&mut self, paths: &[PatrolPath]) {
+        for path in paths {
+            let points = path
+                .points
+                .iter()
+                .map(|point| point.pos.cast::<f32>().unwrap())
+                .collect::<Vec<_>>();
+            for (from, to) in points.iter().tuple_windows() {
+                push_dotted_line(&mut self.instances, *from, *to, 0.5, 0.1, PATROL_PATH_COLOR);
+            }
+            if let (true, Some(first), Some(last)) = (path.looped, points.first(), points.last()) {
+                push_dotted_line(
+                    &mut self.instances,
+                    *last,
+                    *first,
+                    0.5,
+                    0.1,
+                    PATROL_PATH_COLOR,
+                );
+            }
+            for point in points {
+                push_dot(&mut self.instances, point, 0.3, PATROL_PATH_COLOR);
+            }
+        }
+    }
+
+    /// the world split into runs to draw, everything opaque first and then everything that can
+    /// be seen through in the order it was added, `translucent` says which materials can be seen
+    /// through no matter what colour they're drawn in
+    pub fn world_runs(&self, translucent: impl Fn(&str) -> bool) -> Vec<DrawRun<'_>> {
+        let (mut opaque, mut see_through) = (vec![], vec![]);
+        for (i, (start, material)) in self.materials.iter().enumerate() {
+            let end = self
+                .materials
+                .get(i + 1)
+                .map_or(self.world_count, |(end, _)| *end);
+            let material = material.as_deref();
+            if material.is_some_and(&translucent) {
+                see_through.push(DrawRun {
+                    instances: *start..end,
+                    material,
+                    blend: Blend::Translucent,
+                });
+                continue;
+            }
+            for (blend, indices) in
+                &(*start..end).group_by(|&i| blend_of(&self.instances[i as usize]))
+            {
+                let indices = indices.collect::<Vec<_>>();
+                let run = DrawRun {
+                    instances: indices[0]..indices[indices.len() - 1] + 1,
+                    material,
+                    blend,
+                };
+                match blend {
+                    Blend::Opaque => opaque.push(run),
+                    Blend::Translucent => see_through.push(run),
+                }
+            }
+        }
+        opaque.extend(see_through);
+        opaque
+    }
+
+    /// a crosshair at `at`, for where the hook would stick, drawn with the world
+    pub fn reticle(&mut self, at: cgmath::Point2<f64>) {
+        let at = at.cast::<f32>().unwrap();
+        let (length, width) = (0.3, 0.08);
+        let arms = [
+            (1.0, 0.0, length, width),
+            (-1.0, 0.0, length, width),
+            (0.0, 1.0, width, length),
+            (0.0, -1.0, width, length),
+        ];
+        let mut reticle = arms
+            .into_iter()
+            .map(|(x, y, w, h)| {
+                let middle = at + cgmath::vec2(x, y) * (RETICLE_RADIUS - length / 2.0);
+                instance(
+                    [middle.x - w / 2.0, middle.y - h / 2.0, w, h],
+                    RETICLE_COLOR,
+                )
+            })
+            .collect::<Vec<_>>();
+        reticle.push(instance(
+            [at.x - width / 2.0, at.y - width / 2.0, width, width],
+            RETICLE_COLOR,
+        ));
+        self.push_world(reticle);
+    }
+
+    /// adds quads to the end of the world, so they get lit along with everything else
+    pub fn push_world(&mut self, world: impl IntoIterator<Item = [f32; 16]>) {
+        let world = world.into_iter().collect::<Vec<_>>();
+        let count = world.len() as u32;
+        let start = self.world_count as usize;
+        self.instances.splice(start..start, world);
+        self.world_count += count;
+    }
+}
+
+/// where object `index` comes among the objects that get drawn, which is where its quad is
+fn draw_order(state: &GameState, index: usize) -> Option<usize> {
+    state.iter_objects().position(|object| object.id() == index)
+}
+
+/// starts a new run of `material` at instance `start`, unless the last run already uses it
+fn set_material(materials: &mut Vec<(u32, Option<String>)>, start: u32, material: Option<&str>) {
+    match materials.last_mut() {
+        Some((_, last)) if last.as_deref() == material => {}
+        // the last run is empty, so it can just be taken over
+        Some((last_start, last)) if *last_start == start => *last = material.map(str::to_owned),
+        _ => materials.push((start, material.map(str::to_owned))),
+    }
+}
+
+/// whether anything under `instance` shows through its colour
+pub fn blend_of(instance: &[f32; 16]) -> Blend {
+    if instance[7] < 1.0 {
+        Blend::Translucent
+    } else {
+        Blend::Opaque
+    }
+}
+
+/// one instance of the quad, [x, y, width, height], an rgba color, then the stripes, the sprite
+/// frame and the rotation, which are left off here
+pub fn instance(position: [f32; 4], color: [f32; 4]) -> [f32; 16] {
+    let [x, y, w, h] = position;
+    let [r, g, b, a] = color;
+    [
+        x, y, w, h, r, g, b, a, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0,
+    ]
+}
+
+/// turns `instance` by `angle` radians counterclockwise around its middle
+pub fn with_angle(instance: [f32; 16], angle: f32) -> [f32; 16] {
+    let mut instance = instance;
+    instance[15] = angle;
+    instance
+}
+
+/// draws diagonal stripes `width` wide across `instance`, shifted by `scroll` so they can move
+pub fn with_stripes(instance: [f32; 16], scroll: [f32; 2], width: f32) -> [f32; 16] {
+    let mut instance = instance;
+    instance[8..11].copy_from_slice(&[scroll[0], scroll[1], width]);
+    instance
+}
+
+fn push_dot(
+    instances: &mut Vec<[f32; 16]>,
+    center: cgmath::Point2<f32>,
+    size: f32,
+    color: [f32; 4],
+) {
+    let corner = center - cgmath::vec2(size, size) / 2.0;
+    instances.push(instance([corner.x, corner.y, size, size], color));
+}
+
+fn push_dotted_line(
+    instances: &mut Vec<[f32; 16]>,
+    from: cgmath::Point2<f32>,
+    to: cgmath::Point2<f32>,
+    spacing: f32,
+    size: f32,
+    color: [f32; 4],
+) {
+    let steps = ((to - from).magnitude() / spacing).ceil() as usize;
+    for step in 0..steps {
+        push_dot(
+            instances,
+            from + (to - from) * (step as f32 / steps as f32),
+            size,
+            color,
+        );
+    }
+}
+
+/// blends from `from` to `to`, 0 is all `from` and 1 is all `to`
+pub fn lerp<T: Add<T> + Mul<f64, Output = T>>(
+    from: T,
+    to: T,
+    interp_by: f64,
+) -> <T as Add<T>>::Output {
+    (to * interp_by) + (from * (1.0 - interp_by))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game_state::Object;
+    use crate::level::Level;
+
+    #[test]
+    fn translucent_instances_get_drawn_after_opaque_ones() {
+        let solid = instance([0.0, 0.0, 1.0, 1.0], OBJECT_COLOR);
+        let ghost = instance([0.0, 0.0, 1.0, 1.0], GHOST_COLOR);
+        let frame = FrameInstances {
+            instances: vec![ghost, solid, solid, ghost, solid, solid],
+            world_count: 6,
+            materials: vec![(0, None), (4, Some("glass".to_owned()))],
+            ..Default::default()
+        };
+        let runs = frame.world_runs(|material| material == "glass");
+        let summary = runs
+            .iter()
+            .map(|run| (run.instances.clone(), run.material, run.blend))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            summary,
+            vec![
+                (1..3, None, Blend::Opaque),
+                (0..1, None, Blend::Translucent),
+                (3..4, None, Blend::Translucent),
+                (4..6, Some("glass"), Blend::Translucent),
+            ]
+        );
+    }
+
+    #[test]
+    fn objects_out_of_view_get_no_quad_but_keep_their_place() {
+        let state = GameState::from_level(&Level {
+            version: Level::VERSION,
+            objects: vec![
+                Object::movable().build(),
+                Object::fixed().at(100.0, 0.0).build(),
+                Object::fixed().at(2.0, 0.0).build(),
+            ],
+            player: 0,
+            palette: Default::default(),
+            lighting: None,
+            goal: None,
+            triggers: vec![],
+            intro: None,
+            bounds: None,
+            out_of_bounds: Default::default(),
+            movement: Default::default(),
+            turrets: vec![],
+            scripts: vec![],
+            paths: vec![],
+            patrols: vec![],
+            chunking: None,
+            exits: vec![],
+            entrances: vec![],
+            gravity: None,
+            gravity_zones: vec![],
+            hook_limits: Default::default(),
+            checkpoints: vec![],
+            dual_hooks: false,
+            ziplines: vec![],
+            tutorials: vec![],
+            prefabs: Default::default(),
+            placed: vec![],
+            seed: 0,
+        });
+        let view = Region {
+            pos: cgmath::point2(-5.0, -5.0),
+            size: cgmath::vec2(10.0, 10.0),
+        };
+        let colors = CategoryColors::default();
+        let mut frame =
+            FrameInstances::prepare(1.0, &state, &state, None, None, &colors, Some(&view));
+        assert_eq!(frame.object_instances, vec![Some(0), None, Some(1)]);
+        assert_eq!(frame.culled(), 1);
+        assert_eq!(frame.world_count, 2);
+        // it still gets a position, for the shadows
+        assert_eq!(
+            frame.draw_position_of(&state, 1),
+            Some([100.0, 0.0, 1.0, 1.0])
+        );
+        frame.set_sprite(&state, 2, [0.5; 4]);
+        assert_eq!(frame.instances[1][11..15], [0.5; 4]);
+        frame.hide(&state, 1);
+        let everything = FrameInstances::prepare(1.0, &state, &state, None, None, &colors, None);
+        assert_eq!(everything.world_count, 3);
+    }
+}
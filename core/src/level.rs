@@ -0,0 +1,1630 @@
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+use cgmath::prelude::*;
+use color_eyre::eyre::{bail, WrapErr};
+use serde::{Deserialize, Serialize};
+
+use crate::game_state::{Object, ObjectType};
+use crate::tween::{Easing, Tween};
+
+/// the colours of a level at one point in time
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct Palette {
+    /// the clear color behind everything
+    pub background: [f32; 3],
+    /// multiplied onto the color of every object
+    pub tint: [f32; 3],
+    /// added on top of the tinted color
+    pub ambient: [f32; 3],
+}
+
+impl Default for Palette {
+    fn default() -> Self {
+        Self {
+            background: [0.0, 0.0, 0.0],
+            tint: [1.0, 1.0, 1.0],
+            ambient: [0.0, 0.0, 0.0],
+        }
+    }
+}
+
+impl Palette {
+    fn lerp(&self, other: &Palette, by: f32) -> Palette {
+        let mix = |a: [f32; 3], b: [f32; 3]| {
+            [
+                a[0] + (b[0] - a[0]) * by,
+                a[1] + (b[1] - a[1]) * by,
+                a[2] + (b[2] - a[2]) * by,
+            ]
+        };
+        Palette {
+            background: mix(self.background, other.background),
+            tint: mix(self.tint, other.tint),
+            ambient: mix(self.ambient, other.ambient),
+        }
+    }
+}
+
+/// the look of a level, either a single palette or several that get blended through over time
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct PaletteCycle {
+    /// gone through in order, wrapping back around to the first
+    pub palettes: Vec<Palette>,
+    /// how many seconds it takes to go through every palette once, None to stick to the first one
+    #[serde(default)]
+    pub period: Option<f64>,
+}
+
+impl PaletteCycle {
+    /// the palette `time` seconds into the level
+    pub fn at(&self, time: f64) -> Palette {
+        match (self.palettes.len(), self.period) {
+            (0, _) => Palette::default(),
+            (1, _) | (_, None) => self.palettes[0],
+            (count, Some(period)) => {
+                let position = (time / period).rem_euclid(1.0) * count as f64;
+                let index = position.floor() as usize % count;
+                let next = (index + 1) % count;
+                self.palettes[index].lerp(&self.palettes[next], position.fract() as f32)
+            }
+        }
+    }
+}
+
+/// a round light
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct Light {
+    /// the middle of the light
+    pub pos: cgmath::Point2<f64>,
+    /// the colour at the middle
+    pub color: [f32; 3],
+    /// how far the light reaches, it fades out towards the edge
+    pub radius: f64,
+}
+
+/// when a level has this, everything outside the lights gets darkened down to `ambient`
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Lighting {
+    /// what everything outside the lights gets darkened down to
+    pub ambient: [f32; 3],
+    /// the lights, added together where they overlap
+    pub lights: Vec<Light>,
+}
+
+/// an axis aligned area of the level
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct Region {
+    /// the bottom left corner
+    pub pos: cgmath::Point2<f64>,
+    /// the width and height
+    pub size: cgmath::Vector2<f64>,
+}
+
+impl Region {
+    /// whether `point` is inside, the edges count as inside
+    pub fn contains(&self, point: cgmath::Point2<f64>) -> bool {
+        point.x >= self.pos.x
+            && point.x <= self.pos.x + self.size.x
+            && point.y >= self.pos.y
+            && point.y <= self.pos.y + self.size.y
+    }
+}
+
+/// a named region that fires an event whenever something enters it
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Trigger {
+    /// what the trigger is called in `GameEvent::TriggerFired` listeners
+    pub name: String,
+    /// the area that sets it off
+    pub region: Region,
+    /// only objects with this tag set it off, None lets anything
+    #[serde(default)]
+    pub tag: Option<String>,
+}
+
+/// a region that takes players somewhere else once they walk into it, fires
+/// `GameEvent::ExitReached` and leaves going there to whoever is running the game
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Exit {
+    /// the area that has to be walked into
+    pub region: Region,
+    /// the name of the level it goes to, the way it shows up in the level select
+    pub level: String,
+    /// the name of the entrance to come in through, None for where that level normally starts
+    #[serde(default)]
+    pub entrance: Option<String>,
+}
+
+/// a named spawn point, where players come into a level from an exit of another one or get put
+/// with `GameState::spawn_player_at`
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Entrance {
+    /// what exits call it
+    pub name: String,
+    /// where the player gets put, their bottom left corner like the positions of objects
+    pub pos: cgmath::Point2<f64>,
+}
+
+/// a part of a level where things fall some other way than they do in the rest of it, where zones
+/// overlap the one listed last wins
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct GravityZone {
+    /// where it applies, going by the middle of things
+    pub region: Region,
+    /// which way and how hard things get pulled, in units per second squared, zero for floating
+    pub gravity: cgmath::Vector2<f64>,
+}
+
+/// what a tutorial prompt asks players to do
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Lesson {
+    /// run either way
+    Move,
+    /// jump off the ground
+    Jump,
+    /// pull down, or reel in the hook
+    Reel,
+    /// fire the hook
+    FireHook,
+    /// fire the second hook, in levels with `dual_hooks`
+    FireSecondHook,
+}
+
+/// a prompt that comes up the first time players walk into `region`, telling them which buttons
+/// do `lesson`, it goes away once they've done it
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Tutorial {
+    /// where the prompt comes up
+    pub region: Region,
+    /// what it asks players to do
+    pub lesson: Lesson,
+    /// the key of what the prompt says in the string table, `{keys}` in it gets replaced with the
+    /// buttons players have bound
+    pub text: String,
+}
+
+/// a copy of one of a level's prefabs, with anything that's different about this one
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Placement {
+    /// the name of the prefab in `Level::prefabs`
+    pub prefab: String,
+    /// where it goes, the prefab's own position is added on top, so it can say where its origin is
+    pub pos: cgmath::Point2<f64>,
+    /// in place of the prefab's width and height
+    #[serde(default)]
+    pub size: Option<cgmath::Vector2<f64>>,
+    /// in place of the prefab's material
+    #[serde(default)]
+    pub material: Option<String>,
+    /// in place of the prefab's surface friction
+    #[serde(default)]
+    pub friction: Option<f64>,
+    /// how fast it starts out moving, in place of the prefab's velocity
+    #[serde(default)]
+    pub velocity: Option<cgmath::Vector2<f64>>,
+    /// in place of the prefab's health
+    #[serde(default)]
+    pub health: Option<f64>,
+    /// index into `objects` of the object to attach it to
+    #[serde(default)]
+    pub parent: Option<usize>,
+    /// on top of the prefab's tags, for telling apart copies of the same thing
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+/// a cable strung between two points, that the hook catches on and then slides along
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct Zipline {
+    /// one end
+    pub from: cgmath::Point2<f64>,
+    /// the other end
+    pub to: cgmath::Point2<f64>,
+    /// how far the middle hangs down below a straight line between the ends
+    #[serde(default)]
+    pub sag: f64,
+}
+
+impl Zipline {
+    /// how many straight pieces the cable is made of, for finding the closest point and drawing it
+    pub const SEGMENTS: usize = 16;
+
+    /// the point `t` of the way along, from 0 at `from` to 1 at `to`, sagging like a parabola
+    pub fn point_at(&self, t: f64) -> cgmath::Point2<f64> {
+        let t = t.clamp(0.0, 1.0);
+        let straight = self.from + (self.to - self.from) * t;
+        straight - cgmath::vec2(0.0, self.sag * 4.0 * t * (1.0 - t))
+    }
+
+    /// the corners of the pieces the cable is made of, from `from` to `to`
+    pub fn points(&self) -> Vec<cgmath::Point2<f64>> {
+        (0..=Self::SEGMENTS)
+            .map(|i| self.point_at(i as f64 / Self::SEGMENTS as f64))
+            .collect()
+    }
+
+    /// how far along the point on the cable closest to `point` is, and where it is
+    pub fn closest(&self, point: cgmath::Point2<f64>) -> (f64, cgmath::Point2<f64>) {
+        let points = self.points();
+        let mut best = (0.0, points[0], f64::INFINITY);
+        for (i, ends) in points.windows(2).enumerate() {
+            let along = ends[1] - ends[0];
+            let length2 = along.magnitude2();
+            let fraction = match length2 > 0.0 {
+                true => ((point - ends[0]).dot(along) / length2).clamp(0.0, 1.0),
+                false => 0.0,
+            };
+            let closest = ends[0] + along * fraction;
+            let distance2 = closest.distance2(point);
+            if distance2 < best.2 {
+                let t = (i as f64 + fraction) / Self::SEGMENTS as f64;
+                best = (t, closest, distance2);
+            }
+        }
+        (best.0, best.1)
+    }
+}
+
+/// an object that turns to face players it can see and shoots at them
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct Turret {
+    /// index into `objects` of the object the turret is, it shoots from the middle of it
+    pub object: usize,
+    /// how far away it can spot players from
+    #[serde(default = "default_turret_range")]
+    pub range: f64,
+    /// how fast it turns, in radians per second
+    #[serde(default = "default_turret_turn_speed")]
+    pub turn_speed: f64,
+    /// how long it waits between shots, in seconds
+    #[serde(default = "default_turret_fire_interval")]
+    pub fire_interval: f64,
+    /// how fast its shots fly, in units per second
+    #[serde(default = "default_turret_shot_speed")]
+    pub shot_speed: f64,
+    /// how much damage each shot does
+    #[serde(default = "default_turret_damage")]
+    pub damage: f64,
+    /// how far either way its shots can go off where it's aiming, at random, in radians
+    #[serde(default)]
+    pub spread: f64,
+}
+
+fn default_turret_range() -> f64 {
+    20.0
+}
+
+fn default_turret_turn_speed() -> f64 {
+    2.0
+}
+
+fn default_turret_fire_interval() -> f64 {
+    1.0
+}
+
+fn default_turret_shot_speed() -> f64 {
+    15.0
+}
+
+fn default_turret_damage() -> f64 {
+    1.0
+}
+
+/// something a scripted object does, one step of a phase
+// the fields are explained on each variant
+#[allow(missing_docs)]
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub enum Attack {
+    /// does nothing for a while
+    Wait { seconds: f64 },
+    /// slides over to `to` for its position, taking `seconds` to get there
+    Move {
+        to: cgmath::Point2<f64>,
+        seconds: f64,
+        #[serde(default = "default_attack_easing")]
+        easing: Easing,
+    },
+    /// shoots `count` shots at the closest player, fanned out over `spread` radians
+    Aimed {
+        count: u32,
+        #[serde(default)]
+        spread: f64,
+        speed: f64,
+        damage: f64,
+    },
+    /// shoots `count` shots spread evenly in every direction
+    Ring { count: u32, speed: f64, damage: f64 },
+}
+
+fn default_attack_easing() -> Easing {
+    Easing::QuadInOut
+}
+
+/// part of a fight, with its own set of attacks
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Phase {
+    /// it goes into this phase once its health is down to this, the first phase ignores it
+    #[serde(default)]
+    pub below_health: f64,
+    /// what it does, one after the other, starting over after the last one
+    pub attacks: Vec<Attack>,
+}
+
+/// an object that goes through a list of attacks, switching to harder ones as it gets hurt, for
+/// bosses and the like
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Script {
+    /// index into `objects` of the object that does the attacks, it should have health
+    pub object: usize,
+    /// the phases in the order it goes through them, it starts in the first one
+    pub phases: Vec<Phase>,
+}
+
+/// a point along a path
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct Waypoint {
+    /// where the position of the object following the path goes, so its bottom left corner
+    pub pos: cgmath::Point2<f64>,
+    /// how long it stops here for, in seconds
+    #[serde(default)]
+    pub wait: f64,
+    /// how fast it goes on the way here, in place of the speed of the path
+    #[serde(default)]
+    pub speed: Option<f64>,
+}
+
+/// a named route through the level for things to follow, like moving platforms and patrolling
+/// enemies
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PatrolPath {
+    /// what patrols refer to it by
+    pub name: String,
+    /// the points it goes through, in order
+    pub points: Vec<Waypoint>,
+    /// how fast things go along it, in units per second
+    pub speed: f64,
+    /// goes straight from the last point back to the first, instead of back the way it came
+    #[serde(default)]
+    pub looped: bool,
+}
+
+/// an object following a path, forever
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Patrol {
+    /// index into `objects` of the object that moves, it floats along without falling
+    pub object: usize,
+    /// the name of the path it follows, it starts out heading to the first point
+    pub path: String,
+}
+
+/// how players get around a level, and how fast anything in it is allowed to go
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Movement {
+    /// how fast players run along the ground, in units per second
+    pub run_speed: f64,
+    /// how quickly they get up to that speed and back down, in units per second squared
+    pub acceleration: f64,
+    /// the fastest players can fall, None for no limit
+    pub terminal_velocity: Option<f64>,
+    /// the fastest anything can go in any direction, unless the object has its own limit, None
+    /// for no limit
+    pub max_speed: Option<f64>,
+    /// how swinging from the hook feels
+    pub swing: Swing,
+}
+
+impl Default for Movement {
+    fn default() -> Self {
+        Self {
+            run_speed: 10.0,
+            acceleration: 60.0,
+            terminal_velocity: None,
+            max_speed: None,
+            swing: Swing::default(),
+        }
+    }
+}
+
+/// how the hook's rope holds on to players, by default it's perfectly stiff and anything it
+/// stops is lost
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Swing {
+    /// how much of the way players get pulled back each tick when they get further out than the
+    /// rope is long, and how much of their speed away from the hook it catches, from 0 to 1,
+    /// below 1 the rope gives like it's elastic
+    pub stiffness: f64,
+    /// how much of the speed the rope catches it soaks up, from 0 to 1, the rest bounces players
+    /// back towards the hook
+    pub damping: f64,
+    /// how much of what the rope soaks up gets turned into speed along the swing instead of lost,
+    /// from 0 to 1
+    pub momentum: f64,
+    /// what players' speed gets multiplied by when they let go of the hook
+    pub release_boost: f64,
+    /// how much speed gets added along the swing for letting go of the hook right at the bottom
+    /// of it, less the further off the bottom it's let go
+    pub release_bonus: f64,
+    /// how far either side of straight below the hook still gets some of `release_bonus`, in
+    /// degrees
+    pub release_window: f64,
+}
+
+impl Default for Swing {
+    fn default() -> Self {
+        Self {
+            stiffness: 1.0,
+            damping: 1.0,
+            momentum: 0.0,
+            release_boost: 1.0,
+            release_bonus: 0.0,
+            release_window: 20.0,
+        }
+    }
+}
+
+/// limits on the hook, for levels built around using it sparingly, with nothing set it can be
+/// fired as often as players like, hung from forever and pulled on as hard as they like
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HookLimits {
+    /// how long after firing the hook it takes before it can be fired again, in seconds
+    pub cooldown: Option<f64>,
+    /// how many times the hook can be fired, until a checkpoint fills it back up
+    pub uses: Option<u32>,
+    /// how long players can hang from the hook before letting go, in seconds, it comes back
+    /// while they stand on something and all at once at a checkpoint
+    pub stamina: Option<f64>,
+    /// how many seconds of stamina come back for each second spent standing on something
+    pub stamina_recovery: f64,
+    /// how hard the rope can pull before it snaps, as mass times units per second squared, so
+    /// hanging still from it pulls with the player's weight
+    pub max_tension: Option<f64>,
+}
+
+impl Default for HookLimits {
+    fn default() -> Self {
+        Self {
+            cooldown: None,
+            uses: None,
+            stamina: None,
+            stamina_recovery: 1.0,
+            max_tension: None,
+        }
+    }
+}
+
+/// how a level too big to simulate all at once gets split up, into square chunks of which only
+/// the ones near a player are in the world, the rest wait outside of it without moving until
+/// someone comes close
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Chunking {
+    /// how wide and high a chunk is
+    pub size: f64,
+    /// how far from a player chunks stay in the world, along either axis, it has to be more than
+    /// half of what the camera shows plus the reach of the hook, or players will see things
+    /// appearing and have nothing to swing from
+    pub radius: f64,
+}
+
+impl Default for Chunking {
+    fn default() -> Self {
+        Self {
+            size: 32.0,
+            radius: 64.0,
+        }
+    }
+}
+
+/// what happens to a player that leaves the level's bounds
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum OutOfBounds {
+    /// they die, the same as getting crushed
+    #[default]
+    Kill,
+    /// they get put back where they started, without the run ending
+    Respawn,
+}
+
+/// somewhere the camera goes during a cutscene
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct CameraKey {
+    /// what the camera looks at
+    pub pos: cgmath::Point2<f64>,
+    /// how far it is zoomed in, 1 is the normal view
+    pub zoom: f64,
+    /// how long it takes to get here from the key before, in seconds, for the first key it's how
+    /// long the camera stays put before moving on
+    pub duration: f64,
+    /// how it gets here from the key before
+    pub easing: Easing,
+}
+
+/// the camera flying through the level on its own, played before the player gets control
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Cutscene {
+    /// the keys the camera goes through, in order
+    pub path: Vec<CameraKey>,
+}
+
+impl Cutscene {
+    /// how long the whole thing takes, in seconds
+    pub fn length(&self) -> f64 {
+        self.path.iter().map(|key| key.duration.max(0.0)).sum()
+    }
+
+    /// where the camera is looking and how far it's zoomed in `time` seconds in, None once it's
+    /// over
+    pub fn camera_at(&self, time: f64) -> Option<(cgmath::Point2<f64>, f64)> {
+        let first = self.path.first()?;
+        let mut start = first.duration.max(0.0);
+        if time < start {
+            return Some((first.pos, first.zoom));
+        }
+        for (from, to) in self.path.iter().zip(self.path.iter().skip(1)) {
+            let duration = to.duration.max(0.0);
+            if time < start + duration {
+                let pos = Tween {
+                    from: from.pos,
+                    to: to.pos,
+                    start,
+                    duration,
+                    easing: to.easing,
+                };
+                let zoom = Tween {
+                    from: from.zoom,
+                    to: to.zoom,
+                    start,
+                    duration,
+                    easing: to.easing,
+                };
+                return Some((pos.value_at(time), zoom.value_at(time)));
+            }
+            start += duration;
+        }
+        None
+    }
+}
+
+/// how much a problem with a level matters
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Severity {
+    /// the level can't be played like this
+    Error,
+    /// the level works, but probably not the way it was meant to
+    Warning,
+}
+
+/// something wrong with a level, found by `Level::validate`
+#[derive(Clone, Debug)]
+pub struct Problem {
+    /// whether the level can still be played
+    pub severity: Severity,
+    /// where in the level it is, for problems that are somewhere
+    pub at: Option<cgmath::Point2<f64>>,
+    /// what's wrong
+    pub message: String,
+}
+
+impl std::fmt::Display for Problem {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let severity = match self.severity {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        };
+        match self.at {
+            Some(at) => write!(f, "{} at {}, {}: {}", severity, at.x, at.y, self.message),
+            None => write!(f, "{}: {}", severity, self.message),
+        }
+    }
+}
+
+/// the problems found so far while validating
+#[derive(Default)]
+struct Problems(Vec<Problem>);
+
+impl Problems {
+    fn error(&mut self, at: Option<cgmath::Point2<f64>>, message: String) {
+        self.0.push(Problem {
+            severity: Severity::Error,
+            at,
+            message,
+        });
+    }
+
+    fn warning(&mut self, at: Option<cgmath::Point2<f64>>, message: String) {
+        self.0.push(Problem {
+            severity: Severity::Warning,
+            at,
+            message,
+        });
+    }
+}
+
+/// a level as it is stored on disk
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Level {
+    /// the version of the format it was saved in, levels from before there were versions are 0
+    #[serde(default)]
+    pub version: u32,
+    /// everything in the level at the start
+    pub objects: Vec<Object>,
+    /// index into `objects` of the object the player controls
+    pub player: usize,
+    #[serde(default)]
+    /// the colours of the level
+    pub palette: PaletteCycle,
+    #[serde(default)]
+    /// the lights, if the level is lit
+    pub lighting: Option<Lighting>,
+    /// the level is finished once the player gets in here
+    #[serde(default)]
+    pub goal: Option<Region>,
+    #[serde(default)]
+    /// named regions that fire events when something enters them
+    pub triggers: Vec<Trigger>,
+    /// shown when the level gets loaded, before the player can move
+    #[serde(default)]
+    pub intro: Option<Cutscene>,
+    /// anything that leaves this is gone, see `Level::bounds` for what it is when there isn't one
+    #[serde(default)]
+    pub bounds: Option<Region>,
+    /// what happens to the player when they leave the bounds
+    #[serde(default)]
+    pub out_of_bounds: OutOfBounds,
+    /// how fast players run and how fast things can go
+    #[serde(default)]
+    pub movement: Movement,
+    /// the objects that shoot at the player
+    #[serde(default)]
+    pub turrets: Vec<Turret>,
+    /// the objects that fight the player with a script
+    #[serde(default)]
+    pub scripts: Vec<Script>,
+    /// routes for things to follow
+    #[serde(default)]
+    pub paths: Vec<PatrolPath>,
+    /// the objects following one of the paths
+    #[serde(default)]
+    pub patrols: Vec<Patrol>,
+    /// splits the level into chunks that come and go as players move, for levels too long to
+    /// simulate all of at once, None keeps everything in the world the whole time
+    #[serde(default)]
+    pub chunking: Option<Chunking>,
+    /// the ways out to other levels
+    #[serde(default)]
+    pub exits: Vec<Exit>,
+    /// the named spawn points, which players come in at from other levels' exits
+    #[serde(default)]
+    pub entrances: Vec<Entrance>,
+    /// which way and how hard things fall outside of `gravity_zones`, None for straight down at
+    /// `GameState::DEFAULT_GRAVITY`
+    #[serde(default)]
+    pub gravity: Option<cgmath::Vector2<f64>>,
+    /// the parts of the level with a gravity of their own
+    #[serde(default)]
+    pub gravity_zones: Vec<GravityZone>,
+    /// how much players are allowed to use the hook
+    #[serde(default)]
+    pub hook_limits: HookLimits,
+    /// the areas that fill a player's hook back up when they get in them
+    #[serde(default)]
+    pub checkpoints: Vec<Region>,
+    /// players get a second hook on the other fire button, and can hang from both at once
+    #[serde(default)]
+    pub dual_hooks: bool,
+    /// cables the hook can slide along
+    #[serde(default)]
+    pub ziplines: Vec<Zipline>,
+    /// prompts showing new players which buttons to press
+    #[serde(default)]
+    pub tutorials: Vec<Tutorial>,
+    /// objects that get used over and over, by name, for `placed` to make copies of
+    #[serde(default)]
+    pub prefabs: HashMap<String, Object>,
+    /// copies of prefabs, they get added to the end of `objects` in this order when the level is
+    /// loaded, so the first one ends up right after the last object listed there
+    #[serde(default)]
+    pub placed: Vec<Placement>,
+    /// where the level's random numbers start from, like which way debris flies and how far off
+    /// turrets shoot, every play of the level gets the same ones so replays play out the same
+    #[serde(default)]
+    pub seed: u64,
+}
+
+/// just enough of a level to tell which version it is, before trying to read the rest
+#[derive(Deserialize)]
+struct Header {
+    #[serde(default)]
+    version: u32,
+}
+
+/// what changed in the format going from each version to the next, indexed by the version it
+/// starts from, run one after the other to bring an old level up to date
+const MIGRATIONS: [fn(&mut Level); Level::VERSION as usize] = [
+    // levels from before there were versions read the same, apart from having fields nothing
+    // knew about skipped over without a word
+    |_| {},
+    // version 2 added prefabs, which older levels don't have any of
+    |_| {},
+    // version 3 added the seed, older levels all start from 0
+    |_| {},
+    // version 4 added tags and metadata on objects, which older levels don't have any of
+    |_| {},
+];
+
+impl Level {
+    /// the version levels get written in, older ones still load but newer ones don't
+    pub const VERSION: u32 = 4;
+    /// how far past the objects of a level the bounds go when the level doesn't set them
+    const BOUNDS_MARGIN: f64 = 50.0;
+    /// how far static boxes can go into each other before it counts as overlapping, so tiles
+    /// that only meet at their edges don't
+    const OVERLAP: f64 = 1e-6;
+
+    /// the area things have to stay inside of, when the level doesn't say it goes a fair way past
+    /// the objects to the sides and below, with no limit going up
+    pub fn bounds(&self) -> Region {
+        if let Some(bounds) = self.bounds {
+            return bounds;
+        }
+        let (min, max) = self.objects.iter().fold(
+            (
+                cgmath::point2(f64::INFINITY, f64::INFINITY),
+                cgmath::point2(f64::NEG_INFINITY, f64::NEG_INFINITY),
+            ),
+            |(min, max), object| {
+                let (pos, size) = (*object.get_pos(), *object.get_size());
+                (
+                    cgmath::point2(min.x.min(pos.x), min.y.min(pos.y)),
+                    cgmath::point2(max.x.max(pos.x + size.x), max.y.max(pos.y + size.y)),
+                )
+            },
+        );
+        Region {
+            pos: cgmath::point2(min.x, min.y) - cgmath::vec2(1.0, 1.0) * Self::BOUNDS_MARGIN,
+            size: cgmath::vec2(max.x - min.x + 2.0 * Self::BOUNDS_MARGIN, f64::INFINITY),
+        }
+    }
+
+    /// the path called `name`, if there is one
+    pub fn path(&self, name: &str) -> Option<&PatrolPath> {
+        self.paths.iter().find(|path| path.name == name)
+    }
+
+    /// the entrance called `name`, if there is one
+    pub fn entrance(&self, name: &str) -> Option<&Entrance> {
+        self.entrances.iter().find(|entrance| entrance.name == name)
+    }
+
+    /// index into `objects` of the first object tagged `tag`, if any are
+    pub fn find_by_tag(&self, tag: &str) -> Option<usize> {
+        self.objects.iter().position(|object| object.has_tag(tag))
+    }
+
+    /// the name used to keep track of things per level, taken from the file name
+    pub fn name_from_path(path: impl AsRef<Path>) -> String {
+        path.as_ref()
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "unnamed".to_owned())
+    }
+
+    /// reads a level from a ron file, merging the static boxes that line up, fails listing every
+    /// problem `Level::validate` finds if any of them keep the level from being played, and logs
+    /// the rest
+    pub fn load(path: impl AsRef<Path>) -> color_eyre::Result<Self> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path)
+            .wrap_err_with(|| format!("failed to read level file {}", path.display()))?;
+        let mut level = Self::parse(&text)
+            .wrap_err_with(|| format!("failed to parse level file {}", path.display()))?;
+        let mut problems = level.expand_prefabs();
+        if problems.is_empty() {
+            problems = level.validate();
+        }
+        if problems
+            .iter()
+            .any(|problem| problem.severity == Severity::Error)
+        {
+            let list = problems
+                .iter()
+                .map(|problem| format!("\n  {}", problem))
+                .collect::<String>();
+            bail!("broken level file {}:{}", path.display(), list);
+        }
+        for problem in &problems {
+            log::warn!("{}: {}", path.display(), problem);
+        }
+        level.merge_statics();
+        Ok(level)
+    }
+
+    /// reads a level out of ron in any version up to `VERSION`, fails listing every field it
+    /// doesn't know about, which only gets warned about in levels from before there were versions
+    pub fn parse(text: &str) -> color_eyre::Result<Self> {
+        let header: Header = ron::from_str(text)?;
+        if header.version > Self::VERSION {
+            bail!(
+                "it's version {}, which is newer than this game can read (up to {})",
+                header.version,
+                Self::VERSION
+            );
+        }
+        let mut unknown = vec![];
+        let mut deserializer = ron::Deserializer::from_str(text)?;
+        let mut level: Self =
+            serde_ignored::deserialize(&mut deserializer, |path| unknown.push(path.to_string()))?;
+        deserializer.end()?;
+        match (header.version, unknown.is_empty()) {
+            (_, true) => {}
+            (0, false) => {
+                for field in &unknown {
+                    log::warn!("skipping {}, which levels don't have", field);
+                }
+            }
+            (_, false) => bail!(
+                "it has fields that levels don't have: {}",
+                unknown.join(", ")
+            ),
+        }
+        for migration in &MIGRATIONS[header.version as usize..] {
+            migration(&mut level);
+        }
+        level.version = Self::VERSION;
+        Ok(level)
+    }
+
+    /// turns every placement into an object at the end of `objects`, returning the ones that use a
+    /// prefab that doesn't exist
+    pub fn expand_prefabs(&mut self) -> Vec<Problem> {
+        let mut problems = Problems::default();
+        for placement in std::mem::take(&mut self.placed) {
+            let mut object = match self.prefabs.get(&placement.prefab) {
+                Some(prefab) => prefab.clone(),
+                None => {
+                    problems.error(
+                        Some(placement.pos),
+                        format!(
+                            "there's a {} placed, which isn't one of the prefabs",
+                            placement.prefab
+                        ),
+                    );
+                    continue;
+                }
+            };
+            object.set_pos(placement.pos + object.get_pos().to_vec());
+            if let Some(size) = placement.size {
+                object.set_size(size);
+            }
+            if let Some(material) = placement.material {
+                object.set_material(Some(material));
+            }
+            if let Some(friction) = placement.friction {
+                object.set_surface_friction(friction);
+            }
+            if let Some(velocity) = placement.velocity {
+                object.set_velocity(velocity);
+            }
+            if let Some(health) = placement.health {
+                object = object.with_health(health);
+            }
+            if let Some(parent) = placement.parent {
+                object.set_parent(Some(parent));
+            }
+            for tag in placement.tags {
+                object = object.with_tag(tag);
+            }
+            self.objects.push(object);
+        }
+        problems.0
+    }
+
+    /// everything wrong with the level, the things that would break the game as errors and the
+    /// things that are probably mistakes as warnings
+    pub fn validate(&self) -> Vec<Problem> {
+        let mut problems = Problems::default();
+        match self.objects.get(self.player) {
+            None => problems.error(
+                None,
+                format!("the player is object {}, which doesn't exist", self.player),
+            ),
+            Some(player) => {
+                if !matches!(player.get_type(), ObjectType::Movable { .. }) {
+                    problems.warning(
+                        Some(*player.get_pos()),
+                        format!("the player, object {}, can't move", self.player),
+                    );
+                }
+            }
+        }
+        self.check_parents(&mut problems);
+        self.check_references(&mut problems);
+        self.check_statics(&mut problems);
+        self.check_bounds(&mut problems);
+        self.check_spawns(&mut problems);
+        self.check_names(&mut problems);
+        problems.0
+    }
+
+    /// makes sure every turret, script and patrol is on an object that exists, and patrols follow
+    /// a path that exists
+    fn check_references(&self, problems: &mut Problems) {
+        let at = |object: usize| self.objects.get(object).map(|object| *object.get_pos());
+        for turret in &self.turrets {
+            if turret.object >= self.objects.len() {
+                problems.error(
+                    None,
+                    format!(
+                        "there is a turret on object {}, which doesn't exist",
+                        turret.object
+                    ),
+                );
+            }
+        }
+        for script in &self.scripts {
+            if script.object >= self.objects.len() {
+                problems.error(
+                    None,
+                    format!(
+                        "there is a script on object {}, which doesn't exist",
+                        script.object
+                    ),
+                );
+            }
+            if script.phases.is_empty() {
+                problems.error(
+                    at(script.object),
+                    format!("the script on object {} has no phases", script.object),
+                );
+            }
+        }
+        for path in &self.paths {
+            if path.points.is_empty() {
+                problems.error(None, format!("path {} has no points", path.name));
+            }
+        }
+        for patrol in &self.patrols {
+            if patrol.object >= self.objects.len() {
+                problems.error(
+                    None,
+                    format!(
+                        "there is a patrol on object {}, which doesn't exist",
+                        patrol.object
+                    ),
+                );
+            }
+            if self.path(&patrol.path).is_none() {
+                problems.error(
+                    at(patrol.object),
+                    format!(
+                        "object {} patrols path {}, which doesn't exist",
+                        patrol.object, patrol.path
+                    ),
+                );
+            }
+        }
+    }
+
+    /// makes sure every object is attached to one that exists, and nothing ends up attached to
+    /// itself further up the chain
+    fn check_parents(&self, problems: &mut Problems) {
+        'objects: for index in 0..self.objects.len() {
+            let at = Some(*self.objects[index].get_pos());
+            let mut current = index;
+            // going up more steps than there are objects means it went round in a circle
+            for _ in 0..=self.objects.len() {
+                let parent = match self.objects[current].get_parent() {
+                    Some(parent) => parent,
+                    None => continue 'objects,
+                };
+                if parent >= self.objects.len() {
+                    problems.error(
+                        at,
+                        format!(
+                            "object {} is attached to object {}, which doesn't exist",
+                            current, parent
+                        ),
+                    );
+                    continue 'objects;
+                }
+                if parent == index {
+                    problems.error(at, format!("object {} ends up attached to itself", index));
+                    continue 'objects;
+                }
+                current = parent;
+            }
+            problems.error(
+                at,
+                format!("object {} is attached to a circle of objects", index),
+            );
+        }
+    }
+
+    /// static boxes that overlap, which is usually two copies of a tile stacked up, and treadmills
+    /// that can't carry anything
+    fn check_statics(&self, problems: &mut Problems) {
+        // attached boxes move along with what they're on, so they're free to pass through things
+        let mut statics = self
+            .objects
+            .iter()
+            .enumerate()
+            .filter(|(_, object)| {
+                !matches!(object.get_type(), ObjectType::Movable { .. })
+                    && object.get_parent().is_none()
+            })
+            .collect::<Vec<_>>();
+        statics.sort_by(|(_, a), (_, b)| a.get_pos().x.total_cmp(&b.get_pos().x));
+        for (i, &(a, first)) in statics.iter().enumerate() {
+            let (pos, size) = (*first.get_pos(), *first.get_size());
+            for &(b, second) in &statics[i + 1..] {
+                let (other_pos, other_size) = (*second.get_pos(), *second.get_size());
+                if other_pos.x >= pos.x + size.x - Self::OVERLAP {
+                    break;
+                }
+                let overlaps = other_pos.y < pos.y + size.y - Self::OVERLAP
+                    && pos.y < other_pos.y + other_size.y - Self::OVERLAP;
+                if overlaps {
+                    let (a, b) = (a.min(b), a.max(b));
+                    let at = cgmath::point2(other_pos.x, pos.y.max(other_pos.y));
+                    problems.warning(Some(at), format!("static objects {} and {} overlap", a, b));
+                }
+            }
+        }
+        for (index, object) in self.objects.iter().enumerate() {
+            if let (ObjectType::Treadmill { .. }, 0.0) =
+                (object.get_type(), object.get_surface_friction())
+            {
+                problems.warning(
+                    Some(*object.get_pos()),
+                    format!(
+                        "object {} is a treadmill with no friction, so it carries nothing",
+                        index
+                    ),
+                );
+            }
+        }
+    }
+
+    /// objects that start out where they're already gone, when the level sets its bounds
+    fn check_bounds(&self, problems: &mut Problems) {
+        let bounds = match self.bounds {
+            Some(bounds) => bounds,
+            None => return,
+        };
+        for (index, object) in self.objects.iter().enumerate() {
+            let (pos, size) = (*object.get_pos(), *object.get_size());
+            if !bounds.contains(pos) || !bounds.contains(pos + size) {
+                problems.warning(
+                    Some(pos),
+                    format!("object {} isn't inside the bounds of the level", index),
+                );
+            }
+        }
+    }
+
+    /// places players start or have to get to, that are out of bounds or buried in a wall
+    fn check_spawns(&self, problems: &mut Problems) {
+        let size = match self.objects.get(self.player) {
+            Some(player) => *player.get_size(),
+            None => return,
+        };
+        let start = Region {
+            pos: *self.objects[self.player].get_pos(),
+            size,
+        };
+        let entrances = self.entrances.iter().map(|entrance| {
+            (
+                format!("entrance {}", entrance.name),
+                Region {
+                    pos: entrance.pos,
+                    size,
+                },
+            )
+        });
+        let checkpoints = self
+            .checkpoints
+            .iter()
+            .enumerate()
+            .map(|(i, checkpoint)| (format!("checkpoint {}", i), *checkpoint));
+        let spots = std::iter::once(("the player's start".to_owned(), start))
+            .chain(entrances)
+            .chain(checkpoints)
+            .chain(self.goal.map(|goal| ("the goal".to_owned(), goal)));
+        let bounds = self.bounds();
+        for (name, region) in spots {
+            if !bounds.contains(region.pos) || !bounds.contains(region.pos + region.size) {
+                problems.warning(
+                    Some(region.pos),
+                    format!("{} isn't inside the bounds of the level", name),
+                );
+                continue;
+            }
+            let buried_in = self.objects.iter().enumerate().find(|&(index, object)| {
+                let (pos, object_size) = (*object.get_pos(), *object.get_size());
+                index != self.player
+                    && !matches!(object.get_type(), ObjectType::Movable { .. })
+                    && object.get_parent().is_none()
+                    && pos.x <= region.pos.x
+                    && pos.y <= region.pos.y
+                    && pos.x + object_size.x >= region.pos.x + region.size.x
+                    && pos.y + object_size.y >= region.pos.y + region.size.y
+            });
+            if let Some((object, _)) = buried_in {
+                problems.warning(
+                    Some(region.pos),
+                    format!(
+                        "{} is inside object {}, so it can't be reached",
+                        name, object
+                    ),
+                );
+            }
+        }
+    }
+
+    /// names that more than one path, trigger or entrance goes by, only the first of them can
+    /// ever be found by name
+    fn check_names(&self, problems: &mut Problems) {
+        let mut check = |kind: &str, names: Vec<(&String, Option<cgmath::Point2<f64>>)>| {
+            let mut seen = HashSet::new();
+            for (name, at) in names {
+                if !seen.insert(name) {
+                    problems.warning(
+                        at,
+                        format!("there's more than one {} called {}", kind, name),
+                    );
+                }
+            }
+        };
+        check(
+            "path",
+            self.paths
+                .iter()
+                .map(|path| (&path.name, path.points.first().map(|point| point.pos)))
+                .collect(),
+        );
+        check(
+            "trigger",
+            self.triggers
+                .iter()
+                .map(|trigger| (&trigger.name, Some(trigger.region.pos)))
+                .collect(),
+        );
+        check(
+            "entrance",
+            self.entrances
+                .iter()
+                .map(|entrance| (&entrance.name, Some(entrance.pos)))
+                .collect(),
+        );
+        for trigger in &self.triggers {
+            let tag = match &trigger.tag {
+                Some(tag) => tag,
+                None => continue,
+            };
+            if self.find_by_tag(tag).is_none() {
+                problems.warning(
+                    Some(trigger.region.pos),
+                    format!(
+                        "trigger {} only goes off for things tagged {}, which nothing is",
+                        trigger.name, tag
+                    ),
+                );
+            }
+        }
+    }
+
+    /// joins up static boxes that line up edge to edge into bigger ones, so levels built out of
+    /// lots of tiles have less to collide against and no seams between tiles to get caught on
+    pub fn merge_statics(&mut self) {
+        // things attached to a box would lose track of it if it got merged into another one, and
+        // turrets, scripted objects and patrols would end up the size of the wall they're on
+        let parents_of = |level: &Level| {
+            level
+                .objects
+                .iter()
+                .filter_map(|object| object.get_parent())
+                .chain(level.turrets.iter().map(|turret| turret.object))
+                .chain(level.scripts.iter().map(|script| script.object))
+                .chain(level.patrols.iter().map(|patrol| patrol.object))
+                .collect::<HashSet<_>>()
+        };
+        let mut parents = parents_of(self);
+        loop {
+            let mut changed = false;
+            let mut i = 0;
+            while i < self.objects.len() {
+                let mut j = i + 1;
+                while j < self.objects.len() {
+                    if parents.contains(&i) || parents.contains(&j) {
+                        j += 1;
+                        continue;
+                    }
+                    match self.objects[i].merged_with(&self.objects[j]) {
+                        Some(merged) => {
+                            self.objects[i] = merged;
+                            self.objects.remove(j);
+                            if self.player > j {
+                                self.player -= 1;
+                            }
+                            for object in &mut self.objects {
+                                if let Some(parent) = object.get_parent().filter(|&p| p > j) {
+                                    object.set_parent(Some(parent - 1));
+                                }
+                            }
+                            let turrets = self.turrets.iter_mut().map(|turret| &mut turret.object);
+                            let scripts = self.scripts.iter_mut().map(|script| &mut script.object);
+                            let patrols = self.patrols.iter_mut().map(|patrol| &mut patrol.object);
+                            for object in turrets.chain(scripts).chain(patrols) {
+                                if *object > j {
+                                    *object -= 1;
+                                }
+                            }
+                            parents = parents_of(self);
+                            changed = true;
+                            // the bigger box might line up with ones that were already passed over
+                            j = i + 1;
+                        }
+                        None => j += 1,
+                    }
+                }
+                i += 1;
+            }
+            if !changed {
+                break;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game_state::MetaValue;
+
+    fn tile(x: f64, y: f64) -> Object {
+        Object::fixed().at(x, y).build()
+    }
+
+    fn level(objects: Vec<Object>, player: usize) -> Level {
+        Level {
+            version: Level::VERSION,
+            objects,
+            player,
+            palette: Default::default(),
+            lighting: None,
+            goal: None,
+            triggers: vec![],
+            intro: None,
+            bounds: None,
+            out_of_bounds: Default::default(),
+            movement: Default::default(),
+            turrets: vec![],
+            scripts: vec![],
+            paths: vec![],
+            patrols: vec![],
+            chunking: None,
+            exits: vec![],
+            entrances: vec![],
+            gravity: None,
+            gravity_zones: vec![],
+            hook_limits: Default::default(),
+            checkpoints: vec![],
+            dual_hooks: false,
+            ziplines: vec![],
+            tutorials: vec![],
+            prefabs: Default::default(),
+            placed: vec![],
+            seed: 0,
+        }
+    }
+
+    fn errors(level: &Level) -> usize {
+        level
+            .validate()
+            .iter()
+            .filter(|problem| problem.severity == Severity::Error)
+            .count()
+    }
+
+    #[test]
+    fn tiles_merge_into_one_box() {
+        let mut objects = vec![];
+        for y in 0..3 {
+            for x in 0..4 {
+                objects.push(tile(x as f64, y as f64));
+            }
+        }
+        let mut level = level(objects, 0);
+        // the player isn't a tile, so put something movable in the middle of the list
+        level
+            .objects
+            .insert(5, Object::movable().at(0.0, 10.0).build());
+        level.player = 5;
+        level.merge_statics();
+        assert_eq!(level.objects.len(), 2);
+        assert_eq!(
+            *level.objects[level.player].get_pos(),
+            cgmath::point2(0.0, 10.0)
+        );
+        let floor = level
+            .objects
+            .iter()
+            .find(|o| *o.get_size() == cgmath::vec2(4.0, 3.0));
+        assert!(floor.is_some());
+    }
+
+    #[test]
+    fn boxes_that_only_touch_at_a_corner_stay_apart() {
+        let mut level = level(vec![tile(0.0, 0.0), tile(1.0, 1.0), tile(5.0, 0.0)], 0);
+        level.merge_statics();
+        assert_eq!(level.objects.len(), 3);
+    }
+
+    #[test]
+    fn parent_cycles_are_rejected() {
+        let mut objects = vec![tile(0.0, 0.0), tile(5.0, 0.0), tile(10.0, 0.0)];
+        objects[1].set_parent(Some(2));
+        objects[2].set_parent(Some(1));
+        assert_eq!(errors(&level(objects.clone(), 0)), 2);
+        objects[2].set_parent(Some(7));
+        assert_eq!(errors(&level(objects.clone(), 0)), 2);
+        objects[2].set_parent(Some(0));
+        assert_eq!(errors(&level(objects, 0)), 0);
+    }
+
+    #[test]
+    fn attached_tiles_keep_their_parent_through_merging() {
+        let mut objects = vec![
+            tile(0.0, 0.0),
+            tile(1.0, 0.0),
+            tile(2.0, 0.0),
+            tile(3.0, 0.0),
+        ];
+        // hanging off the last tile, which is attached to the one before it
+        let mut lamp = tile(3.0, -1.0);
+        lamp.set_parent(Some(3));
+        objects[3].set_parent(Some(2));
+        objects.push(lamp);
+        let mut level = level(objects, 0);
+        level.merge_statics();
+        // the first two still merge, the rest are part of the chain
+        assert_eq!(level.objects.len(), 4);
+        let lamp = &level.objects[3];
+        assert_eq!(*lamp.get_pos(), cgmath::point2(3.0, -1.0));
+        let parent = &level.objects[lamp.get_parent().unwrap()];
+        assert_eq!(*parent.get_pos(), cgmath::point2(3.0, 0.0));
+        assert_eq!(
+            *level.objects[parent.get_parent().unwrap()].get_pos(),
+            cgmath::point2(2.0, 0.0)
+        );
+    }
+
+    #[test]
+    fn turrets_keep_their_object_through_merging() {
+        let objects = (0..5).map(|x| tile(x as f64, 0.0)).collect();
+        let mut level = level(objects, 0);
+        level.turrets.push(Turret {
+            object: 2,
+            range: default_turret_range(),
+            turn_speed: default_turret_turn_speed(),
+            fire_interval: default_turret_fire_interval(),
+            shot_speed: default_turret_shot_speed(),
+            damage: default_turret_damage(),
+            spread: 0.0,
+        });
+        level.merge_statics();
+        // the tiles on either side still merge, but not into the turret
+        assert_eq!(level.objects.len(), 3);
+        let turret = &level.objects[level.turrets[0].object];
+        assert_eq!(*turret.get_pos(), cgmath::point2(2.0, 0.0));
+        assert_eq!(*turret.get_size(), cgmath::vec2(1.0, 1.0));
+    }
+
+    #[test]
+    fn patrols_have_to_follow_a_path_that_exists() {
+        let mut level = level(vec![tile(0.0, 0.0), tile(5.0, 0.0)], 0);
+        level.patrols.push(Patrol {
+            object: 1,
+            path: "loop".to_owned(),
+        });
+        assert_eq!(errors(&level), 1);
+        level.paths.push(PatrolPath {
+            name: "loop".to_owned(),
+            points: vec![],
+            speed: 1.0,
+            looped: true,
+        });
+        assert_eq!(errors(&level), 1, "the path has no points");
+        level.paths[0].points.push(Waypoint {
+            pos: cgmath::point2(5.0, 3.0),
+            wait: 0.0,
+            speed: None,
+        });
+        assert_eq!(errors(&level), 0);
+    }
+
+    #[test]
+    fn validation_finds_every_problem_and_where_it_is() {
+        let objects = vec![
+            Object::movable().at(0.0, 1.0).build(),
+            Object::fixed().at(-5.0, 0.0).size(10.0, 1.0).build(),
+            // a copy of part of the floor
+            Object::fixed().at(2.0, 0.0).size(2.0, 1.0).build(),
+            Object::treadmill(cgmath::vec2(1.0, 0.0))
+                .at(10.0, 0.0)
+                .friction(0.0)
+                .build(),
+            Object::fixed().at(200.0, 0.0).build(),
+        ];
+        let mut level = level(objects, 0);
+        level.bounds = Some(Region {
+            pos: cgmath::point2(-50.0, -50.0),
+            size: cgmath::vec2(100.0, 100.0),
+        });
+        level.entrances.push(Entrance {
+            name: "left".to_owned(),
+            pos: cgmath::point2(-4.5, 0.0),
+        });
+        for _ in 0..2 {
+            level.triggers.push(Trigger {
+                name: "door".to_owned(),
+                region: Region {
+                    pos: cgmath::point2(5.0, 1.0),
+                    size: cgmath::vec2(1.0, 1.0),
+                },
+                tag: None,
+            });
+        }
+        let problems = level.validate();
+        let found = |message: &str, at: (f64, f64)| {
+            problems.iter().any(|problem| {
+                problem.message.contains(message)
+                    && problem.at == Some(cgmath::point2(at.0, at.1))
+                    && problem.severity == Severity::Warning
+            })
+        };
+        assert!(found("static objects 1 and 2 overlap", (2.0, 0.0)));
+        assert!(found(
+            "object 3 is a treadmill with no friction",
+            (10.0, 0.0)
+        ));
+        assert!(found("object 4 isn't inside the bounds", (200.0, 0.0)));
+        assert!(found("entrance left is inside object 1", (-4.5, 0.0)));
+        assert!(found("more than one trigger called door", (5.0, 1.0)));
+        assert_eq!(problems.len(), 5, "{:?}", problems);
+
+        level.player = 9;
+        assert_eq!(errors(&level), 1, "there's no player");
+    }
+
+    #[test]
+    fn old_levels_load_and_unknown_fields_get_listed() {
+        let object = "(ty: Static, pos: (x: 0, y: 0), size: (x: 1, y: 1)";
+        let old = format!("(player: 0, objects: [{}, colour: 3)], sky: 1)", object);
+        let old = Level::parse(&old).unwrap();
+        assert_eq!((old.version, old.objects.len()), (Level::VERSION, 1));
+
+        let current = format!(
+            "(version: 1, player: 0, objects: [{}, colour: 3)], sky: 1)",
+            object
+        );
+        let error = format!("{}", Level::parse(&current).err().unwrap());
+        assert!(error.contains("objects.0.colour, sky"), "{}", error);
+
+        let newer = Level::parse("(version: 99, player: 0, objects: [], sky: 1)");
+        let error = format!("{}", newer.err().unwrap());
+        assert!(error.contains("newer"), "{}", error);
+    }
+
+    #[test]
+    fn prefabs_get_copied_where_theyre_placed() {
+        let mut level = Level::parse(
+            "(
+                version: 2,
+                player: 0,
+                objects: [(ty: Static, pos: (x: 0, y: 0), size: (x: 10, y: 1))],
+                prefabs: {
+                    \"spike\": (
+                        ty: Static,
+                        pos: (x: 0.25, y: 0),
+                        size: (x: 0.5, y: 0.5),
+                        material: Some(\"metal\"),
+                    ),
+                },
+                placed: [
+                    (prefab: \"spike\", pos: (x: 2, y: 1)),
+                    (prefab: \"spike\", pos: (x: 4, y: 1), size: Some((x: 0.5, y: 2)), parent: Some(0)),
+                    (prefab: \"saw\", pos: (x: 6, y: 1)),
+                ],
+            )",
+        )
+        .unwrap();
+        let problems = level.expand_prefabs();
+        assert_eq!(problems.len(), 1);
+        assert_eq!(problems[0].at, Some(cgmath::point2(6.0, 1.0)));
+        assert_eq!(level.objects.len(), 3);
+        assert!(level.placed.is_empty());
+        let (first, second) = (&level.objects[1], &level.objects[2]);
+        assert_eq!(*first.get_pos(), cgmath::point2(2.25, 1.0));
+        assert_eq!(*first.get_size(), cgmath::vec2(0.5, 0.5));
+        assert_eq!(first.get_material(), Some("metal"));
+        assert_eq!(*second.get_pos(), cgmath::point2(4.25, 1.0));
+        assert_eq!(*second.get_size(), cgmath::vec2(0.5, 2.0));
+        assert_eq!(second.get_parent(), Some(0));
+    }
+
+    #[test]
+    fn tags_and_metadata_come_from_level_files() {
+        let mut level = Level::parse(
+            "(
+                version: 4,
+                player: 0,
+                objects: [
+                    (ty: Static, pos: (x: 0, y: 0), size: (x: 10, y: 1)),
+                    (
+                        ty: Static,
+                        pos: (x: 4, y: 1),
+                        size: (x: 1, y: 3),
+                        tags: [\"door\", \"door_3\"],
+                        metadata: {\"locked\": true, \"opens_in\": 2, \"key\": \"red\"},
+                    ),
+                ],
+                prefabs: {\"door\": (ty: Static, pos: (x: 0, y: 0), size: (x: 1, y: 3), tags: [\"door\"])},
+                placed: [(prefab: \"door\", pos: (x: 8, y: 1), tags: [\"door_4\"])],
+                triggers: [
+                    (name: \"knock\", region: (pos: (x: 3, y: 1), size: (x: 1, y: 1)), tag: Some(\"door_3\")),
+                    (name: \"ghost\", region: (pos: (x: 6, y: 1), size: (x: 1, y: 1)), tag: Some(\"door_9\")),
+                ],
+            )",
+        )
+        .unwrap();
+        assert!(level.expand_prefabs().is_empty());
+        assert_eq!(level.find_by_tag("door"), Some(1));
+        assert_eq!(level.find_by_tag("door_3"), Some(1));
+        assert_eq!(level.find_by_tag("door_4"), Some(2));
+        assert_eq!(level.objects[2].get_tags(), ["door", "door_4"]);
+        let door = &level.objects[1];
+        assert_eq!(door.get_metadata("locked"), Some(&MetaValue::Flag(true)));
+        let opens_in = door.get_metadata("opens_in").and_then(MetaValue::as_number);
+        assert_eq!(opens_in, Some(2.0));
+        let key = door.get_metadata("key").and_then(MetaValue::as_text);
+        assert_eq!(key, Some("red"));
+        let problems = level.validate();
+        let untagged = problems
+            .iter()
+            .filter(|problem| problem.message.contains("tagged"));
+        let untagged = untagged.map(|problem| problem.at).collect::<Vec<_>>();
+        assert_eq!(untagged, [Some(cgmath::point2(6.0, 1.0))], "{:?}", problems);
+    }
+
+    #[test]
+    fn cutscenes_pass_through_every_key() {
+        let key = |x, zoom, duration| CameraKey {
+            pos: cgmath::point2(x, 0.0),
+            zoom,
+            duration,
+            easing: Easing::Linear,
+        };
+        let cutscene = Cutscene {
+            path: vec![key(0.0, 1.0, 1.0), key(10.0, 2.0, 2.0), key(10.0, 1.0, 1.0)],
+        };
+        assert_eq!(cutscene.length(), 4.0);
+        assert_eq!(
+            cutscene.camera_at(0.5),
+            Some((cgmath::point2(0.0, 0.0), 1.0))
+        );
+        assert_eq!(
+            cutscene.camera_at(2.0),
+            Some((cgmath::point2(5.0, 0.0), 1.5))
+        );
+        assert_eq!(
+            cutscene.camera_at(3.5),
+            Some((cgmath::point2(10.0, 0.0), 1.5))
+        );
+        assert_eq!(cutscene.camera_at(4.0), None);
+    }
+}
@@ -0,0 +1,30 @@
+//! the simulation side of grappling hook, everything that doesn't need a window or a gpu
+//!
+//! - [`level`] is the level file format, load one with [`level::Level::load`]
+//! - [`game_state`] runs a level, build one with [`game_state::GameState::from_level`], feed it
+//!   player input with [`game_state::GameState::submit_player_event`] and step it with
+//!   [`game_state::GameState::update`], then react to what happened with
+//!   [`game_state::GameState::drain_events`]
+//! - [`replay`] records runs and plays them back
+//! - [`instances`] turns a game state into quads for a front end to draw
+//! - [`protocol`] is how clients talk to the dedicated server
+//! - [`generate`] makes up courses to swing through from a seed, with [`rng`] for the numbers
+//! - [`tween`] eases values from one thing to another over time, for scripted movement and effects
+#![warn(missing_docs)]
+
+/// running a level, one fixed step at a time
+pub mod game_state;
+/// making up levels from a seed
+pub mod generate;
+/// turning a game state into quads to draw
+pub mod instances;
+/// the level file format
+pub mod level;
+/// what the server and its clients say to each other
+pub mod protocol;
+/// recording and playing back runs
+pub mod replay;
+/// random numbers that come out the same every time
+pub mod rng;
+/// easing values over time
+pub mod tween;
@@ -0,0 +1,103 @@
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::game_state::{Event, GameState};
+
+/// the port the server listens on for players unless told otherwise
+pub const DEFAULT_PORT: u16 = 7878;
+
+/// what a client can send to the server, one message per line
+// the fields are explained on each variant
+#[allow(missing_docs)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum ClientMessage {
+    /// the first thing a client sends, `name` is what the admin sees it as
+    Hello { name: String },
+    /// input for the object the client controls
+    Input(Event),
+}
+
+/// what the server sends back to clients, one message per line
+// the fields are explained on each variant
+#[allow(missing_docs)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum ServerMessage {
+    /// the level called `level` got loaded, and `object` is the one this client controls in it
+    Welcome { level: String, object: usize },
+    /// where all the `objects` are after `tick`
+    Snapshot {
+        tick: u64,
+        objects: Vec<ObjectSnapshot>,
+    },
+    /// the server is dropping this client, for `reason`
+    Kicked { reason: String },
+}
+
+/// the position of one object at one point in time
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct ObjectSnapshot {
+    /// the index of the object in `GameState::objects`
+    pub index: usize,
+    /// [x, y, width, height], the same as replays
+    pub rect: [f64; 4],
+}
+
+/// where every object in `state` is right now
+pub fn snapshot(state: &GameState) -> ServerMessage {
+    let objects = state
+        .iter_objects()
+        .map(|object| {
+            let (pos, size) = (object.get_pos(), object.get_size());
+            ObjectSnapshot {
+                index: object.id(),
+                rect: [pos.x, pos.y, size.x, size.y],
+            }
+        })
+        .collect();
+    ServerMessage::Snapshot {
+        tick: state.tick,
+        objects,
+    }
+}
+
+/// turns a message into a single line of ron, newline included
+pub fn encode(message: &impl Serialize) -> color_eyre::Result<String> {
+    let mut line = ron::ser::to_string(message)?;
+    line.push('\n');
+    Ok(line)
+}
+
+/// reads back a line made by `encode`
+pub fn decode<T: DeserializeOwned>(line: &str) -> color_eyre::Result<T> {
+    Ok(ron::from_str(line.trim_end())?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game_state::{ButtonState, Direction};
+
+    #[test]
+    fn messages_fit_on_one_line() {
+        let message = ClientMessage::Hello {
+            name: "two\nlines".to_owned(),
+        };
+        let line = encode(&message).unwrap();
+        assert_eq!(line.lines().count(), 1);
+        match decode(&line).unwrap() {
+            ClientMessage::Hello { name } => assert_eq!(name, "two\nlines"),
+            other => panic!("got {:?}", other),
+        }
+
+        let input = ClientMessage::Input(Event::Keyboard {
+            button: Direction::Left,
+            state: ButtonState::Pressed,
+        });
+        assert!(matches!(
+            decode(&encode(&input).unwrap()).unwrap(),
+            ClientMessage::Input(Event::Keyboard {
+                button: Direction::Left,
+                state: ButtonState::Pressed
+            })
+        ));
+    }
+}
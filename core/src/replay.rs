@@ -0,0 +1,538 @@
+use std::path::{Path, PathBuf};
+
+use color_eyre::eyre::{bail, WrapErr};
+use serde::{Deserialize, Serialize};
+
+use crate::game_state::{AssistMode, Event, GameState};
+
+/// which of the players an input was for
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Slot {
+    /// the player the level starts with, the only one outside of co-op
+    #[default]
+    First,
+    /// the second player in co-op
+    Partner,
+}
+
+/// how the second player was brought in, for a co-op run
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Coop {
+    /// the object the partner controls
+    pub partner: usize,
+    /// how long the rope tying the players together is
+    pub rope_length: f64,
+}
+
+/// a recording of a run through a level, one entry per tick
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct Replay {
+    /// the version of the format it was saved in, replays from before there were versions are 0
+    #[serde(default)]
+    pub version: u32,
+    /// the name of the level the run was through
+    pub level: String,
+    /// the player events that got submitted before each tick, and who they were for
+    pub inputs: Vec<Vec<(Slot, Event)>>,
+    /// where the player object was after each tick, as [x, y, width, height]
+    pub path: Vec<[f64; 4]>,
+    /// the whole game every `SNAPSHOT_INTERVAL` ticks, so playback can jump ahead without
+    /// simulating everything before
+    #[serde(default)]
+    pub snapshots: Vec<GameState>,
+    /// the help the run was played with, which makes it easier, so it isn't up against runs
+    /// without on even terms
+    #[serde(default)]
+    pub assist_mode: AssistMode,
+    /// the second player, None for runs played alone
+    #[serde(default)]
+    pub coop: Option<Coop>,
+}
+
+/// just enough of a replay to tell which version it is, before trying to read the rest
+#[derive(Deserialize)]
+struct Header {
+    #[serde(default)]
+    version: u32,
+}
+
+/// a replay from before version 2, when inputs didn't say who they were for, they were all the
+/// first player's since the partner's didn't get recorded
+#[derive(Deserialize)]
+struct Unslotted {
+    level: String,
+    inputs: Vec<Vec<Event>>,
+    path: Vec<[f64; 4]>,
+    #[serde(default)]
+    snapshots: Vec<GameState>,
+    #[serde(default)]
+    assist_mode: AssistMode,
+}
+
+impl From<Unslotted> for Replay {
+    fn from(old: Unslotted) -> Self {
+        let inputs = old.inputs.into_iter().map(|tick| {
+            let tick = tick.into_iter().map(|event| (Slot::First, event));
+            tick.collect()
+        });
+        Self {
+            version: Replay::VERSION,
+            level: old.level,
+            inputs: inputs.collect(),
+            path: old.path,
+            snapshots: old.snapshots,
+            assist_mode: old.assist_mode,
+            coop: None,
+        }
+    }
+}
+
+impl Replay {
+    /// the version replays get saved in, older ones still load but newer ones don't
+    pub const VERSION: u32 = 2;
+    /// how many ticks apart the snapshots in a replay are
+    pub const SNAPSHOT_INTERVAL: u64 = 600;
+
+    /// reads a replay saved with `save`
+    pub fn load(path: impl AsRef<Path>) -> color_eyre::Result<Self> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path)
+            .wrap_err_with(|| format!("failed to read replay {}", path.display()))?;
+        let header: Header = ron::from_str(&text)
+            .wrap_err_with(|| format!("failed to parse replay {}", path.display()))?;
+        if header.version > Self::VERSION {
+            bail!(
+                "replay {} is version {}, which is newer than this game can read (up to {})",
+                path.display(),
+                header.version,
+                Self::VERSION
+            );
+        }
+        let parse_error = || format!("failed to parse replay {}", path.display());
+        match header.version {
+            0 | 1 => Ok(ron::from_str::<Unslotted>(&text)
+                .wrap_err_with(parse_error)?
+                .into()),
+            _ => ron::from_str(&text).wrap_err_with(parse_error),
+        }
+    }
+
+    /// writes the replay out as ron, creating the directory it goes in if needed
+    pub fn save(&self, path: impl AsRef<Path>) -> color_eyre::Result<()> {
+        let path = path.as_ref();
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let text = ron::ser::to_string(self)?;
+        std::fs::write(path, text)
+            .wrap_err_with(|| format!("failed to write replay {}", path.display()))
+    }
+
+    /// where the player was at `tick`, interpolated from the tick before it the same way the renderer does
+    pub fn position_at(&self, tick: u64, interpolate: f64) -> Option<[f64; 4]> {
+        let current = *self.path.get((tick as usize).checked_sub(1)?)?;
+        let previous = (tick as usize)
+            .checked_sub(2)
+            .and_then(|index| self.path.get(index))
+            .copied()
+            .unwrap_or(current);
+        let mut result = [0.0; 4];
+        for (result, (previous, current)) in result.iter_mut().zip(previous.iter().zip(current)) {
+            *result = previous + (current - previous) * interpolate;
+        }
+        Some(result)
+    }
+}
+
+/// where the ghost of the best run through a level gets kept, inside wherever saves go
+pub fn ghost_path(level: &str) -> PathBuf {
+    Path::new("ghosts").join(format!("{}.ron", level))
+}
+
+/// records a replay as the game is played
+pub struct Recorder {
+    replay: Replay,
+    pending: Vec<(Slot, Event)>,
+}
+
+impl Recorder {
+    /// starts recording a run through the level with this name
+    pub fn new(level: String) -> Self {
+        Self {
+            replay: Replay {
+                version: Replay::VERSION,
+                level,
+                ..Default::default()
+            },
+            pending: vec![],
+        }
+    }
+
+    /// hands an event to the first player, or every player outside of co-op, keeping track of it
+    /// for the replay
+    pub fn submit(&mut self, state: &mut GameState, event: Event) {
+        self.pending.push((Slot::First, event));
+        submit(state, self.replay.coop, Slot::First, event);
+    }
+
+    /// hands an event to the second player in co-op, keeping track of it for the replay, does
+    /// nothing outside of co-op
+    pub fn submit_partner(&mut self, state: &mut GameState, event: Event) {
+        if self.replay.coop.is_some() {
+            self.pending.push((Slot::Partner, event));
+            submit(state, self.replay.coop, Slot::Partner, event);
+        }
+    }
+
+    /// keeps track of the second player having been brought in, which has to happen before the
+    /// first tick for the replay to play back the same
+    pub fn set_coop(&mut self, coop: Coop) {
+        self.replay.coop = Some(coop);
+    }
+
+    /// keeps track of the help the run is getting, call whenever it changes, a run that got
+    /// any at some point counts as assisted
+    pub fn set_assist_mode(&mut self, assist_mode: AssistMode) {
+        let recorded = &mut self.replay.assist_mode;
+        recorded.game_speed = recorded.game_speed.min(assist_mode.game_speed);
+        recorded.infinite_hooks |= assist_mode.infinite_hooks;
+        recorded.no_death |= assist_mode.no_death;
+    }
+
+    /// call after every update, to close off the tick
+    pub fn end_tick(&mut self, state: &GameState) {
+        self.replay.inputs.push(std::mem::take(&mut self.pending));
+        let position = state
+            .player()
+            .map(|o| {
+                let (pos, size) = (o.get_pos(), o.get_size());
+                [pos.x, pos.y, size.x, size.y]
+            })
+            .unwrap_or_default();
+        self.replay.path.push(position);
+        if state.tick.is_multiple_of(Replay::SNAPSHOT_INTERVAL) {
+            self.replay.snapshots.push(state.clone());
+        }
+    }
+
+    /// everything recorded so far
+    pub fn replay(&self) -> &Replay {
+        &self.replay
+    }
+
+    /// everything recorded so far along with the inputs of the tick that's under way, for saving
+    /// a run that got cut off in the middle of one, playing it back runs that tick as well
+    pub fn unfinished_replay(&self) -> Replay {
+        let mut replay = self.replay.clone();
+        replay.inputs.push(self.pending.clone());
+        replay
+    }
+}
+
+/// hands `event` to whoever `slot` is, the first player only gets it to themselves in co-op
+fn submit(state: &mut GameState, coop: Option<Coop>, slot: Slot, event: Event) {
+    match (slot, coop) {
+        (Slot::First, None) => state.submit_player_event(event),
+        (Slot::First, Some(_)) => state.submit_event_for(state.view_object, event),
+        (Slot::Partner, Some(coop)) => state.submit_event_for(coop.partner, event),
+        (Slot::Partner, None) => {}
+    }
+}
+
+/// plays a replay back by handing its inputs to the game again, keeping a copy of the state every
+/// so often along the way so it can jump back without going all the way to the start
+pub struct Playback {
+    replay: Replay,
+    /// how long each tick was when it got recorded
+    dt: f64,
+    /// the states it can start over from when seeking, in order, the ones saved in the replay and
+    /// then one every `SNAPSHOT_INTERVAL` ticks as far as it's got
+    snapshots: Vec<GameState>,
+    /// where the playback is up to
+    pub state: GameState,
+    /// the state one update back, for interpolating
+    pub last_state: GameState,
+}
+
+impl Playback {
+    /// how many ticks apart the snapshots are, seeking re-simulates at most this many
+    const SNAPSHOT_INTERVAL: u64 = 120;
+
+    /// starts playing `replay` from `start`, which has to be the state its level starts out in,
+    /// with the help the run had and the second player if it had one
+    pub fn new(mut replay: Replay, mut start: GameState, dt: f64) -> Self {
+        start.set_assist_mode(replay.assist_mode);
+        if let Some(coop) = replay.coop {
+            let partner = start.start_coop(coop.rope_length);
+            if partner != Some(coop.partner) {
+                log::warn!(
+                    "the partner got object {:?} instead of {} like when it was recorded, it \
+                     won't play back the same",
+                    partner,
+                    coop.partner
+                );
+            }
+        }
+        let mut snapshots = vec![start.clone()];
+        snapshots.append(&mut replay.snapshots);
+        snapshots.sort_by_key(|snapshot| snapshot.tick);
+        snapshots.dedup_by_key(|snapshot| snapshot.tick);
+        Self {
+            replay,
+            dt,
+            snapshots,
+            last_state: start.clone(),
+            state: start,
+        }
+    }
+
+    /// the replay being played
+    pub fn replay(&self) -> &Replay {
+        &self.replay
+    }
+
+    /// how long each tick is
+    pub fn dt(&self) -> f64 {
+        self.dt
+    }
+
+    /// how many ticks the replay goes on for
+    pub fn length(&self) -> u64 {
+        self.replay.inputs.len() as u64
+    }
+
+    /// whether it's played all the way through
+    pub fn finished(&self) -> bool {
+        self.state.tick >= self.length()
+    }
+
+    /// runs the next tick, doing nothing once it's finished
+    pub fn step(&mut self) {
+        if self.finished() {
+            return;
+        }
+        self.last_state = self.state.clone();
+        self.advance();
+    }
+
+    fn advance(&mut self) {
+        for &(slot, event) in &self.replay.inputs[self.state.tick as usize] {
+            submit(&mut self.state, self.replay.coop, slot, event);
+        }
+        self.state.update(self.dt);
+        let tick = self.state.tick;
+        if tick.is_multiple_of(Self::SNAPSHOT_INTERVAL) {
+            if let Err(at) = self
+                .snapshots
+                .binary_search_by_key(&tick, |snapshot| snapshot.tick)
+            {
+                self.snapshots.insert(at, self.state.clone());
+            }
+        }
+    }
+
+    /// jumps to `tick`, going back to the closest snapshot before it and running forward from there
+    pub fn seek(&mut self, tick: u64) {
+        let tick = tick.min(self.length());
+        // there's always the one from the start
+        let closest = self
+            .snapshots
+            .partition_point(|snapshot| snapshot.tick <= tick)
+            - 1;
+        let snapshot = &self.snapshots[closest];
+        // when it's headed forward and already past the snapshot it can just carry on
+        if tick < self.state.tick || snapshot.tick > self.state.tick {
+            self.state = snapshot.clone();
+        }
+        while self.state.tick < tick {
+            self.advance();
+        }
+        // whatever happened on the way there is old news
+        self.state.drain_events().for_each(drop);
+        self.last_state = self.state.clone();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game_state::{ButtonState, Direction};
+    use crate::level::Level;
+
+    const TICK_RATE: f64 = 1.0 / 60.0;
+
+    fn start() -> GameState {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("../levels/test.ron");
+        GameState::from_level(&Level::load(path).unwrap())
+    }
+
+    /// plays through test.ron with some running, jumping and hooking, returning the replay and the
+    /// checksum after every tick, starting from before the first
+    fn record(ticks: u64) -> (Replay, Vec<u64>) {
+        let mut state = start();
+        let mut recorder = Recorder::new("test".to_owned());
+        let mut checksums = vec![state.checksum()];
+        for tick in 0..ticks {
+            let hook = |state| Event::Hook {
+                state,
+                target: cgmath::point2(6.0, 9.0),
+            };
+            let key = |button, state| Event::Keyboard { button, state };
+            let event = match tick % 400 {
+                10 | 250 => Some(key(Direction::Right, ButtonState::Pressed)),
+                90 | 300 => Some(key(Direction::Right, ButtonState::Released)),
+                150 => Some(key(Direction::Up, ButtonState::Pressed)),
+                160 => Some(key(Direction::Up, ButtonState::Released)),
+                180 => Some(hook(ButtonState::Pressed)),
+                190 => Some(hook(ButtonState::Released)),
+                _ => None,
+            };
+            if let Some(event) = event {
+                recorder.submit(&mut state, event);
+            }
+            state.update(TICK_RATE);
+            recorder.end_tick(&state);
+            checksums.push(state.checksum());
+        }
+        (recorder.replay().clone(), checksums)
+    }
+
+    #[test]
+    fn seeking_ends_up_where_playing_through_does() {
+        let (replay, checksums) = record(400);
+        let mut playback = Playback::new(replay, start(), TICK_RATE);
+        for &tick in &[350, 10, 10, 241, 240, 400, 0, 399, 123] {
+            playback.seek(tick);
+            assert_eq!(playback.state.tick, tick);
+            assert_eq!(playback.state.checksum(), checksums[tick as usize]);
+        }
+        while !playback.finished() {
+            playback.step();
+        }
+        assert_eq!(playback.state.checksum(), checksums[400]);
+        playback.seek(1000);
+        assert_eq!(playback.state.tick, 400);
+    }
+
+    #[test]
+    fn snapshots_saved_in_replays_carry_on_the_same() {
+        let (replay, checksums) = record(1300);
+        assert_eq!(replay.snapshots.len(), 2);
+        let path = std::env::temp_dir().join("grappling_hook_snapshot_test.ron");
+        replay.save(&path).unwrap();
+        let loaded = Replay::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        // straight to the saved ones, without going through what came before
+        let mut playback = Playback::new(loaded, start(), TICK_RATE);
+        for &tick in &[1250, 1200, 700, 600] {
+            playback.seek(tick);
+            assert_eq!(playback.state.checksum(), checksums[tick as usize]);
+        }
+    }
+
+    #[test]
+    fn unfinished_replays_keep_the_inputs_of_the_tick_under_way() {
+        let mut state = start();
+        let mut recorder = Recorder::new("test".to_owned());
+        for _ in 0..3 {
+            state.update(TICK_RATE);
+            recorder.end_tick(&state);
+        }
+        let event = Event::Keyboard {
+            button: Direction::Right,
+            state: ButtonState::Pressed,
+        };
+        recorder.submit(&mut state, event);
+        let replay = recorder.unfinished_replay();
+        assert_eq!(replay.inputs.len(), 4);
+        assert!(matches!(
+            replay.inputs[3][..],
+            [(
+                Slot::First,
+                Event::Keyboard {
+                    button: Direction::Right,
+                    state: ButtonState::Pressed
+                }
+            )]
+        ));
+        assert_eq!(recorder.replay().inputs.len(), 3);
+    }
+
+    #[test]
+    fn replays_from_newer_versions_say_so() {
+        let path = std::env::temp_dir().join("grappling_hook_version_test.ron");
+        std::fs::write(&path, "(version: 99, level: \"test\", new_thing: 3)").unwrap();
+        let error = Replay::load(&path).err().unwrap();
+        std::fs::write(
+            &path,
+            "(level: \"test\", inputs: [[]], path: [(0, 0, 1, 1)])",
+        )
+        .unwrap();
+        let old = Replay::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert!(format!("{}", error).contains("newer"), "{}", error);
+        assert_eq!((old.version, old.inputs.len()), (Replay::VERSION, 1));
+    }
+
+    #[test]
+    fn older_replays_give_every_input_to_the_first_player() {
+        let path = std::env::temp_dir().join("grappling_hook_unslotted_test.ron");
+        std::fs::write(
+            &path,
+            "(version: 1, level: \"test\", inputs: [[Keyboard(button: Right, state: Pressed)]], \
+             path: [(0, 0, 1, 1)])",
+        )
+        .unwrap();
+        let old = Replay::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert!(matches!(
+            old.inputs[0][..],
+            [(Slot::First, Event::Keyboard { .. })]
+        ));
+        assert_eq!(old.coop, None);
+    }
+
+    #[test]
+    fn coop_runs_play_back_with_both_players() {
+        const ROPE: f64 = 4.0;
+        let mut state = start();
+        let mut recorder = Recorder::new("test".to_owned());
+        let partner = state.start_coop(ROPE).unwrap();
+        recorder.set_coop(Coop {
+            partner,
+            rope_length: ROPE,
+        });
+        let key = |button, state| Event::Keyboard { button, state };
+        for tick in 0..300 {
+            // the two of them pull opposite ways, so it only comes out the same if each gets
+            // their own keys
+            match tick {
+                10 => recorder.submit(&mut state, key(Direction::Right, ButtonState::Pressed)),
+                20 => {
+                    recorder.submit_partner(&mut state, key(Direction::Left, ButtonState::Pressed))
+                }
+                120 => recorder.submit(&mut state, key(Direction::Right, ButtonState::Released)),
+                150 => {
+                    recorder.submit_partner(&mut state, key(Direction::Up, ButtonState::Pressed))
+                }
+                _ => {}
+            }
+            state.update(TICK_RATE);
+            recorder.end_tick(&state);
+        }
+        let path = std::env::temp_dir().join("grappling_hook_coop_test.ron");
+        recorder.replay().save(&path).unwrap();
+        let loaded = Replay::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(loaded.coop, recorder.replay().coop);
+        let mut playback = Playback::new(loaded, start(), TICK_RATE);
+        while !playback.finished() {
+            playback.step();
+        }
+        assert_eq!(playback.state.checksum(), state.checksum());
+        // and from partway through, as the viewer does when seeking back
+        playback.seek(100);
+        playback.seek(300);
+        assert_eq!(playback.state.checksum(), state.checksum());
+    }
+}
@@ -0,0 +1,53 @@
+use serde::{Deserialize, Serialize};
+
+/// a small random number generator that always gives the same numbers for the same seed, on
+/// every platform, for anything that has to come out the same way twice, the default starts from
+/// a seed of 0
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    /// starts the sequence for `seed`
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    /// the next number, splitmix64
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// a number from 0 up to but not including 1
+    pub fn next_f64(&mut self) -> f64 {
+        // the top 53 bits are as many as an f64 can hold exactly
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// a number from `from` up to but not including `to`
+    pub fn range(&mut self, from: f64, to: f64) -> f64 {
+        from + (to - from) * self.next_f64()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn same_seed_same_numbers() {
+        let (mut a, mut b, mut c) = (Rng::new(7), Rng::new(7), Rng::new(8));
+        let a = (0..10).map(|_| a.next_u64()).collect::<Vec<_>>();
+        let b = (0..10).map(|_| b.next_u64()).collect::<Vec<_>>();
+        let c = (0..10).map(|_| c.next_u64()).collect::<Vec<_>>();
+        assert_eq!(a, b);
+        assert_ne!(a, c);
+        let mut rng = Rng::new(0);
+        assert!((0..1000).all(|_| (2.0..3.0).contains(&rng.range(2.0, 3.0))));
+    }
+}
@@ -0,0 +1,233 @@
+use serde::{Deserialize, Serialize};
+
+/// how a tween gets from start to finish, all of them start at 0 and end at 1
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Easing {
+    /// the same speed the whole way
+    Linear,
+    /// starts slow and speeds up
+    QuadIn,
+    /// starts fast and slows down at the end
+    QuadOut,
+    /// slow at both ends
+    QuadInOut,
+    /// like `QuadInOut`, but gentler at the ends and faster in the middle
+    CubicInOut,
+    /// goes a bit past the end and settles back
+    BackOut,
+}
+
+impl Easing {
+    /// how far along the tween is `t` of the way through its duration
+    pub fn apply(self, t: f64) -> f64 {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            Easing::Linear => t,
+            Easing::QuadIn => t * t,
+            Easing::QuadOut => t * (2.0 - t),
+            Easing::QuadInOut => {
+                if t < 0.5 {
+                    2.0 * t * t
+                } else {
+                    1.0 - 2.0 * (1.0 - t) * (1.0 - t)
+                }
+            }
+            Easing::CubicInOut => {
+                if t < 0.5 {
+                    4.0 * t * t * t
+                } else {
+                    1.0 - 4.0 * (1.0 - t).powi(3)
+                }
+            }
+            Easing::BackOut => {
+                // how far past the end it goes, this is the usual amount of about 10%
+                const OVERSHOOT: f64 = 1.70158;
+                let t = t - 1.0;
+                1.0 + t * t * ((OVERSHOOT + 1.0) * t + OVERSHOOT)
+            }
+        }
+    }
+}
+
+/// something that can be blended between two values
+pub trait Tweenable: Copy {
+    /// `by` of the way from `self` to `to`, `by` can go a bit outside of 0 to 1 with some easings
+    fn blend(self, to: Self, by: f64) -> Self;
+}
+
+impl Tweenable for f64 {
+    fn blend(self, to: Self, by: f64) -> Self {
+        self + (to - self) * by
+    }
+}
+
+impl Tweenable for [f32; 3] {
+    fn blend(self, to: Self, by: f64) -> Self {
+        let by = by as f32;
+        [
+            self[0] + (to[0] - self[0]) * by,
+            self[1] + (to[1] - self[1]) * by,
+            self[2] + (to[2] - self[2]) * by,
+        ]
+    }
+}
+
+impl Tweenable for cgmath::Point2<f64> {
+    fn blend(self, to: Self, by: f64) -> Self {
+        self + (to - self) * by
+    }
+}
+
+impl Tweenable for cgmath::Vector2<f64> {
+    fn blend(self, to: Self, by: f64) -> Self {
+        self + (to - self) * by
+    }
+}
+
+/// one value going from `from` to `to` over some time
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct Tween<T> {
+    /// where it starts
+    pub from: T,
+    /// where it ends up
+    pub to: T,
+    /// the time it starts at, in whatever clock it gets updated with
+    pub start: f64,
+    /// how long it takes, in seconds
+    pub duration: f64,
+    /// how it gets there
+    pub easing: Easing,
+}
+
+impl<T: Tweenable> Tween<T> {
+    /// the value at `time`, `from` before it starts and `to` after it's done
+    pub fn value_at(&self, time: f64) -> T {
+        if self.duration <= 0.0 {
+            return if time >= self.start {
+                self.to
+            } else {
+                self.from
+            };
+        }
+        let t = (time - self.start) / self.duration;
+        self.from.blend(self.to, self.easing.apply(t))
+    }
+
+    /// whether it has gotten to `to` by `time`
+    pub fn is_done(&self, time: f64) -> bool {
+        time >= self.start + self.duration
+    }
+}
+
+/// names a tween, to cancel it or to recognise it finishing
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct TweenId(pub u64);
+
+/// the tweens of one property of several things, each thing told apart by a key, like an object
+/// index, starting a new tween for a key cancels the one it already had
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Tweens<K, T> {
+    next_id: u64,
+    running: Vec<(TweenId, K, Tween<T>)>,
+}
+
+impl<K, T> Default for Tweens<K, T> {
+    fn default() -> Self {
+        Self {
+            next_id: 0,
+            running: vec![],
+        }
+    }
+}
+
+impl<K: PartialEq + Copy, T: Tweenable> Tweens<K, T> {
+    /// starts `tween` for `key`, replacing whatever tween it had before
+    pub fn start(&mut self, key: K, tween: Tween<T>) -> TweenId {
+        self.running.retain(|(_, k, _)| *k != key);
+        let id = TweenId(self.next_id);
+        self.next_id += 1;
+        self.running.push((id, key, tween));
+        id
+    }
+
+    /// stops a tween where it is, returns whether it was still running
+    pub fn cancel(&mut self, id: TweenId) -> bool {
+        let before = self.running.len();
+        self.running.retain(|(i, _, _)| *i != id);
+        self.running.len() != before
+    }
+
+    /// stops every tween of `key`
+    pub fn cancel_key(&mut self, key: K) {
+        self.running.retain(|(_, k, _)| *k != key);
+    }
+
+    /// whether `key` has a tween going
+    pub fn is_running(&self, key: K) -> bool {
+        self.running.iter().any(|(_, k, _)| *k == key)
+    }
+
+    /// the value of every running tween at `time`, by key
+    pub fn values_at(&self, time: f64) -> impl Iterator<Item = (K, T)> + '_ {
+        self.running
+            .iter()
+            .map(move |(_, key, tween)| (*key, tween.value_at(time)))
+    }
+
+    /// takes out the tweens that are done by `time`, returning them along with their final values
+    pub fn finish(&mut self, time: f64) -> Vec<(TweenId, K, T)> {
+        let mut finished = vec![];
+        self.running.retain(|(id, key, tween)| {
+            let done = tween.is_done(time);
+            if done {
+                finished.push((*id, *key, tween.to));
+            }
+            !done
+        });
+        finished
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn easings_start_at_0_and_end_at_1() {
+        for easing in [
+            Easing::Linear,
+            Easing::QuadIn,
+            Easing::QuadOut,
+            Easing::QuadInOut,
+            Easing::CubicInOut,
+            Easing::BackOut,
+        ] {
+            assert!(easing.apply(0.0).abs() < 1e-9, "{:?}", easing);
+            assert!((easing.apply(1.0) - 1.0).abs() < 1e-9, "{:?}", easing);
+        }
+        assert!(Easing::BackOut.apply(0.7) > 1.0);
+    }
+
+    #[test]
+    fn a_new_tween_replaces_the_old_one() {
+        let tween = |to| Tween {
+            from: 0.0,
+            to,
+            start: 0.0,
+            duration: 1.0,
+            easing: Easing::Linear,
+        };
+        let mut tweens = Tweens::default();
+        let first = tweens.start(0, tween(1.0));
+        tweens.start(1, tween(2.0));
+        let third = tweens.start(0, tween(4.0));
+        assert!(!tweens.cancel(first));
+        let mut values = tweens.values_at(0.5).collect::<Vec<_>>();
+        values.sort_by_key(|(key, _)| *key);
+        assert_eq!(values, vec![(0, 2.0), (1, 1.0)]);
+        let finished = tweens.finish(1.0);
+        assert_eq!(finished.len(), 2);
+        assert!(finished.contains(&(third, 0, 4.0)));
+        assert!(!tweens.is_running(0));
+    }
+}
@@ -0,0 +1,131 @@
+//! plays canned input scripts through levels and checks every tick against what was written down
+//! last time, so changes to how the game moves only go in on purpose
+//!
+//! scripts are the `.ron` files in `tests/golden`, each with a `.golden.ron` next to it holding the
+//! expected results. run with `UPDATE_GOLDEN=1` to write those after changing movement on purpose,
+//! and look over the diff.
+
+use std::path::{Path, PathBuf};
+
+use grappling_hook_core::{
+    game_state::{Event, GameState},
+    level::Level,
+};
+use serde::{Deserialize, Serialize};
+
+const TICK_RATE: f64 = 1.0 / 60.0;
+
+#[derive(Deserialize)]
+struct Script {
+    /// the level file, in the levels directory
+    level: String,
+    /// how many updates to run for
+    ticks: u64,
+    inputs: Vec<Input>,
+}
+
+/// an event handed to the player right before update number `tick`, counting from 0
+#[derive(Deserialize)]
+struct Input {
+    tick: u64,
+    event: Event,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Golden {
+    /// where the player ended up, as [x, y, width, height], to have something readable in the diff
+    final_player: [f64; 4],
+    /// `GameState::checksum` after every update
+    checksums: Vec<u64>,
+}
+
+fn golden_directory() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/golden")
+}
+
+fn run(script: &Script) -> Golden {
+    let level_path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("../levels")
+        .join(&script.level);
+    let level = Level::load(&level_path).unwrap();
+    let mut state = GameState::from_level(&level);
+    let mut checksums = vec![];
+    for tick in 0..script.ticks {
+        for input in script.inputs.iter().filter(|input| input.tick == tick) {
+            state.submit_player_event(input.event);
+        }
+        state.update(TICK_RATE);
+        checksums.push(state.checksum());
+    }
+    let final_player = state
+        .player()
+        .map(|o| {
+            let (pos, size) = (o.get_pos(), o.get_size());
+            [pos.x, pos.y, size.x, size.y]
+        })
+        .unwrap_or_default();
+    Golden {
+        final_player,
+        checksums,
+    }
+}
+
+#[test]
+fn scripts_match_golden_data() {
+    let update = std::env::var_os("UPDATE_GOLDEN").is_some();
+    let mut scripts = std::fs::read_dir(golden_directory())
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| {
+            let name = path.file_name().unwrap().to_string_lossy();
+            name.ends_with(".ron") && !name.ends_with(".golden.ron")
+        })
+        .collect::<Vec<_>>();
+    scripts.sort();
+    assert!(
+        !scripts.is_empty(),
+        "no scripts in {:?}",
+        golden_directory()
+    );
+
+    let mut failures = vec![];
+    for path in scripts {
+        let script: Script = ron::from_str(&std::fs::read_to_string(&path).unwrap())
+            .unwrap_or_else(|e| panic!("failed to parse {}: {}", path.display(), e));
+        let actual = run(&script);
+        let golden_path = path.with_extension("golden.ron");
+        if update {
+            let text =
+                ron::ser::to_string_pretty(&actual, ron::ser::PrettyConfig::default()).unwrap();
+            std::fs::write(&golden_path, text).unwrap();
+            continue;
+        }
+        let expected: Golden = match std::fs::read_to_string(&golden_path) {
+            Ok(text) => ron::from_str(&text).unwrap(),
+            Err(_) => {
+                failures.push(format!(
+                    "{} has no golden data, run with UPDATE_GOLDEN=1 to write it",
+                    path.display()
+                ));
+                continue;
+            }
+        };
+        if actual == expected {
+            continue;
+        }
+        let diverged = actual
+            .checksums
+            .iter()
+            .zip(&expected.checksums)
+            .position(|(actual, expected)| actual != expected)
+            .unwrap_or_else(|| actual.checksums.len().min(expected.checksums.len()));
+        failures.push(format!(
+            "{} first went differently on tick {}, the player ended up at {:?} instead of {:?}",
+            path.display(),
+            diverged,
+            actual.final_player,
+            expected.final_player
+        ));
+    }
+    assert!(failures.is_empty(), "{}", failures.join("\n"));
+}
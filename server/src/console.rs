@@ -0,0 +1,156 @@
+use std::{
+    io::{BufRead, BufReader, Write},
+    net::{TcpListener, TcpStream},
+    sync::mpsc::Sender,
+};
+
+use color_eyre::eyre::WrapErr;
+
+use crate::net::Incoming;
+
+/// the port the admin console listens on unless told otherwise, only reachable from this machine
+pub const DEFAULT_ADMIN_PORT: u16 = 7879;
+
+pub const HELP: &str = "\
+commands:
+  list                  the level and who is playing
+  load <path>           switch every player over to another level
+  kick <id> [reason]    drop a player
+  quit                  shut the server down";
+
+/// something typed into the admin console
+#[derive(Clone, Debug, PartialEq)]
+pub enum Command {
+    Help,
+    List,
+    Load(String),
+    Kick { id: usize, reason: String },
+    Quit,
+}
+
+impl Command {
+    pub fn parse(line: &str) -> Result<Self, String> {
+        let mut words = line.split_whitespace();
+        let command = match words.next() {
+            Some(command) => command,
+            None => return Err("nothing to do".to_owned()),
+        };
+        match command {
+            "help" => Ok(Command::Help),
+            "list" => Ok(Command::List),
+            "load" => match words.next() {
+                Some(path) => Ok(Command::Load(path.to_owned())),
+                None => Err("load needs a level to load".to_owned()),
+            },
+            "kick" => {
+                let id = words
+                    .next()
+                    .ok_or_else(|| "kick needs the id of a player".to_owned())?;
+                let id = id
+                    .parse()
+                    .map_err(|_| format!("{} isn't a player id", id))?;
+                let reason = words.collect::<Vec<_>>().join(" ");
+                let reason = if reason.is_empty() {
+                    "kicked by the admin".to_owned()
+                } else {
+                    reason
+                };
+                Ok(Command::Kick { id, reason })
+            }
+            "quit" => Ok(Command::Quit),
+            _ => Err(format!("unknown command {}, try help", command)),
+        }
+    }
+}
+
+/// where the answer to a command goes
+pub enum Reply {
+    Stdout,
+    Admin(TcpStream),
+}
+
+impl Reply {
+    pub fn println(&mut self, text: &str) {
+        match self {
+            Reply::Stdout => println!("{}", text),
+            Reply::Admin(stream) => {
+                let _ = writeln!(stream, "{}", text);
+            }
+        }
+    }
+}
+
+/// reads commands off stdin in the background
+pub fn listen_on_stdin(incoming: Sender<Incoming>) {
+    std::thread::spawn(move || {
+        for line in std::io::stdin().lock().lines() {
+            let line = match line {
+                Ok(line) => line,
+                Err(_) => break,
+            };
+            let reply = Reply::Stdout;
+            if incoming.send(Incoming::Command { line, reply }).is_err() {
+                break;
+            }
+        }
+    });
+}
+
+/// takes admin connections on `port` in the background, they get the same commands as stdin
+pub fn listen_for_admins(port: u16, incoming: Sender<Incoming>) -> color_eyre::Result<()> {
+    let listener = TcpListener::bind(("127.0.0.1", port))
+        .wrap_err_with(|| format!("failed to listen for admins on port {}", port))?;
+    tracing::info!("admin console on port {}", port);
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let stream = match stream {
+                Ok(stream) => stream,
+                Err(e) => {
+                    tracing::warn!("failed to accept an admin: {}", e);
+                    continue;
+                }
+            };
+            let incoming = incoming.clone();
+            std::thread::spawn(move || {
+                let reader = match stream.try_clone() {
+                    Ok(reader) => BufReader::new(reader),
+                    Err(_) => return,
+                };
+                for line in reader.lines() {
+                    let (line, reply) = match (line, stream.try_clone()) {
+                        (Ok(line), Ok(reply)) => (line, Reply::Admin(reply)),
+                        _ => break,
+                    };
+                    if incoming.send(Incoming::Command { line, reply }).is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+    });
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn commands_parse() {
+        assert_eq!(Command::parse("  list "), Ok(Command::List));
+        assert_eq!(
+            Command::parse("load levels/cave.ron"),
+            Ok(Command::Load("levels/cave.ron".to_owned()))
+        );
+        assert_eq!(
+            Command::parse("kick 3 being rude"),
+            Ok(Command::Kick {
+                id: 3,
+                reason: "being rude".to_owned()
+            })
+        );
+        assert!(Command::parse("kick me").is_err());
+        assert!(Command::parse("load").is_err());
+        assert!(Command::parse("").is_err());
+    }
+}
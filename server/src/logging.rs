@@ -0,0 +1,68 @@
+use std::str::FromStr;
+
+use color_eyre::eyre::{eyre, WrapErr};
+use tracing::Level;
+use tracing_subscriber::filter::{LevelFilter, Targets};
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+
+/// which modules log at which level, in the form `warn,grappling_hook_server=debug`
+pub const FILTER_VAR: &str = "GRAPPLING_HOOK_SERVER_LOG";
+const DEFAULT_FILTER: &str = "warn,grappling_hook_server=info";
+/// what everything coming through `log` is logged under, it can't keep its own target
+const LOG_TARGET: &str = "log";
+
+/// sets up logging to stderr, `log` records from the core crate get passed along too, stdout is
+/// left for the answers to console commands
+pub fn init() -> color_eyre::Result<()> {
+    let directives = std::env::var(FILTER_VAR).unwrap_or_else(|_| DEFAULT_FILTER.to_owned());
+    let targets = Targets::from_str(&directives)
+        .map_err(|e| eyre!("{}", e))
+        .wrap_err_with(|| format!("failed to parse {}={}", FILTER_VAR, directives))?;
+    tracing_subscriber::registry()
+        .with(tracing_subscriber::fmt::layer().with_writer(std::io::stderr))
+        // the bridge checks those against where they really came from
+        .with(targets.clone().with_target(LOG_TARGET, LevelFilter::TRACE))
+        .try_init()?;
+    log::set_boxed_logger(Box::new(LogBridge { targets }))?;
+    log::set_max_level(log::LevelFilter::Trace);
+    Ok(())
+}
+
+/// hands records from the `log` crate over to tracing
+struct LogBridge {
+    targets: Targets,
+}
+
+impl log::Log for LogBridge {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        self.targets
+            .would_enable(metadata.target(), &level(metadata.level()))
+    }
+
+    fn log(&self, record: &log::Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        let (target, message) = (record.target(), record.args());
+        match record.level() {
+            log::Level::Error => tracing::error!(target: LOG_TARGET, target, "{}", message),
+            log::Level::Warn => tracing::warn!(target: LOG_TARGET, target, "{}", message),
+            log::Level::Info => tracing::info!(target: LOG_TARGET, target, "{}", message),
+            log::Level::Debug => tracing::debug!(target: LOG_TARGET, target, "{}", message),
+            log::Level::Trace => tracing::trace!(target: LOG_TARGET, target, "{}", message),
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+fn level(level: log::Level) -> Level {
+    match level {
+        log::Level::Error => Level::ERROR,
+        log::Level::Warn => Level::WARN,
+        log::Level::Info => Level::INFO,
+        log::Level::Debug => Level::DEBUG,
+        log::Level::Trace => Level::TRACE,
+    }
+}
@@ -0,0 +1,58 @@
+mod console;
+mod logging;
+mod net;
+mod server;
+
+use color_eyre::{eyre::eyre, Result};
+use grappling_hook_core::protocol;
+use std::time::{Duration, Instant};
+
+fn main() -> Result<()> {
+    logging::init()?;
+
+    const TICK_RATE: f64 = 1.0 / 60.0;
+
+    let mut level_path = "levels/test.ron".to_owned();
+    let mut port = protocol::DEFAULT_PORT;
+    let mut admin_port = console::DEFAULT_ADMIN_PORT;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--port" => port = parse_port(args.next())?,
+            "--admin-port" => admin_port = parse_port(args.next())?,
+            _ => level_path = arg,
+        }
+    }
+
+    let mut server = server::Server::load(&level_path)?;
+    let (incoming, received) = std::sync::mpsc::channel();
+    net::listen_for_players(port, incoming.clone())?;
+    console::listen_for_admins(admin_port, incoming.clone())?;
+    console::listen_on_stdin(incoming);
+
+    let tick = Duration::from_secs_f64(TICK_RATE);
+    let mut next_tick = Instant::now();
+    while !server.quit {
+        for incoming in received.try_iter() {
+            server.handle(incoming);
+        }
+        server.tick(TICK_RATE);
+        next_tick += tick;
+        let now = Instant::now();
+        if next_tick > now {
+            std::thread::sleep(next_tick - now);
+        } else {
+            // fell behind, skip the missed ticks instead of rushing through them
+            next_tick = now;
+        }
+    }
+    // gives the writer threads a moment to get the goodbyes out
+    std::thread::sleep(Duration::from_millis(100));
+    Ok(())
+}
+
+fn parse_port(arg: Option<String>) -> Result<u16> {
+    let arg = arg.ok_or_else(|| eyre!("expected a port number"))?;
+    arg.parse()
+        .map_err(|_| eyre!("{} isn't a port number", arg))
+}
@@ -0,0 +1,97 @@
+use std::{
+    io::{BufRead, BufReader, Write},
+    net::{TcpListener, TcpStream},
+    sync::mpsc::{self, Sender},
+};
+
+use color_eyre::eyre::WrapErr;
+use grappling_hook_core::protocol::{self, ClientMessage};
+
+use crate::console::Reply;
+
+/// everything the network and console threads hand to the main loop
+pub enum Incoming {
+    /// a player connected, `outgoing` takes lines to send to them
+    Connected {
+        id: usize,
+        outgoing: Sender<String>,
+        stream: TcpStream,
+    },
+    Message {
+        id: usize,
+        message: ClientMessage,
+    },
+    Disconnected {
+        id: usize,
+    },
+    /// a line typed into the admin console, `reply` is where the answer goes
+    Command {
+        line: String,
+        reply: Reply,
+    },
+}
+
+/// accepts players on `port` in the background, every connection gets a thread to read from it
+/// and one to write to it so a slow client can't hold up the simulation
+pub fn listen_for_players(port: u16, incoming: Sender<Incoming>) -> color_eyre::Result<()> {
+    let listener = TcpListener::bind(("0.0.0.0", port))
+        .wrap_err_with(|| format!("failed to listen for players on port {}", port))?;
+    tracing::info!("listening for players on port {}", port);
+    std::thread::spawn(move || {
+        for (id, stream) in listener.incoming().enumerate() {
+            let stream = match stream {
+                Ok(stream) => stream,
+                Err(e) => {
+                    tracing::warn!("failed to accept a player: {}", e);
+                    continue;
+                }
+            };
+            if let Err(e) = accept(id, stream, incoming.clone()) {
+                tracing::warn!("failed to set up player {}: {}", id, e);
+            }
+        }
+    });
+    Ok(())
+}
+
+fn accept(id: usize, stream: TcpStream, incoming: Sender<Incoming>) -> std::io::Result<()> {
+    stream.set_nodelay(true)?;
+    let (outgoing, to_send) = mpsc::channel::<String>();
+    let mut writer = stream.try_clone()?;
+    std::thread::spawn(move || {
+        for line in to_send {
+            if writer.write_all(line.as_bytes()).is_err() {
+                break;
+            }
+        }
+    });
+    let reader = BufReader::new(stream.try_clone()?);
+    if incoming
+        .send(Incoming::Connected {
+            id,
+            outgoing,
+            stream,
+        })
+        .is_err()
+    {
+        return Ok(());
+    }
+    std::thread::spawn(move || {
+        for line in reader.lines() {
+            let line = match line {
+                Ok(line) => line,
+                Err(_) => break,
+            };
+            match protocol::decode(&line) {
+                Ok(message) => {
+                    if incoming.send(Incoming::Message { id, message }).is_err() {
+                        return;
+                    }
+                }
+                Err(e) => tracing::warn!("bad message from player {}: {}", id, e),
+            }
+        }
+        let _ = incoming.send(Incoming::Disconnected { id });
+    });
+    Ok(())
+}
@@ -0,0 +1,233 @@
+use std::{
+    collections::BTreeMap,
+    net::{Shutdown, TcpStream},
+    path::Path,
+    sync::mpsc::Sender,
+};
+
+use grappling_hook_core::{
+    game_state::{GameEvent, GameState, Object},
+    level::Level,
+    protocol::{self, ClientMessage, ServerMessage},
+};
+
+use crate::console::{Command, Reply, HELP};
+use crate::net::Incoming;
+
+/// a connected player
+struct Client {
+    name: String,
+    /// the object this client controls
+    object: usize,
+    outgoing: Sender<String>,
+    stream: TcpStream,
+}
+
+impl Client {
+    fn send(&self, message: &ServerMessage) {
+        match protocol::encode(message) {
+            // a closed channel means the connection is going away, the reader will notice
+            Ok(line) => drop(self.outgoing.send(line)),
+            Err(e) => tracing::warn!("failed to encode message for {}: {}", self.name, e),
+        }
+    }
+}
+
+/// the level being run and everyone playing it
+pub struct Server {
+    level_name: String,
+    state: GameState,
+    /// what every player gets spawned as, taken from the player object of the level
+    player_template: Object,
+    clients: BTreeMap<usize, Client>,
+    pub quit: bool,
+}
+
+impl Server {
+    pub fn load(path: impl AsRef<Path>) -> color_eyre::Result<Self> {
+        let (level_name, state, player_template) = Self::load_level(&path)?;
+        tracing::info!("loaded {}", level_name);
+        Ok(Self {
+            level_name,
+            state,
+            player_template,
+            clients: BTreeMap::new(),
+            quit: false,
+        })
+    }
+
+    /// the state of a level with nobody in it yet, and what players in it look like
+    fn load_level(path: impl AsRef<Path>) -> color_eyre::Result<(String, GameState, Object)> {
+        let level = Level::load(&path)?;
+        let mut state = GameState::from_level(&level);
+        state.remove_player(level.player);
+        state.despawn_object(level.player);
+        Ok((
+            Level::name_from_path(&path),
+            state,
+            level.objects[level.player].clone(),
+        ))
+    }
+
+    pub fn handle(&mut self, incoming: Incoming) {
+        match incoming {
+            Incoming::Connected {
+                id,
+                outgoing,
+                stream,
+            } => {
+                let object = self.spawn_player();
+                let client = Client {
+                    name: format!("player {}", id),
+                    object,
+                    outgoing,
+                    stream,
+                };
+                client.send(&self.welcome(object));
+                self.clients.insert(id, client);
+            }
+            Incoming::Message { id, message } => {
+                let client = match self.clients.get_mut(&id) {
+                    Some(client) => client,
+                    None => return,
+                };
+                match message {
+                    ClientMessage::Hello { name } => {
+                        tracing::info!("{} joined as {}", name, id);
+                        client.name = name;
+                    }
+                    ClientMessage::Input(event) => {
+                        self.state.submit_event_for(client.object, event)
+                    }
+                }
+            }
+            Incoming::Disconnected { id } => {
+                if let Some(client) = self.clients.remove(&id) {
+                    tracing::info!("{} left", client.name);
+                    self.state.remove_player(client.object);
+                    self.state.despawn_object(client.object);
+                }
+            }
+            Incoming::Command { line, mut reply } => match Command::parse(&line) {
+                Ok(command) => self.run(command, &mut reply),
+                Err(e) => reply.println(&e),
+            },
+        }
+    }
+
+    fn run(&mut self, command: Command, reply: &mut Reply) {
+        match command {
+            Command::Help => reply.println(HELP),
+            Command::List => {
+                reply.println(&format!(
+                    "{} at tick {}, {} playing",
+                    self.level_name,
+                    self.state.tick,
+                    self.clients.len()
+                ));
+                for (id, client) in &self.clients {
+                    reply.println(&format!("  {}: {}", id, client.name));
+                }
+            }
+            Command::Load(path) => match Self::load_level(&path) {
+                Ok((level_name, state, player_template)) => {
+                    self.level_name = level_name;
+                    self.state = state;
+                    self.player_template = player_template;
+                    // the old objects went away with the old state, so everyone starts over
+                    let ids = self.clients.keys().copied().collect::<Vec<_>>();
+                    for id in ids {
+                        let object = self.spawn_player();
+                        let welcome = self.welcome(object);
+                        if let Some(client) = self.clients.get_mut(&id) {
+                            client.object = object;
+                            client.send(&welcome);
+                        }
+                    }
+                    reply.println(&format!("loaded {}", self.level_name));
+                }
+                Err(e) => reply.println(&format!("failed to load {}: {:?}", path, e)),
+            },
+            Command::Kick { id, reason } => match self.clients.remove(&id) {
+                Some(client) => {
+                    client.send(&ServerMessage::Kicked { reason });
+                    // lets the writer thread get the message out before the connection closes
+                    drop(client.outgoing);
+                    let _ = client.stream.shutdown(Shutdown::Read);
+                    self.state.remove_player(client.object);
+                    self.state.despawn_object(client.object);
+                    reply.println(&format!("kicked {}", client.name));
+                }
+                None => reply.println(&format!("nobody has the id {}", id)),
+            },
+            Command::Quit => {
+                for client in self.clients.values() {
+                    client.send(&ServerMessage::Kicked {
+                        reason: "the server is shutting down".to_owned(),
+                    });
+                }
+                self.quit = true;
+            }
+        }
+    }
+
+    /// runs a single update and tells everyone where things ended up
+    pub fn tick(&mut self, dt: f64) {
+        self.state.update(dt);
+        let died = self
+            .state
+            .drain_events()
+            .filter_map(|event| match event {
+                GameEvent::Crushed { object }
+                | GameEvent::Killed { object }
+                | GameEvent::OutOfBounds {
+                    object,
+                    killed: true,
+                } => Some(object),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        let ids = self
+            .clients
+            .iter()
+            .filter(|(_, client)| died.contains(&client.object))
+            .map(|(id, _)| *id)
+            .collect::<Vec<_>>();
+        for id in ids {
+            self.respawn(id);
+        }
+        let snapshot = protocol::snapshot(&self.state);
+        for client in self.clients.values() {
+            client.send(&snapshot);
+        }
+    }
+
+    fn spawn_player(&mut self) -> usize {
+        let object = self.state.spawn_object(self.player_template.clone());
+        self.state.add_player(object);
+        object
+    }
+
+    /// gives a client a fresh object at the start of the level, in place of the one it had
+    fn respawn(&mut self, id: usize) {
+        let old = match self.clients.get(&id) {
+            Some(client) => client.object,
+            None => return,
+        };
+        self.state.remove_player(old);
+        self.state.despawn_object(old);
+        let object = self.spawn_player();
+        let welcome = self.welcome(object);
+        if let Some(client) = self.clients.get_mut(&id) {
+            client.object = object;
+            client.send(&welcome);
+        }
+    }
+
+    fn welcome(&self, object: usize) -> ServerMessage {
+        ServerMessage::Welcome {
+            level: self.level_name.clone(),
+            object,
+        }
+    }
+}
@@ -0,0 +1,83 @@
+use serde::{Deserialize, Serialize};
+
+use crate::console::Console;
+
+/// options for playing with a screen reader, or without things moving around the screen
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct Accessibility {
+    /// turns off the screen effects, the camera moving by itself at the start of levels and the
+    /// slow motion when the hook connects
+    #[serde(default)]
+    pub reduced_motion: bool,
+    /// says what's picked in menus, by printing it and putting it in the window title, which
+    /// screen readers read out
+    #[serde(default = "announce_by_default")]
+    pub announce_menus: bool,
+}
+
+fn announce_by_default() -> bool {
+    true
+}
+
+impl Default for Accessibility {
+    fn default() -> Self {
+        Self {
+            reduced_motion: false,
+            announce_menus: announce_by_default(),
+        }
+    }
+}
+
+/// tells screen readers what's focused, only when it changes so the same thing isn't read out
+/// over and over
+#[derive(Default)]
+pub struct Announcer {
+    last: Option<String>,
+}
+
+impl Announcer {
+    const TITLE: &'static str = "Grappling Hook";
+
+    pub fn announce(&mut self, focus: Option<String>, window: &winit::window::Window) {
+        if focus == self.last {
+            return;
+        }
+        match &focus {
+            Some(focus) => {
+                println!("{}", focus);
+                window.set_title(&format!("{} - {}", Self::TITLE, focus));
+            }
+            None => window.set_title(Self::TITLE),
+        }
+        self.last = focus;
+    }
+}
+
+/// the console command for changing accessibility options, they get saved right away
+pub fn register_commands(console: &mut Console) {
+    const USAGE: &str = "accessibility <motion|announce> <on|off>";
+    console.register("accessibility", USAGE, |args, context| {
+        let on = match args.get(1) {
+            Some(&"on") => true,
+            Some(&"off") => false,
+            _ => return Err(format!("usage: {}", USAGE)),
+        };
+        let accessibility = &mut context.settings.accessibility;
+        let changed = match args.first() {
+            Some(&"motion") => {
+                accessibility.reduced_motion = on;
+                "reduced motion"
+            }
+            Some(&"announce") => {
+                accessibility.announce_menus = on;
+                "menu announcements"
+            }
+            _ => return Err(format!("usage: {}", USAGE)),
+        };
+        context
+            .settings
+            .save(context.profile.settings_path())
+            .map_err(|e| format!("{:#}", e))?;
+        Ok(format!("{} set to {}", changed, on))
+    });
+}
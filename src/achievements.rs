@@ -0,0 +1,206 @@
+use std::{
+    collections::BTreeSet,
+    path::{Path, PathBuf},
+    time::Instant,
+};
+
+use color_eyre::eyre::WrapErr;
+use serde::{Deserialize, Serialize};
+
+use crate::game_state::{GameEvent, GameState};
+use crate::locale::Strings;
+use crate::session::Completion;
+use crate::stats::RunTracker;
+use crate::text::Text;
+
+/// what has to happen for an achievement to unlock
+#[derive(Clone, Debug, Deserialize)]
+pub enum Condition {
+    /// reach the goal without landing on anything after taking off with the first hook
+    FinishWithoutTouchingGround,
+    /// reach the goal in less than this many seconds
+    FinishUnder(f64),
+    /// swing faster than this
+    SwingSpeed(f64),
+    /// fire this many hooks in a single run
+    HooksInRun(u32),
+    /// knock this many things out of the world in a single run
+    ObjectsDestroyed(u32),
+    /// get into the trigger region with this name
+    EnterTrigger(String),
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct Definition {
+    pub id: String,
+    pub name: String,
+    pub description: String,
+    pub condition: Condition,
+}
+
+/// the achievements that have been unlocked so far, kept in a file between sessions
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+struct Progress {
+    unlocked: BTreeSet<String>,
+}
+
+pub struct Achievements {
+    definitions: Vec<Definition>,
+    progress: Progress,
+    /// where unlocking something saves to
+    path: PathBuf,
+    /// recently unlocked achievements, and when they were unlocked
+    toasts: Vec<(usize, Instant)>,
+}
+
+impl Achievements {
+    pub const DEFINITIONS: &'static str = "assets/achievements.ron";
+    pub const PATH: &'static str = "achievements.ron";
+    /// how long the notification stays up, in seconds
+    const TOAST_TIME: f64 = 4.0;
+
+    /// broken or missing files just mean there are no achievements to get, or none gotten yet,
+    /// unlocking more saves back to `path`
+    pub fn load(definitions: impl AsRef<Path>, path: impl AsRef<Path>) -> Self {
+        let definitions = definitions.as_ref();
+        let definitions = match std::fs::read_to_string(definitions) {
+            Ok(text) => ron::from_str(&text).unwrap_or_else(|e| {
+                tracing::warn!(
+                    "failed to parse achievements {}: {}",
+                    definitions.display(),
+                    e
+                );
+                vec![]
+            }),
+            Err(e) => {
+                tracing::warn!(
+                    "failed to read achievements {}: {}",
+                    definitions.display(),
+                    e
+                );
+                vec![]
+            }
+        };
+        let progress = std::fs::read_to_string(path.as_ref())
+            .ok()
+            .and_then(|text| ron::from_str(&text).ok())
+            .unwrap_or_default();
+        Self {
+            definitions,
+            progress,
+            path: path.as_ref().to_owned(),
+            toasts: vec![],
+        }
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> color_eyre::Result<()> {
+        let path = path.as_ref();
+        let text = ron::ser::to_string_pretty(&self.progress, ron::ser::PrettyConfig::default())?;
+        std::fs::write(path, text)
+            .wrap_err_with(|| format!("failed to write achievements {}", path.display()))
+    }
+
+    /// how many achievements have been gotten
+    pub fn unlocked(&self) -> usize {
+        self.progress.unlocked.len()
+    }
+
+    /// the ids of everything unlocked so far
+    #[cfg(feature = "steam")]
+    pub fn unlocked_ids(&self) -> impl Iterator<Item = &str> {
+        self.progress.unlocked.iter().map(String::as_str)
+    }
+
+    /// listens to events coming out of the game, `run` should already have seen `event`
+    pub fn on_event(&mut self, event: &GameEvent, run: &RunTracker, state: &GameState) {
+        match *event {
+            GameEvent::HookFired { .. } => self.check(|condition| match *condition {
+                Condition::HooksInRun(count) => run.hooks_fired >= count,
+                _ => false,
+            }),
+            GameEvent::ObjectDestroyed { .. } => self.check(|condition| match *condition {
+                Condition::ObjectsDestroyed(count) => run.objects_destroyed >= count,
+                _ => false,
+            }),
+            GameEvent::TriggerFired { trigger, object } if object == state.view_object => {
+                let name = &state.triggers[trigger].name;
+                self.check(|condition| match condition {
+                    Condition::EnterTrigger(trigger) => trigger == name,
+                    _ => false,
+                })
+            }
+            _ => {}
+        }
+    }
+
+    /// call every frame while playing, for the things that aren't tied to an event
+    pub fn observe(&mut self, run: &RunTracker) {
+        self.check(|condition| match *condition {
+            Condition::SwingSpeed(speed) => run.max_swing_speed > speed,
+            _ => false,
+        })
+    }
+
+    pub fn on_completion(&mut self, completion: &Completion) {
+        self.check(|condition| match *condition {
+            Condition::FinishWithoutTouchingGround => {
+                completion.run.hooks_fired > 0 && !completion.run.touched_ground
+            }
+            Condition::FinishUnder(time) => completion.time < time,
+            _ => false,
+        })
+    }
+
+    /// unlocks every locked achievement whose condition passes, saving if anything changed
+    fn check(&mut self, passes: impl Fn(&Condition) -> bool) {
+        let mut changed = false;
+        for (index, definition) in self.definitions.iter().enumerate() {
+            if self.progress.unlocked.contains(&definition.id) || !passes(&definition.condition) {
+                continue;
+            }
+            tracing::info!("achievement unlocked: {}", definition.name);
+            self.progress.unlocked.insert(definition.id.clone());
+            self.toasts.push((index, Instant::now()));
+            changed = true;
+        }
+        if changed {
+            if let Err(e) = self.save(&self.path) {
+                tracing::warn!("failed to save achievements: {}", e);
+            }
+        }
+    }
+
+    /// the notifications for recently unlocked achievements, fading out as they expire
+    pub fn toasts(&mut self, strings: &Strings) -> Vec<Text> {
+        let now = Instant::now();
+        self.toasts
+            .retain(|(_, unlocked)| (now - *unlocked).as_secs_f64() < Self::TOAST_TIME);
+        let mut text = vec![];
+        for (i, (index, unlocked)) in self.toasts.iter().enumerate() {
+            let definition = &self.definitions[*index];
+            // translations are optional, the definitions themselves are in English
+            let name = strings
+                .lookup(&format!("achievement.{}.name", definition.id))
+                .unwrap_or(&definition.name);
+            let description = strings
+                .lookup(&format!("achievement.{}.description", definition.id))
+                .unwrap_or(&definition.description);
+            let remaining = Self::TOAST_TIME - (now - *unlocked).as_secs_f64();
+            let alpha = remaining.min(1.0) as f32;
+            let y = 840.0 - i as f32 * 60.0;
+            text.push(
+                Text::new(
+                    strings.format("achievement.unlocked", &[("name", &name)]),
+                    (40.0, y),
+                )
+                .with_color([1.0, 0.85, 0.3, alpha]),
+            );
+            text.push(
+                Text::new(description, (40.0, y + 28.0))
+                    .with_size(18.0)
+                    .with_color([0.8, 0.8, 0.8, alpha]),
+            );
+        }
+        text
+    }
+}
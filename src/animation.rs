@@ -0,0 +1,192 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use serde::Deserialize;
+
+use crate::game_state::GameState;
+
+/// what the player is doing, each one plays its own clip
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Deserialize)]
+pub enum MovementState {
+    Idle,
+    Run,
+    /// in the air without the hook attached, jumping or falling
+    Jump,
+    Swing,
+}
+
+impl MovementState {
+    /// slower than this along the ground counts as standing still
+    const RUN_SPEED: f64 = 0.5;
+
+    /// what the view object is doing, None if it's gone
+    pub fn of(state: &GameState) -> Option<Self> {
+        let object = state.object(state.view_object)?;
+        Some(if state.is_swinging() {
+            MovementState::Swing
+        } else if !object.is_on_ground(state.down_at(object.center())) {
+            MovementState::Jump
+        } else if object.get_velocity().x.abs() > Self::RUN_SPEED {
+            MovementState::Run
+        } else {
+            MovementState::Idle
+        })
+    }
+}
+
+/// what happens once a clip gets to its last frame
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+pub enum Playback {
+    /// starts over from the first frame
+    Loop,
+    /// stays on the last frame
+    Once,
+    /// plays backwards to the first frame, then forwards again
+    PingPong,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize)]
+pub struct Frame {
+    /// which cell of the atlas, counting left to right and then top to bottom
+    pub cell: u32,
+    /// how long it stays up, in seconds
+    pub duration: f64,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct Clip {
+    pub playback: Playback,
+    pub frames: Vec<Frame>,
+}
+
+impl Clip {
+    /// the cell showing `time` seconds after the clip started, None if it has no frames
+    pub fn cell_at(&self, time: f64) -> Option<u32> {
+        let length = self.frames.iter().map(|frame| frame.duration).sum::<f64>();
+        if length <= 0.0 {
+            return self.frames.first().map(|frame| frame.cell);
+        }
+        let time = match self.playback {
+            Playback::Loop => time.rem_euclid(length),
+            Playback::Once => time.min(length),
+            Playback::PingPong => {
+                let time = time.rem_euclid(2.0 * length);
+                if time < length {
+                    time
+                } else {
+                    2.0 * length - time
+                }
+            }
+        };
+        let mut end = 0.0;
+        for frame in &self.frames {
+            end += frame.duration;
+            if time < end {
+                return Some(frame.cell);
+            }
+        }
+        self.frames.last().map(|frame| frame.cell)
+    }
+}
+
+/// the clips out of one sprite atlas, and which one plays in each movement state
+#[derive(Clone, Debug, Deserialize)]
+pub struct Animations {
+    /// the atlas is split into a grid of cells this many across
+    pub columns: u32,
+    /// and this many down
+    pub rows: u32,
+    pub clips: HashMap<String, Clip>,
+    pub states: HashMap<MovementState, String>,
+}
+
+impl Animations {
+    pub const DEFINITIONS: &'static str = "assets/animations.ron";
+
+    /// a broken or missing file just means the player doesn't get animated
+    pub fn load(path: impl AsRef<Path>) -> Option<Self> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| tracing::warn!("failed to read animations {}: {}", path.display(), e))
+            .ok()?;
+        let animations: Self = ron::from_str(&text)
+            .map_err(|e| tracing::warn!("failed to parse animations {}: {}", path.display(), e))
+            .ok()?;
+        if animations.columns == 0 || animations.rows == 0 {
+            tracing::warn!("the atlas in {} has no cells", path.display());
+            return None;
+        }
+        Some(animations)
+    }
+
+    /// where `cell` is in the atlas, as [u, v, width, height] in texture coordinates
+    fn cell_rect(&self, cell: u32) -> [f32; 4] {
+        let (width, height) = (1.0 / self.columns as f32, 1.0 / self.rows as f32);
+        let (column, row) = (cell % self.columns, cell / self.columns);
+        [column as f32 * width, row as f32 * height, width, height]
+    }
+}
+
+/// which clip the player is playing and how far into it they are, stepped along with the game
+#[derive(Clone, Copy, Debug)]
+pub struct Animator {
+    movement: MovementState,
+    /// seconds since the clip started, as of the last tick
+    clock: f64,
+    /// the same, but the tick before that, to interpolate between
+    last_clock: f64,
+    /// the sprites face right, so they get flipped when moving left
+    facing_left: bool,
+}
+
+impl Default for Animator {
+    fn default() -> Self {
+        Self {
+            movement: MovementState::Idle,
+            clock: 0.0,
+            last_clock: 0.0,
+            facing_left: false,
+        }
+    }
+}
+
+impl Animator {
+    /// moves the clock along after an update, switching clips when the movement state changes
+    pub fn tick(&mut self, state: &GameState, dt: f64) {
+        let movement = match MovementState::of(state) {
+            Some(movement) => movement,
+            None => return,
+        };
+        if movement != self.movement {
+            self.movement = movement;
+            self.clock = 0.0;
+            self.last_clock = 0.0;
+        } else {
+            self.last_clock = self.clock;
+            self.clock += dt;
+        }
+        let velocity = state
+            .object(state.view_object)
+            .map(|object| object.get_velocity().x)
+            .unwrap_or(0.0);
+        // keep facing the same way when stopping, rather than snapping back to the right
+        if velocity.abs() > MovementState::RUN_SPEED {
+            self.facing_left = velocity < 0.0;
+        }
+    }
+
+    /// the frame to draw, `interpolate` of the way from the last tick to the current one, as
+    /// [u, v, width, height] in the atlas
+    pub fn frame(&self, animations: &Animations, interpolate: f64) -> Option<[f32; 4]> {
+        let clip = animations
+            .clips
+            .get(animations.states.get(&self.movement)?)?;
+        let time = self.last_clock + (self.clock - self.last_clock) * interpolate;
+        let [u, v, width, height] = animations.cell_rect(clip.cell_at(time)?);
+        Some(if self.facing_left {
+            [u + width, v, -width, height]
+        } else {
+            [u, v, width, height]
+        })
+    }
+}
@@ -0,0 +1,183 @@
+use crate::console::{self, Console};
+use crate::game_state::{AssistMode, ButtonState, Event, GameState, HookState};
+
+/// optional help for the player, none of it changes how the game itself plays, only what input
+/// gets handed to it
+#[derive(Clone, Copy, Debug)]
+pub struct Assists {
+    /// show where the hook would go and how you'd swing while aiming
+    pub trajectory_preview: bool,
+    /// the hook goes out as soon as the button is pressed instead of when it's let go, and the
+    /// next press lets go of it, so nothing has to be held down
+    pub toggle_hook: bool,
+    /// how far off something the hook can stick to the aim can be and still get moved onto it,
+    /// in degrees, 0 turns it off
+    pub aim_snap: f64,
+}
+
+impl Default for Assists {
+    fn default() -> Self {
+        Self {
+            trajectory_preview: true,
+            toggle_hook: false,
+            aim_snap: 0.0,
+        }
+    }
+}
+
+impl Assists {
+    /// how many rays get tried on each side of the aim when snapping
+    const SNAP_STEPS: u32 = 12;
+    /// how far past the edge of what it snapped to the aim goes, so shots that only just reach
+    /// it still stick
+    const SNAP_DEPTH: f64 = 0.3;
+    /// the widest cone `aim_snap` can be set to, either side of the aim
+    pub const MAX_AIM_SNAP: f64 = 45.0;
+
+    /// what the game gets handed for the fire button going down or coming up with the cursor at
+    /// `target`
+    pub fn hook_events(
+        &self,
+        state: &GameState,
+        pressed: bool,
+        target: cgmath::Point2<f64>,
+    ) -> Vec<Event> {
+        let event = |state| Event::Hook { state, target };
+        let fire = || Event::Hook {
+            state: ButtonState::Released,
+            target: self.snap_aim(state, target),
+        };
+        if !self.toggle_hook {
+            return vec![if pressed {
+                event(ButtonState::Pressed)
+            } else {
+                fire()
+            }];
+        }
+        if !pressed {
+            return vec![];
+        }
+        let hook_out = state
+            .player_input(state.view_object)
+            .is_some_and(|input| input.hook != HookState::Idle);
+        if hook_out {
+            // a press lets go of it, same as without the assist
+            vec![event(ButtonState::Pressed)]
+        } else {
+            vec![event(ButtonState::Pressed), fire()]
+        }
+    }
+
+    /// whether to show the trajectory preview, with the toggle hook there's no holding the button
+    /// down to aim so it shows whenever the hook is ready
+    pub fn show_preview(&self, state: &GameState) -> bool {
+        if !self.trajectory_preview {
+            return false;
+        }
+        match state.player_input(state.view_object) {
+            Some(input) if self.toggle_hook => input.hook == HookState::Idle,
+            Some(input) => input.aiming,
+            None => false,
+        }
+    }
+
+    /// where the hook should be aimed to go at `target`, moved onto the closest thing it can
+    /// reach within `aim_snap` degrees if it would miss everything
+    pub fn snap_aim(&self, state: &GameState, target: cgmath::Point2<f64>) -> cgmath::Point2<f64> {
+        let player = match state.object(state.view_object) {
+            Some(player) => player.center(),
+            None => return target,
+        };
+        let to_target = target - player;
+        if self.aim_snap <= 0.0 || to_target.x == 0.0 && to_target.y == 0.0 {
+            return target;
+        }
+        let aim = to_target.y.atan2(to_target.x);
+        let step = self.aim_snap.to_radians() / Self::SNAP_STEPS as f64;
+        // straight ahead first, then further and further out on both sides
+        let offsets = (0..=Self::SNAP_STEPS).flat_map(|i| [i as f64 * step, -(i as f64) * step]);
+        for offset in offsets {
+            let direction = cgmath::vec2((aim + offset).cos(), (aim + offset).sin());
+            let reach = player + direction * GameState::HOOK_RANGE;
+            if let Some((_, hit)) = state.raycast(player, reach, Some(state.view_object)) {
+                if offset == 0.0 {
+                    return target;
+                }
+                return hit + direction * Self::SNAP_DEPTH;
+            }
+        }
+        target
+    }
+}
+
+/// the console commands for changing assists, and the assist mode, which does change how the
+/// game plays, so it gets saved right away and goes for the level being played as well
+pub fn register_commands(console: &mut Console) {
+    const ASSIST: &str = "assist <preview|toggle|snap> <on|off|degrees>";
+    console.register("assist", ASSIST, |args, context| {
+        let on = || match args.get(1) {
+            Some(&"on") => Ok(true),
+            Some(&"off") => Ok(false),
+            _ => Err(format!("usage: {}", ASSIST)),
+        };
+        let assists = &mut *context.assists;
+        match args.first() {
+            Some(&"preview") => {
+                assists.trajectory_preview = on()?;
+                Ok(format!(
+                    "trajectory preview set to {}",
+                    assists.trajectory_preview
+                ))
+            }
+            Some(&"toggle") => {
+                assists.toggle_hook = on()?;
+                Ok(format!("toggle hook set to {}", assists.toggle_hook))
+            }
+            Some(&"snap") => {
+                let degrees = match args.get(1) {
+                    Some(&"off") => 0.0,
+                    _ => console::number(args, 1, ASSIST)?,
+                };
+                assists.aim_snap = degrees.clamp(0.0, Assists::MAX_AIM_SNAP);
+                Ok(format!("aim snap set to {} degrees", assists.aim_snap))
+            }
+            _ => Err(format!("usage: {}", ASSIST)),
+        }
+    });
+
+    const MODE: &str = "assistmode <speed <0.5 to 1>|hooks <on|off>|nodeath <on|off>>";
+    console.register("assistmode", MODE, |args, context| {
+        let on = || match args.get(1) {
+            Some(&"on") => Ok(true),
+            Some(&"off") => Ok(false),
+            _ => Err(format!("usage: {}", MODE)),
+        };
+        let mode = &mut context.settings.assist_mode;
+        let changed = match args.first() {
+            Some(&"speed") => {
+                mode.game_speed =
+                    console::number(args, 1, MODE)?.clamp(AssistMode::MIN_GAME_SPEED, 1.0);
+                format!("game speed set to {}", mode.game_speed)
+            }
+            Some(&"hooks") => {
+                mode.infinite_hooks = on()?;
+                format!("infinite hooks set to {}", mode.infinite_hooks)
+            }
+            Some(&"nodeath") => {
+                mode.no_death = on()?;
+                format!("no death set to {}", mode.no_death)
+            }
+            _ => return Err(format!("usage: {}", MODE)),
+        };
+        let mode = *mode;
+        context.time_scale.assist = mode.game_speed;
+        if let Ok(session) = context.session() {
+            session.set_assist_mode(mode);
+        }
+        context
+            .settings
+            .save(context.profile.settings_path())
+            .map_err(|e| format!("{:#}", e))?;
+        Ok(changed)
+    });
+}
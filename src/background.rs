@@ -0,0 +1,107 @@
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+use winit::event_loop::ControlFlow;
+
+use crate::console::Console;
+
+/// what drawing does while the window is unfocused or minimized, the game keeps going either way
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BackgroundMode {
+    /// keeps drawing as fast as it can
+    Full,
+    /// draws a few frames a second, enough to see what's going on from another window
+    #[default]
+    Throttled,
+    /// draws nothing until the window comes back
+    Paused,
+}
+
+impl BackgroundMode {
+    fn parse(name: &str) -> Option<Self> {
+        match name {
+            "full" => Some(BackgroundMode::Full),
+            "throttled" => Some(BackgroundMode::Throttled),
+            "paused" => Some(BackgroundMode::Paused),
+            _ => None,
+        }
+    }
+}
+
+/// keeps track of whether the window is in the background, and slows the event loop down while
+/// it is so the game doesn't keep the gpu busy for nothing
+pub struct Throttle {
+    focused: bool,
+    minimized: bool,
+    last_frame: Instant,
+}
+
+impl Default for Throttle {
+    fn default() -> Self {
+        Self {
+            focused: true,
+            minimized: false,
+            last_frame: Instant::now(),
+        }
+    }
+}
+
+impl Throttle {
+    /// how often things happen in the background, drawn or not
+    const FRAME_TIME: Duration = Duration::from_millis(100);
+
+    pub fn on_focus(&mut self, focused: bool) {
+        self.focused = focused;
+    }
+
+    /// a minimized window gets resized down to nothing on most platforms
+    pub fn on_resize(&mut self, size: winit::dpi::PhysicalSize<u32>) {
+        self.minimized = size.width == 0 || size.height == 0;
+    }
+
+    pub fn in_background(&self) -> bool {
+        !self.focused || self.minimized
+    }
+
+    /// whether to draw a frame now, the first one back in the foreground always is
+    pub fn should_render(&mut self, mode: BackgroundMode, now: Instant) -> bool {
+        let render = match (self.in_background(), mode) {
+            (false, _) | (true, BackgroundMode::Full) => true,
+            // nothing can be seen of a minimized window anyway
+            (true, BackgroundMode::Throttled) => {
+                !self.minimized && now - self.last_frame >= Self::FRAME_TIME
+            }
+            (true, BackgroundMode::Paused) => false,
+        };
+        if render {
+            self.last_frame = now;
+        }
+        render
+    }
+
+    /// how long the event loop should wait before coming back around, when nothing else happens
+    pub fn control_flow(&self, mode: BackgroundMode, now: Instant) -> ControlFlow {
+        if self.in_background() && mode != BackgroundMode::Full {
+            ControlFlow::WaitUntil(now + Self::FRAME_TIME)
+        } else {
+            ControlFlow::Poll
+        }
+    }
+}
+
+/// the console command for picking what happens in the background, it gets saved right away
+pub fn register_commands(console: &mut Console) {
+    const USAGE: &str = "background <full|throttled|paused>";
+    console.register("background", USAGE, |args, context| {
+        let mode = match args {
+            [mode] => BackgroundMode::parse(mode).ok_or(format!("usage: {}", USAGE))?,
+            _ => return Err(format!("usage: {}", USAGE)),
+        };
+        context.settings.background = mode;
+        context
+            .settings
+            .save(context.profile.settings_path())
+            .map_err(|e| format!("{:#}", e))?;
+        Ok(format!("background mode set to {:?}", mode))
+    });
+}
@@ -0,0 +1,417 @@
+use serde::{Deserialize, Serialize};
+use winit::event::{MouseButton, VirtualKeyCode};
+
+use crate::game_state::{ButtonState, Direction};
+use crate::level::Lesson;
+
+/// something a player can do, whichever button on whichever device it came from
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Action {
+    MoveLeft,
+    MoveRight,
+    Jump,
+    FireHook,
+    /// the other hook, in levels that give players two
+    FireSecondHook,
+    /// pulls down, which is also how no-clip goes downwards
+    Reel,
+    /// leaves the level for the level select
+    Pause,
+    /// opens and closes the console
+    DebugToggle,
+    /// in practice mode, saves where the player is
+    SaveState,
+    /// in practice mode, goes back to where the player saved
+    LoadState,
+    /// starts the level over, keeping the run that was going in the stats
+    Restart,
+    /// moves the selection in a menu one up
+    MenuUp,
+    /// moves the selection in a menu one down
+    MenuDown,
+    /// turns what's selected down, in menus with settings
+    MenuLeft,
+    /// turns what's selected up, in menus with settings
+    MenuRight,
+    /// picks what's selected, or on the level complete screen, goes again
+    Confirm,
+    /// leaves a menu for the one before it, or quits from the level select
+    Back,
+    /// on the level select, watches the best run through the selected level
+    WatchBest,
+    /// on the level select, turns co-op on or off
+    ToggleCoop,
+    /// on the level select, goes to the save slots
+    SaveSlots,
+    /// on the level select, goes to the gamepad settings
+    GamepadSettings,
+    /// in a replay, stops it where it is or carries on
+    PlayPause,
+    /// in a replay, goes back a tick
+    StepBack,
+    /// in a replay, goes forward a tick
+    StepForward,
+    /// the debug pause, where the game only moves on with `Step`
+    ToggleStepping,
+    /// runs one tick while the debug pause is on
+    Step,
+    /// the debug speeds, slowest first, see `TimeScale::DEBUG_SPEEDS`
+    DebugSpeed1,
+    DebugSpeed2,
+    DebugSpeed3,
+    DebugSpeed4,
+    DebugSpeed5,
+    CycleMsaa,
+    TogglePostProcessing,
+    ToggleLimbBody,
+    ToggleTrajectoryPreview,
+    ToggleFreeCamera,
+    ToggleNoClip,
+    ToggleInspector,
+    /// picking things up and throwing them around with the mouse
+    ToggleDragMode,
+    TogglePaths,
+    ToggleInputDisplay,
+    CycleColorScheme,
+}
+
+impl Action {
+    /// which of the debug speeds this picks, None for the ones that don't
+    pub fn debug_speed(self) -> Option<usize> {
+        match self {
+            Action::DebugSpeed1 => Some(0),
+            Action::DebugSpeed2 => Some(1),
+            Action::DebugSpeed3 => Some(2),
+            Action::DebugSpeed4 => Some(3),
+            Action::DebugSpeed5 => Some(4),
+            _ => None,
+        }
+    }
+
+    /// the movement key the game gets for this, None for the ones that aren't one
+    pub fn direction(self) -> Option<Direction> {
+        match self {
+            Action::MoveLeft => Some(Direction::Left),
+            Action::MoveRight => Some(Direction::Right),
+            Action::Jump => Some(Direction::Up),
+            Action::Reel => Some(Direction::Down),
+            _ => None,
+        }
+    }
+
+    /// the actions that do what a tutorial asks
+    pub fn for_lesson(lesson: Lesson) -> &'static [Action] {
+        match lesson {
+            Lesson::Move => &[Action::MoveLeft, Action::MoveRight],
+            Lesson::Jump => &[Action::Jump],
+            Lesson::Reel => &[Action::Reel],
+            Lesson::FireHook => &[Action::FireHook],
+            Lesson::FireSecondHook => &[Action::FireSecondHook],
+        }
+    }
+}
+
+/// an action starting or stopping, or for moving with a stick, how far it's pushed
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ActionEvent {
+    Button(Action, ButtonState),
+    /// from -1 for all the way left to 1 for all the way right, it only counts while neither
+    /// `MoveLeft` nor `MoveRight` is held, which are always all the way
+    Move(f64),
+}
+
+/// the gamepad buttons that can be bound, by where they are on the pad rather than what's
+/// printed on them
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum GamepadButton {
+    South,
+    East,
+    North,
+    West,
+    LeftTrigger,
+    LeftTrigger2,
+    RightTrigger,
+    RightTrigger2,
+    Select,
+    Start,
+}
+
+/// one button on one device
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Input {
+    /// a key by where it is on the keyboard, so it stays put whatever the layout
+    Scancode(u32),
+    /// a key by what it's labelled
+    Key(VirtualKeyCode),
+    Mouse(MouseButton),
+    Gamepad(GamepadButton),
+}
+
+impl Input {
+    /// what the button is called, for showing players which one to press, keys bound by where
+    /// they are go by what's there on a US keyboard
+    pub fn label(self) -> String {
+        const ROWS: [(u32, &str); 4] = [
+            (2, "1234567890"),
+            (16, "QWERTYUIOP"),
+            (30, "ASDFGHJKL"),
+            (44, "ZXCVBNM"),
+        ];
+        match self {
+            Input::Scancode(code) => {
+                let letter = ROWS.iter().find_map(|&(start, keys)| {
+                    let offset = code.checked_sub(start)? as usize;
+                    keys.get(offset..offset + 1)
+                });
+                match (letter, code) {
+                    (Some(letter), _) => letter.to_owned(),
+                    (None, 57) => "Space".to_owned(),
+                    (None, code) => format!("key {}", code),
+                }
+            }
+            Input::Key(key) => format!("{:?}", key),
+            Input::Mouse(MouseButton::Left) => "Left Mouse".to_owned(),
+            Input::Mouse(MouseButton::Right) => "Right Mouse".to_owned(),
+            Input::Mouse(MouseButton::Middle) => "Middle Mouse".to_owned(),
+            Input::Mouse(MouseButton::Other(button)) => format!("Mouse {}", button),
+            Input::Gamepad(button) => format!("Pad {:?}", button),
+        }
+    }
+}
+
+/// who an action is for
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Player {
+    First,
+    /// the second player in coop, who shares the keyboard
+    Partner,
+}
+
+/// which buttons do what, saved with the rest of the settings
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Bindings {
+    pub player: Vec<(Input, Action)>,
+    /// only looked at while there's a second player, before `player`
+    pub partner: Vec<(Input, Action)>,
+    /// for getting around the menus and starting levels over, kept apart from `player` so the
+    /// same keys can do both
+    #[serde(default = "default_menu")]
+    pub menu: Vec<(Input, Action)>,
+    /// the debug keys, which work on every screen, looked at before everything else
+    #[serde(default = "default_debug")]
+    pub debug: Vec<(Input, Action)>,
+}
+
+fn default_menu() -> Vec<(Input, Action)> {
+    use Action::*;
+    vec![
+        (Input::Scancode(17), MenuUp),
+        (Input::Key(VirtualKeyCode::Up), MenuUp),
+        (Input::Scancode(31), MenuDown),
+        (Input::Key(VirtualKeyCode::Down), MenuDown),
+        (Input::Scancode(30), MenuLeft),
+        (Input::Key(VirtualKeyCode::Left), MenuLeft),
+        (Input::Scancode(32), MenuRight),
+        (Input::Key(VirtualKeyCode::Right), MenuRight),
+        (Input::Key(VirtualKeyCode::Return), Confirm),
+        (Input::Key(VirtualKeyCode::Escape), Back),
+        (Input::Key(VirtualKeyCode::R), Restart),
+        (Input::Gamepad(GamepadButton::Select), Restart),
+        (Input::Key(VirtualKeyCode::V), WatchBest),
+        (Input::Key(VirtualKeyCode::Tab), ToggleCoop),
+        (Input::Key(VirtualKeyCode::P), SaveSlots),
+        (Input::Key(VirtualKeyCode::G), GamepadSettings),
+        (Input::Key(VirtualKeyCode::Space), PlayPause),
+        (Input::Key(VirtualKeyCode::Comma), StepBack),
+        (Input::Key(VirtualKeyCode::Period), StepForward),
+    ]
+}
+
+fn default_debug() -> Vec<(Input, Action)> {
+    use Action::*;
+    vec![
+        (Input::Key(VirtualKeyCode::F1), ToggleStepping),
+        (Input::Key(VirtualKeyCode::Period), Step),
+        (Input::Key(VirtualKeyCode::Key1), DebugSpeed1),
+        (Input::Key(VirtualKeyCode::Key2), DebugSpeed2),
+        (Input::Key(VirtualKeyCode::Key3), DebugSpeed3),
+        (Input::Key(VirtualKeyCode::Key4), DebugSpeed4),
+        (Input::Key(VirtualKeyCode::Key5), DebugSpeed5),
+        (Input::Key(VirtualKeyCode::F2), CycleMsaa),
+        (Input::Key(VirtualKeyCode::F3), TogglePostProcessing),
+        (Input::Key(VirtualKeyCode::F4), ToggleTrajectoryPreview),
+        (Input::Key(VirtualKeyCode::F5), ToggleFreeCamera),
+        (Input::Key(VirtualKeyCode::F6), ToggleNoClip),
+        (Input::Key(VirtualKeyCode::F7), ToggleInspector),
+        (Input::Key(VirtualKeyCode::F8), ToggleDragMode),
+        (Input::Key(VirtualKeyCode::F9), ToggleLimbBody),
+        (Input::Key(VirtualKeyCode::F10), TogglePaths),
+        (Input::Key(VirtualKeyCode::F11), ToggleInputDisplay),
+        (Input::Key(VirtualKeyCode::F12), CycleColorScheme),
+    ]
+}
+
+impl Default for Bindings {
+    fn default() -> Self {
+        use Action::*;
+        Self {
+            player: vec![
+                // WASD, tested on my keyboard
+                (Input::Scancode(30), MoveLeft),
+                (Input::Scancode(32), MoveRight),
+                (Input::Scancode(17), Jump),
+                (Input::Scancode(31), Reel),
+                (Input::Mouse(MouseButton::Left), FireHook),
+                (Input::Mouse(MouseButton::Right), FireSecondHook),
+                (Input::Key(VirtualKeyCode::Escape), Pause),
+                (Input::Key(VirtualKeyCode::Grave), DebugToggle),
+                // next to WASD, for going over a swing again without moving a hand
+                (Input::Scancode(16), SaveState),
+                (Input::Scancode(18), LoadState),
+                (Input::Gamepad(GamepadButton::South), Jump),
+                (Input::Gamepad(GamepadButton::RightTrigger), FireHook),
+                (Input::Gamepad(GamepadButton::RightTrigger2), FireHook),
+                (Input::Gamepad(GamepadButton::LeftTrigger), FireSecondHook),
+                (Input::Gamepad(GamepadButton::LeftTrigger2), FireSecondHook),
+                (Input::Gamepad(GamepadButton::Start), Pause),
+                (Input::Gamepad(GamepadButton::North), SaveState),
+                (Input::Gamepad(GamepadButton::West), LoadState),
+            ],
+            partner: vec![
+                (Input::Key(VirtualKeyCode::Left), MoveLeft),
+                (Input::Key(VirtualKeyCode::Right), MoveRight),
+                (Input::Key(VirtualKeyCode::Up), Jump),
+                (Input::Key(VirtualKeyCode::Down), Reel),
+            ],
+            menu: default_menu(),
+            debug: default_debug(),
+        }
+    }
+}
+
+impl Bindings {
+    /// what pressing any of `inputs` does, the first one bound wins, the partner's bindings are
+    /// only looked at with `partner` set
+    pub fn resolve(&self, inputs: &[Input], partner: bool) -> Option<(Player, Action)> {
+        let partner = match partner {
+            true => find(&self.partner, inputs).map(|action| (Player::Partner, action)),
+            false => None,
+        };
+        partner.or_else(|| find(&self.player, inputs).map(|action| (Player::First, action)))
+    }
+
+    /// what pressing any of `inputs` does in a menu, or for `Restart`, in a level
+    pub fn resolve_menu(&self, inputs: &[Input]) -> Option<Action> {
+        find(&self.menu, inputs)
+    }
+
+    /// which debug key any of `inputs` is
+    pub fn resolve_debug(&self, inputs: &[Input]) -> Option<Action> {
+        find(&self.debug, inputs)
+    }
+
+    /// what the first player's buttons for `actions` are called, each only once, menu actions
+    /// included
+    pub fn labels(&self, actions: &[Action]) -> Vec<String> {
+        let mut labels = Vec::new();
+        for (input, action) in self.player.iter().chain(&self.menu).chain(&self.debug) {
+            let label = input.label();
+            if actions.contains(action) && !labels.contains(&label) {
+                labels.push(label);
+            }
+        }
+        labels
+    }
+
+    /// the first player's action for `button`, which is all a gamepad can do, restarting
+    /// included
+    pub fn gamepad(&self, button: GamepadButton) -> Option<Action> {
+        self.player
+            .iter()
+            .chain(&self.menu)
+            .find(|&&(input, _)| input == Input::Gamepad(button))
+            .map(|&(_, action)| action)
+    }
+}
+
+/// the action the first of `inputs` with one is bound to, the first binding for it wins
+fn find(bindings: &[(Input, Action)], inputs: &[Input]) -> Option<Action> {
+    inputs.iter().find_map(|input| {
+        bindings
+            .iter()
+            .find(|(bound, _)| bound == input)
+            .map(|&(_, action)| action)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bindings() -> Bindings {
+        Bindings {
+            player: vec![
+                (Input::Scancode(17), Action::Jump),
+                (Input::Key(VirtualKeyCode::Up), Action::Jump),
+                (Input::Key(VirtualKeyCode::Up), Action::Reel),
+                (Input::Key(VirtualKeyCode::Space), Action::FireHook),
+            ],
+            partner: vec![(Input::Key(VirtualKeyCode::Up), Action::MoveLeft)],
+            menu: vec![
+                (Input::Key(VirtualKeyCode::Up), Action::MenuUp),
+                (Input::Key(VirtualKeyCode::Up), Action::MenuDown),
+            ],
+            debug: vec![(Input::Key(VirtualKeyCode::F1), Action::ToggleStepping)],
+        }
+    }
+
+    #[test]
+    fn the_partner_goes_first_only_with_one() {
+        let bindings = bindings();
+        let up = [Input::Key(VirtualKeyCode::Up)];
+        assert_eq!(
+            bindings.resolve(&up, true),
+            Some((Player::Partner, Action::MoveLeft))
+        );
+        assert_eq!(
+            bindings.resolve(&up, false),
+            Some((Player::First, Action::Jump))
+        );
+        // the partner having nothing for a key leaves it to the first player
+        let space = [Input::Key(VirtualKeyCode::Space)];
+        assert_eq!(
+            bindings.resolve(&space, true),
+            Some((Player::First, Action::FireHook))
+        );
+    }
+
+    #[test]
+    fn the_first_binding_wins() {
+        let bindings = bindings();
+        let up = [Input::Key(VirtualKeyCode::Up)];
+        assert_eq!(
+            bindings.resolve(&up, false),
+            Some((Player::First, Action::Jump))
+        );
+        assert_eq!(bindings.resolve_menu(&up), Some(Action::MenuUp));
+        // and of the inputs a key press comes with, the first that's bound
+        let both = [Input::Scancode(30), Input::Key(VirtualKeyCode::Up)];
+        assert_eq!(bindings.resolve_menu(&both), Some(Action::MenuUp));
+        let both = [Input::Scancode(17), Input::Key(VirtualKeyCode::Space)];
+        assert_eq!(
+            bindings.resolve(&both, false),
+            Some((Player::First, Action::Jump))
+        );
+    }
+
+    #[test]
+    fn each_list_only_has_its_own_actions() {
+        let bindings = bindings();
+        let f1 = [Input::Key(VirtualKeyCode::F1)];
+        assert_eq!(bindings.resolve(&f1, false), None);
+        assert_eq!(bindings.resolve_menu(&f1), None);
+        assert_eq!(bindings.resolve_debug(&f1), Some(Action::ToggleStepping));
+        let space = [Input::Key(VirtualKeyCode::Space)];
+        assert_eq!(bindings.resolve_menu(&space), None);
+    }
+}
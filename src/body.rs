@@ -0,0 +1,358 @@
+use std::f64::consts::{FRAC_PI_2, PI};
+
+use cgmath::prelude::*;
+
+use crate::game_state::GameState;
+use crate::instances;
+
+/// one part of the body, a box sticking out from where it's attached on its parent
+struct Bone {
+    /// None for the torso, which everything else hangs off of
+    parent: Option<usize>,
+    /// how far along the parent it's attached, 0 is where the parent starts and 1 where it ends
+    anchor: f64,
+    /// in units of the player's height
+    length: f64,
+    thickness: f64,
+    color: [f32; 4],
+}
+
+const TORSO: usize = 0;
+const HEAD: usize = 1;
+const BACK_UPPER_ARM: usize = 2;
+const BACK_FOREARM: usize = 3;
+const FRONT_UPPER_ARM: usize = 4;
+const FRONT_FOREARM: usize = 5;
+const BACK_THIGH: usize = 6;
+const BACK_SHIN: usize = 7;
+const FRONT_THIGH: usize = 8;
+const FRONT_SHIN: usize = 9;
+
+const SHIRT: [f32; 4] = [0.15, 0.35, 0.9, 1.0];
+const SKIN: [f32; 4] = [0.95, 0.78, 0.62, 1.0];
+const TROUSERS: [f32; 4] = [0.1, 0.15, 0.5, 1.0];
+/// the limbs on the far side are a bit darker, so they read as being behind
+const BACK_SHADE: f32 = 0.7;
+
+/// parents always come before their children, so transforms can be worked out in one pass, the
+/// legs, torso and head add up to the height of the player
+const BONES: [Bone; 10] = [
+    Bone {
+        parent: None,
+        anchor: 0.0,
+        length: 0.34,
+        thickness: 0.3,
+        color: SHIRT,
+    },
+    Bone {
+        parent: Some(TORSO),
+        anchor: 1.0,
+        length: 0.22,
+        thickness: 0.28,
+        color: SKIN,
+    },
+    Bone {
+        parent: Some(TORSO),
+        anchor: 0.9,
+        length: 0.2,
+        thickness: 0.1,
+        color: SHIRT,
+    },
+    Bone {
+        parent: Some(BACK_UPPER_ARM),
+        anchor: 1.0,
+        length: 0.2,
+        thickness: 0.09,
+        color: SKIN,
+    },
+    Bone {
+        parent: Some(TORSO),
+        anchor: 0.9,
+        length: 0.2,
+        thickness: 0.1,
+        color: SHIRT,
+    },
+    Bone {
+        parent: Some(FRONT_UPPER_ARM),
+        anchor: 1.0,
+        length: 0.2,
+        thickness: 0.09,
+        color: SKIN,
+    },
+    Bone {
+        parent: Some(TORSO),
+        anchor: 0.0,
+        length: 0.22,
+        thickness: 0.13,
+        color: TROUSERS,
+    },
+    Bone {
+        parent: Some(BACK_THIGH),
+        anchor: 1.0,
+        length: 0.22,
+        thickness: 0.11,
+        color: TROUSERS,
+    },
+    Bone {
+        parent: Some(TORSO),
+        anchor: 0.0,
+        length: 0.22,
+        thickness: 0.13,
+        color: TROUSERS,
+    },
+    Bone {
+        parent: Some(FRONT_THIGH),
+        anchor: 1.0,
+        length: 0.22,
+        thickness: 0.11,
+        color: TROUSERS,
+    },
+];
+
+/// back to front
+const DRAW_ORDER: [usize; 10] = [
+    BACK_UPPER_ARM,
+    BACK_FOREARM,
+    BACK_THIGH,
+    BACK_SHIN,
+    TORSO,
+    HEAD,
+    FRONT_THIGH,
+    FRONT_SHIN,
+    FRONT_UPPER_ARM,
+    FRONT_FOREARM,
+];
+
+/// the angle of every bone relative to its parent, in radians counterclockwise, the torso's is
+/// relative to pointing right
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Pose([f64; 10]);
+
+impl Pose {
+    /// the quads for the body standing on the bottom middle of `rect`, as [x, y, width, height],
+    /// scaled to fit its height
+    pub fn instances(&self, rect: [f32; 4]) -> Vec<[f32; 16]> {
+        let [x, y, width, height] = rect.map(f64::from);
+        let scale = height;
+        // the legs reach down from the hips to the bottom of the rect when standing straight
+        let hips = cgmath::vec2(
+            x + width / 2.0,
+            y + (BONES[BACK_THIGH].length + BONES[BACK_SHIN].length) * scale,
+        );
+        // where each bone starts and which way it points, in the world
+        let mut transforms = [(cgmath::vec2(0.0, 0.0), 0.0); 10];
+        for (index, bone) in BONES.iter().enumerate() {
+            transforms[index] = match bone.parent {
+                None => (hips, self.0[index]),
+                Some(parent) => {
+                    let (start, angle) = transforms[parent];
+                    let along = BONES[parent].length * scale * bone.anchor;
+                    let start = start + cgmath::vec2(angle.cos(), angle.sin()) * along;
+                    (start, angle + self.0[index])
+                }
+            };
+        }
+        DRAW_ORDER
+            .iter()
+            .map(|&index| {
+                let bone = &BONES[index];
+                let (start, angle) = transforms[index];
+                let (length, thickness) = (bone.length * scale, bone.thickness * scale);
+                let middle = start + cgmath::vec2(angle.cos(), angle.sin()) * length / 2.0;
+                let shade = match index {
+                    BACK_UPPER_ARM | BACK_FOREARM | BACK_THIGH | BACK_SHIN => BACK_SHADE,
+                    _ => 1.0,
+                };
+                let [r, g, b, a] = bone.color;
+                instances::with_angle(
+                    instances::instance(
+                        [
+                            (middle.x - length / 2.0) as f32,
+                            (middle.y - thickness / 2.0) as f32,
+                            length as f32,
+                            thickness as f32,
+                        ],
+                        [r * shade, g * shade, b * shade, a],
+                    ),
+                    angle as f32,
+                )
+            })
+            .collect()
+    }
+}
+
+/// the player's arms and legs, posed after how it's moving and eased towards that every tick
+#[derive(Clone, Copy, Debug)]
+pub struct Body {
+    pose: Pose,
+    /// the pose as of the tick before, to interpolate between
+    last_pose: Pose,
+    /// how far through the running cycle the legs are, in radians
+    stride: f64,
+    /// 1 facing right, -1 facing left
+    facing: f64,
+}
+
+impl Default for Body {
+    fn default() -> Self {
+        let pose = Self::standing(0.0, 1.0);
+        Self {
+            pose,
+            last_pose: pose,
+            stride: 0.0,
+            facing: 1.0,
+        }
+    }
+}
+
+impl Body {
+    /// how quickly the limbs catch up with where they should be, per second
+    const STIFFNESS: f64 = 18.0;
+    /// radians of stride per unit run
+    const STRIDE_LENGTH: f64 = 2.5;
+    /// slower than this counts as standing still
+    const RUN_SPEED: f64 = 0.5;
+
+    /// moves the limbs after an update
+    pub fn tick(&mut self, state: &GameState, dt: f64) {
+        let object = match state.object(state.view_object) {
+            Some(object) => object,
+            None => return,
+        };
+        let velocity = object.get_velocity();
+        if velocity.x.abs() > Self::RUN_SPEED {
+            self.facing = velocity.x.signum();
+        }
+        let center = object.center();
+        let rope = state
+            .hook_lines()
+            .into_iter()
+            .find(|(from, _)| *from == center)
+            .map(|(from, to)| (to.y - from.y).atan2(to.x - from.x));
+        let target = if let Some(rope) = rope {
+            self.swinging(rope, velocity)
+        } else if !object.is_on_ground(state.down_at(center)) {
+            Self::flying(velocity, self.facing)
+        } else if velocity.x.abs() > Self::RUN_SPEED {
+            self.stride += velocity.x.abs() * Self::STRIDE_LENGTH * dt;
+            Self::running(self.stride, velocity.x, self.facing)
+        } else {
+            Self::standing(state.time, self.facing)
+        };
+        self.last_pose = self.pose;
+        let blend = 1.0 - (-Self::STIFFNESS * dt).exp();
+        for (angle, target) in self.pose.0.iter_mut().zip(target.0) {
+            *angle += angle_between(*angle, target) * blend;
+        }
+    }
+
+    /// the pose `interpolate` of the way from the last tick to the current one
+    pub fn pose(&self, interpolate: f64) -> Pose {
+        let mut pose = self.last_pose;
+        for (angle, current) in pose.0.iter_mut().zip(self.pose.0) {
+            *angle += angle_between(*angle, current) * interpolate;
+        }
+        pose
+    }
+
+    /// turns angles in the world into angles relative to each bone's parent
+    fn relative(world: [f64; 10]) -> Pose {
+        let mut pose = world;
+        for (index, bone) in BONES.iter().enumerate() {
+            if let Some(parent) = bone.parent {
+                pose[index] = world[index] - world[parent];
+            }
+        }
+        Pose(pose)
+    }
+
+    /// breathing a little, arms hanging by the sides
+    fn standing(time: f64, facing: f64) -> Pose {
+        let breath = (time * 2.0).sin() * 0.04;
+        let down = -FRAC_PI_2;
+        let mut world = [0.0; 10];
+        world[TORSO] = FRAC_PI_2;
+        world[HEAD] = FRAC_PI_2;
+        world[BACK_UPPER_ARM] = down - facing * (0.12 + breath);
+        world[BACK_FOREARM] = world[BACK_UPPER_ARM] + facing * 0.15;
+        world[FRONT_UPPER_ARM] = down + facing * (0.12 + breath);
+        world[FRONT_FOREARM] = world[FRONT_UPPER_ARM] + facing * 0.15;
+        world[BACK_THIGH] = down - facing * 0.08;
+        world[BACK_SHIN] = world[BACK_THIGH];
+        world[FRONT_THIGH] = down + facing * 0.08;
+        world[FRONT_SHIN] = world[FRONT_THIGH];
+        Self::relative(world)
+    }
+
+    /// legs and arms swinging in opposite directions, leaning into the run
+    fn running(stride: f64, speed: f64, facing: f64) -> Pose {
+        let lean = (speed.abs() * 0.03).min(0.35);
+        let swing = stride.sin();
+        let down = -FRAC_PI_2;
+        let mut world = [0.0; 10];
+        world[TORSO] = FRAC_PI_2 - facing * lean;
+        world[HEAD] = FRAC_PI_2 - facing * lean * 0.5;
+        world[FRONT_UPPER_ARM] = down - facing * swing * 0.8;
+        world[FRONT_FOREARM] = world[FRONT_UPPER_ARM] + facing * 0.7;
+        world[BACK_UPPER_ARM] = down + facing * swing * 0.8;
+        world[BACK_FOREARM] = world[BACK_UPPER_ARM] + facing * 0.7;
+        world[FRONT_THIGH] = down + facing * swing * 0.7;
+        // knees only bend backwards, and mostly while the leg is coming forward
+        world[FRONT_SHIN] = world[FRONT_THIGH] - facing * 0.9 * stride.cos().max(0.0);
+        world[BACK_THIGH] = down - facing * swing * 0.7;
+        world[BACK_SHIN] = world[BACK_THIGH] - facing * 0.9 * (-stride.cos()).max(0.0);
+        Self::relative(world)
+    }
+
+    /// arms out for balance, legs trailing behind the way it's going
+    fn flying(velocity: cgmath::Vector2<f64>, facing: f64) -> Pose {
+        let trail = trailing(velocity);
+        let mut world = [0.0; 10];
+        world[TORSO] = FRAC_PI_2 - (velocity.x * 0.02).clamp(-0.3, 0.3);
+        world[HEAD] = world[TORSO];
+        world[FRONT_UPPER_ARM] = FRAC_PI_2 - facing * 1.1;
+        world[FRONT_FOREARM] = world[FRONT_UPPER_ARM] + facing * 0.5;
+        world[BACK_UPPER_ARM] = FRAC_PI_2 + facing * 1.4;
+        world[BACK_FOREARM] = world[BACK_UPPER_ARM] - facing * 0.4;
+        world[FRONT_THIGH] = trail + facing * 0.2;
+        world[FRONT_SHIN] = world[FRONT_THIGH] - facing * 0.5;
+        world[BACK_THIGH] = trail - facing * 0.15;
+        world[BACK_SHIN] = world[BACK_THIGH] - facing * 0.3;
+        Self::relative(world)
+    }
+
+    /// hanging from the rope, both hands reaching along it and the legs trailing behind
+    fn swinging(&self, rope: f64, velocity: cgmath::Vector2<f64>) -> Pose {
+        let trail = trailing(velocity);
+        let mut world = [0.0; 10];
+        // the body hangs between straight up and along the rope
+        world[TORSO] = FRAC_PI_2 + angle_between(FRAC_PI_2, rope) * 0.6;
+        world[HEAD] = world[TORSO];
+        world[FRONT_UPPER_ARM] = rope - 0.08;
+        world[FRONT_FOREARM] = rope;
+        world[BACK_UPPER_ARM] = rope + 0.08;
+        world[BACK_FOREARM] = rope;
+        world[FRONT_THIGH] = trail + self.facing * 0.1;
+        world[FRONT_SHIN] = world[FRONT_THIGH] - self.facing * 0.25;
+        world[BACK_THIGH] = trail - self.facing * 0.1;
+        world[BACK_SHIN] = world[BACK_THIGH] - self.facing * 0.4;
+        Self::relative(world)
+    }
+}
+
+/// which way the legs point when in the air, straight down when still and further back the faster
+/// it's going
+fn trailing(velocity: cgmath::Vector2<f64>) -> f64 {
+    let down = -FRAC_PI_2;
+    let speed = velocity.magnitude();
+    if speed < 1e-6 {
+        return down;
+    }
+    let behind = (-velocity.y).atan2(-velocity.x);
+    down + angle_between(down, behind) * (speed / 25.0).min(0.7)
+}
+
+/// the shortest turn from `from` to `to`, between -pi and pi
+fn angle_between(from: f64, to: f64) -> f64 {
+    (to - from + PI).rem_euclid(2.0 * PI) - PI
+}
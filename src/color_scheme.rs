@@ -0,0 +1,79 @@
+use serde::{Deserialize, Serialize};
+
+use crate::instances::CategoryColors;
+use crate::level::Palette;
+
+/// which colours the kinds of things in a level get drawn in, the ones besides the normal look
+/// stay easy to tell apart with common kinds of colour blindness, and ignore the level's palette
+/// so nothing gets tinted back together
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ColorScheme {
+    /// the colours the levels were made with
+    #[default]
+    Normal,
+    /// bright colours on black
+    HighContrast,
+    /// for protanopia and deuteranopia, where reds and greens look alike
+    RedGreen,
+    /// for tritanopia, where blues and greens look alike and so do yellows and pinks
+    BlueYellow,
+}
+
+impl ColorScheme {
+    const ALL: [ColorScheme; 4] = [
+        ColorScheme::Normal,
+        ColorScheme::HighContrast,
+        ColorScheme::RedGreen,
+        ColorScheme::BlueYellow,
+    ];
+
+    /// the one after this, wrapping back around to the normal look
+    pub fn next(self) -> Self {
+        let index = Self::ALL
+            .iter()
+            .position(|&scheme| scheme == self)
+            .unwrap_or(0);
+        Self::ALL[(index + 1) % Self::ALL.len()]
+    }
+
+    pub fn colors(self) -> CategoryColors {
+        match self {
+            ColorScheme::Normal => CategoryColors::default(),
+            ColorScheme::HighContrast => CategoryColors {
+                solid: [1.0, 1.0, 1.0, 1.0],
+                hazard: [1.0, 0.85, 0.0, 1.0],
+                projectile: [1.0, 0.2, 0.8, 1.0],
+                treadmill: [0.0, 0.9, 1.0, 1.0],
+                goal: [0.0, 1.0, 0.0, 0.45],
+            },
+            // out of the Okabe-Ito palette
+            ColorScheme::RedGreen => CategoryColors {
+                solid: [0.0, 0.45, 0.7, 1.0],
+                hazard: [0.9, 0.62, 0.0, 1.0],
+                projectile: [0.84, 0.37, 0.0, 1.0],
+                treadmill: [0.34, 0.71, 0.91, 1.0],
+                goal: [0.94, 0.89, 0.26, 0.4],
+            },
+            ColorScheme::BlueYellow => CategoryColors {
+                solid: [0.0, 0.45, 0.7, 1.0],
+                hazard: [0.84, 0.37, 0.0, 1.0],
+                projectile: [1.0, 1.0, 1.0, 1.0],
+                treadmill: [0.8, 0.8, 0.8, 1.0],
+                goal: [0.8, 0.47, 0.65, 0.4],
+            },
+        }
+    }
+
+    /// what to draw with in place of the level's own palette
+    pub fn palette(self, level: Palette) -> Palette {
+        let background = match self {
+            ColorScheme::Normal => return level,
+            ColorScheme::HighContrast => [0.0, 0.0, 0.0],
+            ColorScheme::RedGreen | ColorScheme::BlueYellow => [0.05, 0.05, 0.05],
+        };
+        Palette {
+            background,
+            ..Palette::default()
+        }
+    }
+}
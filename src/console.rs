@@ -0,0 +1,165 @@
+use std::{collections::BTreeMap, path::Path};
+
+use crate::assists::Assists;
+use crate::gamepad::Gamepad;
+use crate::locale::Strings;
+use crate::logging::LogPanel;
+use crate::online::Online;
+use crate::profiler::Profiler;
+use crate::profiles::Profile;
+use crate::screens::Screen;
+use crate::selection::Selection;
+use crate::session::Session;
+use crate::settings::Settings;
+use crate::text::Text;
+use crate::time_scale::TimeScale;
+
+/// what a command gets to work with
+pub struct Context<'a> {
+    pub screen: &'a mut Screen,
+    pub time_scale: &'a mut TimeScale,
+    pub shared_ghost: Option<&'a Path>,
+    pub selection: &'a Selection,
+    pub assists: &'a mut Assists,
+    pub settings: &'a mut Settings,
+    /// the save slot being played, settings changed from the console get saved into it
+    pub profile: &'a Profile,
+    pub strings: &'a mut Strings,
+    pub gamepad: &'a mut Gamepad,
+    pub profiler: &'a mut Profiler,
+    pub online: &'a mut Online,
+    pub log_panel: &'a mut LogPanel,
+    /// how long a tick is, for the commands that play things back
+    pub tick_rate: f64,
+}
+
+impl Context<'_> {
+    /// the level being played, for the commands that only make sense in one
+    pub fn session(&mut self) -> Result<&mut Session, String> {
+        match self.screen {
+            Screen::Playing(session) => Ok(session),
+            _ => Err("not playing a level".to_owned()),
+        }
+    }
+}
+
+/// runs a command with the words typed after its name, returning what to print
+pub type Handler = Box<dyn Fn(&[&str], &mut Context) -> Result<String, String>>;
+
+struct Command {
+    usage: &'static str,
+    handler: Handler,
+}
+
+/// the developer console, toggled with ~
+#[derive(Default)]
+pub struct Console {
+    pub open: bool,
+    input: String,
+    /// what got typed and what came back, oldest first, along with the colour to show it in
+    log: Vec<(String, [f32; 4])>,
+    commands: BTreeMap<&'static str, Command>,
+}
+
+impl Console {
+    /// how many lines of the log are shown
+    const SHOWN_LINES: usize = 12;
+    const INPUT_COLOR: [f32; 4] = [1.0, 1.0, 1.0, 1.0];
+    const OUTPUT_COLOR: [f32; 4] = [0.75, 0.75, 0.75, 1.0];
+    const ERROR_COLOR: [f32; 4] = [1.0, 0.4, 0.4, 1.0];
+
+    /// adds a command, `usage` is shown by help and should start with `name`
+    pub fn register(
+        &mut self,
+        name: &'static str,
+        usage: &'static str,
+        handler: impl Fn(&[&str], &mut Context) -> Result<String, String> + 'static,
+    ) {
+        let handler = Box::new(handler);
+        if self
+            .commands
+            .insert(name, Command { usage, handler })
+            .is_some()
+        {
+            tracing::warn!("console command {} registered twice", name);
+        }
+    }
+
+    pub fn toggle(&mut self) {
+        self.open = !self.open;
+    }
+
+    pub fn type_char(&mut self, c: char) {
+        // ~ opens and closes the console, so it never ends up in a command
+        if !c.is_control() && c != '~' && c != '`' {
+            self.input.push(c);
+        }
+    }
+
+    pub fn backspace(&mut self) {
+        self.input.pop();
+    }
+
+    /// runs whatever has been typed so far
+    pub fn submit(&mut self, context: &mut Context) {
+        let line = std::mem::take(&mut self.input);
+        self.log.push((format!("> {}", line), Self::INPUT_COLOR));
+        let (output, color) = match self.execute(&line, context) {
+            Ok(output) => (output, Self::OUTPUT_COLOR),
+            Err(e) => (e, Self::ERROR_COLOR),
+        };
+        for line in output.lines() {
+            self.log.push((line.to_owned(), color));
+        }
+    }
+
+    pub fn execute(&self, line: &str, context: &mut Context) -> Result<String, String> {
+        let words = line.split_whitespace().collect::<Vec<_>>();
+        let (name, args) = match words.split_first() {
+            Some(split) => split,
+            None => return Ok(String::new()),
+        };
+        if *name == "help" {
+            return Ok(self
+                .commands
+                .values()
+                .map(|command| command.usage)
+                .collect::<Vec<_>>()
+                .join("\n"));
+        }
+        match self.commands.get(name) {
+            Some(command) => (command.handler)(args, context),
+            None => Err(format!("unknown command {}, try help", name)),
+        }
+    }
+
+    /// the log and the line being typed, drawn over the top of the screen while open
+    pub fn text(&self) -> Vec<Text> {
+        if !self.open {
+            return vec![];
+        }
+        let shown = self.log.len().saturating_sub(Self::SHOWN_LINES);
+        let mut text = self.log[shown..]
+            .iter()
+            .enumerate()
+            .map(|(i, (line, color))| {
+                Text::new(line.clone(), (20.0, 20.0 + i as f32 * 22.0))
+                    .with_size(18.0)
+                    .with_color(*color)
+            })
+            .collect::<Vec<_>>();
+        let y = 20.0 + (self.log.len() - shown) as f32 * 22.0;
+        text.push(
+            Text::new(format!("> {}_", self.input), (20.0, y))
+                .with_size(18.0)
+                .with_color([1.0, 0.9, 0.3, 1.0]),
+        );
+        text
+    }
+}
+
+/// reads the argument at `index` as a number
+pub fn number(args: &[&str], index: usize, usage: &str) -> Result<f64, String> {
+    let arg = args.get(index).ok_or_else(|| format!("usage: {}", usage))?;
+    arg.parse().map_err(|_| format!("{} isn't a number", arg))
+}
@@ -0,0 +1,125 @@
+use std::backtrace::Backtrace;
+use std::panic::PanicHookInfo;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use color_eyre::eyre::WrapErr;
+
+use crate::session::Session;
+
+#[derive(Default)]
+struct Shared {
+    /// what's known about the machine, written into every report
+    system: String,
+    /// the folder the last panic got written to, so the game state can go in with it
+    folder: Option<PathBuf>,
+}
+
+/// writes everything needed to look into a crash to a folder, the panic and the machine it
+/// happened on from a panic hook, and the game state and replay from wherever catches the panic
+#[derive(Clone, Default)]
+pub struct CrashReporter {
+    shared: Arc<Mutex<Shared>>,
+}
+
+impl CrashReporter {
+    /// installs the panic hook, the one that was there before still runs after it
+    pub fn install() -> Self {
+        let reporter = Self::default();
+        reporter.lock().system = format!(
+            "grappling hook {}\n{} {}\n",
+            env!("CARGO_PKG_VERSION"),
+            std::env::consts::OS,
+            std::env::consts::ARCH
+        );
+        let shared = reporter.shared.clone();
+        let previous = std::panic::take_hook();
+        std::panic::set_hook(Box::new(move |info| {
+            let mut shared = shared.lock().unwrap_or_else(|e| e.into_inner());
+            // not through tracing, whatever panicked might have been in the middle of logging
+            match write_panic(info, &shared.system) {
+                Ok(folder) => {
+                    eprintln!("crash report written to {}", folder.display());
+                    shared.folder = Some(folder);
+                }
+                Err(e) => eprintln!("failed to write crash report: {}", e),
+            }
+            drop(shared);
+            previous(info);
+        }));
+        reporter
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Shared> {
+        self.shared.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// adds the graphics adapter to what gets written about the machine
+    pub fn set_adapter(&self, adapter: &wgpu::AdapterInfo) {
+        self.lock().system += &format!(
+            "{} ({:?} on {:?}, vendor {:#06x}, device {:#06x})\n",
+            adapter.name, adapter.device_type, adapter.backend, adapter.vendor, adapter.device
+        );
+    }
+
+    /// writes the game as it was when it panicked, and the replay of how it got there, next to
+    /// the last panic, for calling once one has been caught
+    pub fn dump_session(&self, session: &Session) -> color_eyre::Result<PathBuf> {
+        let folder = match self.lock().folder.clone() {
+            Some(folder) => folder,
+            None => new_folder()?,
+        };
+        let replay = folder.join("replay.ron");
+        session.recorder.unfinished_replay().save(&replay)?;
+        let state = folder.join("state.ron");
+        std::fs::write(&state, ron::ser::to_string(&session.state)?)
+            .wrap_err_with(|| format!("failed to write {}", state.display()))?;
+        let notes = folder.join("level.txt");
+        std::fs::write(
+            &notes,
+            format!(
+                "level {}, tick {}\nrun `watch {}` in the console to play it up to the crash again\n",
+                session.level_name,
+                session.state.tick,
+                replay.display()
+            ),
+        )
+        .wrap_err_with(|| format!("failed to write {}", notes.display()))?;
+        Ok(folder)
+    }
+}
+
+/// an empty folder for a report, named after when it was made
+fn new_folder() -> std::io::Result<PathBuf> {
+    let time = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |time| time.as_secs());
+    let directory = crate::paths::crashes_dir();
+    let mut folder = directory.join(time.to_string());
+    // more than one in a second, like a panic while handling another
+    for n in 1.. {
+        if !folder.exists() {
+            break;
+        }
+        folder = directory.join(format!("{}-{}", time, n));
+    }
+    std::fs::create_dir_all(&folder)?;
+    Ok(folder)
+}
+
+fn write_panic(info: &PanicHookInfo, system: &str) -> std::io::Result<PathBuf> {
+    let folder = new_folder()?;
+    let thread = std::thread::current();
+    std::fs::write(
+        folder.join("panic.txt"),
+        format!(
+            "{}\nthread '{}' {}\n\n{}\n",
+            system,
+            thread.name().unwrap_or("<unnamed>"),
+            info,
+            Backtrace::force_capture()
+        ),
+    )?;
+    Ok(folder)
+}
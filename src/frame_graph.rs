@@ -0,0 +1,224 @@
+use std::collections::HashMap;
+
+use crate::render::{GraphicsConfig, DEPTH_FORMAT};
+
+/// a texture that passes draw into or sample from
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Resource {
+    /// the window's surface, whatever ends up here is what gets shown
+    Frame,
+    /// the world before post processing gets applied to it
+    Scene,
+    /// what the world gets drawn into with msaa on, before it's resolved into its target
+    Multisampled,
+    /// how near whatever opaque is at each pixel of the world is, so see-through things drawn
+    /// later stay behind it
+    Depth,
+}
+
+/// what a pass does when it runs
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PassKind {
+    /// nothing but clearing, for when there's no world to draw
+    Clear,
+    /// the objects of the level, lit if the level has lighting
+    World,
+    /// the screen effects, drawn from the scene onto the frame, which also turns it into sRGB for
+    /// frames that don't do that themselves
+    PostProcess,
+    /// the overlay text, on top of everything else
+    Text,
+    /// the screen being covered up while it changes, over the text too
+    Transition,
+}
+
+/// one step of drawing a frame, along with the textures it needs
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Pass {
+    pub kind: PassKind,
+    /// textures it samples, which an earlier pass has to have written
+    pub reads: Vec<Resource>,
+    /// the texture it draws into
+    pub writes: Resource,
+    /// textures it only needs while it runs
+    pub scratch: Vec<Resource>,
+}
+
+impl Pass {
+    fn new(kind: PassKind, writes: Resource) -> Self {
+        Self {
+            kind,
+            reads: vec![],
+            writes,
+            scratch: vec![],
+        }
+    }
+
+    fn resources(&self) -> impl Iterator<Item = Resource> + '_ {
+        self.reads
+            .iter()
+            .chain(&self.scratch)
+            .copied()
+            .chain(std::iter::once(self.writes))
+    }
+}
+
+/// the passes for a frame, in the order they run, `world` is whether there's a level to draw,
+/// `linear_frame` whether the frame stores linear colours, see `srgb::stores_linear`, and
+/// `transition` whether the screen is partly covered up
+pub fn build(
+    config: &GraphicsConfig,
+    world: bool,
+    linear_frame: bool,
+    transition: bool,
+) -> Vec<Pass> {
+    let mut passes = vec![];
+    if world {
+        // the world can only go straight onto the frame when there's nothing to do after it
+        let post_process = config.post_processing || !linear_frame;
+        let mut pass = Pass::new(
+            PassKind::World,
+            if post_process {
+                Resource::Scene
+            } else {
+                Resource::Frame
+            },
+        );
+        pass.scratch.push(Resource::Depth);
+        if config.msaa.sample_count() > 1 {
+            pass.scratch.push(Resource::Multisampled);
+        }
+        passes.push(pass);
+        if post_process {
+            passes.push(Pass {
+                reads: vec![Resource::Scene],
+                ..Pass::new(PassKind::PostProcess, Resource::Frame)
+            });
+        }
+    } else {
+        passes.push(Pass::new(PassKind::Clear, Resource::Frame));
+    }
+    passes.push(Pass::new(PassKind::Text, Resource::Frame));
+    if transition {
+        passes.push(Pass::new(PassKind::Transition, Resource::Frame));
+    }
+    debug_assert!(
+        reads_are_written(&passes),
+        "a pass reads something nothing before it wrote: {:?}",
+        passes
+    );
+    passes
+}
+
+/// whether everything that gets read was written by an earlier pass
+fn reads_are_written(passes: &[Pass]) -> bool {
+    passes.iter().enumerate().all(|(i, pass)| {
+        pass.reads
+            .iter()
+            .all(|read| passes[..i].iter().any(|earlier| earlier.writes == *read))
+    })
+}
+
+/// how a transient texture was made, so it can be made again when that changes
+#[derive(Clone, Copy, PartialEq, Eq)]
+struct Description {
+    width: u32,
+    height: u32,
+    format: wgpu::TextureFormat,
+    sample_count: u32,
+    usage: wgpu::TextureUsages,
+}
+
+/// the textures passes hand to each other or need while they run, everything but the frame,
+/// they're made the first time a pass needs them and kept for the frames after
+#[derive(Default)]
+pub struct Transients {
+    textures: HashMap<Resource, (Description, wgpu::TextureView)>,
+}
+
+impl Transients {
+    /// makes sure every texture `passes` use is there and the right shape, and lets go of the
+    /// ones nothing uses anymore
+    pub fn prepare(
+        &mut self,
+        device: &wgpu::Device,
+        surface_config: &wgpu::SurfaceConfiguration,
+        world_format: wgpu::TextureFormat,
+        sample_count: u32,
+        passes: &[Pass],
+    ) {
+        let used = passes
+            .iter()
+            .flat_map(Pass::resources)
+            .filter(|resource| *resource != Resource::Frame)
+            .collect::<Vec<_>>();
+        self.textures.retain(|resource, _| used.contains(resource));
+        for resource in used {
+            let description = Description {
+                width: surface_config.width,
+                height: surface_config.height,
+                format: match resource {
+                    Resource::Depth => DEPTH_FORMAT,
+                    _ => world_format,
+                },
+                sample_count: match resource {
+                    Resource::Multisampled | Resource::Depth => sample_count,
+                    _ => 1,
+                },
+                usage: match resource {
+                    Resource::Scene => {
+                        wgpu::TextureUsages::RENDER_ATTACHMENT
+                            | wgpu::TextureUsages::TEXTURE_BINDING
+                    }
+                    _ => wgpu::TextureUsages::RENDER_ATTACHMENT,
+                },
+            };
+            if matches!(self.textures.get(&resource), Some((existing, _)) if *existing == description)
+            {
+                continue;
+            }
+            let texture = device.create_texture(&wgpu::TextureDescriptor {
+                label: Some(label(resource)),
+                size: wgpu::Extent3d {
+                    width: description.width,
+                    height: description.height,
+                    depth_or_array_layers: 1,
+                },
+                mip_level_count: 1,
+                sample_count: description.sample_count,
+                dimension: wgpu::TextureDimension::D2,
+                format: description.format,
+                usage: description.usage,
+            });
+            let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+            self.textures.insert(resource, (description, view));
+        }
+    }
+
+    /// the view of `resource`, `frame` being the one for the surface
+    pub fn view<'a>(
+        &'a self,
+        resource: Resource,
+        frame: &'a wgpu::TextureView,
+    ) -> &'a wgpu::TextureView {
+        match resource {
+            Resource::Frame => frame,
+            _ => {
+                &self
+                    .textures
+                    .get(&resource)
+                    .expect("transients get prepared for every pass before it runs")
+                    .1
+            }
+        }
+    }
+}
+
+fn label(resource: Resource) -> &'static str {
+    match resource {
+        Resource::Frame => "frame",
+        Resource::Scene => "scene texture",
+        Resource::Multisampled => "msaa color target",
+        Resource::Depth => "depth buffer",
+    }
+}
@@ -0,0 +1,53 @@
+use std::collections::HashSet;
+
+use crate::game_state::Direction;
+
+/// a camera that flies around by itself instead of following the player, for looking over levels
+pub struct FreeCamera {
+    pub position: cgmath::Point2<f64>,
+    /// how far in it is zoomed, 1 is the normal view
+    pub zoom: f64,
+    held: HashSet<Direction>,
+}
+
+impl FreeCamera {
+    /// in units per second, at the normal zoom
+    const SPEED: f64 = 30.0;
+    const MIN_ZOOM: f64 = 0.1;
+    const MAX_ZOOM: f64 = 10.0;
+    /// how much one notch of the scroll wheel zooms
+    const ZOOM_STEP: f64 = 1.1;
+
+    pub fn new(position: cgmath::Point2<f64>) -> Self {
+        Self {
+            position,
+            zoom: 1.0,
+            held: HashSet::new(),
+        }
+    }
+
+    pub fn on_key(&mut self, direction: Direction, pressed: bool) {
+        if pressed {
+            self.held.insert(direction);
+        } else {
+            self.held.remove(&direction);
+        }
+    }
+
+    /// positive `notches` zoom in
+    pub fn on_scroll(&mut self, notches: f64) {
+        self.zoom =
+            (self.zoom * Self::ZOOM_STEP.powf(notches)).clamp(Self::MIN_ZOOM, Self::MAX_ZOOM);
+    }
+
+    /// moves along with whatever keys are held, `dt` is real time so it still works while paused
+    pub fn update(&mut self, dt: f64) {
+        let held = |direction| self.held.contains(&direction) as i32 as f64;
+        let direction = cgmath::vec2(
+            held(Direction::Right) - held(Direction::Left),
+            held(Direction::Up) - held(Direction::Down),
+        );
+        // zoomed out it has to go further to cross the screen
+        self.position += direction * Self::SPEED / self.zoom * dt;
+    }
+}
@@ -0,0 +1,808 @@
+use std::collections::HashSet;
+
+use cgmath::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::bindings::{Action, ActionEvent, Bindings, GamepadButton};
+use crate::console::Console;
+use crate::game_state::{ButtonState, GameState};
+use crate::text::Text;
+
+/// how much of a stick's push gets through, after the deadzone is taken off
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ResponseCurve {
+    #[default]
+    Linear,
+    /// gentler near the middle, for finer control at small pushes
+    Quadratic,
+    Cubic,
+}
+
+impl ResponseCurve {
+    const ALL: [ResponseCurve; 3] = [
+        ResponseCurve::Linear,
+        ResponseCurve::Quadratic,
+        ResponseCurve::Cubic,
+    ];
+
+    fn apply(self, x: f64) -> f64 {
+        match self {
+            ResponseCurve::Linear => x,
+            ResponseCurve::Quadratic => x * x,
+            ResponseCurve::Cubic => x * x * x,
+        }
+    }
+
+    fn parse(name: &str) -> Option<Self> {
+        match name {
+            "linear" => Some(ResponseCurve::Linear),
+            "quadratic" => Some(ResponseCurve::Quadratic),
+            "cubic" => Some(ResponseCurve::Cubic),
+            _ => None,
+        }
+    }
+}
+
+/// how one stick's raw position turns into what the game gets
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct StickConfig {
+    pub deadzone: Deadzone,
+    pub curve: ResponseCurve,
+    /// multiplied onto the push after the curve, so less of a push gets all the way
+    pub sensitivity: f64,
+}
+
+/// how far a stick has to go along each axis before that axis counts, from 0 to 1
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(from = "StoredDeadzone")]
+pub struct Deadzone {
+    pub x: f64,
+    pub y: f64,
+}
+
+/// a deadzone the way it's read from the settings, ones saved before it was split up by axis
+/// have one number for both
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum StoredDeadzone {
+    Both(f64),
+    PerAxis { x: f64, y: f64 },
+}
+
+impl From<StoredDeadzone> for Deadzone {
+    fn from(stored: StoredDeadzone) -> Self {
+        match stored {
+            StoredDeadzone::Both(both) => Self { x: both, y: both },
+            StoredDeadzone::PerAxis { x, y } => Self { x, y },
+        }
+    }
+}
+
+impl StickConfig {
+    /// the largest the deadzone can be set to, any more and the stick barely does anything
+    pub const MAX_DEADZONE: f64 = 0.9;
+
+    /// the stick at `raw`, with each axis from -1 to 1, as the game should see it
+    pub fn apply(&self, raw: cgmath::Vector2<f64>) -> cgmath::Vector2<f64> {
+        // rescaled so the push along an axis starts from nothing right at the edge of its deadzone
+        let axis = |value: f64, deadzone: f64| {
+            let deadzone = deadzone.min(Self::MAX_DEADZONE);
+            let push = (value.abs().min(1.0) - deadzone).max(0.0) / (1.0 - deadzone);
+            push.copysign(value)
+        };
+        let shaped = cgmath::vec2(axis(raw.x, self.deadzone.x), axis(raw.y, self.deadzone.y));
+        let push = shaped.magnitude();
+        if push == 0.0 {
+            return cgmath::vec2(0.0, 0.0);
+        }
+        shaped / push * (self.curve.apply(push.min(1.0)) * self.sensitivity).min(1.0)
+    }
+
+    /// anything out of range brought back in, and anything that isn't a number put back to what
+    /// it is in `default`
+    fn sanitize(&mut self, default: &StickConfig) {
+        self.deadzone.x =
+            finite_or(self.deadzone.x, default.deadzone.x).clamp(0.0, Self::MAX_DEADZONE);
+        self.deadzone.y =
+            finite_or(self.deadzone.y, default.deadzone.y).clamp(0.0, Self::MAX_DEADZONE);
+        self.sensitivity = finite_or(self.sensitivity, default.sensitivity).max(0.0);
+    }
+}
+
+fn finite_or(value: f64, fallback: f64) -> f64 {
+    match value.is_finite() {
+        true => value,
+        false => fallback,
+    }
+}
+
+/// the settings for both sticks, the left one moves and the right one aims the hook
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct GamepadConfig {
+    pub movement: StickConfig,
+    pub aim: StickConfig,
+    /// how strongly the gamepad buzzes while the rope is close to snapping, 0 turns it off
+    #[serde(default = "full_rumble")]
+    pub rumble: f64,
+}
+
+fn full_rumble() -> f64 {
+    1.0
+}
+
+/// one line of the gamepad settings menu, in the order they're listed
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MenuEntry {
+    Deadzone(Stick, Axis),
+    Curve(Stick),
+    Sensitivity(Stick),
+    Rumble,
+}
+
+/// which stick a setting is for
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Stick {
+    Movement,
+    Aim,
+}
+
+/// which way along a stick a deadzone is for
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Axis {
+    X,
+    Y,
+}
+
+impl MenuEntry {
+    pub const ALL: [MenuEntry; 9] = [
+        MenuEntry::Deadzone(Stick::Movement, Axis::X),
+        MenuEntry::Deadzone(Stick::Movement, Axis::Y),
+        MenuEntry::Curve(Stick::Movement),
+        MenuEntry::Sensitivity(Stick::Movement),
+        MenuEntry::Deadzone(Stick::Aim, Axis::X),
+        MenuEntry::Deadzone(Stick::Aim, Axis::Y),
+        MenuEntry::Curve(Stick::Aim),
+        MenuEntry::Sensitivity(Stick::Aim),
+        MenuEntry::Rumble,
+    ];
+}
+
+impl GamepadConfig {
+    /// how much one press changes a deadzone, sensitivity or the rumble in the menu
+    const MENU_STEP: f64 = 0.05;
+
+    fn stick(&mut self, stick: Stick) -> &mut StickConfig {
+        match stick {
+            Stick::Movement => &mut self.movement,
+            Stick::Aim => &mut self.aim,
+        }
+    }
+
+    /// the setting on `entry` moved a step up, or down with `up` off, curves go round to the
+    /// next one
+    pub fn adjust(&mut self, entry: MenuEntry, up: bool) {
+        let step = if up {
+            Self::MENU_STEP
+        } else {
+            -Self::MENU_STEP
+        };
+        // rounded to the step, so going up and back down lands where it started
+        let nudge = |value: f64| ((value + step) / Self::MENU_STEP).round() * Self::MENU_STEP;
+        match entry {
+            MenuEntry::Deadzone(stick, axis) => {
+                let deadzone = &mut self.stick(stick).deadzone;
+                let deadzone = match axis {
+                    Axis::X => &mut deadzone.x,
+                    Axis::Y => &mut deadzone.y,
+                };
+                *deadzone = nudge(*deadzone).clamp(0.0, StickConfig::MAX_DEADZONE);
+            }
+            MenuEntry::Curve(stick) => {
+                let stick = self.stick(stick);
+                let count = ResponseCurve::ALL.len();
+                let index = ResponseCurve::ALL
+                    .iter()
+                    .position(|&curve| curve == stick.curve)
+                    .unwrap_or(0);
+                let index = if up { index + 1 } else { index + count - 1 };
+                stick.curve = ResponseCurve::ALL[index % count];
+            }
+            MenuEntry::Sensitivity(stick) => {
+                let stick = self.stick(stick);
+                stick.sensitivity = nudge(stick.sensitivity).max(0.0);
+            }
+            MenuEntry::Rumble => self.rumble = nudge(self.rumble).clamp(0.0, 1.0),
+        }
+    }
+
+    /// what `entry` is set to, the way the menu shows it
+    pub fn value(&self, entry: MenuEntry) -> String {
+        let stick = |stick| match stick {
+            Stick::Movement => &self.movement,
+            Stick::Aim => &self.aim,
+        };
+        match entry {
+            MenuEntry::Deadzone(s, Axis::X) => format!("{:.2}", stick(s).deadzone.x),
+            MenuEntry::Deadzone(s, Axis::Y) => format!("{:.2}", stick(s).deadzone.y),
+            MenuEntry::Curve(s) => format!("{:?}", stick(s).curve),
+            MenuEntry::Sensitivity(s) => format!("{:.2}", stick(s).sensitivity),
+            MenuEntry::Rumble => format!("{:.2}", self.rumble),
+        }
+    }
+
+    /// brings settings from a file that was edited by hand back into range, anything that isn't
+    /// a number goes back to its default
+    pub fn sanitize(&mut self) {
+        let default = Self::default();
+        self.movement.sanitize(&default.movement);
+        self.aim.sanitize(&default.aim);
+        self.rumble = finite_or(self.rumble, default.rumble).clamp(0.0, 1.0);
+    }
+}
+
+impl Default for GamepadConfig {
+    fn default() -> Self {
+        Self {
+            movement: StickConfig {
+                deadzone: Deadzone { x: 0.2, y: 0.2 },
+                curve: ResponseCurve::Linear,
+                sensitivity: 1.0,
+            },
+            aim: StickConfig {
+                deadzone: Deadzone { x: 0.15, y: 0.15 },
+                curve: ResponseCurve::Quadratic,
+                sensitivity: 1.0,
+            },
+            rumble: full_rumble(),
+        }
+    }
+}
+
+/// what a gamepad has held down, read once a frame
+#[derive(Clone, Debug)]
+struct Reading {
+    movement: cgmath::Vector2<f64>,
+    aim: cgmath::Vector2<f64>,
+    buttons: Vec<GamepadButton>,
+}
+
+impl Reading {
+    /// both sticks left alone and nothing pressed
+    fn centered() -> Self {
+        Self {
+            movement: cgmath::vec2(0.0, 0.0),
+            aim: cgmath::vec2(0.0, 0.0),
+            buttons: vec![],
+        }
+    }
+}
+
+/// turns the first connected gamepad into the same actions the keyboard and mouse give, it only
+/// finds any with the `gamepad` feature on
+pub struct Gamepad {
+    #[cfg(feature = "gamepad")]
+    gilrs: Option<gilrs::Gilrs>,
+    reading: Reading,
+    held: HashSet<Action>,
+    /// how far the movement stick was pushed sideways, as last handed out
+    movement: f64,
+    /// where the hook was last aimed, it stays put once the stick is let go
+    aim: cgmath::Vector2<f64>,
+    /// show how the sticks are set up and where they are
+    pub preview: bool,
+    /// the buzz that's playing, set up again each time it starts so it reaches gamepads
+    /// connected since
+    #[cfg(feature = "gamepad")]
+    rumble: Option<gilrs::ff::Effect>,
+    /// how strong the buzz is, 0 while it's stopped
+    buzzing: f64,
+}
+
+impl Gamepad {
+    /// how far a processed push has to go along an axis to hold that direction down
+    const PRESS: f64 = 0.5;
+    /// sideways pushes get rounded to this many steps each way, so a stick that's a little noisy
+    /// doesn't send the game a new push every frame
+    const MOVEMENT_STEPS: f64 = 32.0;
+    /// how close to its `max_tension` a rope has to get before the gamepad starts buzzing, it
+    /// gets stronger from there until the rope snaps
+    const RUMBLE_FROM: f64 = 0.6;
+
+    #[cfg(feature = "gamepad")]
+    pub fn new() -> Self {
+        // the deadzone is ours to apply, gilrs would otherwise put its own in front of it
+        let gilrs = gilrs::GilrsBuilder::new()
+            .with_default_filters(false)
+            .build()
+            .map_err(|e| tracing::warn!("failed to start gamepad support: {}", e))
+            .ok();
+        Self {
+            gilrs,
+            reading: Reading::centered(),
+            held: HashSet::new(),
+            movement: 0.0,
+            aim: cgmath::vec2(1.0, 0.0),
+            preview: false,
+            rumble: None,
+            buzzing: 0.0,
+        }
+    }
+
+    #[cfg(not(feature = "gamepad"))]
+    pub fn new() -> Self {
+        Self {
+            reading: Reading::centered(),
+            held: HashSet::new(),
+            movement: 0.0,
+            aim: cgmath::vec2(1.0, 0.0),
+            preview: false,
+            buzzing: 0.0,
+        }
+    }
+
+    #[cfg(feature = "gamepad")]
+    fn read(&mut self) -> Option<Reading> {
+        use gilrs::{Axis, Button};
+
+        let gilrs = self.gilrs.as_mut()?;
+        // the state only gets updated as the events are taken out
+        while gilrs.next_event().is_some() {}
+        let (_, pad) = gilrs.gamepads().next()?;
+        let stick = |x, y| cgmath::vec2(pad.value(x) as f64, pad.value(y) as f64);
+        Some(Reading {
+            movement: stick(Axis::LeftStickX, Axis::LeftStickY),
+            aim: stick(Axis::RightStickX, Axis::RightStickY),
+            buttons: [
+                (Button::South, GamepadButton::South),
+                (Button::East, GamepadButton::East),
+                (Button::North, GamepadButton::North),
+                (Button::West, GamepadButton::West),
+                (Button::LeftTrigger, GamepadButton::LeftTrigger),
+                (Button::LeftTrigger2, GamepadButton::LeftTrigger2),
+                (Button::RightTrigger, GamepadButton::RightTrigger),
+                (Button::RightTrigger2, GamepadButton::RightTrigger2),
+                (Button::Select, GamepadButton::Select),
+                (Button::Start, GamepadButton::Start),
+            ]
+            .into_iter()
+            .filter(|&(button, _)| pad.is_pressed(button))
+            .map(|(_, button)| button)
+            .collect(),
+        })
+    }
+
+    #[cfg(not(feature = "gamepad"))]
+    fn read(&mut self) -> Option<Reading> {
+        None
+    }
+
+    /// takes where the sticks are now for the preview, without handing out any actions, for
+    /// while the settings are being changed
+    pub fn refresh(&mut self) {
+        if let Some(reading) = self.read() {
+            self.reading = reading;
+        }
+    }
+
+    /// buzzes while a rope of the view object is close to snapping, `state` is None when nothing
+    /// is being played
+    pub fn rumble_for_ropes(&mut self, config: &GamepadConfig, state: Option<&GameState>) {
+        let strain = state.and_then(|state| {
+            let max_tension = state.hook_limits.max_tension?;
+            let tension = state.hook_tension(state.view_object)?;
+            Some(tension[0].max(tension[1]) / max_tension)
+        });
+        let strength = (strain.unwrap_or(0.0) - Self::RUMBLE_FROM) / (1.0 - Self::RUMBLE_FROM);
+        self.set_rumble((strength * config.rumble).clamp(0.0, 1.0));
+    }
+
+    #[cfg(feature = "gamepad")]
+    fn set_rumble(&mut self, strength: f64) {
+        use gilrs::ff::{BaseEffect, BaseEffectType, EffectBuilder, Repeat, Replay, Ticks};
+
+        if strength == self.buzzing {
+            return;
+        }
+        let gilrs = match self.gilrs.as_mut() {
+            Some(gilrs) => gilrs,
+            None => return,
+        };
+        if self.buzzing == 0.0 {
+            let pads = gilrs
+                .gamepads()
+                .filter(|(_, pad)| pad.is_ff_supported())
+                .map(|(id, _)| id)
+                .collect::<Vec<_>>();
+            self.rumble = EffectBuilder::new()
+                .add_effect(BaseEffect {
+                    kind: BaseEffectType::Weak {
+                        magnitude: u16::MAX,
+                    },
+                    scheduling: Replay {
+                        play_for: Ticks::from_ms(100),
+                        ..Default::default()
+                    },
+                    envelope: Default::default(),
+                })
+                .repeat(Repeat::Infinitely)
+                .gamepads(&pads)
+                .finish(gilrs)
+                .map_err(|e| tracing::warn!("failed to set up rumble: {}", e))
+                .ok();
+        }
+        self.buzzing = strength;
+        let effect = match &self.rumble {
+            Some(effect) => effect,
+            None => return,
+        };
+        let result = match strength {
+            strength if strength > 0.0 => {
+                effect.set_gain(strength as f32).and_then(|_| effect.play())
+            }
+            _ => effect.stop(),
+        };
+        if let Err(e) = result {
+            tracing::warn!("failed to rumble: {}", e);
+        }
+    }
+
+    #[cfg(not(feature = "gamepad"))]
+    fn set_rumble(&mut self, strength: f64) {
+        self.buzzing = strength;
+    }
+
+    /// the actions that started or stopped since last frame, the movement stick runs as far as
+    /// it's pushed sideways and jumps or reels when pushed up or down, and the buttons do what
+    /// `bindings` says
+    pub fn update(&mut self, config: &GamepadConfig, bindings: &Bindings) -> Vec<ActionEvent> {
+        let reading = match self.read() {
+            Some(reading) => reading,
+            None => return vec![],
+        };
+        let movement = config.movement.apply(reading.movement);
+        let mut wanted = [
+            (Action::Jump, movement.y > Self::PRESS),
+            (Action::Reel, movement.y < -Self::PRESS),
+        ]
+        .into_iter()
+        .filter(|&(_, down)| down)
+        .map(|(action, _)| action)
+        .collect::<HashSet<_>>();
+        wanted.extend(
+            reading
+                .buttons
+                .iter()
+                .filter_map(|&button| bindings.gamepad(button)),
+        );
+        let aim = config.aim.apply(reading.aim);
+        if aim.magnitude2() > 0.0 {
+            self.aim = aim;
+        }
+        self.reading = reading;
+
+        let mut buttons = self
+            .held
+            .difference(&wanted)
+            .map(|&action| (action, ButtonState::Released))
+            .chain(
+                wanted
+                    .difference(&self.held)
+                    .map(|&action| (action, ButtonState::Pressed)),
+            )
+            .collect::<Vec<_>>();
+        // the order doesn't matter to the game, but it should be the same each time
+        buttons.sort_by_key(|&(action, state)| (action as u8, state == ButtonState::Pressed));
+        self.held = wanted;
+        let mut actions = buttons
+            .into_iter()
+            .map(|(action, state)| ActionEvent::Button(action, state))
+            .collect::<Vec<_>>();
+        let push = (movement.x * Self::MOVEMENT_STEPS).round() / Self::MOVEMENT_STEPS;
+        if push != self.movement {
+            self.movement = push;
+            actions.push(ActionEvent::Move(push));
+        }
+        actions
+    }
+
+    /// where the hook goes when it's fired from the gamepad, out from the player as far as the aim
+    /// stick was last pushed
+    pub fn aim_target(&self, state: &GameState) -> Option<cgmath::Point2<f64>> {
+        let player = state.object(state.view_object)?;
+        Some(player.center() + self.aim * GameState::HOOK_RANGE)
+    }
+
+    /// the settings for each stick, with where it is right now drawn before and after them, and
+    /// how hard a push comes out for a few pushes along the way
+    pub fn preview_text(&self, config: &GamepadConfig) -> Vec<Text> {
+        let mut text = vec![];
+        let sticks = [
+            ("movement", &config.movement, self.reading.movement),
+            ("aim", &config.aim, self.reading.aim),
+        ];
+        for (i, (name, stick, raw)) in sticks.into_iter().enumerate() {
+            let x = 40.0 + i as f32 * 420.0;
+            text.push(Text::new(
+                format!(
+                    "{}: deadzone {:.2}/{:.2}  {:?}  sensitivity {:.2}",
+                    name, stick.deadzone.x, stick.deadzone.y, stick.curve, stick.sensitivity
+                ),
+                (x, 560.0),
+            ));
+            let processed = stick.apply(raw);
+            for (row, line) in stick_grid(stick.deadzone, raw, processed)
+                .into_iter()
+                .enumerate()
+            {
+                text.push(
+                    Text::new(line, (x, 595.0 + row as f32 * 16.0))
+                        .with_size(16.0)
+                        .with_color([0.8, 0.8, 0.8, 1.0]),
+                );
+            }
+            let curve = (1..=5)
+                .map(|step| {
+                    let push = step as f64 * 0.2;
+                    format!("{:.1}>{:.2}", push, stick.apply(cgmath::vec2(push, 0.0)).x)
+                })
+                .collect::<Vec<_>>()
+                .join(" ");
+            text.push(
+                Text::new(curve, (x, 790.0))
+                    .with_size(16.0)
+                    .with_color([0.7, 0.7, 0.7, 1.0]),
+            );
+        }
+        if !self.connected() {
+            text.push(
+                Text::new("no gamepad connected", (40.0, 820.0))
+                    .with_size(16.0)
+                    .with_color([1.0, 0.5, 0.4, 1.0]),
+            );
+        }
+        text
+    }
+
+    #[cfg(feature = "gamepad")]
+    fn connected(&self) -> bool {
+        self.gilrs
+            .as_ref()
+            .is_some_and(|gilrs| gilrs.gamepads().next().is_some())
+    }
+
+    #[cfg(not(feature = "gamepad"))]
+    fn connected(&self) -> bool {
+        false
+    }
+}
+
+/// a little square picture of a stick, `.` where neither axis is past its deadzone, `o` where the
+/// stick is and `x` where that comes out after the settings
+fn stick_grid(
+    deadzone: Deadzone,
+    raw: cgmath::Vector2<f64>,
+    processed: cgmath::Vector2<f64>,
+) -> Vec<String> {
+    const SIZE: i32 = 11;
+    let half = (SIZE / 2) as f64;
+    let cell = |v: cgmath::Vector2<f64>| {
+        (
+            (half + v.x.clamp(-1.0, 1.0) * half).round() as i32,
+            (half - v.y.clamp(-1.0, 1.0) * half).round() as i32,
+        )
+    };
+    let (raw, processed) = (cell(raw), cell(processed));
+    (0..SIZE)
+        .map(|row| {
+            (0..SIZE)
+                .map(|column| {
+                    let offset = cgmath::vec2(column as f64 - half, half - row as f64) / half;
+                    if (column, row) == processed {
+                        'x'
+                    } else if (column, row) == raw {
+                        'o'
+                    } else if offset.magnitude() > 1.0 {
+                        ' '
+                    } else if offset.x.abs() <= deadzone.x && offset.y.abs() <= deadzone.y {
+                        '.'
+                    } else {
+                        '-'
+                    }
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// the console command for tuning the sticks, changes get saved right away and the preview comes
+/// up to show them
+pub fn register_commands(console: &mut Console) {
+    const USAGE: &str =
+        "gamepad <movement|aim> <deadzone|deadzone_x|deadzone_y|curve|sensitivity> \
+        <value>, gamepad rumble <strength>, or gamepad preview";
+    console.register("gamepad", USAGE, |args, context| {
+        if let ["preview"] = args {
+            context.gamepad.preview = !context.gamepad.preview;
+            return Ok(format!(
+                "gamepad preview set to {}",
+                context.gamepad.preview
+            ));
+        }
+        if let ["rumble", value] = args {
+            context.settings.gamepad.rumble = number(value)?.clamp(0.0, 1.0);
+            context
+                .settings
+                .save(context.profile.settings_path())
+                .map_err(|e| format!("{:#}", e))?;
+            return Ok(format!(
+                "gamepad rumble set to {}",
+                context.settings.gamepad.rumble
+            ));
+        }
+        let (stick, setting, value) = match args {
+            [stick, setting, value] => (*stick, *setting, *value),
+            _ => return Err(format!("usage: {}", USAGE)),
+        };
+        let config = &mut context.settings.gamepad;
+        let stick = match stick {
+            "movement" => &mut config.movement,
+            "aim" => &mut config.aim,
+            _ => return Err(format!("usage: {}", USAGE)),
+        };
+        let deadzone = || Ok::<_, String>(number(value)?.clamp(0.0, StickConfig::MAX_DEADZONE));
+        match setting {
+            "deadzone" => {
+                let both = deadzone()?;
+                stick.deadzone = Deadzone { x: both, y: both };
+            }
+            "deadzone_x" => stick.deadzone.x = deadzone()?,
+            "deadzone_y" => stick.deadzone.y = deadzone()?,
+            "sensitivity" => stick.sensitivity = number(value)?.max(0.0),
+            "curve" => {
+                stick.curve = ResponseCurve::parse(value)
+                    .ok_or("curves are linear, quadratic or cubic".to_owned())?
+            }
+            _ => return Err(format!("usage: {}", USAGE)),
+        }
+        context.gamepad.preview = true;
+        context
+            .settings
+            .save(context.profile.settings_path())
+            .map_err(|e| format!("{:#}", e))?;
+        Ok(format!("gamepad {:?}", context.settings.gamepad))
+    });
+}
+
+/// `value` as a number, infinities and NaN aren't taken since nothing can be set to them
+fn number(value: &str) -> Result<f64, String> {
+    value
+        .parse::<f64>()
+        .ok()
+        .filter(|number| number.is_finite())
+        .ok_or_else(|| format!("{} isn't a number", value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stick(deadzone: (f64, f64), curve: ResponseCurve, sensitivity: f64) -> StickConfig {
+        StickConfig {
+            deadzone: Deadzone {
+                x: deadzone.0,
+                y: deadzone.1,
+            },
+            curve,
+            sensitivity,
+        }
+    }
+
+    fn assert_close(a: cgmath::Vector2<f64>, b: cgmath::Vector2<f64>) {
+        assert!((a - b).magnitude() < 1e-9, "{:?} isn't {:?}", a, b);
+    }
+
+    #[test]
+    fn pushes_start_from_nothing_at_the_edge_of_the_deadzone() {
+        let stick = stick((0.2, 0.2), ResponseCurve::Linear, 1.0);
+        assert_close(
+            stick.apply(cgmath::vec2(0.15, -0.1)),
+            cgmath::vec2(0.0, 0.0),
+        );
+        assert_close(stick.apply(cgmath::vec2(0.2, 0.0)), cgmath::vec2(0.0, 0.0));
+        assert_close(stick.apply(cgmath::vec2(0.6, 0.0)), cgmath::vec2(0.5, 0.0));
+        assert_close(
+            stick.apply(cgmath::vec2(0.0, -1.0)),
+            cgmath::vec2(0.0, -1.0),
+        );
+    }
+
+    #[test]
+    fn each_axis_has_its_own_deadzone() {
+        let stick = stick((0.5, 0.0), ResponseCurve::Linear, 1.0);
+        assert_close(stick.apply(cgmath::vec2(0.4, 0.5)), cgmath::vec2(0.0, 0.5));
+        assert_close(
+            stick.apply(cgmath::vec2(-0.75, 0.0)),
+            cgmath::vec2(-0.5, 0.0),
+        );
+    }
+
+    #[test]
+    fn the_curve_and_sensitivity_go_on_after_the_deadzone() {
+        let quadratic = stick((0.0, 0.0), ResponseCurve::Quadratic, 1.0);
+        assert_close(
+            quadratic.apply(cgmath::vec2(0.5, 0.0)),
+            cgmath::vec2(0.25, 0.0),
+        );
+        let cubic = stick((0.0, 0.0), ResponseCurve::Cubic, 1.0);
+        assert_close(
+            cubic.apply(cgmath::vec2(0.0, -0.5)),
+            cgmath::vec2(0.0, -0.125),
+        );
+        let sensitive = stick((0.0, 0.0), ResponseCurve::Linear, 2.0);
+        assert_close(
+            sensitive.apply(cgmath::vec2(0.25, 0.0)),
+            cgmath::vec2(0.5, 0.0),
+        );
+        assert_close(
+            sensitive.apply(cgmath::vec2(0.8, 0.0)),
+            cgmath::vec2(1.0, 0.0),
+        );
+    }
+
+    #[test]
+    fn a_full_push_never_comes_out_past_one() {
+        let stick = stick((0.1, 0.3), ResponseCurve::Linear, 1.5);
+        for raw in [
+            cgmath::vec2(1.0, 1.0),
+            cgmath::vec2(-1.0, 0.9),
+            cgmath::vec2(2.0, 0.0),
+        ] {
+            assert!(stick.apply(raw).magnitude() <= 1.0 + 1e-9);
+        }
+    }
+
+    #[test]
+    fn broken_settings_are_brought_back_into_range() {
+        let mut config = GamepadConfig {
+            movement: stick((f64::NAN, 1.5), ResponseCurve::Linear, f64::INFINITY),
+            aim: stick((1.0, -0.5), ResponseCurve::Cubic, f64::NAN),
+            rumble: f64::NAN,
+        };
+        config.sanitize();
+        let default = GamepadConfig::default();
+        assert_eq!(config.movement.deadzone.x, default.movement.deadzone.x);
+        assert_eq!(config.movement.deadzone.y, StickConfig::MAX_DEADZONE);
+        assert_eq!(config.movement.sensitivity, default.movement.sensitivity);
+        assert_eq!(config.aim.deadzone.x, StickConfig::MAX_DEADZONE);
+        assert_eq!(config.aim.deadzone.y, 0.0);
+        assert_eq!(config.aim.sensitivity, default.aim.sensitivity);
+        assert_eq!(config.rumble, default.rumble);
+        for stick in [config.movement, config.aim] {
+            for raw in [cgmath::vec2(1.0, 1.0), cgmath::vec2(0.95, -0.3)] {
+                let processed = stick.apply(raw);
+                assert!(processed.x.is_finite() && processed.y.is_finite());
+            }
+        }
+    }
+
+    #[test]
+    fn a_deadzone_saved_as_one_number_is_used_for_both_axes() {
+        let stick =
+            ron::from_str::<StickConfig>("(deadzone: 0.3, curve: Linear, sensitivity: 1.0)")
+                .unwrap();
+        assert_eq!(stick.deadzone, Deadzone { x: 0.3, y: 0.3 });
+        let stick = ron::from_str::<StickConfig>(
+            "(deadzone: (x: 0.1, y: 0.4), curve: Linear, sensitivity: 1.0)",
+        )
+        .unwrap();
+        assert_eq!(stick.deadzone, Deadzone { x: 0.1, y: 0.4 });
+    }
+
+    #[test]
+    fn the_console_only_takes_finite_numbers() {
+        assert_eq!(number("0.25"), Ok(0.25));
+        assert!(number("nan").is_err());
+        assert!(number("inf").is_err());
+        assert!(number("fast").is_err());
+    }
+}
@@ -0,0 +1,172 @@
+use std::future::Future;
+use std::pin::Pin;
+
+use futures::FutureExt;
+
+use crate::frame_graph::PassKind;
+
+/// how long the gpu spent on one frame and each of its passes, in milliseconds
+#[derive(Clone, Debug, Default)]
+pub struct GpuTimes {
+    pub frame: f64,
+    pub passes: Vec<(PassKind, f64)>,
+}
+
+type MapFuture = Pin<Box<dyn Future<Output = Result<(), wgpu::BufferAsyncError>> + Send>>;
+
+/// a frame's timestamps on their way back from the gpu
+struct Pending {
+    /// counts up every frame, so the newest one can be told apart when several come back at once
+    frame: u64,
+    passes: Vec<PassKind>,
+    map: MapFuture,
+}
+
+/// somewhere to copy the timestamps of one frame to, so they can be read back
+struct Readback {
+    buffer: wgpu::Buffer,
+    /// None when it's free to be used again
+    pending: Option<Pending>,
+}
+
+/// times the passes of a frame with timestamp queries, the results come back a few frames late
+/// since the gpu runs behind
+pub struct GpuTimer {
+    queries: wgpu::QuerySet,
+    readbacks: Vec<Readback>,
+    /// nanoseconds per timestamp tick
+    period: f64,
+    frame: u64,
+    /// the readback for the frame being recorded and the passes in it so far
+    recording: Option<(usize, Vec<PassKind>)>,
+    latest: Option<(u64, GpuTimes)>,
+}
+
+impl GpuTimer {
+    /// one timestamp before the first pass and one after each of them
+    const MAX_QUERIES: u32 = 16;
+    /// how many frames can be waiting to be read back, ones past that just don't get timed
+    const FRAMES_IN_FLIGHT: usize = 3;
+    const BUFFER_SIZE: wgpu::BufferAddress = Self::MAX_QUERIES as wgpu::BufferAddress * 8;
+
+    /// None when the device wasn't made with timestamp queries, not everything supports them
+    pub fn new(device: &wgpu::Device, queue: &wgpu::Queue) -> Option<Self> {
+        if !device.features().contains(wgpu::Features::TIMESTAMP_QUERY) {
+            return None;
+        }
+        let queries = device.create_query_set(&wgpu::QuerySetDescriptor {
+            label: Some("gpu timer queries"),
+            ty: wgpu::QueryType::Timestamp,
+            count: Self::MAX_QUERIES,
+        });
+        let readbacks = (0..Self::FRAMES_IN_FLIGHT)
+            .map(|_| Readback {
+                buffer: device.create_buffer(&wgpu::BufferDescriptor {
+                    label: Some("gpu timer readback buffer"),
+                    size: Self::BUFFER_SIZE,
+                    usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+                    mapped_at_creation: false,
+                }),
+                pending: None,
+            })
+            .collect();
+        Some(Self {
+            queries,
+            readbacks,
+            period: queue.get_timestamp_period() as f64,
+            frame: 0,
+            recording: None,
+            latest: None,
+        })
+    }
+
+    /// starts timing a frame, if there's a readback free for it
+    pub fn begin(&mut self, encoder: &mut wgpu::CommandEncoder) {
+        self.recording = self
+            .readbacks
+            .iter()
+            .position(|readback| readback.pending.is_none())
+            .map(|readback| (readback, vec![]));
+        if self.recording.is_some() {
+            encoder.write_timestamp(&self.queries, 0);
+        }
+    }
+
+    /// marks where `pass` ended, it started where the one before it did
+    pub fn end_pass(&mut self, encoder: &mut wgpu::CommandEncoder, pass: PassKind) {
+        if let Some((_, passes)) = &mut self.recording {
+            if passes.len() + 1 < Self::MAX_QUERIES as usize {
+                passes.push(pass);
+                encoder.write_timestamp(&self.queries, passes.len() as u32);
+            }
+        }
+    }
+
+    /// copies the frame's timestamps out to be read back, before the encoder gets finished
+    pub fn resolve(&self, encoder: &mut wgpu::CommandEncoder) {
+        if let Some((readback, passes)) = &self.recording {
+            encoder.resolve_query_set(
+                &self.queries,
+                0..passes.len() as u32 + 1,
+                &self.readbacks[*readback].buffer,
+                0,
+            );
+        }
+    }
+
+    /// starts reading the frame back, once it's been submitted
+    pub fn submitted(&mut self) {
+        if let Some((readback, passes)) = self.recording.take() {
+            let readback = &mut self.readbacks[readback];
+            readback.pending = Some(Pending {
+                frame: self.frame,
+                passes,
+                map: Box::pin(readback.buffer.slice(..).map_async(wgpu::MapMode::Read)),
+            });
+            self.frame += 1;
+        }
+    }
+
+    /// the newest times that have made it back from the gpu, each frame's only get given out once
+    pub fn take(&mut self, device: &wgpu::Device) -> Option<GpuTimes> {
+        device.poll(wgpu::Maintain::Poll);
+        for readback in &mut self.readbacks {
+            let mapped = match &mut readback.pending {
+                Some(pending) => match pending.map.as_mut().now_or_never() {
+                    Some(mapped) => mapped,
+                    None => continue,
+                },
+                None => continue,
+            };
+            let pending = readback.pending.take().expect("it was just polled");
+            if let Err(e) = mapped {
+                tracing::warn!("failed to read back gpu timestamps: {}", e);
+                continue;
+            }
+            let times = {
+                let data = readback.buffer.slice(..).get_mapped_range();
+                let stamps: &[u64] = bytemuck::cast_slice(&data);
+                let ms = |from: u64, to: u64| to.saturating_sub(from) as f64 * self.period / 1e6;
+                let count = pending.passes.len();
+                GpuTimes {
+                    frame: ms(stamps[0], stamps[count]),
+                    passes: pending
+                        .passes
+                        .iter()
+                        .enumerate()
+                        .map(|(i, pass)| (*pass, ms(stamps[i], stamps[i + 1])))
+                        .collect(),
+                }
+            };
+            readback.buffer.unmap();
+            if self
+                .latest
+                .as_ref()
+                .is_none_or(|(frame, _)| *frame < pending.frame)
+            {
+                self.latest = Some((pending.frame, times));
+            }
+        }
+        self.latest.take().map(|(_, times)| times)
+    }
+}
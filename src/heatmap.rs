@@ -0,0 +1,177 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use color_eyre::eyre::{eyre, WrapErr};
+
+use crate::console::Console;
+use crate::game_state::GameState;
+use crate::instances;
+
+/// counts where the player touches things, tick after tick and across restarts, to show level
+/// designers which walls and ledges a route actually leans on
+#[derive(Default)]
+pub struct Heatmap {
+    /// ticks spent touching something in each cell
+    counts: HashMap<(i64, i64), u32>,
+    hottest: u32,
+}
+
+impl Heatmap {
+    /// how big a cell is, in world units, and a pixel once saved
+    const CELL: f64 = 0.5;
+    /// how see-through the coldest and hottest cells get drawn
+    const MIN_ALPHA: f32 = 0.2;
+    const MAX_ALPHA: f32 = 0.65;
+
+    /// adds the contacts the player had on the update `state` just had
+    pub fn record(&mut self, state: &GameState) {
+        let player = match state.object(state.view_object) {
+            Some(player) => player,
+            None => return,
+        };
+        let (pos, size) = (*player.get_pos(), *player.get_size());
+        for contact in state.contacts() {
+            let other = match contact.objects {
+                (a, b) if a == state.view_object => b,
+                (a, b) if b == state.view_object => a,
+                _ => continue,
+            };
+            let other = match state.object(other) {
+                Some(other) => other,
+                None => continue,
+            };
+            let (other_pos, other_size) = (*other.get_pos(), *other.get_size());
+            // the middle of where the boxes overlap, which is a thin strip along the edges that
+            // are touching
+            let middle = |from: f64, to: f64, other_from: f64, other_to: f64| {
+                (from.max(other_from) + to.min(other_to)) / 2.0
+            };
+            let x = middle(
+                pos.x,
+                pos.x + size.x,
+                other_pos.x,
+                other_pos.x + other_size.x,
+            );
+            let y = middle(
+                pos.y,
+                pos.y + size.y,
+                other_pos.y,
+                other_pos.y + other_size.y,
+            );
+            let cell = (
+                (x / Self::CELL).floor() as i64,
+                (y / Self::CELL).floor() as i64,
+            );
+            let count = self.counts.entry(cell).or_default();
+            *count += 1;
+            self.hottest = self.hottest.max(*count);
+        }
+    }
+
+    /// how hot a cell is from 0 to 1, on a log scale so a ledge that's stood on for seconds
+    /// doesn't wash out every wall that only got kicked off once
+    fn heat(&self, count: u32) -> f32 {
+        ((1.0 + count as f64).ln() / (1.0 + self.hottest as f64).ln()) as f32
+    }
+
+    /// blue for rarely touched, through yellow to red for touched the most
+    fn color(heat: f32) -> [f32; 3] {
+        match heat {
+            heat if heat < 0.5 => [heat * 2.0, heat * 2.0, 1.0 - heat * 2.0],
+            heat => [1.0, 2.0 - heat * 2.0, 0.0],
+        }
+    }
+
+    /// the quads for the cells, drawn over the level
+    pub fn instances(&self) -> Vec<[f32; 16]> {
+        self.counts
+            .iter()
+            .map(|(&(x, y), &count)| {
+                let heat = self.heat(count);
+                let [r, g, b] = Self::color(heat);
+                let alpha = Self::MIN_ALPHA + (Self::MAX_ALPHA - Self::MIN_ALPHA) * heat;
+                let cell = Self::CELL as f32;
+                instances::instance(
+                    [x as f32 * cell, y as f32 * cell, cell, cell],
+                    [r, g, b, alpha],
+                )
+            })
+            .collect()
+    }
+
+    /// writes the heatmap out as a png, a pixel per cell with untouched cells left clear, returns
+    /// the corner of the level its bottom left pixel is at
+    pub fn save(&self, path: impl AsRef<Path>) -> color_eyre::Result<(f64, f64)> {
+        let path = path.as_ref();
+        if self.counts.is_empty() {
+            return Err(eyre!("nothing's been touched yet"));
+        }
+        let (min_x, max_x, min_y, max_y) = self.counts.keys().fold(
+            (i64::MAX, i64::MIN, i64::MAX, i64::MIN),
+            |(min_x, max_x, min_y, max_y), &(x, y)| {
+                (min_x.min(x), max_x.max(x), min_y.min(y), max_y.max(y))
+            },
+        );
+        let (width, height) = ((max_x - min_x + 1) as u32, (max_y - min_y + 1) as u32);
+        let mut pixels = vec![0; width as usize * height as usize * 4];
+        for (&(x, y), &count) in &self.counts {
+            let heat = self.heat(count);
+            let [r, g, b] = Self::color(heat);
+            // images go top down, the level goes bottom up
+            let row = (max_y - y) as usize;
+            let i = (row * width as usize + (x - min_x) as usize) * 4;
+            pixels[i..i + 4]
+                .copy_from_slice(&[r, g, b, 0.3 + 0.7 * heat].map(|c| (c * 255.0) as u8));
+        }
+        let write = || -> Result<(), png::EncodingError> {
+            let file = std::io::BufWriter::new(std::fs::File::create(path)?);
+            let mut encoder = png::Encoder::new(file, width, height);
+            encoder.set_color(png::ColorType::Rgba);
+            encoder.set_depth(png::BitDepth::Eight);
+            encoder.write_header()?.write_image_data(&pixels)
+        };
+        write().wrap_err_with(|| format!("failed to write heatmap {}", path.display()))?;
+        Ok((min_x as f64 * Self::CELL, min_y as f64 * Self::CELL))
+    }
+}
+
+/// the console command for the heatmap, it keeps counting across restarts of the level
+pub fn register_commands(console: &mut Console) {
+    const USAGE: &str = "heatmap <on|off|clear|save <file.png>>";
+    console.register("heatmap", USAGE, |args, context| {
+        let session = context.session()?;
+        match args {
+            ["on"] => {
+                session.heatmap.get_or_insert_with(Heatmap::default);
+                Ok("recording where the player touches things".to_owned())
+            }
+            ["off"] => {
+                session.heatmap = None;
+                Ok("heatmap turned off".to_owned())
+            }
+            ["clear"] => {
+                let heatmap = session
+                    .heatmap
+                    .as_mut()
+                    .ok_or("the heatmap isn't on".to_owned())?;
+                *heatmap = Heatmap::default();
+                Ok("heatmap cleared".to_owned())
+            }
+            ["save", path] => {
+                let heatmap = session
+                    .heatmap
+                    .as_ref()
+                    .ok_or("the heatmap isn't on".to_owned())?;
+                let (x, y) = heatmap.save(path).map_err(|e| format!("{:#}", e))?;
+                Ok(format!(
+                    "heatmap written to {}, {} units a pixel from {}, {}",
+                    path,
+                    Heatmap::CELL,
+                    x,
+                    y
+                ))
+            }
+            _ => Err(format!("usage: {}", USAGE)),
+        }
+    });
+}
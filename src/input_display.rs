@@ -0,0 +1,72 @@
+use crate::game_state::{Direction, HookState, PlayerInput};
+use crate::locale::Strings;
+use crate::text::Text;
+
+/// where the keys go, the top left of the W
+const ORIGIN: (f32, f32) = (800.0, 760.0);
+/// how far apart the keys are
+const KEY_SPACING: f32 = 36.0;
+const HELD_COLOR: [f32; 4] = [1.0, 0.9, 0.3, 1.0];
+const IDLE_COLOR: [f32; 4] = [0.45, 0.45, 0.45, 1.0];
+
+/// the keys the player is holding and what their hook is doing, laid out in the bottom right for
+/// people recording the game, `aim` is the angle to the cursor while the fire button is held
+pub fn text(input: &PlayerInput, aim: Option<f64>, strings: &Strings) -> Vec<Text> {
+    let color = |on: bool| if on { HELD_COLOR } else { IDLE_COLOR };
+    let held = |direction| input.held.contains(&direction);
+    let key = |label: &str, column: f32, row: f32, direction| {
+        Text::new(
+            label,
+            (
+                ORIGIN.0 + column * KEY_SPACING,
+                ORIGIN.1 + row * KEY_SPACING,
+            ),
+        )
+        .with_color(color(held(direction)))
+    };
+    let mut text = vec![
+        key("W", 1.0, 0.0, Direction::Up),
+        key("A", 0.0, 1.0, Direction::Left),
+        key("S", 1.0, 1.0, Direction::Down),
+        key("D", 2.0, 1.0, Direction::Right),
+        Text::new(
+            strings.get("inputs.jump"),
+            (ORIGIN.0 + 2.0 * KEY_SPACING, ORIGIN.1 + 6.0),
+        )
+        .with_size(16.0)
+        .with_color(color(held(Direction::Up))),
+        Text::new(
+            strings.get("inputs.fire"),
+            (ORIGIN.0, ORIGIN.1 + 2.2 * KEY_SPACING),
+        )
+        .with_size(18.0)
+        .with_color(color(input.aiming)),
+    ];
+    let hook = match (input.hook, aim) {
+        (HookState::Idle, Some(aim)) => strings.format(
+            "inputs.aiming",
+            &[("angle", &format!("{:.0}", aim.to_degrees()))],
+        ),
+        (HookState::Idle, None) => strings.get("inputs.ready").to_owned(),
+        (HookState::Flying { angle, distance }, _) => strings.format(
+            "inputs.flying",
+            &[
+                ("distance", &format!("{:.1}", distance)),
+                ("angle", &format!("{:.0}", angle.to_degrees())),
+            ],
+        ),
+        (HookState::Attached { angle, length }, _) => strings.format(
+            "inputs.rope",
+            &[
+                ("length", &format!("{:.1}", length)),
+                ("angle", &format!("{:.0}", angle.to_degrees())),
+            ],
+        ),
+    };
+    text.push(
+        Text::new(hook, (ORIGIN.0, ORIGIN.1 + 2.9 * KEY_SPACING))
+            .with_size(18.0)
+            .with_color(color(input.hook != HookState::Idle)),
+    );
+    text
+}
@@ -0,0 +1,133 @@
+use crate::game_state::{GameState, ObjectType};
+use crate::render::{RenderState, TextWindow};
+use crate::text::Text;
+
+/// a second window listing every object in the level as it changes, for debugging
+pub struct Inspector {
+    pub window: winit::window::Window,
+    target: TextWindow,
+}
+
+impl Inspector {
+    const LINE_HEIGHT: f32 = 20.0;
+    const SELECTED_COLOR: [f32; 4] = [1.0, 0.9, 0.3, 1.0];
+
+    pub fn open(
+        window_target: &winit::event_loop::EventLoopWindowTarget<()>,
+        render_state: &RenderState,
+    ) -> color_eyre::Result<Self> {
+        let window = winit::window::WindowBuilder::new()
+            .with_title("Grappling Hook - Inspector")
+            .with_inner_size(winit::dpi::PhysicalSize {
+                width: 560,
+                height: 960,
+            })
+            .with_resizable(false)
+            .build(window_target)?;
+        let target = render_state.create_text_window(&window)?;
+        Ok(Self { window, target })
+    }
+
+    /// `selected` is the object picked in the main window, it gets shown in more detail
+    pub fn render(
+        &mut self,
+        render_state: &RenderState,
+        state: Option<&GameState>,
+        selected: Option<usize>,
+    ) {
+        let text = match state {
+            Some(state) => self.text(state, selected),
+            None => vec![Text::new("not playing a level", (20.0, 20.0))],
+        };
+        if let Err(e) = render_state.render_text_window(&mut self.target, &text) {
+            tracing::warn!("failed to draw the inspector: {}", e);
+        }
+    }
+
+    /// the selected object in detail, then a line for every object
+    fn text(&self, state: &GameState, selected: Option<usize>) -> Vec<Text> {
+        let mut lines = vec![(
+            format!(
+                "tick {}, {} objects, right click to select",
+                state.tick,
+                state.object_count()
+            ),
+            [0.7, 0.7, 0.7, 1.0],
+        )];
+        match selected.and_then(|index| state.object(index)) {
+            Some(object) => {
+                let index = object.id();
+                let color = Self::SELECTED_COLOR;
+                lines.push((
+                    format!("object {}: {}", index, describe_type(object.get_type())),
+                    color,
+                ));
+                let (pos, size) = (object.get_pos(), object.get_size());
+                lines.push((format!("  pos  {:8.3} {:8.3}", pos.x, pos.y), color));
+                lines.push((format!("  size {:8.3} {:8.3}", size.x, size.y), color));
+                let velocity = object.get_velocity();
+                lines.push((
+                    format!("  vel  {:8.3} {:8.3}", velocity.x, velocity.y),
+                    color,
+                ));
+                if let Some(parent) = object.get_parent() {
+                    lines.push((format!("  attached to {}", parent), color));
+                }
+                let mut touching = object.get_touching().iter().collect::<Vec<_>>();
+                touching.sort_by_key(|(index, _)| **index);
+                if touching.is_empty() {
+                    lines.push(("  touching nothing".to_owned(), color));
+                }
+                for (other, direction) in touching {
+                    lines.push((
+                        format!("  touching {} on the {:?}", other, direction),
+                        color,
+                    ));
+                }
+            }
+            None => lines.push(("nothing selected".to_owned(), [0.7, 0.7, 0.7, 1.0])),
+        }
+        lines.push((String::new(), [1.0; 4]));
+        for object in state.iter_objects() {
+            let index = object.id();
+            let (pos, velocity) = (object.get_pos(), object.get_velocity());
+            let color = if Some(index) == selected {
+                Self::SELECTED_COLOR
+            } else {
+                [1.0; 4]
+            };
+            lines.push((
+                format!(
+                    "{:4} {:9} pos {:7.2} {:7.2} vel {:6.2} {:6.2}",
+                    index,
+                    describe_type(object.get_type()),
+                    pos.x,
+                    pos.y,
+                    velocity.x,
+                    velocity.y
+                ),
+                color,
+            ));
+        }
+        let fits = ((self.window.inner_size().height as f32 / Self::LINE_HEIGHT) as usize)
+            .saturating_sub(1);
+        lines
+            .into_iter()
+            .take(fits)
+            .enumerate()
+            .map(|(i, (line, color))| {
+                Text::new(line, (20.0, 10.0 + i as f32 * Self::LINE_HEIGHT))
+                    .with_size(16.0)
+                    .with_color(color)
+            })
+            .collect()
+    }
+}
+
+fn describe_type(ty: &ObjectType) -> &'static str {
+    match ty {
+        ObjectType::Static => "static",
+        ObjectType::Movable { .. } => "movable",
+        ObjectType::Treadmill { .. } => "treadmill",
+    }
+}
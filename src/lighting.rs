@@ -0,0 +1,168 @@
+use crate::level;
+
+use wgpu::util::DeviceExt;
+
+/// has to match the array sizes in lighting.wgsl
+const MAX_LIGHTS: usize = 16;
+const MAX_OCCLUDERS: usize = 128;
+
+/// darkens the scene outside of the level's lights, with objects casting shadows
+///
+/// the light is computed per pixel in a fullscreen pass and multiplied onto what was already drawn
+pub struct Lighting {
+    pipeline: wgpu::RenderPipeline,
+    bind_group_layout: wgpu::BindGroupLayout,
+}
+
+impl Lighting {
+    pub fn new(device: &wgpu::Device, format: wgpu::TextureFormat, sample_count: u32) -> Self {
+        let shader = device.create_shader_module(&wgpu::include_wgsl!("lighting.wgsl"));
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("lighting bind group layout"),
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::VERTEX | wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: (uniform_size() as u64).try_into().ok(),
+                },
+                count: None,
+            }],
+        });
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("lighting pipeline"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let multiply = wgpu::BlendComponent {
+            src_factor: wgpu::BlendFactor::Dst,
+            dst_factor: wgpu::BlendFactor::Zero,
+            operation: wgpu::BlendOperation::Add,
+        };
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("lighting pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: "vs_main",
+                buffers: &[],
+            },
+            primitive: wgpu::PrimitiveState::default(),
+            // it's drawn in the same pass as the world, so it has to take the depth buffer, but
+            // it covers everything no matter what's in it
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: crate::render::DEPTH_FORMAT,
+                depth_write_enabled: false,
+                depth_compare: wgpu::CompareFunction::Always,
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState::default(),
+            }),
+            multisample: wgpu::MultisampleState {
+                count: sample_count,
+                mask: !0,
+                alpha_to_coverage_enabled: false,
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: "fs_main",
+                targets: &[wgpu::ColorTargetState {
+                    format,
+                    blend: Some(wgpu::BlendState {
+                        color: multiply,
+                        alpha: multiply,
+                    }),
+                    write_mask: wgpu::ColorWrites::ALL,
+                }],
+            }),
+            multiview: None,
+        });
+        Self {
+            pipeline,
+            bind_group_layout,
+        }
+    }
+
+    /// builds the bind group for one frame
+    ///
+    /// `occluders` are the boxes that cast shadows, as [x, y, width, height]
+    pub fn prepare(
+        &self,
+        device: &wgpu::Device,
+        inverse_view: cgmath::Matrix4<f32>,
+        settings: &level::Lighting,
+        occluders: &[[f32; 4]],
+    ) -> wgpu::BindGroup {
+        if settings.lights.len() > MAX_LIGHTS {
+            tracing::warn!(
+                "level has {} lights, only the first {} are drawn",
+                settings.lights.len(),
+                MAX_LIGHTS
+            );
+        }
+        let lights = &settings.lights[..settings.lights.len().min(MAX_LIGHTS)];
+        let occluders = &occluders[..occluders.len().min(MAX_OCCLUDERS)];
+
+        let mut contents = Vec::with_capacity(uniform_size());
+        contents.extend_from_slice(bytemuck::cast_slice(AsRef::<[f32; 16]>::as_ref(
+            &inverse_view,
+        )));
+        // light colours are picked in sRGB, but they add up in linear space
+        let [r, g, b] = crate::srgb::color_to_linear(settings.ambient);
+        contents.extend_from_slice(bytemuck::cast_slice(&[r, g, b, 1.0]));
+        contents.extend_from_slice(bytemuck::cast_slice(&[
+            lights.len() as u32,
+            occluders.len() as u32,
+            0,
+            0,
+        ]));
+        for index in 0..MAX_LIGHTS {
+            let light = lights.get(index).map(|light| {
+                let [r, g, b] = crate::srgb::color_to_linear(light.color);
+                [
+                    light.pos.x as f32,
+                    light.pos.y as f32,
+                    light.radius as f32,
+                    0.0,
+                    r,
+                    g,
+                    b,
+                    1.0,
+                ]
+            });
+            contents.extend_from_slice(bytemuck::cast_slice(&light.unwrap_or([0.0; 8])));
+        }
+        for index in 0..MAX_OCCLUDERS {
+            let occluder = occluders
+                .get(index)
+                .map(|&[x, y, w, h]| [x, y, x + w, y + h]);
+            contents.extend_from_slice(bytemuck::cast_slice(&occluder.unwrap_or([0.0; 4])));
+        }
+
+        let buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("lighting buffer"),
+            contents: &contents,
+            usage: wgpu::BufferUsages::UNIFORM,
+        });
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("lighting bind group"),
+            layout: &self.bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: buffer.as_entire_binding(),
+            }],
+        })
+    }
+
+    pub fn draw<'a>(&'a self, rpass: &mut wgpu::RenderPass<'a>, bind_group: &'a wgpu::BindGroup) {
+        rpass.set_pipeline(&self.pipeline);
+        rpass.set_bind_group(0, bind_group, &[]);
+        rpass.draw(0..3, 0..1);
+    }
+}
+
+fn uniform_size() -> usize {
+    let mat4 = 16 * 4;
+    let vec4 = 4 * 4;
+    mat4 + vec4 + vec4 + MAX_LIGHTS * 2 * vec4 + MAX_OCCLUDERS * vec4
+}
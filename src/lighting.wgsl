@@ -0,0 +1,87 @@
+struct VertexOutput {
+    [[builtin(position)]] position: vec4<f32>;
+    [[location(0)]] world: vec2<f32>;
+};
+
+struct Light {
+    position: vec2<f32>;
+    radius: f32;
+    padding: f32;
+    color: vec4<f32>;
+};
+
+// an axis aligned box that blocks light
+struct Occluder {
+    min: vec2<f32>;
+    max: vec2<f32>;
+};
+
+struct Lighting {
+    inverse_view: mat4x4<f32>;
+    ambient: vec4<f32>;
+    light_count: u32;
+    occluder_count: u32;
+    padding: vec2<u32>;
+    lights: array<Light, 16>;
+    occluders: array<Occluder, 128>;
+};
+
+[[group(0), binding(0)]]
+var<uniform> l: Lighting;
+
+// a single triangle covering the whole screen
+[[stage(vertex)]]
+fn vs_main([[builtin(vertex_index)]] index: u32) -> VertexOutput {
+    var output: VertexOutput;
+    let uv = vec2<f32>(f32((index << 1u) & 2u), f32(index & 2u));
+    let clip = uv * 2.0 - 1.0;
+    output.position = vec4<f32>(clip, 0.0, 1.0);
+    output.world = (l.inverse_view * vec4<f32>(clip, 0.0, 1.0)).xy;
+    return output;
+}
+
+fn inside(point: vec2<f32>, occluder: Occluder) -> bool {
+    return all(point >= occluder.min) && all(point <= occluder.max);
+}
+
+// slab test of the segment from -> to against the box
+fn blocks(from: vec2<f32>, to: vec2<f32>, occluder: Occluder) -> bool {
+    var direction = to - from;
+    if (abs(direction.x) < 0.00001) {
+        direction.x = 0.00001;
+    }
+    if (abs(direction.y) < 0.00001) {
+        direction.y = 0.00001;
+    }
+    let t1 = (occluder.min - from) / direction;
+    let t2 = (occluder.max - from) / direction;
+    let t_min = max(min(t1.x, t2.x), min(t1.y, t2.y));
+    let t_max = min(max(t1.x, t2.x), max(t1.y, t2.y));
+    return t_max >= max(t_min, 0.0) && t_min <= 1.0;
+}
+
+[[stage(fragment)]]
+fn fs_main(input: VertexOutput) -> [[location(0)]] vec4<f32> {
+    var light = l.ambient.rgb;
+    for (var i = 0u; i < l.light_count; i = i + 1u) {
+        let current = l.lights[i];
+        let distance = length(current.position - input.world);
+        if (distance > current.radius) {
+            continue;
+        }
+        var shadowed = false;
+        for (var j = 0u; j < l.occluder_count; j = j + 1u) {
+            let occluder = l.occluders[j];
+            // surfaces of objects should still get lit, so they don't shadow themselves
+            if (!inside(input.world, occluder) && blocks(input.world, current.position, occluder)) {
+                shadowed = true;
+                break;
+            }
+        }
+        if (!shadowed) {
+            let falloff = 1.0 - smoothStep(0.0, current.radius, distance);
+            light = light + current.color.rgb * falloff * falloff;
+        }
+    }
+    return vec4<f32>(light, 1.0);
+}
@@ -0,0 +1,130 @@
+use std::path::PathBuf;
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+
+use color_eyre::eyre::eyre;
+
+use crate::generate::Course;
+use crate::level::Level;
+use crate::session::Session;
+
+/// what a level that's loading is busy with
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Stage {
+    Reading,
+    Building,
+    Ghost,
+}
+
+impl Stage {
+    const ALL: [Stage; 3] = [Stage::Reading, Stage::Building, Stage::Ghost];
+
+    /// the key for what the loading screen says about it
+    pub fn key(self) -> &'static str {
+        match self {
+            Stage::Reading => "loading.reading",
+            Stage::Building => "loading.building",
+            Stage::Ghost => "loading.ghost",
+        }
+    }
+
+    /// how much of the loading is done by the time it gets here, from 0 to 1
+    pub fn progress(self) -> f32 {
+        let index = Self::ALL
+            .iter()
+            .position(|stage| *stage == self)
+            .unwrap_or(0);
+        index as f32 / Self::ALL.len() as f32
+    }
+}
+
+enum Message {
+    Stage(Stage),
+    Done(color_eyre::Result<Box<Session>>),
+}
+
+/// a level being loaded on a thread of its own, so the window keeps drawing instead of freezing
+/// until it's ready
+pub struct Loading {
+    pub level_name: String,
+    pub stage: Stage,
+    /// whether the second player joins once it's loaded
+    pub coop: bool,
+    receiver: Receiver<Message>,
+}
+
+impl Loading {
+    /// starts loading the level at `path`, with the players coming in through `entrance` if
+    /// there is one, dropping it cancels the load
+    pub fn start(
+        path: PathBuf,
+        shared_ghost: Option<PathBuf>,
+        coop: bool,
+        entrance: Option<String>,
+    ) -> Self {
+        let level_name = Level::name_from_path(&path);
+        Self::spawn(level_name, shared_ghost, coop, entrance, move || {
+            Level::load(&path)
+        })
+    }
+
+    /// starts making up the course for `seed`, it goes through the same stages as one read from
+    /// a file so it gets its own stats and ghosts
+    pub fn generated(seed: u64, shared_ghost: Option<PathBuf>) -> Self {
+        Self::spawn(Course::name(seed), shared_ghost, false, None, move || {
+            Course::generate(seed).map(|course| course.level)
+        })
+    }
+
+    fn spawn(
+        level_name: String,
+        shared_ghost: Option<PathBuf>,
+        coop: bool,
+        entrance: Option<String>,
+        read: impl FnOnce() -> color_eyre::Result<Level> + Send + 'static,
+    ) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        let name = level_name.clone();
+        std::thread::spawn(move || {
+            // once nobody is listening the load got cancelled, so there's no point going on
+            let report = |stage| sender.send(Message::Stage(stage)).is_ok();
+            let load = || {
+                if !report(Stage::Reading) {
+                    return Err(eyre!("cancelled"));
+                }
+                let level = read()?;
+                if !report(Stage::Building) {
+                    return Err(eyre!("cancelled"));
+                }
+                let mut session = Session::new(level, name, shared_ghost);
+                session.enter(entrance);
+                if !report(Stage::Ghost) {
+                    return Err(eyre!("cancelled"));
+                }
+                session.load_ghost();
+                Ok(Box::new(session))
+            };
+            let _ = sender.send(Message::Done(load()));
+        });
+        Self {
+            level_name,
+            stage: Stage::Reading,
+            coop,
+            receiver,
+        }
+    }
+
+    /// the session once it's ready to swap in, or why it couldn't be loaded, None while it's
+    /// still going
+    pub fn poll(&mut self) -> Option<color_eyre::Result<Session>> {
+        loop {
+            match self.receiver.try_recv() {
+                Ok(Message::Stage(stage)) => self.stage = stage,
+                Ok(Message::Done(result)) => return Some(result.map(|session| *session)),
+                Err(TryRecvError::Empty) => return None,
+                Err(TryRecvError::Disconnected) => {
+                    return Some(Err(eyre!("loading {} crashed", self.level_name)))
+                }
+            }
+        }
+    }
+}
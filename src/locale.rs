@@ -0,0 +1,104 @@
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::path::Path;
+
+use crate::console::Console;
+
+/// the text shown in menus and on the hud, looked up by key in the string table of the chosen
+/// language, anything that one doesn't have comes from the English one
+pub struct Strings {
+    strings: HashMap<String, String>,
+    fallback: HashMap<String, String>,
+}
+
+impl Strings {
+    pub const DIRECTORY: &'static str = "assets/locales";
+    pub const FALLBACK: &'static str = "en";
+
+    /// a missing or broken table just means everything comes from the fallback, or shows its key
+    pub fn load(locale: &str) -> Self {
+        let fallback = load_table(Self::FALLBACK);
+        let strings = if locale == Self::FALLBACK {
+            HashMap::new()
+        } else {
+            load_table(locale)
+        };
+        let missing = fallback
+            .keys()
+            .filter(|key| !strings.contains_key(*key))
+            .count();
+        if locale != Self::FALLBACK && missing > 0 {
+            tracing::warn!(
+                "locale {} is missing {} strings, they'll be in English",
+                locale,
+                missing
+            );
+        }
+        Self { strings, fallback }
+    }
+
+    /// the text for `key`, if any table has it
+    pub fn lookup(&self, key: &str) -> Option<&str> {
+        self.strings
+            .get(key)
+            .or_else(|| self.fallback.get(key))
+            .map(String::as_str)
+    }
+
+    /// the text for `key`, or the key itself so it's obvious what's missing
+    pub fn get<'a>(&'a self, key: &'a str) -> &'a str {
+        self.lookup(key).unwrap_or(key)
+    }
+
+    /// `get`, with each `{name}` in it replaced by the value going with that name
+    pub fn format(&self, key: &str, args: &[(&str, &dyn Display)]) -> String {
+        let mut text = self.get(key).to_owned();
+        for (name, value) in args {
+            text = text.replace(&format!("{{{}}}", name), &value.to_string());
+        }
+        text
+    }
+}
+
+/// the console command for switching language, which gets saved right away
+pub fn register_commands(console: &mut Console) {
+    console.register("language", "language <code>", |args, context| {
+        let locale = match args {
+            [locale] => *locale,
+            _ => return Err("usage: language <code>".to_owned()),
+        };
+        let path = Path::new(Strings::DIRECTORY)
+            .join(locale)
+            .with_extension("ron");
+        if !path.exists() {
+            return Err(format!(
+                "no strings for {} in {}/",
+                locale,
+                Strings::DIRECTORY
+            ));
+        }
+        *context.strings = Strings::load(locale);
+        context.settings.locale = locale.to_owned();
+        context
+            .settings
+            .save(context.profile.settings_path())
+            .map_err(|e| format!("{:#}", e))?;
+        Ok(format!("language set to {}", locale))
+    });
+}
+
+fn load_table(locale: &str) -> HashMap<String, String> {
+    let path = Path::new(Strings::DIRECTORY)
+        .join(locale)
+        .with_extension("ron");
+    match std::fs::read_to_string(&path) {
+        Ok(text) => ron::from_str(&text).unwrap_or_else(|e| {
+            tracing::warn!("failed to parse strings {}: {}", path.display(), e);
+            HashMap::new()
+        }),
+        Err(e) => {
+            tracing::warn!("failed to read strings {}: {}", path.display(), e);
+            HashMap::new()
+        }
+    }
+}
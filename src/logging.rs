@@ -0,0 +1,257 @@
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+
+use color_eyre::eyre::{eyre, WrapErr};
+use tracing::field::{Field, Visit};
+use tracing::{Event, Level, Subscriber};
+use tracing_subscriber::filter::{LevelFilter, Targets};
+use tracing_subscriber::layer::{Context, SubscriberExt};
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::Layer;
+
+use crate::console::Console;
+use crate::text::Text;
+
+/// which modules log at which level, in the form `warn,grappling_hook=info,wgpu_core=error`
+pub const FILTER_VAR: &str = "GRAPPLING_HOOK_LOG";
+const DEFAULT_FILTER: &str = "warn,grappling_hook=info,grappling_hook_core=info";
+/// where the log file goes, the older ones get a number put after them
+const LOG_FILE: &str = "grappling_hook.log";
+/// how big the log file gets before it's moved out of the way for a fresh one
+const MAX_FILE_SIZE: u64 = 1 << 20;
+/// how many of the old log files are kept around
+const OLD_FILES: usize = 3;
+/// what everything coming through `log` is logged under, it can't keep its own target
+const LOG_TARGET: &str = "log";
+
+/// sets up logging to stderr, the log file and the in-game panel, `log` records from dependencies
+/// and the core crate get passed along too
+pub fn init() -> color_eyre::Result<LogPanel> {
+    let directives = std::env::var(FILTER_VAR).unwrap_or_else(|_| DEFAULT_FILTER.to_owned());
+    let targets = Targets::from_str(&directives)
+        .map_err(|e| eyre!("{}", e))
+        .wrap_err_with(|| format!("failed to parse {}={}", FILTER_VAR, directives))?;
+    let panel = LogPanel::default();
+    let file = RollingFile::open(crate::paths::logs_dir().join(LOG_FILE))?;
+    tracing_subscriber::registry()
+        .with(tracing_subscriber::fmt::layer().with_writer(std::io::stderr))
+        .with(tracing_subscriber::fmt::layer().with_writer(move || file.clone()))
+        .with(PanelLayer {
+            lines: panel.lines.clone(),
+        })
+        // the bridge checks those against where they really came from
+        .with(targets.clone().with_target(LOG_TARGET, LevelFilter::TRACE))
+        .try_init()?;
+    log::set_boxed_logger(Box::new(LogBridge { targets }))?;
+    log::set_max_level(log::LevelFilter::Trace);
+    Ok(panel)
+}
+
+/// hands records from the `log` crate over to tracing
+struct LogBridge {
+    targets: Targets,
+}
+
+impl log::Log for LogBridge {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        self.targets
+            .would_enable(metadata.target(), &level(metadata.level()))
+    }
+
+    fn log(&self, record: &log::Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        let (target, message) = (record.target(), record.args());
+        match record.level() {
+            log::Level::Error => tracing::error!(target: LOG_TARGET, target, "{}", message),
+            log::Level::Warn => tracing::warn!(target: LOG_TARGET, target, "{}", message),
+            log::Level::Info => tracing::info!(target: LOG_TARGET, target, "{}", message),
+            log::Level::Debug => tracing::debug!(target: LOG_TARGET, target, "{}", message),
+            log::Level::Trace => tracing::trace!(target: LOG_TARGET, target, "{}", message),
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+fn level(level: log::Level) -> Level {
+    match level {
+        log::Level::Error => Level::ERROR,
+        log::Level::Warn => Level::WARN,
+        log::Level::Info => Level::INFO,
+        log::Level::Debug => Level::DEBUG,
+        log::Level::Trace => Level::TRACE,
+    }
+}
+
+/// the log file, started over with the old one moved aside whenever it gets too big
+#[derive(Clone)]
+struct RollingFile {
+    inner: Arc<Mutex<(File, u64)>>,
+    path: PathBuf,
+}
+
+impl RollingFile {
+    fn open(path: impl AsRef<Path>) -> color_eyre::Result<Self> {
+        let path = path.as_ref();
+        let open = || -> std::io::Result<File> {
+            if let Some(dir) = path.parent() {
+                std::fs::create_dir_all(dir)?;
+            }
+            // every run starts its own file, so the last one is always there to look at
+            rotate(path)?;
+            File::create(path)
+        };
+        let file =
+            open().wrap_err_with(|| format!("failed to open log file {}", path.display()))?;
+        Ok(Self {
+            inner: Arc::new(Mutex::new((file, 0))),
+            path: path.to_owned(),
+        })
+    }
+}
+
+impl Write for RollingFile {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let mut inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        let (file, size) = &mut *inner;
+        if *size + buf.len() as u64 > MAX_FILE_SIZE {
+            rotate(&self.path)?;
+            *file = File::create(&self.path)?;
+            *size = 0;
+        }
+        let written = file.write(buf)?;
+        *size += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .0
+            .flush()
+    }
+}
+
+/// `path.1` becomes `path.2` and so on, with the oldest one dropped, then `path` becomes `path.1`
+fn rotate(path: &Path) -> std::io::Result<()> {
+    let numbered = |n: usize| PathBuf::from(format!("{}.{}", path.display(), n));
+    for n in (1..OLD_FILES).rev() {
+        if numbered(n).exists() {
+            std::fs::rename(numbered(n), numbered(n + 1))?;
+        }
+    }
+    if path.exists() {
+        std::fs::rename(path, numbered(1))?;
+    }
+    Ok(())
+}
+
+/// a warning or error, as it gets shown in the panel
+struct LogLine {
+    level: Level,
+    text: String,
+}
+
+/// the latest warnings and errors, shown in the overlay while it's open, so problems like render
+/// failures are seen without a terminal
+#[derive(Default)]
+pub struct LogPanel {
+    lines: Arc<Mutex<VecDeque<LogLine>>>,
+    pub shown: bool,
+}
+
+impl LogPanel {
+    const KEPT_LINES: usize = 12;
+    const LINE_HEIGHT: f32 = 18.0;
+
+    pub fn text(&self) -> Vec<Text> {
+        if !self.shown {
+            return vec![];
+        }
+        let lines = self.lines.lock().unwrap_or_else(|e| e.into_inner());
+        if lines.is_empty() {
+            return vec![Text::new("no warnings or errors", (20.0, 920.0))
+                .with_size(16.0)
+                .with_color([0.7, 0.7, 0.7, 1.0])];
+        }
+        let top = 940.0 - lines.len() as f32 * Self::LINE_HEIGHT;
+        lines
+            .iter()
+            .enumerate()
+            .map(|(i, line)| {
+                Text::new(
+                    line.text.clone(),
+                    (20.0, top + i as f32 * Self::LINE_HEIGHT),
+                )
+                .with_size(16.0)
+                .with_color(if line.level == Level::ERROR {
+                    [1.0, 0.4, 0.4, 1.0]
+                } else {
+                    [1.0, 0.8, 0.3, 1.0]
+                })
+            })
+            .collect()
+    }
+}
+
+/// puts warnings and errors into the panel
+struct PanelLayer {
+    lines: Arc<Mutex<VecDeque<LogLine>>>,
+}
+
+impl<S: Subscriber> Layer<S> for PanelLayer {
+    fn on_event(&self, event: &Event<'_>, _: Context<'_, S>) {
+        let level = *event.metadata().level();
+        if !matches!(level, Level::WARN | Level::ERROR) {
+            return;
+        }
+        let mut message = Message::default();
+        event.record(&mut message);
+        let mut lines = self.lines.lock().unwrap_or_else(|e| e.into_inner());
+        if lines.len() == LogPanel::KEPT_LINES {
+            lines.pop_front();
+        }
+        lines.push_back(LogLine {
+            level,
+            text: format!("{} {}", level, message.into_line()),
+        });
+    }
+}
+
+/// an event's message with its other fields after it
+#[derive(Default)]
+struct Message {
+    message: String,
+    fields: String,
+}
+
+impl Message {
+    fn into_line(self) -> String {
+        format!("{}{}", self.message, self.fields)
+    }
+}
+
+impl Visit for Message {
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        if field.name() == "message" {
+            self.message = format!("{:?}", value);
+        } else {
+            self.fields += &format!(" {}={:?}", field.name(), value);
+        }
+    }
+}
+
+/// the console command for showing the panel
+pub fn register_commands(console: &mut Console) {
+    console.register("logs", "logs", |_, context| {
+        context.log_panel.shown = !context.log_panel.shown;
+        Ok(format!("log panel set to {}", context.log_panel.shown))
+    });
+}
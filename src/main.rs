@@ -1,18 +1,117 @@
-mod game_state;
+mod accessibility;
+mod achievements;
+mod animation;
+mod assists;
+mod background;
+mod bindings;
+mod body;
+mod color_scheme;
+mod console;
+mod crash;
+mod frame_graph;
+mod free_camera;
+mod gamepad;
+mod gpu_timer;
+mod heatmap;
+mod input_display;
+mod inspector;
+mod lighting;
+mod loading;
+mod locale;
+mod logging;
+mod material;
+mod online;
+mod paths;
+mod post_process;
+mod practice;
+mod profiler;
+mod profiles;
+mod quality;
 mod render;
+mod replay_viewer;
+mod ribbons;
+mod screens;
+mod selection;
+mod session;
+mod settings;
+mod sparks;
+mod srgb;
+mod stats;
+#[cfg(feature = "steam")]
+mod steam;
+mod stepping;
+mod telemetry;
+mod text;
+mod time_scale;
+mod trail;
+mod transition;
+mod world_state;
 
+use bindings::{Action, ActionEvent, Input, Player};
 use color_eyre::Result;
+use grappling_hook_core::{game_state, generate, instances, level, replay, tween};
+use screens::Screen;
 use std::time::Instant;
 use winit::{
-    event::{Event, KeyboardInput, WindowEvent},
+    event::{
+        ElementState, Event, KeyboardInput, MouseButton, MouseScrollDelta, VirtualKeyCode,
+        WindowEvent,
+    },
     event_loop::ControlFlow,
 };
 
 fn main() -> Result<()> {
-    simple_logger::init_with_level(log::Level::Warn)?;
+    let mut level_path = None;
+    // a seed to make a course up from instead of loading a level
+    let mut seed = None;
+    // a ghost file someone shared, to race against instead of your own best
+    let mut shared_ghost = None;
+    // keeps saves, settings and logs next to the game instead of in the platform's folders
+    let mut portable = false;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--ghost" => shared_ghost = args.next().map(std::path::PathBuf::from),
+            "--portable" => portable = true,
+            "--generate" => {
+                let arg = args.next().unwrap_or_default();
+                seed = Some(arg.parse::<u64>().map_err(|_| {
+                    color_eyre::eyre::eyre!("--generate needs a number to use as the seed")
+                })?);
+            }
+            _ => level_path = Some(arg),
+        }
+    }
+    paths::init(portable);
+
+    let mut log_panel = logging::init()?;
+    let crash_reporter = crash::CrashReporter::install();
+    let mut old_files = profiles::Profile::open(0).old_files();
+    old_files.push(("ghosts", paths::data_dir().join("ghosts")));
+    old_files.push(("crashes", paths::crashes_dir()));
+    paths::migrate(&old_files);
 
     const TICK_RATE: f64 = 1.0 / 60.0;
 
+    let mut screen = match (seed, level_path) {
+        (Some(seed), _) => Screen::Loading(loading::Loading::generated(seed, shared_ghost.clone())),
+        (None, Some(path)) => Screen::Loading(loading::Loading::start(
+            path.into(),
+            shared_ghost.clone(),
+            false,
+            None,
+        )),
+        (None, None) => Screen::level_select(),
+    };
+    let mut profile = profiles::Profile::last_played();
+    if let Err(e) = profile.remember() {
+        tracing::warn!("failed to set up save slot: {:#}", e);
+    }
+    let mut stats = profile.stats();
+    let mut world = profile.world();
+    let mut achievements = profile.achievements();
+    let animations = animation::Animations::load(animation::Animations::DEFINITIONS);
+
     let event_loop = winit::event_loop::EventLoop::new();
 
     let window = winit::window::WindowBuilder::new()
@@ -26,13 +125,98 @@ fn main() -> Result<()> {
 
     let instance = wgpu::Instance::new(wgpu::Backends::PRIMARY);
 
-    let mut state = game_state::GameState::new();
-    let mut last_state = state.clone();
-    let mut render_state = render::RenderState::new(instance, &window)?;
+    let mut settings = settings::Settings::load(profile.settings_path());
+    let graphics_config = render::GraphicsConfig {
+        color_scheme: settings.color_scheme,
+        post_processing: !settings.accessibility.reduced_motion,
+        ..Default::default()
+    };
+    let mut strings = locale::Strings::load(&settings.locale);
+    let mut announcer = accessibility::Announcer::default();
+    let mut gamepad = gamepad::Gamepad::new();
+    #[cfg(feature = "steam")]
+    let mut steam = steam::Steam::init();
+    // turned on with the profile command, shows how long frames take on the cpu and gpu
+    let mut profiler = profiler::Profiler::default();
+    // ghosts on their way to or from the ghost server
+    let mut online = online::Online::default();
+    // toggled with F1, the game only moves on when . is pressed
+    let mut stepping = stepping::Stepping::default();
+    // the screen coming back into view after it changes or the player gets put back
+    let mut transition: Option<transition::Transition> = None;
+    let mut last_screen = std::mem::discriminant(&screen);
+    let mut render_state = render::RenderState::new(instance, &window, graphics_config)?;
+    crash_reporter.set_adapter(&render_state.adapter_info());
+
+    let mut console = console::Console::default();
+    session::register_commands(&mut console);
+    screens::register_commands(&mut console);
+    selection::register_commands(&mut console);
+    assists::register_commands(&mut console);
+    accessibility::register_commands(&mut console);
+    locale::register_commands(&mut console);
+    gamepad::register_commands(&mut console);
+    online::register_commands(&mut console);
+    profiler::register_commands(&mut console);
+    background::register_commands(&mut console);
+    telemetry::register_commands(&mut console);
+    heatmap::register_commands(&mut console);
+    practice::register_commands(&mut console);
+    logging::register_commands(&mut console);
+    world_state::register_commands(&mut console);
+    console.register("timescale", "timescale <speed>", |args, context| {
+        let scale = console::number(args, 0, "timescale <speed>")?;
+        if scale < 0.0 {
+            return Err("time can't go backwards".to_owned());
+        }
+        context.time_scale.base = scale;
+        Ok(format!("time scale set to {}", scale))
+    });
+    const SLOWMO: &str = "slowmo <scale> <hold ms> <ease ms>";
+    console.register("slowmo", SLOWMO, |args, context| {
+        let slow_motion = time_scale::SlowMotion {
+            scale: console::number(args, 0, SLOWMO)?.clamp(0.0, 1.0),
+            hold: console::number(args, 1, SLOWMO)?.max(0.0) / 1000.0,
+            ease: console::number(args, 2, SLOWMO)?.max(0.0) / 1000.0,
+        };
+        context.time_scale.on_attach = slow_motion;
+        Ok(format!("slow motion on attach set to {:?}", slow_motion))
+    });
+    let mut time_scale = time_scale::TimeScale::default();
+    time_scale.assist = settings.assist_mode.game_speed;
+
+    let mut assists = assists::Assists::default();
+    // while this is around the camera flies on its own and the movement keys steer it
+    let mut free_camera: Option<free_camera::FreeCamera> = None;
+    let mut cursor_position = winit::dpi::PhysicalPosition::new(0.0, 0.0);
+
+    // the object picked with a right click
+    let mut selection = selection::Selection::default();
+    // the debug window, opened with F7
+    let mut inspector: Option<inspector::Inspector> = None;
+    // toggled with F8, left clicking grabs things and drags them around instead of firing the hook
+    let mut drag_mode = false;
+    // toggled with F10, shows the routes moving platforms and patrols follow
+    let mut show_paths = false;
+    // toggled with Tab on the level select, levels get started with a second player
+    let mut coop = false;
+    // toggled with F11, shows the keys being pressed and what the hook is doing
+    let mut show_inputs = false;
+    // slows things down while the window is unfocused or minimized
+    let mut throttle = background::Throttle::default();
 
     let mut accum = 0.0;
     let mut last_time = Instant::now();
-    event_loop.run(move |event, _window, control_flow| {
+    event_loop.run(move |event, window_target, control_flow| {
+        // everything below is for the main window, the inspector only needs to know when to close
+        if let Event::WindowEvent { window_id, event } = &event {
+            if Some(*window_id) == inspector.as_ref().map(|inspector| inspector.window.id()) {
+                if let WindowEvent::CloseRequested = event {
+                    inspector = None;
+                }
+                return;
+            }
+        }
         match event {
             Event::WindowEvent {
                 event: WindowEvent::CloseRequested,
@@ -40,49 +224,863 @@ fn main() -> Result<()> {
             } => {
                 *control_flow = ControlFlow::Exit;
             }
+            Event::WindowEvent {
+                event: WindowEvent::Focused(focused),
+                ..
+            } => {
+                throttle.on_focus(focused);
+                // keys let go of in another window never get reported here
+                if let (false, Screen::Playing(session)) = (focused, &mut screen) {
+                    session.release_keys();
+                }
+            }
+            Event::WindowEvent {
+                event: WindowEvent::Resized(size),
+                ..
+            } => throttle.on_resize(size),
             Event::WindowEvent {
                 event:
                     WindowEvent::KeyboardInput {
                         input:
                             KeyboardInput {
-                                scancode, state: e, ..
+                                scancode,
+                                state: e,
+                                virtual_keycode,
+                                ..
                             },
                         ..
                     },
                 ..
             } => {
-                //println!("{}", scancode);
-                let direction = match scancode {
-                    // tested on my keyboard
-                    30 => game_state::Direction::Left,  // A
-                    17 => game_state::Direction::Up,    // W
-                    32 => game_state::Direction::Right, // D
-                    31 => game_state::Direction::Down,  // S
-                    _ => return,
-                };
-                state.submit_player_event(game_state::Event::Keyboard {
-                    button: direction,
-                    state: e,
-                })
+                tracing::trace!(scancode, ?virtual_keycode, state = ?e, "key");
+                let inputs = [
+                    Some(Input::Scancode(scancode)),
+                    virtual_keycode.map(Input::Key),
+                ];
+                let inputs = inputs.into_iter().flatten().collect::<Vec<_>>();
+                let partner =
+                    matches!(&screen, Screen::Playing(session) if session.partner.is_some());
+                let action = settings.bindings.resolve(&inputs, partner);
+                let menu = settings.bindings.resolve_menu(&inputs);
+                let debug = settings.bindings.resolve_debug(&inputs);
+                if let (Some((_, Action::DebugToggle)), ElementState::Pressed) = (action, e) {
+                    console.toggle();
+                    return;
+                }
+                if console.open {
+                    if e == ElementState::Pressed {
+                        match virtual_keycode {
+                            Some(VirtualKeyCode::Return) => console.submit(&mut console::Context {
+                                screen: &mut screen,
+                                time_scale: &mut time_scale,
+                                shared_ghost: shared_ghost.as_deref(),
+                                selection: &selection,
+                                assists: &mut assists,
+                                settings: &mut settings,
+                                profile: &profile,
+                                strings: &mut strings,
+                                gamepad: &mut gamepad,
+                                profiler: &mut profiler,
+                                online: &mut online,
+                                log_panel: &mut log_panel,
+                                tick_rate: TICK_RATE,
+                            }),
+                            Some(VirtualKeyCode::Back) => console.backspace(),
+                            Some(VirtualKeyCode::Escape) => console.toggle(),
+                            _ => {}
+                        }
+                    }
+                    return;
+                }
+                if let (Some(Action::ToggleStepping), ElementState::Pressed) = (debug, e) {
+                    stepping.toggle();
+                    tracing::info!("stepping set to {}", stepping.on);
+                    return;
+                }
+                if let (true, Some(Action::Step), ElementState::Pressed) = (stepping.on, debug, e) {
+                    stepping.step();
+                    return;
+                }
+                let debug_speed = debug.and_then(Action::debug_speed);
+                if let (Some(speed), ElementState::Pressed) = (debug_speed, e) {
+                    // only changes how many ticks run each frame, each tick is still as long
+                    time_scale.debug = time_scale::TimeScale::DEBUG_SPEEDS[speed];
+                    tracing::info!("simulation speed set to {}x", time_scale.debug);
+                    return;
+                }
+                if let (Some(Action::CycleMsaa), ElementState::Pressed) = (debug, e) {
+                    let mut config = render_state.graphics_config();
+                    config.msaa = config.msaa.cycle();
+                    render_state.set_graphics_config(config);
+                    tracing::info!("msaa set to {:?}", render_state.graphics_config().msaa);
+                    return;
+                }
+                if let (Some(Action::TogglePostProcessing), ElementState::Pressed) = (debug, e) {
+                    let mut config = render_state.graphics_config();
+                    config.post_processing = !config.post_processing;
+                    render_state.set_graphics_config(config);
+                    tracing::info!("post processing set to {}", config.post_processing);
+                    return;
+                }
+                if let (Some(Action::ToggleLimbBody), ElementState::Pressed) = (debug, e) {
+                    let mut config = render_state.graphics_config();
+                    config.limb_body = !config.limb_body;
+                    render_state.set_graphics_config(config);
+                    tracing::info!("limb body set to {}", config.limb_body);
+                    return;
+                }
+                if let (Some(Action::ToggleTrajectoryPreview), ElementState::Pressed) = (debug, e) {
+                    assists.trajectory_preview = !assists.trajectory_preview;
+                    tracing::info!("trajectory preview set to {}", assists.trajectory_preview);
+                    return;
+                }
+                if let (Some(Action::ToggleFreeCamera), ElementState::Pressed) = (debug, e) {
+                    free_camera = match free_camera {
+                        Some(_) => None,
+                        None => {
+                            let position = screen
+                                .world()
+                                .and_then(|(state, _)| state.object(state.view_object))
+                                .map(|object| object.center())
+                                .unwrap_or_else(|| cgmath::point2(0.0, 0.0));
+                            Some(free_camera::FreeCamera::new(position))
+                        }
+                    };
+                    tracing::info!("free camera set to {}", free_camera.is_some());
+                    return;
+                }
+                if let (Some(Action::ToggleInspector), ElementState::Pressed) = (debug, e) {
+                    inspector = match inspector.take() {
+                        Some(_) => None,
+                        None => match inspector::Inspector::open(window_target, &render_state) {
+                            Ok(inspector) => Some(inspector),
+                            Err(e) => {
+                                tracing::warn!("failed to open the inspector: {:?}", e);
+                                None
+                            }
+                        },
+                    };
+                    return;
+                }
+                if let (Some(Action::ToggleDragMode), ElementState::Pressed) = (debug, e) {
+                    drag_mode = !drag_mode;
+                    if let Screen::Playing(session) = &mut screen {
+                        session.state.release_grab();
+                    }
+                    tracing::info!("drag mode set to {}", drag_mode);
+                    return;
+                }
+                if let (Some(Action::TogglePaths), ElementState::Pressed) = (debug, e) {
+                    show_paths = !show_paths;
+                    tracing::info!("path preview set to {}", show_paths);
+                    return;
+                }
+                if let (Some(Action::CycleColorScheme), ElementState::Pressed) = (debug, e) {
+                    let mut config = render_state.graphics_config();
+                    config.color_scheme = config.color_scheme.next();
+                    render_state.set_graphics_config(config);
+                    settings.color_scheme = config.color_scheme;
+                    if let Err(e) = settings.save(profile.settings_path()) {
+                        tracing::warn!("failed to save settings: {}", e);
+                    }
+                    tracing::info!("color scheme set to {:?}", config.color_scheme);
+                    return;
+                }
+                if let (Some(Action::ToggleInputDisplay), ElementState::Pressed) = (debug, e) {
+                    show_inputs = !show_inputs;
+                    tracing::info!("input display set to {}", show_inputs);
+                    return;
+                }
+                if let (
+                    Some(Action::ToggleNoClip),
+                    ElementState::Pressed,
+                    Screen::Playing(session),
+                ) = (debug, e, &mut screen)
+                {
+                    let player = session.state.view_object;
+                    let no_clip = !session
+                        .state
+                        .object(player)
+                        .is_some_and(|object| object.is_no_clip());
+                    session.state.set_no_clip(player, no_clip);
+                    session.cheated = true;
+                    tracing::info!("no-clip set to {}", no_clip);
+                    return;
+                }
+                match &mut screen {
+                    Screen::LevelSelect { levels, selected } => {
+                        if e != ElementState::Pressed {
+                            return;
+                        }
+                        match menu {
+                            Some(Action::MenuUp) => {
+                                *selected = selected.saturating_sub(1);
+                            }
+                            Some(Action::MenuDown) => {
+                                *selected = (*selected + 1).min(levels.len().saturating_sub(1));
+                            }
+                            Some(Action::Confirm) => {
+                                if let Some(path) = levels.get(*selected) {
+                                    screen = Screen::Loading(loading::Loading::start(
+                                        path.clone(),
+                                        shared_ghost.clone(),
+                                        coop,
+                                        None,
+                                    ));
+                                }
+                            }
+                            Some(Action::WatchBest) => {
+                                if let Some(path) = levels.get(*selected) {
+                                    match screens::watch_best(path, TICK_RATE) {
+                                        Ok(viewer) => screen = Screen::Watching(viewer),
+                                        Err(e) => {
+                                            tracing::warn!("failed to load replay: {:?}", e)
+                                        }
+                                    }
+                                }
+                            }
+                            Some(Action::ToggleCoop) => {
+                                coop = !coop;
+                            }
+                            Some(Action::SaveSlots) => {
+                                screen = Screen::profiles(profile.slot);
+                            }
+                            Some(Action::GamepadSettings) => {
+                                screen = Screen::GamepadSettings { selected: 0 };
+                            }
+                            Some(Action::Back) => {
+                                *control_flow = ControlFlow::Exit;
+                            }
+                            _ => {}
+                        }
+                    }
+                    Screen::Profiles {
+                        slots, selected, ..
+                    } => {
+                        if e != ElementState::Pressed {
+                            return;
+                        }
+                        match menu {
+                            Some(Action::MenuUp) => {
+                                *selected = selected.saturating_sub(1);
+                            }
+                            Some(Action::MenuDown) => {
+                                *selected = (*selected + 1).min(slots.len().saturating_sub(1));
+                            }
+                            Some(Action::Confirm) => {
+                                profile = profiles::Profile::open(*selected);
+                                if let Err(e) = profile.remember() {
+                                    tracing::warn!("failed to switch save slot: {:#}", e);
+                                }
+                                stats = profile.stats();
+                                world = profile.world();
+                                achievements = profile.achievements();
+                                // slots that never changed a setting keep the ones already set
+                                if profile.has_settings() {
+                                    settings = settings::Settings::load(profile.settings_path());
+                                    strings = locale::Strings::load(&settings.locale);
+                                    let mut config = render_state.graphics_config();
+                                    config.color_scheme = settings.color_scheme;
+                                    config.post_processing = !settings.accessibility.reduced_motion;
+                                    render_state.set_graphics_config(config);
+                                    time_scale.assist = settings.assist_mode.game_speed;
+                                }
+                                tracing::info!("playing in save slot {}", profile.slot + 1);
+                                screen = Screen::level_select();
+                            }
+                            Some(Action::Back) => {
+                                screen = Screen::level_select();
+                            }
+                            _ => {}
+                        }
+                    }
+                    Screen::GamepadSettings { selected } => {
+                        if e != ElementState::Pressed {
+                            return;
+                        }
+                        let entries = gamepad::MenuEntry::ALL;
+                        let up = match menu {
+                            Some(Action::MenuUp) => {
+                                *selected = selected.saturating_sub(1);
+                                return;
+                            }
+                            Some(Action::MenuDown) => {
+                                *selected = (*selected + 1).min(entries.len() - 1);
+                                return;
+                            }
+                            Some(Action::Back) => {
+                                screen = Screen::level_select();
+                                return;
+                            }
+                            Some(Action::MenuLeft) => false,
+                            Some(Action::MenuRight) => true,
+                            _ => return,
+                        };
+                        settings.gamepad.adjust(entries[*selected], up);
+                        if let Err(e) = settings.save(profile.settings_path()) {
+                            tracing::warn!("failed to save settings: {:#}", e);
+                        }
+                    }
+                    Screen::Loading(_) => {
+                        // the thread finishes on its own, nobody takes what it loaded
+                        if let (Some(Action::Back), ElementState::Pressed) = (menu, e) {
+                            screen = Screen::level_select();
+                        }
+                    }
+                    Screen::Playing(session) => {
+                        // nothing gets through to the game during the intro, any key skips it
+                        if session.in_cutscene() {
+                            if e == ElementState::Pressed {
+                                session.skip_cutscene();
+                            }
+                            return;
+                        }
+                        if let (Some(Action::Restart), ElementState::Pressed) = (menu, e) {
+                            restart(session, &mut stats, &profile);
+                            return;
+                        }
+                        if let (Some((_, Action::Pause)), ElementState::Pressed) = (action, e) {
+                            screen = Screen::level_select();
+                            return;
+                        }
+                        if let Some(action) = action {
+                            let target = render_state.screen_to_world(cursor_position);
+                            play(session, &assists, &mut free_camera, action, e, target);
+                        }
+                    }
+                    Screen::LevelComplete { .. } => {
+                        if e != ElementState::Pressed {
+                            return;
+                        }
+                        match menu {
+                            Some(Action::Confirm) => {
+                                if let Screen::LevelComplete { mut session, .. } =
+                                    std::mem::replace(&mut screen, Screen::level_select())
+                                {
+                                    session.restart();
+                                    screen = Screen::Playing(session);
+                                }
+                            }
+                            Some(Action::Back) => {
+                                screen = Screen::level_select();
+                            }
+                            _ => {}
+                        }
+                    }
+                    Screen::Watching(viewer) => {
+                        let direction = action.and_then(|(_, action)| action.direction());
+                        if let (Some(camera), Some(direction)) = (&mut free_camera, direction) {
+                            camera.on_key(direction, e == ElementState::Pressed);
+                            return;
+                        }
+                        if e != ElementState::Pressed {
+                            return;
+                        }
+                        match menu {
+                            Some(Action::Back) => screen = Screen::level_select(),
+                            Some(Action::PlayPause) => viewer.paused = !viewer.paused,
+                            Some(Action::MenuLeft) => viewer.seek_by(-1.0),
+                            Some(Action::MenuRight) => viewer.seek_by(1.0),
+                            Some(Action::StepBack) => viewer.step_by(-1),
+                            Some(Action::StepForward) => viewer.step_by(1),
+                            Some(Action::MenuUp) => viewer.faster(),
+                            Some(Action::MenuDown) => viewer.slower(),
+                            _ => {}
+                        }
+                    }
+                }
+            }
+            Event::WindowEvent {
+                event: WindowEvent::ReceivedCharacter(c),
+                ..
+            } if console.open => console.type_char(c),
+            Event::WindowEvent {
+                event: WindowEvent::MouseWheel { delta, .. },
+                ..
+            } => {
+                if let Some(camera) = &mut free_camera {
+                    camera.on_scroll(match delta {
+                        MouseScrollDelta::LineDelta(_, lines) => lines as f64,
+                        // roughly what one notch scrolls on most mice
+                        MouseScrollDelta::PixelDelta(pixels) => pixels.y / 40.0,
+                    });
+                }
+            }
+            Event::WindowEvent {
+                event: WindowEvent::CursorMoved { position, .. },
+                ..
+            } => {
+                cursor_position = position;
+            }
+            Event::WindowEvent {
+                event:
+                    WindowEvent::MouseInput {
+                        state: e, button, ..
+                    },
+                ..
+            } => {
+                let partner =
+                    matches!(&screen, Screen::Playing(session) if session.partner.is_some());
+                let dual_hooks =
+                    matches!(&screen, Screen::Playing(session) if session.state.dual_hooks);
+                // without a second hook the button is free for picking things to look at
+                let action = settings
+                    .bindings
+                    .resolve(&[Input::Mouse(button)], partner)
+                    .filter(|&(_, action)| action != Action::FireSecondHook || dual_hooks);
+                match (&mut screen, button, action) {
+                    (Screen::Playing(session), MouseButton::Left, _) if drag_mode => match e {
+                        ElementState::Pressed => {
+                            let point = render_state.screen_to_world(cursor_position);
+                            if session.state.grab(point) {
+                                session.cheated = true;
+                            }
+                        }
+                        ElementState::Released => session.state.release_grab(),
+                    },
+                    (Screen::Playing(session), _, Some(action)) => {
+                        if session.in_cutscene() {
+                            if e == ElementState::Pressed {
+                                session.skip_cutscene();
+                            }
+                            return;
+                        }
+                        if let ((_, Action::Pause), ElementState::Pressed) = (action, e) {
+                            screen = Screen::level_select();
+                            return;
+                        }
+                        let target = render_state.screen_to_world(cursor_position);
+                        play(session, &assists, &mut free_camera, action, e, target);
+                    }
+                    (_, MouseButton::Right, _) if e == ElementState::Pressed => {
+                        if let Some((state, _)) = screen.world() {
+                            let point = render_state.screen_to_world(cursor_position);
+                            selection.pick(state, point);
+                        }
+                    }
+                    _ => {}
+                }
             }
             Event::MainEventsCleared => {
+                // the whole session goes in at once, so nothing ever sees a half loaded level
+                if let Screen::Loading(loading) = &mut screen {
+                    let coop = loading.coop;
+                    match loading.poll() {
+                        Some(Ok(mut session)) => {
+                            session.restore(world.level(&session.level_name));
+                            session.set_assist_mode(settings.assist_mode);
+                            // after everything else, so the partner turns up next to the player
+                            if coop {
+                                session.start_coop();
+                            }
+                            screen = Screen::Playing(session);
+                        }
+                        Some(Err(e)) => {
+                            tracing::warn!("failed to load level: {:?}", e);
+                            screen = Screen::level_select();
+                        }
+                        None => {}
+                    }
+                }
                 let now = Instant::now();
-                accum += (now - last_time).as_secs_f64();
+                // replays go at their own speed, on top of the time scale
+                let playback_speed = match &screen {
+                    Screen::Watching(viewer) => viewer.speed(),
+                    _ => 1.0,
+                };
+                accum += stepping.advance(
+                    (now - last_time).as_secs_f64() * time_scale.at(now) * playback_speed,
+                    TICK_RATE,
+                );
+                let mut paused = false;
+                if let Screen::Playing(session) = &mut screen {
+                    // a run played slowed down or a tick at a time isn't one to compare against
+                    if time_scale.tampered() || stepping.on {
+                        session.cheated = true;
+                    }
+                    let target = render_state.screen_to_world(cursor_position);
+                    session.state.drag_to(target);
+                    if !session.in_cutscene() {
+                        let actions = gamepad.update(&settings.gamepad, &settings.bindings);
+                        let aim_target = gamepad.aim_target(&session.state);
+                        // Steam Input controllers aim instead, once one has
+                        #[cfg(feature = "steam")]
+                        let (actions, aim_target) = match &mut steam {
+                            Some(steam) => (
+                                [actions, steam.update_input()].concat(),
+                                steam.aim_target(&session.state).or(aim_target),
+                            ),
+                            None => (actions, aim_target),
+                        };
+                        for action in actions {
+                            let pressed = game_state::ButtonState::Pressed;
+                            match action {
+                                ActionEvent::Button(Action::Pause, state) => {
+                                    paused |= state == pressed
+                                }
+                                ActionEvent::Button(Action::DebugToggle, state) => {
+                                    if state == pressed {
+                                        console.toggle();
+                                    }
+                                }
+                                // the stick aims instead of the cursor, without any assists
+                                ActionEvent::Button(Action::FireHook, state) => {
+                                    if let Some(target) = aim_target {
+                                        session.submit(game_state::Event::Hook { state, target });
+                                    }
+                                }
+                                ActionEvent::Button(Action::FireSecondHook, state) => {
+                                    if let Some(target) = aim_target {
+                                        session.submit(game_state::Event::SecondHook {
+                                            state,
+                                            target,
+                                        });
+                                    }
+                                }
+                                ActionEvent::Button(Action::Restart, state) => {
+                                    if state == pressed {
+                                        restart(session, &mut stats, &profile);
+                                    }
+                                }
+                                _ => session.act(Player::First, action),
+                            }
+                        }
+                    }
+                }
+                if paused {
+                    screen = Screen::level_select();
+                }
+                if let Screen::GamepadSettings { .. } = screen {
+                    gamepad.refresh();
+                }
+                let playing = match &screen {
+                    Screen::Playing(session) => Some(&session.state),
+                    _ => None,
+                };
+                gamepad.rumble_for_ropes(&settings.gamepad, playing);
+                #[cfg(feature = "steam")]
+                if let Some(steam) = &mut steam {
+                    steam.run_callbacks();
+                    steam.set_level(screen.session().map(|session| session.level_name.as_str()));
+                    steam.mirror_achievements(achievements.unlocked_ids());
+                }
+                let reduced_motion = settings.accessibility.reduced_motion;
+                // a circle closing in is a lot of movement, so it's only a fade with less motion
+                let wipe = match reduced_motion {
+                    true => transition::Kind::Fade,
+                    false => transition::Kind::Wipe,
+                };
+                if reduced_motion && render_state.graphics_config().post_processing {
+                    let mut config = render_state.graphics_config();
+                    config.post_processing = false;
+                    render_state.set_graphics_config(config);
+                }
+                // frames are meant to be slow while throttled, that's no reason to lower the quality
+                if !throttle.in_background() {
+                    if let Some(quality) = render_state.adapt_quality(now - last_time) {
+                        tracing::info!("adaptive quality set to {:?}", quality);
+                    }
+                }
+
+                for transfer in online.poll() {
+                    match transfer {
+                        online::Transfer::Uploaded { level } => {
+                            tracing::info!("uploaded your ghost for {}", level)
+                        }
+                        online::Transfer::Downloaded {
+                            level,
+                            friend,
+                            path,
+                            assisted,
+                        } => {
+                            let friend = match assisted {
+                                true => format!("{} (assisted)", friend),
+                                false => friend,
+                            };
+                            match &mut screen {
+                                Screen::Playing(session) if session.level_name == level => {
+                                    tracing::info!("racing {}'s ghost", friend);
+                                    session.race(path);
+                                }
+                                _ => tracing::info!("got {}'s ghost for {}", friend, level),
+                            }
+                        }
+                        online::Transfer::Failed(e) => tracing::warn!("{}", e),
+                    }
+                }
 
+                let update_start = Instant::now();
                 while accum >= TICK_RATE {
                     accum -= TICK_RATE;
+                    let session = match &mut screen {
+                        Screen::Playing(session) => session,
+                        Screen::Watching(viewer) => {
+                            viewer.playback.step();
+                            continue;
+                        }
+                        _ => continue,
+                    };
+                    // the intro is the camera flying around by itself
+                    if reduced_motion {
+                        session.skip_cutscene();
+                    }
+                    if session.in_cutscene() {
+                        session.advance_cutscene(TICK_RATE);
+                        continue;
+                    }
                     if accum < TICK_RATE {
                         // last update before render, save previos iteration for interpolation/extrapolation
                         // NOTE: if the state gets too large, it might be worth it to stop doing interpolation to save a bit of time here
-                        last_state = state.clone();
+                        session.last_state = session.state.clone();
+                    }
+                    // physics panics get the state and the replay that led up to them saved
+                    let tick = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                        session.tick(TICK_RATE)
+                    }));
+                    let run_end = match tick {
+                        Ok(run_end) => run_end,
+                        Err(panic) => {
+                            if let Err(e) = crash_reporter.dump_session(session) {
+                                tracing::error!(
+                                    "failed to save the game for the crash report: {:#}",
+                                    e
+                                );
+                            }
+                            std::panic::resume_unwind(panic)
+                        }
+                    };
+                    match run_end {
+                        Some(session::RunEnd::Died) => {
+                            tracing::info!("died!");
+                            stats.record_run(&session.level_name, &session.run, None);
+                            if let Err(e) = stats.save(profile.path(stats::Stats::PATH)) {
+                                tracing::warn!("failed to save stats: {}", e);
+                            }
+                            session.restart();
+                            transition = Some(transition::Transition::new(wipe, now));
+                        }
+                        Some(session::RunEnd::Completed(completion)) => {
+                            tracing::info!("level completed in {:.2}s", completion.time);
+                            stats.record_run(
+                                &session.level_name,
+                                &completion.run,
+                                Some(completion.time),
+                            );
+                            if let Err(e) = stats.save(profile.path(stats::Stats::PATH)) {
+                                tracing::warn!("failed to save stats: {}", e);
+                            }
+                            achievements.on_completion(&completion);
+                            if completion.new_best && settings.online.server.is_some() {
+                                if let Err(e) = online.upload(&settings.online, &session.level_name)
+                                {
+                                    tracing::warn!("{}", e);
+                                }
+                            }
+                            if let Screen::Playing(session) =
+                                std::mem::replace(&mut screen, Screen::level_select())
+                            {
+                                screen = Screen::LevelComplete {
+                                    session,
+                                    completion,
+                                };
+                            }
+                        }
+                        None => {}
+                    }
+                }
+
+                let update_time = update_start.elapsed();
+                let interpolate = stepping.interpolate(accum / TICK_RATE);
+
+                if settings.accessibility.announce_menus {
+                    announcer.announce(screen.focus(&stats, &strings, &settings), &window);
+                }
+                let mut overlay = render::Overlay {
+                    text: screen.text(&stats, &strings, &settings),
+                    show_paths,
+                    ..Default::default()
+                };
+                if let (true, Screen::LevelSelect { .. }) = (coop, &screen) {
+                    overlay.text.push(
+                        text::Text::new(strings.get("level_select.coop"), (40.0, 870.0))
+                            .with_size(18.0)
+                            .with_color([0.3, 0.9, 1.0, 1.0]),
+                    );
+                }
+                // where the players are off to, once they walk into an exit
+                let mut travel = None;
+                if let Screen::Playing(session) = &mut screen {
+                    let view_object = session.state.view_object;
+                    for event in session.drain_events() {
+                        if let game_state::GameEvent::ExitReached { exit, .. } = event {
+                            let exit = session.state.exits.get(exit).cloned();
+                            travel = travel.or(exit.map(|exit| (exit, session.partner.is_some())));
+                        }
+                        if let game_state::GameEvent::OutOfBounds {
+                            object,
+                            killed: false,
+                        } = event
+                        {
+                            if object == view_object {
+                                transition = Some(transition::Transition::new(wipe, now));
+                            }
+                        }
+                        if let game_state::GameEvent::HookAttached { object } = event {
+                            if object == view_object && !reduced_motion {
+                                time_scale.slow_down(time_scale.on_attach);
+                            }
+                        }
+                        session.sparks.on_event(&event);
+                        render_state.on_event(&event, view_object);
+                        achievements.on_event(&event, &session.run, &session.state);
                     }
-                    state.update(TICK_RATE);
+                    if world.remember(&session.level_name, &session.memory) {
+                        if let Err(e) = world.save(profile.path(world_state::WorldState::PATH)) {
+                            tracing::warn!("failed to save world state: {}", e);
+                        }
+                    }
+                    achievements.observe(&session.run);
+                    overlay.ghost = session.ghost_position(interpolate);
+                    if let Some(camera) = session.cutscene_camera(accum) {
+                        overlay.camera = Some(camera);
+                    } else {
+                        // the fade in starts once the intro is over and the game gets going
+                        let (zoom, tint) = session.intro(interpolate);
+                        if !reduced_motion {
+                            overlay.zoom = Some(zoom);
+                        }
+                        overlay.tint = Some(tint);
+                    }
+                    if !reduced_motion {
+                        overlay.afterimages = session.trail.instances();
+                    }
+                    overlay.sparks = session.sparks.instances();
+                    if let Some(heatmap) = &session.heatmap {
+                        overlay.heatmap = heatmap.instances();
+                    }
+                    overlay.ribbons = session.ribbons.strips(
+                        session.state.time,
+                        interpolate,
+                        &render_state.graphics_config().color_scheme.colors(),
+                    );
+                    if render_state.graphics_config().limb_body {
+                        overlay.body = Some((view_object, session.body.pose(interpolate)));
+                    } else {
+                        overlay.sprite = animations
+                            .as_ref()
+                            .and_then(|animations| session.animator.frame(animations, interpolate))
+                            .map(|frame| (view_object, frame));
+                    }
+                    let target = render_state.screen_to_world(cursor_position);
+                    let target = assists.snap_aim(&session.state, target);
+                    if assists.show_preview(&session.state) {
+                        overlay.trajectory = Some(session.state.predict_hook(target, TICK_RATE));
+                    }
+                    // only while there's a hook ready to fire
+                    let ready = session
+                        .state
+                        .player_input(view_object)
+                        .is_some_and(|input| input.hook == game_state::HookState::Idle)
+                        && session
+                            .state
+                            .hook_charge(view_object)
+                            .is_none_or(|charge| charge.ready());
+                    if ready {
+                        overlay.reticle = session.state.aim_point(target);
+                    }
+                }
+                if let Some((exit, coop)) = travel {
+                    screen = Screen::Loading(loading::Loading::start(
+                        screens::level_path(&exit.level),
+                        shared_ghost.clone(),
+                        coop,
+                        exit.entrance,
+                    ));
+                }
+                if let Screen::Watching(viewer) = &mut screen {
+                    let view_object = viewer.playback.state.view_object;
+                    for event in viewer.playback.state.drain_events() {
+                        render_state.on_event(&event, view_object);
+                    }
+                }
+                overlay.text.extend(achievements.toasts(&strings));
+                if let Some((state, _)) = screen.world() {
+                    overlay.selected = selection.get(state);
+                    overlay.text.extend(selection.text(state));
+                }
+                if let (true, Some((state, _))) = (show_inputs, screen.world()) {
+                    if let Some(input) = state.player_input(state.view_object) {
+                        // replays don't know where the cursor was, only where the hook went
+                        let aim = match (&screen, state.object(state.view_object)) {
+                            (Screen::Playing(_), Some(player)) if input.aiming => {
+                                let target = render_state.screen_to_world(cursor_position);
+                                let to_target = target - player.center();
+                                Some(to_target.y.atan2(to_target.x))
+                            }
+                            _ => None,
+                        };
+                        overlay
+                            .text
+                            .extend(input_display::text(&input, aim, &strings));
+                    }
+                }
+                if gamepad.preview || matches!(screen, Screen::GamepadSettings { .. }) {
+                    overlay.text.extend(gamepad.preview_text(&settings.gamepad));
+                }
+                profiler.gpu_supported = render_state.set_gpu_timing(profiler.enabled);
+                if profiler.enabled {
+                    if let Some(times) = render_state.gpu_times() {
+                        profiler.record_gpu(times);
+                    }
+                    if screen.world().is_some() {
+                        let (drawn, culled) = render_state.object_counts();
+                        profiler.record_objects(drawn, culled);
+                    }
+                    overlay.text.extend(profiler.text());
+                }
+                overlay.text.extend(time_scale.text());
+                if let (true, Some((state, _))) = (stepping.on, screen.world()) {
+                    overlay.text.extend(stepping.text(state));
+                }
+                overlay.text.extend(log_panel.text());
+                overlay.text.extend(console.text());
+                if let Some(camera) = &mut free_camera {
+                    camera.update((now - last_time).as_secs_f64());
+                    overlay.camera = Some((camera.position, camera.zoom));
+                }
+
+                // new screens come in from black, levels through a wipe
+                if std::mem::discriminant(&screen) != last_screen {
+                    last_screen = std::mem::discriminant(&screen);
+                    let kind = match &screen {
+                        Screen::Playing(_) => wipe,
+                        _ => transition::Kind::Fade,
+                    };
+                    transition = Some(transition::Transition::new(kind, now));
+                }
+                overlay.transition = transition
+                    .as_ref()
+                    .and_then(|transition| transition.cover(now));
+                if overlay.transition.is_none() {
+                    transition = None;
                 }
 
-                let render_result = render_state.render(accum / TICK_RATE, &state, &last_state);
-                if let Err(e) = render_result {
-                    eprintln!("WARNING, Render error occured! {}", e);
+                if throttle.should_render(settings.background, now) {
+                    let render_start = Instant::now();
+                    let render_result = render_state.render(interpolate, screen.world(), &overlay);
+                    if profiler.enabled {
+                        profiler.record_cpu(update_time, render_start.elapsed());
+                    }
+                    if let Err(e) = render_result {
+                        tracing::error!("render error: {}", e);
+                    }
+                    if let Some(inspector) = &mut inspector {
+                        let state = screen.world().map(|(state, _)| state);
+                        let selected = state.and_then(|state| selection.get(state));
+                        inspector.render(&render_state, state, selected);
+                    }
                 }
+                *control_flow = throttle.control_flow(settings.background, now);
 
                 last_time = now;
             }
@@ -90,3 +1088,58 @@ fn main() -> Result<()> {
         }
     });
 }
+
+/// what an action from the keyboard or mouse does in the level being played, `target` is where the
+/// cursor is in the world
+fn play(
+    session: &mut session::Session,
+    assists: &assists::Assists,
+    free_camera: &mut Option<free_camera::FreeCamera>,
+    (player, action): (Player, Action),
+    e: ElementState,
+    target: cgmath::Point2<f64>,
+) {
+    if action == Action::FireHook {
+        let pressed = e == ElementState::Pressed;
+        for event in assists.hook_events(&session.state, pressed, target) {
+            session.submit(event);
+        }
+        return;
+    }
+    // the assists only know about the first hook, apart from the aim
+    if action == Action::FireSecondHook {
+        let target = match e {
+            ElementState::Pressed => target,
+            ElementState::Released => assists.snap_aim(&session.state, target),
+        };
+        session.submit(game_state::Event::SecondHook {
+            state: button_state(e),
+            target,
+        });
+        return;
+    }
+    // the first player's movement flies the camera around while it's free
+    if let (Player::First, Some(camera), Some(direction)) =
+        (player, free_camera.as_mut(), action.direction())
+    {
+        camera.on_key(direction, e == ElementState::Pressed);
+        return;
+    }
+    session.act(player, ActionEvent::Button(action, button_state(e)));
+}
+
+/// starts the level over, with the run so far kept in the stats
+fn restart(session: &mut session::Session, stats: &mut stats::Stats, profile: &profiles::Profile) {
+    stats.record_run(&session.level_name, &session.run, None);
+    if let Err(e) = stats.save(profile.path(stats::Stats::PATH)) {
+        tracing::warn!("failed to save stats: {}", e);
+    }
+    session.restart();
+}
+
+fn button_state(state: ElementState) -> game_state::ButtonState {
+    match state {
+        ElementState::Pressed => game_state::ButtonState::Pressed,
+        ElementState::Released => game_state::ButtonState::Released,
+    }
+}
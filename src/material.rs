@@ -0,0 +1,346 @@
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+use color_eyre::eyre::{eyre, WrapErr};
+use serde::Deserialize;
+use wgpu::util::DeviceExt;
+
+use crate::instances::Blend;
+
+/// which fragment shader in shader.wgsl a material draws with, each one gets its own pipeline
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Deserialize)]
+pub enum Shader {
+    /// the colour of the object, the same as having no material
+    Flat,
+    /// the texture repeated every `params[0]` world units, in place of the colour of the object
+    Textured,
+    /// the colour of the object pulsing brighter `params[0]` times a second, by up to `params[1]`,
+    /// tinted by the texture
+    Glow,
+    /// one frame out of the texture at a time, picked by the animation, the texture is the atlas
+    Sprite,
+}
+
+impl Shader {
+    fn entry_point(self) -> &'static str {
+        match self {
+            Shader::Flat => "fs_main",
+            Shader::Textured => "fs_textured",
+            Shader::Glow => "fs_glow",
+            Shader::Sprite => "fs_sprite",
+        }
+    }
+}
+
+/// how an object gets drawn, objects pick one by name in the level file
+#[derive(Clone, Debug, Deserialize)]
+pub struct Material {
+    pub shader: Shader,
+    /// a png in the textures directory, plain white if there is none
+    #[serde(default)]
+    pub texture: Option<String>,
+    /// handed to the shader as is, what they mean depends on `shader`
+    #[serde(default)]
+    pub params: [f32; 4],
+    /// whether its texture has see-through parts, so it gets blended over what's under it
+    #[serde(default)]
+    pub translucent: bool,
+}
+
+/// the materials objects can use, with the pipeline and bind group for each made the first time
+/// something gets drawn with it and kept around after that
+pub struct Materials {
+    definitions: HashMap<String, Material>,
+    bind_group_layout: wgpu::BindGroupLayout,
+    pipeline_layout: wgpu::PipelineLayout,
+    sampler: wgpu::Sampler,
+    /// used by materials without a texture
+    white: wgpu::TextureView,
+    /// one per shader and way of blending, they depend on the sample count so they get thrown out
+    /// when that changes
+    pipelines: HashMap<(Shader, Blend), wgpu::RenderPipeline>,
+    /// one per material, with the buffer holding its parameters
+    bind_groups: HashMap<String, (wgpu::BindGroup, wgpu::Buffer)>,
+    /// names objects asked for that aren't defined, so each only gets warned about once
+    missing: HashSet<String>,
+}
+
+impl Materials {
+    pub const DEFINITIONS: &'static str = "assets/materials.ron";
+    pub const TEXTURES: &'static str = "assets/textures";
+    /// the parameters and then the time, as 2 vec4s
+    const UNIFORM_SIZE: usize = 8 * std::mem::size_of::<f32>();
+
+    /// a broken or missing definitions file just means everything gets drawn plain
+    pub fn load(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        transform_bind_group_layout: &wgpu::BindGroupLayout,
+        palette_bind_group_layout: &wgpu::BindGroupLayout,
+        definitions: impl AsRef<Path>,
+    ) -> Self {
+        let definitions = definitions.as_ref();
+        let definitions = match std::fs::read_to_string(definitions) {
+            Ok(text) => ron::from_str(&text).unwrap_or_else(|e| {
+                tracing::warn!("failed to parse materials {}: {}", definitions.display(), e);
+                HashMap::new()
+            }),
+            Err(e) => {
+                tracing::warn!("failed to read materials {}: {}", definitions.display(), e);
+                HashMap::new()
+            }
+        };
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("material bind group layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: (Self::UNIFORM_SIZE as u64).try_into().ok(),
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+            ],
+        });
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("material pipeline"),
+            bind_group_layouts: &[
+                transform_bind_group_layout,
+                palette_bind_group_layout,
+                &bind_group_layout,
+            ],
+            push_constant_ranges: &[],
+        });
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("material sampler"),
+            address_mode_u: wgpu::AddressMode::Repeat,
+            address_mode_v: wgpu::AddressMode::Repeat,
+            // textures are small and meant to look crisp up close
+            mag_filter: wgpu::FilterMode::Nearest,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+        let white = create_texture(device, queue, "white texture", (1, 1), &[255; 4]);
+        Self {
+            definitions,
+            bind_group_layout,
+            pipeline_layout,
+            sampler,
+            white,
+            pipelines: HashMap::new(),
+            bind_groups: HashMap::new(),
+            missing: HashSet::new(),
+        }
+    }
+
+    /// drops the pipelines, for when they have to be made again with a different sample count
+    pub fn clear_pipelines(&mut self) {
+        self.pipelines.clear();
+    }
+
+    /// makes sure everything needed to draw with the materials in `names` is there, and updates
+    /// their time
+    #[allow(clippy::too_many_arguments)]
+    pub fn prepare<'a>(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        shader: &wgpu::ShaderModule,
+        format: wgpu::TextureFormat,
+        sample_count: u32,
+        names: impl IntoIterator<Item = &'a str>,
+        time: f32,
+    ) {
+        for name in names {
+            let material = match self.definitions.get(name) {
+                Some(material) => material,
+                None => {
+                    if self.missing.insert(name.to_owned()) {
+                        tracing::warn!("no material called {}, drawing it plain", name);
+                    }
+                    continue;
+                }
+            };
+            let pipeline_layout = &self.pipeline_layout;
+            // an opaque material can still have see-through objects drawn with it
+            for blend in [Blend::Opaque, Blend::Translucent] {
+                self.pipelines
+                    .entry((material.shader, blend))
+                    .or_insert_with(|| {
+                        crate::render::create_pipeline(
+                            device,
+                            pipeline_layout,
+                            shader,
+                            material.shader.entry_point(),
+                            format,
+                            sample_count,
+                            blend,
+                        )
+                    });
+            }
+            if !self.bind_groups.contains_key(name) {
+                let bind_group = self.create_bind_group(device, queue, name, material);
+                self.bind_groups.insert(name.to_owned(), bind_group);
+            }
+            let (_, buffer) = &self.bind_groups[name];
+            let mut uniform = [0.0; 8];
+            uniform[..4].copy_from_slice(&material.params);
+            uniform[4] = time;
+            queue.write_buffer(buffer, 0, bytemuck::cast_slice(&uniform));
+        }
+    }
+
+    /// whether everything drawn with `name` can be seen through
+    pub fn translucent(&self, name: &str) -> bool {
+        self.definitions
+            .get(name)
+            .is_some_and(|material| material.translucent)
+    }
+
+    /// what to draw with for `name`, None if it isn't defined or wasn't prepared this frame
+    pub fn get(
+        &self,
+        name: &str,
+        blend: Blend,
+    ) -> Option<(&wgpu::RenderPipeline, &wgpu::BindGroup)> {
+        let material = self.definitions.get(name)?;
+        let pipeline = self.pipelines.get(&(material.shader, blend))?;
+        let (bind_group, _) = self.bind_groups.get(name)?;
+        Some((pipeline, bind_group))
+    }
+
+    fn create_bind_group(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        name: &str,
+        material: &Material,
+    ) -> (wgpu::BindGroup, wgpu::Buffer) {
+        let texture = material.texture.as_ref().and_then(|texture| {
+            let path = Path::new(Self::TEXTURES).join(texture);
+            load_texture(device, queue, &path)
+                .map_err(|e| {
+                    tracing::warn!("failed to load the texture of material {}: {:?}", name, e);
+                })
+                .ok()
+        });
+        let buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("material buffer"),
+            contents: &[0; Self::UNIFORM_SIZE],
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("material bind group"),
+            layout: &self.bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::TextureView(
+                        texture.as_ref().unwrap_or(&self.white),
+                    ),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: wgpu::BindingResource::Sampler(&self.sampler),
+                },
+            ],
+        });
+        (bind_group, buffer)
+    }
+}
+
+/// reads a png into a texture, whatever colour type it's saved with
+fn load_texture(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    path: &Path,
+) -> color_eyre::Result<wgpu::TextureView> {
+    let file =
+        std::fs::File::open(path).wrap_err_with(|| format!("failed to open {}", path.display()))?;
+    let mut decoder = png::Decoder::new(file);
+    decoder.set_transformations(png::Transformations::normalize_to_color8());
+    let mut reader = decoder
+        .read_info()
+        .wrap_err_with(|| format!("failed to read {}", path.display()))?;
+    let mut pixels = vec![0; reader.output_buffer_size()];
+    let info = reader
+        .next_frame(&mut pixels)
+        .wrap_err_with(|| format!("failed to decode {}", path.display()))?;
+    let pixels = &pixels[..info.buffer_size()];
+    let rgba = match info.color_type {
+        png::ColorType::Rgba => pixels.to_vec(),
+        png::ColorType::Rgb => pixels
+            .chunks_exact(3)
+            .flat_map(|p| [p[0], p[1], p[2], 255])
+            .collect(),
+        png::ColorType::GrayscaleAlpha => pixels
+            .chunks_exact(2)
+            .flat_map(|p| [p[0], p[0], p[0], p[1]])
+            .collect(),
+        png::ColorType::Grayscale => pixels.iter().flat_map(|&p| [p, p, p, 255]).collect(),
+        png::ColorType::Indexed => {
+            return Err(eyre!(
+                "{} still has a palette after expanding",
+                path.display()
+            ))
+        }
+    };
+    Ok(create_texture(
+        device,
+        queue,
+        "material texture",
+        (info.width, info.height),
+        &rgba,
+    ))
+}
+
+fn create_texture(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    label: &str,
+    (width, height): (u32, u32),
+    rgba: &[u8],
+) -> wgpu::TextureView {
+    device
+        .create_texture_with_data(
+            queue,
+            &wgpu::TextureDescriptor {
+                label: Some(label),
+                size: wgpu::Extent3d {
+                    width,
+                    height,
+                    depth_or_array_layers: 1,
+                },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format: wgpu::TextureFormat::Rgba8UnormSrgb,
+                usage: wgpu::TextureUsages::TEXTURE_BINDING,
+            },
+            rgba,
+        )
+        .create_view(&wgpu::TextureViewDescriptor::default())
+}
@@ -0,0 +1,265 @@
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::console::Console;
+use crate::paths;
+use crate::replay::{self, Replay};
+
+/// where ghosts get shared and who they're shared as
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct OnlineConfig {
+    /// the ghost server, nothing gets sent anywhere without one
+    #[serde(default)]
+    pub server: Option<String>,
+    /// who your ghosts go up as, for friends to race
+    #[serde(default = "default_name")]
+    pub name: String,
+}
+
+impl Default for OnlineConfig {
+    fn default() -> Self {
+        Self {
+            server: None,
+            name: default_name(),
+        }
+    }
+}
+
+fn default_name() -> String {
+    "player".to_owned()
+}
+
+/// a transfer that's finished
+pub enum Transfer {
+    Uploaded {
+        level: String,
+    },
+    /// a friend's ghost, saved to `path` and ready to race
+    Downloaded {
+        level: String,
+        friend: String,
+        path: PathBuf,
+        /// whether they had the assist mode on for it
+        assisted: bool,
+    },
+    Failed(String),
+}
+
+/// ghosts going up to and coming down from the ghost server, each on a thread of its own so the
+/// game doesn't wait on the network, the server keeps them at `<server>/ghosts/<level>/<name>.ron`
+/// taking them with PUT and handing them out with GET
+#[derive(Default)]
+pub struct Online {
+    pending: Vec<Receiver<Transfer>>,
+}
+
+impl Online {
+    const TIMEOUT: Duration = Duration::from_secs(10);
+
+    /// sends your best run through `level` up for friends to race
+    pub fn upload(&mut self, config: &OnlineConfig, level: &str) -> Result<(), String> {
+        let url = ghost_url(config, level, &config.name)?;
+        let ghost = paths::ghost(level);
+        let text = std::fs::read_to_string(&ghost)
+            .map_err(|_| format!("no best run through {} to upload", level))?;
+        let level = level.to_owned();
+        self.spawn(move || {
+            match agent()
+                .put(&url)
+                .set("Content-Type", "application/ron")
+                .send_string(&text)
+            {
+                Ok(_) => Transfer::Uploaded { level },
+                Err(e) => Transfer::Failed(format!("failed to upload ghost to {}: {}", url, e)),
+            }
+        });
+        Ok(())
+    }
+
+    /// fetches `friend`'s best run through `level`
+    pub fn download(
+        &mut self,
+        config: &OnlineConfig,
+        friend: &str,
+        level: &str,
+    ) -> Result<(), String> {
+        let url = ghost_url(config, level, friend)?;
+        let path = friend_ghost_path(friend, level);
+        let (friend, level) = (friend.to_owned(), level.to_owned());
+        self.spawn(move || {
+            let text = match agent()
+                .get(&url)
+                .call()
+                .map(|response| response.into_string())
+            {
+                Ok(Ok(text)) => text,
+                Ok(Err(e)) => return Transfer::Failed(format!("failed to read {}: {}", url, e)),
+                Err(e) => return Transfer::Failed(format!("failed to download {}: {}", url, e)),
+            };
+            // written out first so loading it goes through the same checks as any other replay
+            match save_ghost(&path, &text) {
+                Ok(ghost) => Transfer::Downloaded {
+                    level,
+                    friend,
+                    path,
+                    assisted: ghost.assist_mode.assisted(),
+                },
+                Err(e) => {
+                    let _ = std::fs::remove_file(&path);
+                    Transfer::Failed(format!(
+                        "{}'s ghost for {} is no good: {:#}",
+                        friend, level, e
+                    ))
+                }
+            }
+        });
+        Ok(())
+    }
+
+    /// the transfers that finished since the last call, call every frame
+    pub fn poll(&mut self) -> Vec<Transfer> {
+        let mut finished = vec![];
+        self.pending.retain(|receiver| match receiver.try_recv() {
+            Ok(transfer) => {
+                finished.push(transfer);
+                false
+            }
+            Err(TryRecvError::Empty) => true,
+            Err(TryRecvError::Disconnected) => {
+                finished.push(Transfer::Failed("a ghost transfer went missing".to_owned()));
+                false
+            }
+        });
+        finished
+    }
+
+    fn spawn(&mut self, transfer: impl FnOnce() -> Transfer + Send + 'static) {
+        let (sender, receiver) = mpsc::channel();
+        std::thread::spawn(move || {
+            let _ = sender.send(transfer());
+        });
+        self.pending.push(receiver);
+    }
+}
+
+fn agent() -> ureq::Agent {
+    ureq::AgentBuilder::new().timeout(Online::TIMEOUT).build()
+}
+
+/// names go into urls and file names as they are, so they're kept to letters, digits, - and _
+fn valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '-' || c == '_')
+}
+
+fn ghost_url(config: &OnlineConfig, level: &str, name: &str) -> Result<String, String> {
+    let server = config
+        .server
+        .as_ref()
+        .ok_or("no ghost server set, use online server <url>")?;
+    if !valid_name(name) {
+        return Err(format!("{} can't be used as a name", name));
+    }
+    Ok(format!(
+        "{}/ghosts/{}/{}.ron",
+        server.trim_end_matches('/'),
+        percent_encode(level),
+        name
+    ))
+}
+
+/// `text` made safe to go in a url path, level names come from file names and can have anything
+/// in them, `.` is encoded too so a level can't be named `..`
+fn percent_encode(text: &str) -> String {
+    text.bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'~' => {
+                (byte as char).to_string()
+            }
+            _ => format!("%{:02X}", byte),
+        })
+        .collect()
+}
+
+/// where a friend's ghost gets kept once it's been downloaded
+fn friend_ghost_path(friend: &str, level: &str) -> PathBuf {
+    paths::data_dir()
+        .join("friends")
+        .join(friend)
+        .join(replay::ghost_path(level))
+}
+
+fn save_ghost(path: &Path, text: &str) -> color_eyre::Result<Replay> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(path, text)?;
+    Replay::load(path)
+}
+
+/// the console command for sharing ghosts
+pub fn register_commands(console: &mut Console) {
+    const USAGE: &str = "online <server <url|off>|name <name>|race <friend>|upload>";
+    console.register("online", USAGE, |args, context| {
+        let changed = match args {
+            ["server", "off"] => {
+                context.settings.online.server = None;
+                "ghost server turned off".to_owned()
+            }
+            ["server", url] => {
+                context.settings.online.server = Some((*url).to_owned());
+                format!("ghost server set to {}", url)
+            }
+            ["name", name] if valid_name(name) => {
+                context.settings.online.name = (*name).to_owned();
+                format!("ghosts go up as {}", name)
+            }
+            ["name", name] => return Err(format!("{} can't be used as a name", name)),
+            ["race", friend] => {
+                let config = context.settings.online.clone();
+                let level = context.session()?.level_name.clone();
+                context.online.download(&config, friend, &level)?;
+                return Ok(format!("fetching {}'s ghost for {}", friend, level));
+            }
+            ["upload"] => {
+                let config = context.settings.online.clone();
+                let level = context.session()?.level_name.clone();
+                context.online.upload(&config, &level)?;
+                return Ok(format!("uploading your ghost for {}", level));
+            }
+            _ => return Err(format!("usage: {}", USAGE)),
+        };
+        context
+            .settings
+            .save(context.profile.settings_path())
+            .map_err(|e| format!("{:#}", e))?;
+        Ok(changed)
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn level_names_get_encoded_in_ghost_urls() {
+        let config = OnlineConfig {
+            server: Some("https://ghosts.example/".to_owned()),
+            ..Default::default()
+        };
+        assert_eq!(
+            ghost_url(&config, "the pit", "alex").unwrap(),
+            "https://ghosts.example/ghosts/the%20pit/alex.ron"
+        );
+        assert_eq!(
+            ghost_url(&config, "../über?x#1", "alex").unwrap(),
+            "https://ghosts.example/ghosts/%2E%2E%2F%C3%BCber%3Fx%231/alex.ron"
+        );
+        assert!(ghost_url(&config, "the pit", "a/b").is_err());
+    }
+}
@@ -0,0 +1,161 @@
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use crate::replay;
+
+/// where the files the game writes go, worked out once at startup, the game's own assets and
+/// levels still come from the working directory
+struct Dirs {
+    /// settings
+    config: PathBuf,
+    /// save slots and ghosts, the things worth keeping
+    data: PathBuf,
+    /// logs and crash reports, which only matter to this machine
+    local: PathBuf,
+}
+
+static DIRS: OnceLock<Dirs> = OnceLock::new();
+
+impl Dirs {
+    /// the platform's own places, XDG folders, AppData or Library, or the working directory where
+    /// there's no home folder to find them in
+    fn platform() -> Self {
+        match directories::ProjectDirs::from("", "SpaceCat-Chan", "grappling_hook") {
+            Some(dirs) => Self {
+                config: dirs.config_dir().to_owned(),
+                data: dirs.data_dir().to_owned(),
+                local: dirs.data_local_dir().to_owned(),
+            },
+            None => Self::all_in(PathBuf::from(".")),
+        }
+    }
+
+    /// everything next to the game itself, for running off a usb stick
+    fn portable() -> Self {
+        let dir = std::env::current_exe()
+            .ok()
+            .and_then(|exe| exe.parent().map(Path::to_owned))
+            .unwrap_or_else(|| PathBuf::from("."));
+        Self::all_in(dir)
+    }
+
+    fn all_in(dir: PathBuf) -> Self {
+        Self {
+            config: dir.clone(),
+            data: dir.clone(),
+            local: dir,
+        }
+    }
+}
+
+/// picks where files go, has to come before anything reads or writes one, later calls do nothing
+pub fn init(portable: bool) {
+    DIRS.get_or_init(|| match portable {
+        true => Dirs::portable(),
+        false => Dirs::platform(),
+    });
+}
+
+fn dirs() -> &'static Dirs {
+    DIRS.get_or_init(Dirs::platform)
+}
+
+pub fn config_dir() -> &'static Path {
+    &dirs().config
+}
+
+pub fn data_dir() -> &'static Path {
+    &dirs().data
+}
+
+pub fn logs_dir() -> PathBuf {
+    dirs().local.join("logs")
+}
+
+pub fn crashes_dir() -> PathBuf {
+    dirs().local.join("crashes")
+}
+
+/// where the ghost of the best run through `level` is kept
+pub fn ghost(level: &str) -> PathBuf {
+    data_dir().join(replay::ghost_path(level))
+}
+
+/// moves files the game used to write to the working directory over to where they go now, leaving
+/// alone anything that's already been written in the new place
+pub fn migrate(moves: &[(&str, PathBuf)]) {
+    for (old, new) in moves {
+        let old = Path::new(old);
+        if !old.exists() || new.exists() {
+            continue;
+        }
+        let moved = new
+            .parent()
+            .map_or(Ok(()), std::fs::create_dir_all)
+            .and_then(|_| std::fs::rename(old, new).or_else(|_| copy_over(old, new)));
+        match moved {
+            Ok(()) => tracing::info!("moved {} to {}", old.display(), new.display()),
+            Err(e) => tracing::warn!(
+                "failed to move {} to {}, it's been left where it was: {}",
+                old.display(),
+                new.display(),
+                e
+            ),
+        }
+    }
+}
+
+/// moves `old` to `new` the long way, for when they're on different filesystems and renaming
+/// can't, a copy that fails halfway is cleaned up so the next start tries again
+fn copy_over(old: &Path, new: &Path) -> std::io::Result<()> {
+    if let Err(e) = copy_all(old, new) {
+        let _ = remove_all(new);
+        return Err(e);
+    }
+    remove_all(old)
+}
+
+/// copies a file, or a directory with everything in it
+fn copy_all(from: &Path, to: &Path) -> std::io::Result<()> {
+    if !from.is_dir() {
+        return std::fs::copy(from, to).map(|_| ());
+    }
+    std::fs::create_dir_all(to)?;
+    for entry in std::fs::read_dir(from)? {
+        let entry = entry?;
+        copy_all(&entry.path(), &to.join(entry.file_name()))?;
+    }
+    Ok(())
+}
+
+fn remove_all(path: &Path) -> std::io::Result<()> {
+    match path.is_dir() {
+        true => std::fs::remove_dir_all(path),
+        false => std::fs::remove_file(path),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn copying_over_moves_everything_inside() {
+        let root =
+            std::env::temp_dir().join(format!("grappling_hook_paths_{}", std::process::id()));
+        let (old, new) = (root.join("old"), root.join("new"));
+        std::fs::create_dir_all(old.join("nested")).unwrap();
+        std::fs::write(old.join("top.ron"), "top").unwrap();
+        std::fs::write(old.join("nested").join("inner.ron"), "inner").unwrap();
+
+        copy_over(&old, &new).unwrap();
+        assert!(!old.exists());
+        assert_eq!(std::fs::read_to_string(new.join("top.ron")).unwrap(), "top");
+        let inner = std::fs::read_to_string(new.join("nested").join("inner.ron")).unwrap();
+        assert_eq!(inner, "inner");
+
+        assert!(copy_over(&root.join("missing"), &root.join("elsewhere")).is_err());
+        assert!(!root.join("elsewhere").exists());
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
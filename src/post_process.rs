@@ -0,0 +1,237 @@
+use std::time::Instant;
+
+use wgpu::util::DeviceExt;
+
+use crate::game_state;
+
+/// how strong each effect currently is, they get kicked up by gameplay and decay back down over time
+#[derive(Clone, Copy, Default)]
+struct EffectStack {
+    flash: f32,
+    vignette: f32,
+    aberration: f32,
+}
+
+impl EffectStack {
+    /// impacts slower than this don't flash the screen, resting contacts hit at gravity speeds every tick
+    const FLASH_THRESHOLD: f64 = 8.0;
+    /// swinging faster than this starts darkening the edges
+    const VIGNETTE_SPEED: f64 = 12.0;
+    /// and faster than this splits the colors a bit
+    const ABERRATION_SPEED: f64 = 20.0;
+
+    fn on_event(&mut self, event: &game_state::GameEvent, view_object: usize) {
+        match *event {
+            game_state::GameEvent::Impact {
+                objects: (a, b),
+                speed,
+            } if (a == view_object || b == view_object) && speed > Self::FLASH_THRESHOLD => {
+                let strength = ((speed - Self::FLASH_THRESHOLD) / 20.0).min(0.6) as f32;
+                self.flash = self.flash.max(strength);
+            }
+            game_state::GameEvent::Crushed { object } if object == view_object => {
+                self.flash = 1.0;
+            }
+            game_state::GameEvent::ProjectileHit { object, .. } if object == view_object => {
+                self.flash = self.flash.max(0.4);
+            }
+            game_state::GameEvent::Damaged { object, .. } if object == view_object => {
+                self.flash = self.flash.max(0.4);
+            }
+            game_state::GameEvent::RopeSnapped { object, .. } if object == view_object => {
+                self.flash = self.flash.max(0.3);
+            }
+            game_state::GameEvent::ReleaseBoost { object, .. } if object == view_object => {
+                self.flash = self.flash.max(0.2);
+            }
+            game_state::GameEvent::PhaseChanged { .. } => {
+                self.flash = self.flash.max(0.3);
+            }
+            game_state::GameEvent::Explosion { .. } => {
+                self.flash = self.flash.max(0.5);
+            }
+            _ => {}
+        }
+    }
+
+    fn update(&mut self, dt: f32, speed: f64) {
+        self.flash = (self.flash - dt * 3.0).max(0.0);
+        let target_vignette = ((speed - Self::VIGNETTE_SPEED) / 15.0).clamp(0.0, 0.7) as f32;
+        let target_aberration = ((speed - Self::ABERRATION_SPEED) / 10.0).clamp(0.0, 1.0) as f32;
+        // ease towards the targets so it doesn't pop in and out
+        let ease = (dt * 5.0).min(1.0);
+        self.vignette += (target_vignette - self.vignette) * ease;
+        self.aberration += (target_aberration - self.aberration) * ease;
+    }
+
+    fn as_uniform(&self, encode_srgb: bool) -> [f32; 4] {
+        [
+            self.flash,
+            self.vignette,
+            self.aberration,
+            if encode_srgb { 1.0 } else { 0.0 },
+        ]
+    }
+}
+
+/// renders the scene from an intermediate texture onto the surface, applying the effect stack on the way
+/// and turning it into sRGB for surfaces that don't do that themselves
+pub struct PostProcess {
+    pipeline: wgpu::RenderPipeline,
+    bind_group_layout: wgpu::BindGroupLayout,
+    sampler: wgpu::Sampler,
+    effects_buffer: wgpu::Buffer,
+    effects: EffectStack,
+    last_frame: Instant,
+    encode_srgb: bool,
+}
+
+impl PostProcess {
+    pub fn new(device: &wgpu::Device, format: wgpu::TextureFormat) -> Self {
+        let shader = device.create_shader_module(&wgpu::include_wgsl!("post_process.wgsl"));
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("post process bind group layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: (4 * std::mem::size_of::<f32>() as u64).try_into().ok(),
+                    },
+                    count: None,
+                },
+            ],
+        });
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("post process pipeline"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("post process pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: "vs_main",
+                buffers: &[],
+            },
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: "fs_main",
+                targets: &[wgpu::ColorTargetState {
+                    format,
+                    blend: None,
+                    write_mask: wgpu::ColorWrites::ALL,
+                }],
+            }),
+            multiview: None,
+        });
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("post process sampler"),
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+        let effects = EffectStack::default();
+        let effects_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("post process effects buffer"),
+            contents: bytemuck::cast_slice(&effects.as_uniform(false)),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+        Self {
+            pipeline,
+            bind_group_layout,
+            sampler,
+            effects_buffer,
+            effects,
+            last_frame: Instant::now(),
+            encode_srgb: !crate::srgb::stores_linear(format),
+        }
+    }
+
+    pub fn on_event(&mut self, event: &game_state::GameEvent, view_object: usize) {
+        self.effects.on_event(event, view_object);
+    }
+
+    /// advances the effects and records the pass that draws `scene` onto `target`, `view_speed`
+    /// is None with the effects turned off, which just copies it over
+    pub fn run(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        encoder: &mut wgpu::CommandEncoder,
+        scene: &wgpu::TextureView,
+        target: &wgpu::TextureView,
+        view_speed: Option<f64>,
+    ) {
+        let now = Instant::now();
+        match view_speed {
+            Some(view_speed) => self
+                .effects
+                .update((now - self.last_frame).as_secs_f32(), view_speed),
+            // so they don't all come back at once when they get turned on again
+            None => self.effects = EffectStack::default(),
+        }
+        self.last_frame = now;
+        queue.write_buffer(
+            &self.effects_buffer,
+            0,
+            bytemuck::cast_slice(&self.effects.as_uniform(self.encode_srgb)),
+        );
+
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("post process bind group"),
+            layout: &self.bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(scene),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(&self.sampler),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: self.effects_buffer.as_entire_binding(),
+                },
+            ],
+        });
+        let mut rpass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("post process pass"),
+            color_attachments: &[wgpu::RenderPassColorAttachment {
+                view: target,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                    store: true,
+                },
+            }],
+            depth_stencil_attachment: None,
+        });
+        rpass.set_pipeline(&self.pipeline);
+        rpass.set_bind_group(0, &bind_group, &[]);
+        rpass.draw(0..3, 0..1);
+    }
+}
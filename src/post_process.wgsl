@@ -0,0 +1,51 @@
+struct VertexOutput {
+    [[builtin(position)]] position: vec4<f32>;
+    [[location(0)]] uv: vec2<f32>;
+};
+
+struct Effects {
+    flash: f32;
+    vignette: f32;
+    aberration: f32;
+    // 1 when the surface doesn't turn linear colours into sRGB by itself, so it has to happen here
+    encode_srgb: f32;
+};
+
+[[group(0), binding(0)]]
+var scene: texture_2d<f32>;
+[[group(0), binding(1)]]
+var scene_sampler: sampler;
+[[group(0), binding(2)]]
+var<uniform> effects: Effects;
+
+// a single triangle covering the whole screen
+[[stage(vertex)]]
+fn vs_main([[builtin(vertex_index)]] index: u32) -> VertexOutput {
+    var output: VertexOutput;
+    let uv = vec2<f32>(f32((index << 1u) & 2u), f32(index & 2u));
+    output.position = vec4<f32>(uv * 2.0 - 1.0, 0.0, 1.0);
+    output.uv = vec2<f32>(uv.x, 1.0 - uv.y);
+    return output;
+}
+
+fn to_srgb(color: vec3<f32>) -> vec3<f32> {
+    let low = color * 12.92;
+    let high = 1.055 * pow(color, vec3<f32>(1.0 / 2.4, 1.0 / 2.4, 1.0 / 2.4)) - 0.055;
+    return select(high, low, color <= vec3<f32>(0.0031308, 0.0031308, 0.0031308));
+}
+
+[[stage(fragment)]]
+fn fs_main(input: VertexOutput) -> [[location(0)]] vec4<f32> {
+    let from_center = input.uv - vec2<f32>(0.5, 0.5);
+    let shift = from_center * effects.aberration * 0.02;
+    let r = textureSample(scene, scene_sampler, input.uv + shift).r;
+    let g = textureSample(scene, scene_sampler, input.uv).g;
+    let b = textureSample(scene, scene_sampler, input.uv - shift).b;
+    var color = vec3<f32>(r, g, b);
+    color = color * (1.0 - effects.vignette * smoothStep(0.2, 0.75, length(from_center)));
+    color = mix(color, vec3<f32>(1.0, 1.0, 1.0), effects.flash);
+    if (effects.encode_srgb > 0.5) {
+        color = to_srgb(color);
+    }
+    return vec4<f32>(color, 1.0);
+}
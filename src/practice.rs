@@ -0,0 +1,42 @@
+use crate::console::Console;
+use crate::game_state::GameState;
+
+/// practice mode, the player can save where they are and go back to it as often as they like,
+/// for going over one swing again and again without playing the whole level up to it
+#[derive(Default)]
+pub struct Practice {
+    /// the whole game as it was when it got saved, speed and rope and all
+    saved: Option<GameState>,
+}
+
+impl Practice {
+    pub fn save(&mut self, state: &GameState) {
+        self.saved = Some(state.clone());
+    }
+
+    /// the game as it was at the last save, None if nothing's been saved yet
+    pub fn saved(&self) -> Option<&GameState> {
+        self.saved.as_ref()
+    }
+}
+
+/// the console command for practice mode, runs with it on don't count, it stays on and keeps
+/// its save across restarts of the level
+pub fn register_commands(console: &mut Console) {
+    const USAGE: &str = "practice <on|off>";
+    console.register("practice", USAGE, |args, context| {
+        let session = context.session()?;
+        match args {
+            ["on"] => {
+                session.practice.get_or_insert_with(Practice::default);
+                session.cheated = true;
+                Ok("practice mode on, runs won't count".to_owned())
+            }
+            ["off"] => {
+                session.practice = None;
+                Ok("practice mode off, the next run counts again".to_owned())
+            }
+            _ => Err(format!("usage: {}", USAGE)),
+        }
+    });
+}
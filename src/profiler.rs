@@ -0,0 +1,144 @@
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use crate::console::Console;
+use crate::frame_graph::PassKind;
+use crate::gpu_timer::GpuTimes;
+use crate::text::Text;
+
+/// how long the cpu spent on one frame, in milliseconds
+#[derive(Clone, Copy, Debug)]
+struct CpuTimes {
+    /// stepping the game forward
+    update: f64,
+    /// recording and submitting the frame
+    render: f64,
+}
+
+/// keeps the last couple of seconds of frame times around and shows them in the overlay, so it
+/// can be told whether a hitch was waiting on the cpu or the gpu
+#[derive(Default)]
+pub struct Profiler {
+    pub enabled: bool,
+    /// false when the gpu can't be timed, only the cpu times get shown then
+    pub gpu_supported: bool,
+    cpu: VecDeque<CpuTimes>,
+    gpu: VecDeque<GpuTimes>,
+    /// how many objects the last frame drew and culled
+    objects: Option<(usize, usize)>,
+}
+
+impl Profiler {
+    /// how many frames the averages and maximums are over
+    const HISTORY: usize = 120;
+    const COLOR: [f32; 4] = [0.6, 1.0, 0.6, 1.0];
+
+    pub fn record_cpu(&mut self, update: Duration, render: Duration) {
+        push(
+            &mut self.cpu,
+            CpuTimes {
+                update: update.as_secs_f64() * 1000.0,
+                render: render.as_secs_f64() * 1000.0,
+            },
+        );
+    }
+
+    pub fn record_gpu(&mut self, times: GpuTimes) {
+        push(&mut self.gpu, times);
+    }
+
+    pub fn record_objects(&mut self, drawn: usize, culled: usize) {
+        self.objects = Some((drawn, culled));
+    }
+
+    /// drops everything recorded, so turning it back on doesn't show stale times
+    pub fn clear(&mut self) {
+        self.cpu.clear();
+        self.gpu.clear();
+        self.objects = None;
+    }
+
+    /// the average and worst times of each part of the frame, with whichever side is slower
+    pub fn text(&self) -> Vec<Text> {
+        let mut lines = vec!["frame times (ms)   avg    max".to_owned()];
+        let line =
+            |name: &str, (avg, max): (f64, f64)| format!("{:16} {:6.2} {:6.2}", name, avg, max);
+        let update = stat(self.cpu.iter().map(|times| times.update));
+        let render = stat(self.cpu.iter().map(|times| times.render));
+        let cpu = stat(self.cpu.iter().map(|times| times.update + times.render));
+        lines.push(line("cpu update", update));
+        lines.push(line("cpu render", render));
+        lines.push(line("cpu total", cpu));
+        if self.gpu_supported {
+            let gpu = stat(self.gpu.iter().map(|times| times.frame));
+            lines.push(line("gpu total", gpu));
+            let mut kinds: Vec<PassKind> = vec![];
+            for (kind, _) in self.gpu.iter().flat_map(|times| &times.passes) {
+                if !kinds.contains(kind) {
+                    kinds.push(*kind);
+                }
+            }
+            for kind in kinds {
+                let times = self.gpu.iter().flat_map(|times| {
+                    times
+                        .passes
+                        .iter()
+                        .filter(move |(pass, _)| *pass == kind)
+                        .map(|(_, time)| *time)
+                });
+                lines.push(line(
+                    &format!("  {}", format!("{:?}", kind).to_lowercase()),
+                    stat(times),
+                ));
+            }
+            if !self.cpu.is_empty() && !self.gpu.is_empty() {
+                lines.push(format!(
+                    "mostly waiting on the {}",
+                    if gpu.0 > cpu.0 { "gpu" } else { "cpu" }
+                ));
+            }
+        } else {
+            lines.push("gpu timing isn't supported here".to_owned());
+        }
+        if let Some((drawn, culled)) = self.objects {
+            lines.push(format!("objects {} drawn, {} culled", drawn, culled));
+        }
+        lines
+            .into_iter()
+            .enumerate()
+            .map(|(i, line)| {
+                Text::new(line, (560.0, 20.0 + i as f32 * 18.0))
+                    .with_size(16.0)
+                    .with_color(Self::COLOR)
+            })
+            .collect()
+    }
+}
+
+fn push<T>(history: &mut VecDeque<T>, item: T) {
+    if history.len() == Profiler::HISTORY {
+        history.pop_front();
+    }
+    history.push_back(item);
+}
+
+/// the average and maximum of `times`, zeros if there aren't any
+fn stat(times: impl Iterator<Item = f64>) -> (f64, f64) {
+    let (count, sum, max) = times.fold((0, 0.0, 0.0_f64), |(count, sum, max), time| {
+        (count + 1, sum + time, max.max(time))
+    });
+    if count == 0 {
+        (0.0, 0.0)
+    } else {
+        (sum / count as f64, max)
+    }
+}
+
+pub fn register_commands(console: &mut Console) {
+    console.register("profile", "profile", |_, context| {
+        let profiler = &mut *context.profiler;
+        profiler.enabled = !profiler.enabled;
+        profiler.clear();
+        Ok(format!("frame times set to {}", profiler.enabled))
+    });
+}
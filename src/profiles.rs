@@ -0,0 +1,132 @@
+use std::path::PathBuf;
+
+use color_eyre::eyre::WrapErr;
+use serde::{Deserialize, Serialize};
+
+use crate::achievements::Achievements;
+use crate::paths;
+use crate::settings::Settings;
+use crate::stats::Stats;
+use crate::world_state::WorldState;
+
+/// which slot got played last, so the game starts back up in it
+#[derive(Default, Serialize, Deserialize)]
+struct LastPlayed {
+    slot: usize,
+}
+
+/// a save slot, with its own folders for everything that's kept between sessions, so more than
+/// one person can play through the game on the same computer
+pub struct Profile {
+    pub slot: usize,
+    dir: PathBuf,
+    /// where its settings go, apart from the rest on platforms that keep config somewhere else
+    config_dir: PathBuf,
+}
+
+impl Profile {
+    pub const SLOTS: usize = 3;
+    const LAST_PLAYED: &'static str = "profile.ron";
+
+    pub fn open(slot: usize) -> Self {
+        let slot = slot.min(Self::SLOTS - 1);
+        let folder = format!("slot{}", slot + 1);
+        Self {
+            slot,
+            dir: paths::data_dir().join(&folder),
+            config_dir: paths::config_dir().join(&folder),
+        }
+    }
+
+    /// the slot that was played last time, or the first one
+    pub fn last_played() -> Self {
+        let last = std::fs::read_to_string(paths::config_dir().join(Self::LAST_PLAYED))
+            .ok()
+            .and_then(|text| ron::from_str::<LastPlayed>(&text).ok())
+            .unwrap_or_default();
+        Self::open(last.slot)
+    }
+
+    /// makes this the slot the game starts in next time, and makes sure its folders are there to
+    /// save into
+    pub fn remember(&self) -> color_eyre::Result<()> {
+        for dir in [&self.dir, &self.config_dir] {
+            std::fs::create_dir_all(dir)
+                .wrap_err_with(|| format!("failed to create save folder {}", dir.display()))?;
+        }
+        let path = paths::config_dir().join(Self::LAST_PLAYED);
+        let text = ron::to_string(&LastPlayed { slot: self.slot })?;
+        std::fs::write(&path, text).wrap_err_with(|| format!("failed to write {}", path.display()))
+    }
+
+    /// where this slot keeps `file`
+    pub fn path(&self, file: &str) -> PathBuf {
+        self.dir.join(file)
+    }
+
+    pub fn settings_path(&self) -> PathBuf {
+        self.config_dir.join(Settings::PATH)
+    }
+
+    /// whether this slot has changed any settings of its own, slots that haven't go with whatever
+    /// was set before switching to them
+    pub fn has_settings(&self) -> bool {
+        self.settings_path().exists()
+    }
+
+    pub fn stats(&self) -> Stats {
+        Stats::load(self.path(Stats::PATH))
+    }
+
+    pub fn world(&self) -> WorldState {
+        WorldState::load(self.path(WorldState::PATH))
+    }
+
+    pub fn achievements(&self) -> Achievements {
+        Achievements::load(Achievements::DEFINITIONS, self.path(Achievements::PATH))
+    }
+
+    /// files the game used to keep in the working directory, before there were slots, and where
+    /// they go in this slot
+    pub fn old_files(&self) -> Vec<(&'static str, PathBuf)> {
+        vec![
+            (Settings::PATH, self.settings_path()),
+            (Stats::PATH, self.path(Stats::PATH)),
+            (WorldState::PATH, self.path(WorldState::PATH)),
+            (Achievements::PATH, self.path(Achievements::PATH)),
+        ]
+    }
+
+    /// how far along the slot is, for picking between them
+    pub fn summary(&self) -> Summary {
+        let stats = self.stats();
+        Summary {
+            slot: self.slot,
+            empty: stats.levels.is_empty(),
+            completed: stats
+                .levels
+                .values()
+                .filter(|level| level.completions > 0)
+                .count(),
+            unlocked: self.achievements().unlocked(),
+        }
+    }
+}
+
+/// the line each slot gets when choosing one
+pub struct Summary {
+    pub slot: usize,
+    /// nothing's been played in it yet
+    pub empty: bool,
+    /// levels that have been finished at least once
+    pub completed: usize,
+    /// achievements gotten
+    pub unlocked: usize,
+}
+
+/// every slot, for choosing between them
+pub fn summaries() -> Vec<Summary> {
+    (0..Profile::SLOTS)
+        .map(|slot| Profile::open(slot).summary())
+        .collect()
+}
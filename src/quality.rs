@@ -0,0 +1,111 @@
+use std::time::Duration;
+
+use crate::render::{GraphicsConfig, Msaa};
+
+/// when frames count as too slow, and when there's room to go back up again
+#[derive(Clone, Copy, Debug)]
+pub struct QualityThresholds {
+    /// how long a frame is allowed to take, in milliseconds
+    pub budget: f64,
+    /// frames in a row over budget before quality gets lowered a step
+    pub slow_frames: u32,
+    /// the fraction of the budget frames have to stay under for quality to come back a step
+    pub headroom: f64,
+    /// frames in a row that need to do that, more than `slow_frames` so it doesn't flip back and forth
+    pub fast_frames: u32,
+}
+
+impl Default for QualityThresholds {
+    fn default() -> Self {
+        Self {
+            budget: 1000.0 / 60.0,
+            slow_frames: 30,
+            headroom: 0.6,
+            fast_frames: 300,
+        }
+    }
+}
+
+/// how much has been turned off to keep up, each step also has what the ones before it turned off
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Quality {
+    /// everything the config asks for
+    #[default]
+    Full,
+    NoPostProcessing,
+    NoMsaa,
+}
+
+impl Quality {
+    fn lower(self) -> Option<Self> {
+        match self {
+            Quality::Full => Some(Quality::NoPostProcessing),
+            Quality::NoPostProcessing => Some(Quality::NoMsaa),
+            Quality::NoMsaa => None,
+        }
+    }
+
+    fn raise(self) -> Option<Self> {
+        match self {
+            Quality::Full => None,
+            Quality::NoPostProcessing => Some(Quality::Full),
+            Quality::NoMsaa => Some(Quality::NoPostProcessing),
+        }
+    }
+
+    /// `config` with what this turns off taken out of it
+    pub fn apply(self, config: GraphicsConfig) -> GraphicsConfig {
+        GraphicsConfig {
+            post_processing: config.post_processing && self < Quality::NoPostProcessing,
+            msaa: if self < Quality::NoMsaa {
+                config.msaa
+            } else {
+                Msaa::Off
+            },
+            ..config
+        }
+    }
+}
+
+/// watches how long frames take and picks the quality to draw them at
+#[derive(Debug, Default)]
+pub struct AdaptiveQuality {
+    pub quality: Quality,
+    /// how many frames in a row have been over budget
+    slow: u32,
+    /// how many frames in a row have been comfortably under it
+    fast: u32,
+}
+
+impl AdaptiveQuality {
+    /// counts a frame that took `frame_time`, returning the new quality when it changes
+    pub fn observe(
+        &mut self,
+        thresholds: &QualityThresholds,
+        frame_time: Duration,
+    ) -> Option<Quality> {
+        let ms = frame_time.as_secs_f64() * 1000.0;
+        if ms > thresholds.budget {
+            self.slow = self.slow.saturating_add(1);
+            self.fast = 0;
+        } else if ms < thresholds.budget * thresholds.headroom {
+            self.fast = self.fast.saturating_add(1);
+            self.slow = 0;
+        } else {
+            self.slow = 0;
+            self.fast = 0;
+        }
+        let next = if self.slow >= thresholds.slow_frames {
+            self.quality.lower()
+        } else if self.fast >= thresholds.fast_frames {
+            self.quality.raise()
+        } else {
+            None
+        }?;
+        // changing it makes a slow frame or two of its own, which shouldn't count towards the next
+        self.slow = 0;
+        self.fast = 0;
+        self.quality = next;
+        Some(next)
+    }
+}
@@ -1,31 +1,187 @@
-use std::borrow::BorrowMut;
-use std::ops::{Add, Mul};
-
-use cgmath::{prelude::*, Matrix4};
+use cgmath::prelude::*;
 use color_eyre::eyre::eyre;
 use color_eyre::Help;
 use wgpu::util::DeviceExt;
 
+use crate::body::Pose;
+use crate::color_scheme::ColorScheme;
+use crate::frame_graph::{self, Pass, PassKind, Resource, Transients};
 use crate::game_state;
+use crate::gpu_timer::{GpuTimer, GpuTimes};
+use crate::instances::{self, lerp, Blend, FrameInstances};
+use crate::level::Region;
+use crate::lighting::Lighting;
+use crate::material::Materials;
+use crate::post_process::PostProcess;
+use crate::quality::{AdaptiveQuality, Quality, QualityThresholds};
+use crate::ribbons::{RibbonRenderer, RibbonVertex};
+use crate::srgb;
+use crate::text::{Text, TextRenderer};
+use crate::transition::{self, TransitionPass};
+
+/// how many samples per pixel to render the scene with
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Msaa {
+    Off,
+    X2,
+    X4,
+    X8,
+}
+
+impl Msaa {
+    pub fn sample_count(self) -> u32 {
+        match self {
+            Msaa::Off => 1,
+            Msaa::X2 => 2,
+            Msaa::X4 => 4,
+            Msaa::X8 => 8,
+        }
+    }
+
+    /// the sample counts the adapter can be expected to handle, 1x and 4x are guaranteed by wgpu,
+    /// the native backends basically always support 2x and 8x as well
+    fn supported(adapter: &wgpu::Adapter) -> &'static [Msaa] {
+        match adapter.get_info().backend {
+            wgpu::Backend::Vulkan | wgpu::Backend::Metal | wgpu::Backend::Dx12 => {
+                &[Msaa::Off, Msaa::X2, Msaa::X4, Msaa::X8]
+            }
+            _ => &[Msaa::Off, Msaa::X4],
+        }
+    }
+
+    /// the next setting up, wrapping back around to off
+    pub fn cycle(self) -> Self {
+        match self {
+            Msaa::Off => Msaa::X2,
+            Msaa::X2 => Msaa::X4,
+            Msaa::X4 => Msaa::X8,
+            Msaa::X8 => Msaa::Off,
+        }
+    }
+
+    /// picks the highest supported setting that isn't above the requested one
+    fn clamp_to(self, adapter: &wgpu::Adapter) -> Self {
+        Self::supported(adapter)
+            .iter()
+            .copied()
+            .rev()
+            .find(|m| m.sample_count() <= self.sample_count())
+            .unwrap_or(Msaa::Off)
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct GraphicsConfig {
+    pub msaa: Msaa,
+    /// screen effects like the hit flash and speed vignette, costs an extra fullscreen pass
+    pub post_processing: bool,
+    /// draws the player with arms and legs that move with it, instead of the sprite
+    pub limb_body: bool,
+    /// the colours things get drawn in
+    pub color_scheme: ColorScheme,
+    /// turns the expensive parts above off for as long as frames keep going over budget, None
+    /// leaves them as they are
+    pub adaptive_quality: Option<QualityThresholds>,
+}
+
+impl Default for GraphicsConfig {
+    fn default() -> Self {
+        Self {
+            msaa: Msaa::X4,
+            post_processing: true,
+            limb_body: true,
+            color_scheme: ColorScheme::Normal,
+            adaptive_quality: Some(QualityThresholds::default()),
+        }
+    }
+}
+
+/// things drawn on top of the world that aren't part of the game state
+#[derive(Default)]
+pub struct Overlay {
+    pub trajectory: Option<game_state::Trajectory>,
+    /// where the ghost of the best run is, as [x, y, width, height]
+    pub ghost: Option<[f64; 4]>,
+    pub text: Vec<Text>,
+    /// where to look and how far zoomed in, instead of following the view object
+    pub camera: Option<(cgmath::Point2<f64>, f64)>,
+    /// the object picked for debugging, it gets outlined
+    pub selected: Option<usize>,
+    /// an object drawn with a frame out of its sprite atlas, and where the frame is in it as
+    /// [u, v, width, height]
+    pub sprite: Option<(usize, [f32; 4])>,
+    /// an object drawn as a body in this pose instead of its box
+    pub body: Option<(usize, Pose)>,
+    /// see-through copies of the player where it just was, drawn with the world
+    pub afterimages: Vec<[f32; 16]>,
+    /// bits of snapped ropes flying off, drawn with the world
+    pub sparks: Vec<[f32; 16]>,
+    /// where the player has touched things the most, drawn over the world
+    pub heatmap: Vec<[f32; 16]>,
+    /// where the hook would stick if it were fired now, marked with a crosshair
+    pub reticle: Option<cgmath::Point2<f64>>,
+    /// the ribbons things leave behind, see `Ribbons::strips`
+    pub ribbons: Vec<Vec<RibbonVertex>>,
+    /// how much further in than usual to zoom while following the view object
+    pub zoom: Option<f64>,
+    /// multiplied onto the palette, for fading in and out
+    pub tint: Option<[f32; 3]>,
+    /// draws the routes things follow, for editing levels
+    pub show_paths: bool,
+    /// how much of the screen is covered up while it changes
+    pub transition: Option<transition::Cover>,
+}
+
+/// another window that only ever gets text drawn in it, sharing the device with the main one
+pub struct TextWindow {
+    surface: wgpu::Surface,
+    surface_config: wgpu::SurfaceConfiguration,
+    text: TextRenderer,
+}
 
 pub struct RenderState {
     instance: wgpu::Instance,
     surface: wgpu::Surface,
     surface_config: wgpu::SurfaceConfiguration,
+    /// what the world gets drawn in, which always stores linear colours, see `srgb::world_format`
+    world_format: wgpu::TextureFormat,
     adapter: wgpu::Adapter,
     device: wgpu::Device,
     queue: wgpu::Queue,
     shader: wgpu::ShaderModule,
     pipeline_layout: wgpu::PipelineLayout,
-    pipeline: wgpu::RenderPipeline,
+    /// for quads without a material, opaque and then translucent
+    pipelines: [wgpu::RenderPipeline; 2],
     transform_bind_group_layout: wgpu::BindGroupLayout,
+    palette_bind_group_layout: wgpu::BindGroupLayout,
     vertex_buffer: wgpu::Buffer,
+    /// what was asked for
+    graphics_config: GraphicsConfig,
+    /// what frames actually get drawn with, which is less when they've been too slow
+    active_config: GraphicsConfig,
+    quality: AdaptiveQuality,
+    /// the textures passes draw into other than the surface
+    transients: Transients,
+    lighting: Lighting,
+    ribbons: RibbonRenderer,
+    materials: Materials,
+    /// maps from clip space back to the world, as of the last frame
+    inverse_camera: cgmath::Matrix4<f64>,
+    /// also what turns the world into sRGB for surfaces that don't do that themselves
+    post_process: PostProcess,
+    transition: TransitionPass,
+    text: TextRenderer,
+    /// only around while the frame times are being shown, and when the device can do it
+    gpu_timer: Option<GpuTimer>,
+    /// how many objects the last frame drew, and how many it left out for being off screen
+    object_counts: (usize, usize),
 }
 
 impl RenderState {
     pub fn new(
         instance: wgpu::Instance,
         window: &winit::window::Window,
+        graphics_config: GraphicsConfig,
     ) -> color_eyre::Result<Self> {
         let surface = unsafe { instance.create_surface(window) };
         let adapter =
@@ -47,12 +203,14 @@ impl RenderState {
         let (device, queue) = futures::executor::block_on(adapter.request_device(
             &wgpu::DeviceDescriptor {
                 label: Some("the device, for rendering"),
-                features: wgpu::Features::default(),
+                // asked for when it's there, so the frame times can say how long the gpu took
+                features: adapter.features() & wgpu::Features::TIMESTAMP_QUERY,
                 limits: wgpu::Limits::downlevel_defaults(),
             },
             None,
         )).note("you have a graphics card, we have access to it, it just doesn't support the needed features/limits to get this thing running")?;
         surface.configure(&device, &surface_config);
+        let world_format = srgb::world_format(surface_config.format);
         let shader = device.create_shader_module(&wgpu::include_wgsl!("shader.wgsl"));
         let transform_bind_group_layout =
             device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
@@ -68,57 +226,58 @@ impl RenderState {
                     count: None,
                 }],
             });
+        let palette_bind_group_layout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("palette_bind_group_layout"),
+                entries: &[wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: (8 * std::mem::size_of::<f32>() as u64).try_into().ok(),
+                    },
+                    count: None,
+                }],
+            });
         let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
             label: Some("render pipeline"),
-            bind_group_layouts: &[&transform_bind_group_layout],
+            bind_group_layouts: &[&transform_bind_group_layout, &palette_bind_group_layout],
             push_constant_ranges: &[],
         });
 
-        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-            label: Some("render pipeline"),
-            layout: Some(&pipeline_layout),
-            vertex: wgpu::VertexState {
-                module: &shader,
-                entry_point: "vs_main",
-                buffers: &[
-                    wgpu::VertexBufferLayout {
-                        array_stride: 2 * std::mem::size_of::<f32>() as u64,
-                        step_mode: wgpu::VertexStepMode::Vertex,
-                        attributes: &wgpu::vertex_attr_array![0 => Float32x2],
-                    },
-                    wgpu::VertexBufferLayout {
-                        array_stride: 4 * std::mem::size_of::<f32>() as u64,
-                        step_mode: wgpu::VertexStepMode::Instance,
-                        attributes: &wgpu::vertex_attr_array![1 => Float32x2, 2 => Float32x2],
-                    },
-                ],
-            },
-            primitive: wgpu::PrimitiveState {
-                topology: wgpu::PrimitiveTopology::TriangleList,
-                strip_index_format: None,
-                front_face: wgpu::FrontFace::Cw,
-                cull_mode: None,
-                unclipped_depth: false,
-                polygon_mode: wgpu::PolygonMode::Fill,
-                conservative: false,
-            },
-            depth_stencil: None,
-            multisample: wgpu::MultisampleState {
-                count: 1,
-                mask: !0,
-                alpha_to_coverage_enabled: false,
-            },
-            fragment: Some(wgpu::FragmentState {
-                module: &shader,
-                entry_point: "fs_main",
-                targets: &[wgpu::ColorTargetState {
-                    format: surface_config.format,
-                    blend: None,
-                    write_mask: wgpu::ColorWrites::ALL,
-                }],
-            }),
-            multiview: None,
+        let graphics_config = GraphicsConfig {
+            msaa: graphics_config.msaa.clamp_to(&adapter),
+            ..graphics_config
+        };
+        let pipelines = [Blend::Opaque, Blend::Translucent].map(|blend| {
+            create_pipeline(
+                &device,
+                &pipeline_layout,
+                &shader,
+                "fs_main",
+                world_format,
+                graphics_config.msaa.sample_count(),
+                blend,
+            )
         });
+        let materials = Materials::load(
+            &device,
+            &queue,
+            &transform_bind_group_layout,
+            &palette_bind_group_layout,
+            Materials::DEFINITIONS,
+        );
+        let lighting = Lighting::new(&device, world_format, graphics_config.msaa.sample_count());
+        let ribbons = RibbonRenderer::new(
+            &device,
+            &pipeline_layout,
+            &shader,
+            world_format,
+            graphics_config.msaa.sample_count(),
+        );
+        let post_process = PostProcess::new(&device, surface_config.format);
+        let transition = TransitionPass::new(&device, surface_config.format);
         let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
             label: Some("vertex buffer"),
             contents: bytemuck::cast_slice(&[
@@ -131,71 +290,472 @@ impl RenderState {
             ]),
             usage: wgpu::BufferUsages::VERTEX,
         });
+        let text = TextRenderer::new(&device, surface_config.format)?;
         Ok(Self {
             instance,
             adapter,
             surface,
             surface_config,
+            world_format,
+            gpu_timer: None,
+            object_counts: (0, 0),
             device,
             queue,
             shader,
             pipeline_layout,
-            pipeline,
+            pipelines,
             transform_bind_group_layout,
+            palette_bind_group_layout,
             vertex_buffer,
+            graphics_config,
+            active_config: graphics_config,
+            quality: AdaptiveQuality::default(),
+            transients: Transients::default(),
+            lighting,
+            ribbons,
+            materials,
+            inverse_camera: cgmath::Matrix4::identity(),
+            post_process,
+            transition,
+            text,
+        })
+    }
+
+    /// what the game is drawing with, for crash reports
+    pub fn adapter_info(&self) -> wgpu::AdapterInfo {
+        self.adapter.get_info()
+    }
+
+    pub fn graphics_config(&self) -> GraphicsConfig {
+        self.graphics_config
+    }
+
+    /// applies a new graphics config, recreating whatever depends on it
+    pub fn set_graphics_config(&mut self, graphics_config: GraphicsConfig) {
+        self.graphics_config = GraphicsConfig {
+            msaa: graphics_config.msaa.clamp_to(&self.adapter),
+            ..graphics_config
+        };
+        if self.graphics_config.adaptive_quality.is_none() {
+            self.quality = AdaptiveQuality::default();
+        }
+        self.activate();
+    }
+
+    /// counts how long the last frame took, lowering or raising the quality when frames have
+    /// been too slow or fast enough for a while, returns the quality when it changes
+    pub fn adapt_quality(&mut self, frame_time: std::time::Duration) -> Option<Quality> {
+        let thresholds = self.graphics_config.adaptive_quality?;
+        let quality = self.quality.observe(&thresholds, frame_time)?;
+        self.activate();
+        Some(quality)
+    }
+
+    /// switches to the config the quality allows, recreating whatever depends on it
+    fn activate(&mut self) {
+        let active_config = self.quality.quality.apply(self.graphics_config);
+        if active_config.msaa != self.active_config.msaa {
+            let sample_count = active_config.msaa.sample_count();
+            self.pipelines = [Blend::Opaque, Blend::Translucent].map(|blend| {
+                create_pipeline(
+                    &self.device,
+                    &self.pipeline_layout,
+                    &self.shader,
+                    "fs_main",
+                    self.world_format,
+                    sample_count,
+                    blend,
+                )
+            });
+            self.materials.clear_pipelines();
+            self.lighting = Lighting::new(&self.device, self.world_format, sample_count);
+            self.ribbons = RibbonRenderer::new(
+                &self.device,
+                &self.pipeline_layout,
+                &self.shader,
+                self.world_format,
+                sample_count,
+            );
+        }
+        self.active_config = active_config;
+    }
+
+    /// starts or stops timing frames on the gpu, returns whether it can be
+    pub fn set_gpu_timing(&mut self, enabled: bool) -> bool {
+        let supported = self
+            .device
+            .features()
+            .contains(wgpu::Features::TIMESTAMP_QUERY);
+        if enabled != self.gpu_timer.is_some() {
+            self.gpu_timer = enabled
+                .then(|| GpuTimer::new(&self.device, &self.queue))
+                .flatten();
+        }
+        supported
+    }
+
+    /// how long the gpu took on the newest frame that's finished since the last call, if it's
+    /// being timed
+    pub fn gpu_times(&mut self) -> Option<GpuTimes> {
+        self.gpu_timer.as_mut()?.take(&self.device)
+    }
+
+    /// how many objects the last frame with the world in it drew, and how many were culled
+    pub fn object_counts(&self) -> (usize, usize) {
+        self.object_counts
+    }
+
+    /// lets the screen effects react to what happened in the game
+    pub fn on_event(&mut self, event: &game_state::GameEvent, view_object: usize) {
+        if self.active_config.post_processing {
+            self.post_process.on_event(event, view_object);
+        }
+    }
+
+    /// converts a position in the window, in physical pixels, to where it is in the world
+    pub fn screen_to_world(
+        &self,
+        position: winit::dpi::PhysicalPosition<f64>,
+    ) -> cgmath::Point2<f64> {
+        let clip = cgmath::vec4(
+            position.x / self.surface_config.width as f64 * 2.0 - 1.0,
+            1.0 - position.y / self.surface_config.height as f64 * 2.0,
+            0.0,
+            1.0,
+        );
+        let world = self.inverse_camera * clip;
+        cgmath::point2(world.x, world.y)
+    }
+
+    /// where a point in the world is in the window, in physical pixels, as of the last frame
+    fn world_to_screen(&self, point: cgmath::Point2<f64>) -> [f32; 2] {
+        let camera = self
+            .inverse_camera
+            .invert()
+            .unwrap_or_else(cgmath::Matrix4::identity);
+        let clip = camera * cgmath::vec4(point.x, point.y, 0.0, 1.0);
+        [
+            ((clip.x + 1.0) / 2.0 * self.surface_config.width as f64) as f32,
+            ((1.0 - clip.y) / 2.0 * self.surface_config.height as f64) as f32,
+        ]
+    }
+
+    /// sets up drawing into another window, for debug tools
+    pub fn create_text_window(
+        &self,
+        window: &winit::window::Window,
+    ) -> color_eyre::Result<TextWindow> {
+        let surface = unsafe { self.instance.create_surface(window) };
+        let format = surface
+            .get_preferred_format(&self.adapter)
+            .ok_or_else(|| eyre!("the new window can't be drawn to with this adapter"))?;
+        let winit::dpi::PhysicalSize { width, height } = window.inner_size();
+        let surface_config = wgpu::SurfaceConfiguration {
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            format,
+            width,
+            height,
+            present_mode: wgpu::PresentMode::Mailbox,
+        };
+        surface.configure(&self.device, &surface_config);
+        Ok(TextWindow {
+            text: TextRenderer::new(&self.device, format)?,
+            surface,
+            surface_config,
         })
     }
 
+    /// clears `target` and writes `text` into it
+    pub fn render_text_window(
+        &self,
+        target: &mut TextWindow,
+        text: &[Text],
+    ) -> color_eyre::Result<()> {
+        let frame = target.surface.get_current_texture()?;
+        let frame_view = frame.texture.create_view(&wgpu::TextureViewDescriptor {
+            label: Some("text window target"),
+            ..Default::default()
+        });
+        let mut encoder = self
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("text window encoder"),
+            });
+        encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("text window clear pass"),
+            color_attachments: &[wgpu::RenderPassColorAttachment {
+                view: &frame_view,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(srgb::clear_color(
+                        [0.02, 0.02, 0.03],
+                        target.surface_config.format,
+                    )),
+                    store: true,
+                },
+            }],
+            depth_stencil_attachment: None,
+        });
+        target.text.draw(
+            &self.device,
+            &mut encoder,
+            &frame_view,
+            (target.surface_config.width, target.surface_config.height),
+            text,
+        )?;
+        self.queue.submit([encoder.finish()]);
+        frame.present();
+        target.text.recall();
+        Ok(())
+    }
+
+    /// draws a frame, `world` is the current and previous game state if there's a level going
     pub fn render(
         &mut self,
         interpolate: f64,
+        world: Option<(&game_state::GameState, &game_state::GameState)>,
+        overlay: &Overlay,
+    ) -> color_eyre::Result<()> {
+        let frame = self.surface.get_current_texture()?;
+        let frame_view = frame.texture.create_view(&wgpu::TextureViewDescriptor {
+            label: Some("render target"),
+            ..Default::default()
+        });
+
+        let mut encoder = self
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("render pass encoder"),
+            });
+        let passes = frame_graph::build(
+            &self.active_config,
+            world.is_some(),
+            srgb::stores_linear(self.surface_config.format),
+            overlay.transition.is_some(),
+        );
+        self.transients.prepare(
+            &self.device,
+            &self.surface_config,
+            self.world_format,
+            self.active_config.msaa.sample_count(),
+            &passes,
+        );
+        if let Some(timer) = &mut self.gpu_timer {
+            timer.begin(&mut encoder);
+        }
+        // the passes want the rest of self while they draw into these, so they're put back after
+        let transients = std::mem::take(&mut self.transients);
+        let result = self.run_passes(
+            &passes,
+            &transients,
+            &mut encoder,
+            &frame_view,
+            interpolate,
+            world,
+            overlay,
+        );
+        self.transients = transients;
+        result?;
+        if let Some(timer) = &self.gpu_timer {
+            timer.resolve(&mut encoder);
+        }
+        self.queue.submit([encoder.finish()]);
+        if let Some(timer) = &mut self.gpu_timer {
+            timer.submitted();
+        }
+        frame.present();
+        self.text.recall();
+        Ok(())
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn run_passes(
+        &mut self,
+        passes: &[Pass],
+        transients: &Transients,
+        encoder: &mut wgpu::CommandEncoder,
+        frame_view: &wgpu::TextureView,
+        interpolate: f64,
+        world: Option<(&game_state::GameState, &game_state::GameState)>,
+        overlay: &Overlay,
+    ) -> color_eyre::Result<()> {
+        for pass in passes {
+            let target = transients.view(pass.writes, frame_view);
+            match (pass.kind, world) {
+                (PassKind::Clear, _) => {
+                    encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                        label: Some("clear pass"),
+                        color_attachments: &[wgpu::RenderPassColorAttachment {
+                            view: target,
+                            resolve_target: None,
+                            ops: wgpu::Operations {
+                                load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                                store: true,
+                            },
+                        }],
+                        depth_stencil_attachment: None,
+                    });
+                }
+                (PassKind::World, Some((state, last_state))) => {
+                    let multisampled = pass
+                        .scratch
+                        .contains(&Resource::Multisampled)
+                        .then(|| transients.view(Resource::Multisampled, frame_view));
+                    self.draw_world(
+                        encoder,
+                        target,
+                        multisampled,
+                        transients.view(Resource::Depth, frame_view),
+                        interpolate,
+                        state,
+                        last_state,
+                        overlay,
+                    );
+                }
+                (PassKind::PostProcess, Some((state, _))) => {
+                    let view_speed = state
+                        .object(state.view_object)
+                        .map(|o| o.get_velocity().magnitude())
+                        .unwrap_or(0.0);
+                    self.post_process.run(
+                        &self.device,
+                        &self.queue,
+                        encoder,
+                        transients.view(Resource::Scene, frame_view),
+                        target,
+                        self.active_config.post_processing.then_some(view_speed),
+                    );
+                }
+                (PassKind::Text, _) => self.text.draw(
+                    &self.device,
+                    encoder,
+                    target,
+                    (self.surface_config.width, self.surface_config.height),
+                    &overlay.text,
+                )?,
+                (PassKind::Transition, _) => {
+                    let size = [
+                        self.surface_config.width as f32,
+                        self.surface_config.height as f32,
+                    ];
+                    // a wipe closes in on the view object, or the middle with nothing to follow
+                    let center = world.map_or([size[0] / 2.0, size[1] / 2.0], |(state, last)| {
+                        let center = follow_view_object(interpolate, state, last);
+                        self.world_to_screen(cgmath::Point2::from_vec(center))
+                    });
+                    if let Some(cover) = overlay.transition {
+                        self.transition
+                            .run(&self.queue, encoder, target, cover, center, size);
+                    }
+                }
+                (PassKind::World | PassKind::PostProcess, None) => {}
+            }
+            if let Some(timer) = &mut self.gpu_timer {
+                timer.end_pass(encoder, pass.kind);
+            }
+        }
+        Ok(())
+    }
+
+    /// what quads without a material get drawn with
+    fn plain_pipeline(&self, blend: Blend) -> &wgpu::RenderPipeline {
+        let [opaque, translucent] = &self.pipelines;
+        match blend {
+            Blend::Opaque => opaque,
+            Blend::Translucent => translucent,
+        }
+    }
+
+    /// `multisampled` is drawn into and resolved to `target` when msaa is on, `depth` has to have
+    /// the same sample count as whichever one gets drawn into
+    #[allow(clippy::too_many_arguments)]
+    fn draw_world(
+        &mut self,
+        encoder: &mut wgpu::CommandEncoder,
+        target: &wgpu::TextureView,
+        multisampled: Option<&wgpu::TextureView>,
+        depth: &wgpu::TextureView,
+        interpolate: f64,
         state: &game_state::GameState,
         last_state: &game_state::GameState,
-    ) -> color_eyre::Result<()> {
-        let mut draw_position = Vec::with_capacity(state.objects.num_elements());
-        for (index, new_object) in &state.objects {
-            let new_object = new_object.borrow();
-            let last_object = last_state.objects.get(index);
-            if let Some(last_object) = last_object {
-                let last_object = last_object.borrow();
-                let pos = lerp(
-                    last_object.get_pos().to_vec(),
-                    new_object.get_pos().to_vec(),
-                    interpolate,
-                );
-                let size = lerp(*last_object.get_size(), *new_object.get_size(), interpolate);
-                draw_position.push([pos.x as f32, pos.y as f32, size.x as f32, size.y as f32]);
-            } else {
-                let pos = new_object.get_pos().to_vec();
-                let size = new_object.get_size();
-                draw_position.push([pos.x as f32, pos.y as f32, size.x as f32, size.y as f32]);
+        overlay: &Overlay,
+    ) {
+        let (camera_position, zoom) = match overlay.camera {
+            Some((position, zoom)) => (position.to_vec(), zoom),
+            None => (
+                follow_view_object(interpolate, state, last_state),
+                overlay.zoom.unwrap_or(1.0),
+            ),
+        };
+        // what's on screen, with some room for things that move in between ticks
+        let reach = cgmath::vec2(1.0, 1.0) * (1.0 / (CAMERA_SCALE * zoom) + CULL_MARGIN);
+        let view = Region {
+            pos: cgmath::Point2::from_vec(camera_position - reach),
+            size: reach * 2.0,
+        };
+        let mut frame_instances = FrameInstances::prepare(
+            interpolate,
+            state,
+            last_state,
+            overlay.ghost,
+            overlay.trajectory.as_ref(),
+            &self.active_config.color_scheme.colors(),
+            Some(&view),
+        );
+        let culled = frame_instances.culled();
+        self.object_counts = (frame_instances.object_instances.len() - culled, culled);
+        frame_instances.push_world(overlay.afterimages.iter().copied());
+        frame_instances.push_world(overlay.sparks.iter().copied());
+        frame_instances.push_world(overlay.heatmap.iter().copied());
+        if let Some(at) = overlay.reticle {
+            frame_instances.reticle(at);
+        }
+        if let Some((index, frame)) = overlay.sprite {
+            frame_instances.set_sprite(state, index, frame);
+        }
+        if let Some((index, pose)) = &overlay.body {
+            if let Some(rect) = frame_instances.draw_position_of(state, *index) {
+                frame_instances.hide(state, *index);
+                frame_instances.push_world(pose.instances(rect));
             }
         }
+        if let Some(selected) = overlay.selected {
+            frame_instances.outline(state, selected, 0.1, instances::SELECTED_COLOR);
+        }
+        if overlay.show_paths {
+            frame_instances.paths(&state.paths);
+        }
+        self.materials.prepare(
+            &self.device,
+            &self.queue,
+            &self.shader,
+            self.world_format,
+            self.active_config.msaa.sample_count(),
+            frame_instances
+                .materials
+                .iter()
+                .filter_map(|(_, name)| name.as_deref()),
+            state.time as f32,
+        );
+        let runs = frame_instances.world_runs(|name| self.materials.translucent(name));
+        let FrameInstances {
+            draw_position,
+            instances,
+            world_count,
+            ..
+        } = &frame_instances;
         let position_buffer = self
             .device
             .create_buffer_init(&wgpu::util::BufferInitDescriptor {
                 label: Some("positions buffer"),
-                contents: bytemuck::cast_slice(&draw_position[..]),
+                contents: bytemuck::cast_slice(instances),
                 usage: wgpu::BufferUsages::VERTEX,
             });
 
-        let camera_position = {
-            let new_position = state
-                .objects
-                .get(state.view_object)
-                .map(|o| o.borrow())
-                .map(|o| o.get_pos().to_vec() + o.get_size() / 2.0)
-                .unwrap_or_else(|| cgmath::vec2(0.0, 0.0));
-            let old_position = last_state
-                .objects
-                .get(state.view_object)
-                .map(|o| o.borrow())
-                .map(|o| o.get_pos().to_vec() + o.get_size() / 2.0)
-                .unwrap_or(new_position);
-            lerp(old_position, new_position, interpolate)
-        };
-        let camera = cgmath::Matrix4::from_scale(0.04)
+        let camera = cgmath::Matrix4::from_scale(CAMERA_SCALE * zoom)
             * cgmath::Matrix4::from_translation(-camera_position.extend(0.0));
+        self.inverse_camera = camera.invert().unwrap_or_else(cgmath::Matrix4::identity);
+        let inverse_camera = self.inverse_camera.cast::<f32>().unwrap();
         let camera = camera.cast::<f32>().unwrap();
         let camera_buffer = self
             .device
@@ -213,42 +773,194 @@ impl RenderState {
                 resource: camera_buffer.as_entire_binding(),
             }],
         });
-        let frame = self.surface.get_current_texture()?;
-        let frame_view = frame.texture.create_view(&wgpu::TextureViewDescriptor {
-            label: Some("render target"),
-            ..Default::default()
+        let ribbons = self.ribbons.prepare(&self.device, &overlay.ribbons);
+        let lighting_bind_group = state.lighting.as_ref().map(|settings| {
+            self.lighting
+                .prepare(&self.device, inverse_camera, settings, draw_position)
         });
 
-        let mut encoder = self
+        let mut palette = self
+            .active_config
+            .color_scheme
+            .palette(state.palette.at(state.time));
+        if let Some(tint) = overlay.tint {
+            for (i, tint) in tint.into_iter().enumerate() {
+                palette.tint[i] *= tint;
+                palette.ambient[i] *= tint;
+                palette.background[i] *= tint;
+            }
+        }
+        // the palette is picked in sRGB like every other colour
+        let (tint, ambient) = (
+            srgb::color_to_linear(palette.tint),
+            srgb::color_to_linear(palette.ambient),
+        );
+        let palette_buffer = self
             .device
-            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
-                label: Some("render pass encoder"),
+            .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("palette buffer"),
+                contents: bytemuck::cast_slice(&[
+                    tint[0], tint[1], tint[2], 1.0, ambient[0], ambient[1], ambient[2], 0.0,
+                ]),
+                usage: wgpu::BufferUsages::UNIFORM,
             });
+        let palette_bind_group = self.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("palette bind group"),
+            layout: &self.palette_bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: palette_buffer.as_entire_binding(),
+            }],
+        });
         {
             let mut rpass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
                 label: Some("render pass"),
                 color_attachments: &[wgpu::RenderPassColorAttachment {
-                    view: &frame_view,
-                    resolve_target: None,
+                    view: multisampled.unwrap_or(target),
+                    resolve_target: multisampled.map(|_| target),
                     ops: wgpu::Operations {
-                        load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                        load: wgpu::LoadOp::Clear(srgb::clear_color(
+                            palette.background,
+                            self.world_format,
+                        )),
                         store: true,
                     },
                 }],
-                depth_stencil_attachment: None,
+                depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                    view: depth,
+                    depth_ops: Some(wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(1.0),
+                        store: false,
+                    }),
+                    stencil_ops: None,
+                }),
             });
-            rpass.set_pipeline(&self.pipeline);
+            rpass.set_bind_group(0, &camera_bind_group, &[]);
+            rpass.set_bind_group(1, &palette_bind_group, &[]);
+            // first, so whatever is see-through in the world gets blended over them
+            if let Some(ribbons) = &ribbons {
+                self.ribbons.draw(&mut rpass, ribbons);
+            }
             rpass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
             rpass.set_vertex_buffer(1, position_buffer.slice(..));
-            rpass.set_bind_group(0, &camera_bind_group, &[]);
-            rpass.draw(0..6, 0..(draw_position.len() as _));
+            for run in &runs {
+                match run
+                    .material
+                    .and_then(|name| self.materials.get(name, run.blend))
+                {
+                    Some((pipeline, bind_group)) => {
+                        rpass.set_pipeline(pipeline);
+                        rpass.set_bind_group(2, bind_group, &[]);
+                    }
+                    None => rpass.set_pipeline(self.plain_pipeline(run.blend)),
+                }
+                rpass.draw(0..6, run.instances.clone());
+            }
+            if let Some(lighting_bind_group) = &lighting_bind_group {
+                self.lighting.draw(&mut rpass, lighting_bind_group);
+                // the lighting pass swaps out the pipeline and bind groups
+                rpass.set_bind_group(0, &camera_bind_group, &[]);
+                rpass.set_bind_group(1, &palette_bind_group, &[]);
+            }
+            // what's drawn on top of the world is mostly see through, and it's last anyway
+            rpass.set_pipeline(self.plain_pipeline(Blend::Translucent));
+            rpass.draw(0..6, *world_count..(instances.len() as _));
         }
-        self.queue.submit([encoder.finish()].into_iter());
-        frame.present();
-        Ok(())
     }
 }
 
-fn lerp<T: Add<T> + Mul<f64, Output = T>>(from: T, to: T, interp_by: f64) -> <T as Add<T>>::Output {
-    (to * interp_by) + (from * (1.0 - interp_by))
+/// the middle of the view object, where the camera normally is
+fn follow_view_object(
+    interpolate: f64,
+    state: &game_state::GameState,
+    last_state: &game_state::GameState,
+) -> cgmath::Vector2<f64> {
+    let new_position = state
+        .object(state.view_object)
+        .map(|o| o.get_pos().to_vec() + o.get_size() / 2.0)
+        .unwrap_or_else(|| cgmath::vec2(0.0, 0.0));
+    let old_position = last_state
+        .object(state.view_object)
+        .map(|o| o.get_pos().to_vec() + o.get_size() / 2.0)
+        .unwrap_or(new_position);
+    lerp(old_position, new_position, interpolate)
+}
+
+/// how much of clip space a unit of the world takes up at a zoom of 1
+const CAMERA_SCALE: f64 = 0.04;
+/// how far past the edge of the screen objects still get drawn, so nothing pops in at the edge
+/// while it's being interpolated towards where it is now
+const CULL_MARGIN: f64 = 4.0;
+
+/// what the world's depth buffer holds, everything drawn into the world has to use it
+pub const DEPTH_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Depth32Float;
+
+/// the pipeline for drawing quads out of the instance buffer, `fragment_entry_point` picks the
+/// fragment shader in shader.wgsl
+///
+/// opaque quads replace what's under them and mark themselves in the depth buffer, translucent
+/// ones get blended over what's there and only check it, so they have to be drawn after
+pub fn create_pipeline(
+    device: &wgpu::Device,
+    pipeline_layout: &wgpu::PipelineLayout,
+    shader: &wgpu::ShaderModule,
+    fragment_entry_point: &str,
+    format: wgpu::TextureFormat,
+    sample_count: u32,
+    blend: Blend,
+) -> wgpu::RenderPipeline {
+    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some("render pipeline"),
+        layout: Some(pipeline_layout),
+        vertex: wgpu::VertexState {
+            module: shader,
+            entry_point: "vs_main",
+            buffers: &[
+                wgpu::VertexBufferLayout {
+                    array_stride: 2 * std::mem::size_of::<f32>() as u64,
+                    step_mode: wgpu::VertexStepMode::Vertex,
+                    attributes: &wgpu::vertex_attr_array![0 => Float32x2],
+                },
+                wgpu::VertexBufferLayout {
+                    array_stride: 16 * std::mem::size_of::<f32>() as u64,
+                    step_mode: wgpu::VertexStepMode::Instance,
+                    attributes: &wgpu::vertex_attr_array![1 => Float32x2, 2 => Float32x2, 3 => Float32x4, 4 => Float32x3, 5 => Float32x4, 6 => Float32],
+                },
+            ],
+        },
+        primitive: wgpu::PrimitiveState {
+            topology: wgpu::PrimitiveTopology::TriangleList,
+            strip_index_format: None,
+            front_face: wgpu::FrontFace::Cw,
+            cull_mode: None,
+            unclipped_depth: false,
+            polygon_mode: wgpu::PolygonMode::Fill,
+            conservative: false,
+        },
+        depth_stencil: Some(wgpu::DepthStencilState {
+            format: DEPTH_FORMAT,
+            depth_write_enabled: blend == Blend::Opaque,
+            depth_compare: wgpu::CompareFunction::Less,
+            stencil: wgpu::StencilState::default(),
+            bias: wgpu::DepthBiasState::default(),
+        }),
+        multisample: wgpu::MultisampleState {
+            count: sample_count,
+            mask: !0,
+            alpha_to_coverage_enabled: false,
+        },
+        fragment: Some(wgpu::FragmentState {
+            module: shader,
+            entry_point: fragment_entry_point,
+            targets: &[wgpu::ColorTargetState {
+                format,
+                blend: match blend {
+                    Blend::Opaque => Some(wgpu::BlendState::REPLACE),
+                    Blend::Translucent => Some(wgpu::BlendState::ALPHA_BLENDING),
+                },
+                write_mask: wgpu::ColorWrites::ALL,
+            }],
+        }),
+        multiview: None,
+    })
 }
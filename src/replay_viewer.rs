@@ -0,0 +1,100 @@
+use std::path::Path;
+
+use crate::bindings::Bindings;
+use crate::game_state::GameState;
+use crate::level::Level;
+use crate::locale::Strings;
+use crate::replay::{Playback, Replay};
+use crate::screens::{menu_hint, LEVEL_DIRECTORY};
+use crate::text::Text;
+
+/// watching a recorded run, which can be paused, scrubbed through and sped up or slowed down
+pub struct ReplayViewer {
+    pub playback: Playback,
+    pub paused: bool,
+    /// index into `SPEEDS`
+    speed: usize,
+}
+
+impl ReplayViewer {
+    /// the playback speeds to pick from, 1 is how fast it was played
+    const SPEEDS: [f64; 6] = [0.125, 0.25, 0.5, 1.0, 2.0, 4.0];
+    const NORMAL_SPEED: usize = 3;
+    /// how far one press of the seek keys jumps, in seconds
+    const SEEK_STEP: f64 = 2.0;
+
+    /// loads a replay file along with the level it was recorded in
+    pub fn load(path: impl AsRef<Path>, dt: f64) -> color_eyre::Result<Self> {
+        Self::new(Replay::load(path)?, dt)
+    }
+
+    pub fn new(replay: Replay, dt: f64) -> color_eyre::Result<Self> {
+        let level_path = Path::new(LEVEL_DIRECTORY)
+            .join(&replay.level)
+            .with_extension("ron");
+        let start = GameState::from_level(&Level::load(level_path)?);
+        Ok(Self {
+            playback: Playback::new(replay, start, dt),
+            paused: false,
+            speed: Self::NORMAL_SPEED,
+        })
+    }
+
+    /// how fast time goes for the replay, 0 while it's paused
+    pub fn speed(&self) -> f64 {
+        if self.paused {
+            0.0
+        } else {
+            Self::SPEEDS[self.speed]
+        }
+    }
+
+    pub fn faster(&mut self) {
+        self.speed = (self.speed + 1).min(Self::SPEEDS.len() - 1);
+    }
+
+    pub fn slower(&mut self) {
+        self.speed = self.speed.saturating_sub(1);
+    }
+
+    /// jumps `steps` seek steps forward, or back for negative ones
+    pub fn seek_by(&mut self, steps: f64) {
+        let ticks = (steps * Self::SEEK_STEP / self.playback.dt()).round() as i64;
+        self.step_by(ticks);
+    }
+
+    /// moves `ticks` ticks forward or back, for going through it frame by frame
+    pub fn step_by(&mut self, ticks: i64) {
+        let tick = (self.playback.state.tick as i64 + ticks).max(0);
+        self.playback.seek(tick as u64);
+    }
+
+    /// what gets written on top of the replay
+    pub fn text(&self, strings: &Strings, bindings: &Bindings) -> Vec<Text> {
+        let dt = self.playback.dt();
+        let mut status = strings.format(
+            "replay.status",
+            &[
+                ("level", &self.playback.replay().level),
+                (
+                    "time",
+                    &format!("{:.2}", self.playback.state.tick as f64 * dt),
+                ),
+                (
+                    "length",
+                    &format!("{:.2}", self.playback.length() as f64 * dt),
+                ),
+                ("speed", &Self::SPEEDS[self.speed]),
+            ],
+        );
+        if self.paused {
+            status = format!("{}  {}", status, strings.get("replay.paused"));
+        } else if self.playback.finished() {
+            status = format!("{}  {}", status, strings.get("replay.finished"));
+        }
+        vec![
+            Text::new(status, (40.0, 40.0)),
+            Text::new(menu_hint("replay.hint", strings, bindings), (40.0, 900.0)).with_size(18.0),
+        ]
+    }
+}
@@ -0,0 +1,281 @@
+use std::collections::{BTreeMap, VecDeque};
+use std::ops::Range;
+
+use cgmath::prelude::*;
+use wgpu::util::DeviceExt;
+
+use crate::game_state::{GameState, Ribbon};
+use crate::instances::{CategoryColors, ROPE_COLOR};
+
+/// what leaves a ribbon behind, by the slot it goes by
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum Emitter {
+    Object(usize),
+    /// a hook thrown by this object while it's flying, and which of its hooks it is
+    Hook(usize, usize),
+    Projectile(usize),
+}
+
+/// what a ribbon looks like, the colour of some of them depends on the colour scheme
+#[derive(Clone, Copy, Debug)]
+enum Kind {
+    Object(Ribbon),
+    Hook,
+    Projectile { radius: f64 },
+}
+
+impl Kind {
+    fn ribbon(self, colors: &CategoryColors) -> Ribbon {
+        match self {
+            Kind::Object(ribbon) => ribbon,
+            Kind::Hook => Ribbon {
+                width: 0.25,
+                fade: 0.2,
+                color: [ROPE_COLOR[0], ROPE_COLOR[1], ROPE_COLOR[2], 0.6],
+            },
+            Kind::Projectile { radius } => Ribbon {
+                width: 2.0 * radius,
+                fade: 0.15,
+                color: [
+                    colors.projectile[0],
+                    colors.projectile[1],
+                    colors.projectile[2],
+                    0.5,
+                ],
+            },
+        }
+    }
+
+    /// how long a bit of the ribbon lasts, which doesn't depend on the colour scheme
+    fn fade(self) -> f64 {
+        self.ribbon(&CategoryColors::default()).fade
+    }
+}
+
+/// one ribbon, made of where its emitter was on each tick, newest first, with the game time it
+/// was there at
+struct Trace {
+    kind: Kind,
+    points: VecDeque<(cgmath::Point2<f64>, f64)>,
+}
+
+/// the ribbons left behind by objects that have one, the hook while it flies and projectiles
+///
+/// a ribbon whose emitter goes away stays behind until it's faded out
+#[derive(Default)]
+pub struct Ribbons {
+    live: BTreeMap<Emitter, Trace>,
+    fading: Vec<Trace>,
+}
+
+impl Ribbons {
+    /// moving further than this in one tick is a teleport or a slot that got reused, which
+    /// shouldn't get joined up across the level
+    const MAX_STEP: f64 = 10.0;
+
+    /// adds where every emitter got to on this tick and lets go of what's faded out
+    pub fn tick(&mut self, state: &GameState) {
+        let now = state.time;
+        let objects = state.iter_objects().filter_map(|object| {
+            let ribbon = object.get_ribbon()?;
+            Some((
+                Emitter::Object(object.id()),
+                Kind::Object(ribbon),
+                object.center(),
+            ))
+        });
+        let hooks = state
+            .flying_hooks()
+            .into_iter()
+            .map(|(object, which, pos)| (Emitter::Hook(object, which), Kind::Hook, pos));
+        let projectiles = state.projectiles().map(|(slot, projectile)| {
+            (
+                Emitter::Projectile(slot),
+                Kind::Projectile {
+                    radius: projectile.radius,
+                },
+                projectile.pos,
+            )
+        });
+        let emitters = objects.chain(hooks).chain(projectiles).collect::<Vec<_>>();
+        let mut live = BTreeMap::new();
+        for (emitter, kind, pos) in emitters {
+            let mut trace = match self.live.remove(&emitter) {
+                Some(trace)
+                    if trace
+                        .points
+                        .front()
+                        .is_none_or(|&(last, _)| last.distance(pos) <= Self::MAX_STEP) =>
+                {
+                    trace
+                }
+                old => {
+                    self.fading.extend(old);
+                    Trace {
+                        kind,
+                        points: VecDeque::new(),
+                    }
+                }
+            };
+            trace.kind = kind;
+            trace.points.push_front((pos, now));
+            live.insert(emitter, trace);
+        }
+        self.fading
+            .extend(std::mem::replace(&mut self.live, live).into_values());
+        for trace in self.live.values_mut().chain(&mut self.fading) {
+            let fade = trace.kind.fade();
+            while let Some(&(_, time)) = trace.points.back() {
+                if now - time < fade {
+                    break;
+                }
+                trace.points.pop_back();
+            }
+        }
+        self.fading.retain(|trace| trace.points.len() > 1);
+    }
+
+    /// the triangle strips to draw the ribbons with as of `time`, with the newest end of each
+    /// `interpolate` of the way from the tick before to the latest one like everything else
+    pub fn strips(
+        &self,
+        time: f64,
+        interpolate: f64,
+        colors: &CategoryColors,
+    ) -> Vec<Vec<RibbonVertex>> {
+        self.live
+            .values()
+            .chain(&self.fading)
+            .filter(|trace| trace.points.len() > 1)
+            .map(|trace| strip(trace, time, interpolate, colors))
+            .collect()
+    }
+}
+
+/// [x, y, r, g, b, a]
+pub type RibbonVertex = [f32; 6];
+
+/// two vertices for each point of the trace, across it on either side, getting narrower and more
+/// see-through towards the old end
+fn strip(trace: &Trace, time: f64, interpolate: f64, colors: &CategoryColors) -> Vec<RibbonVertex> {
+    let ribbon = trace.kind.ribbon(colors);
+    let mut points = trace.points.iter().copied().collect::<Vec<_>>();
+    let (head, next) = (points[0].0, points[1].0);
+    points[0].0 = next + (head - next) * interpolate;
+    let mut across = cgmath::vec2(0.0, 0.0);
+    let mut vertices = Vec::with_capacity(points.len() * 2);
+    for (i, &(pos, at)) in points.iter().enumerate() {
+        let before = points[i.saturating_sub(1)].0;
+        let after = points[(i + 1).min(points.len() - 1)].0;
+        let along = before - after;
+        // standing still leaves no direction to go across, so it keeps the last one
+        if along.magnitude2() > 1e-12 {
+            across = cgmath::vec2(-along.y, along.x).normalize();
+        }
+        let age = ((time - at) / ribbon.fade).clamp(0.0, 1.0);
+        let offset = across * ribbon.width * (1.0 - age) / 2.0;
+        let [r, g, b, a] = ribbon.color;
+        let alpha = a * (1.0 - age) as f32;
+        for side in [pos + offset, pos - offset] {
+            vertices.push([side.x as f32, side.y as f32, r, g, b, alpha]);
+        }
+    }
+    vertices
+}
+
+/// draws the ribbons in the world pass, behind everything else in the world
+pub struct RibbonRenderer {
+    pipeline: wgpu::RenderPipeline,
+}
+
+impl RibbonRenderer {
+    /// `pipeline_layout` and `shader` are the ones the world's quads are drawn with
+    pub fn new(
+        device: &wgpu::Device,
+        pipeline_layout: &wgpu::PipelineLayout,
+        shader: &wgpu::ShaderModule,
+        format: wgpu::TextureFormat,
+        sample_count: u32,
+    ) -> Self {
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("ribbon pipeline"),
+            layout: Some(pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: shader,
+                entry_point: "vs_ribbon",
+                buffers: &[wgpu::VertexBufferLayout {
+                    array_stride: std::mem::size_of::<RibbonVertex>() as u64,
+                    step_mode: wgpu::VertexStepMode::Vertex,
+                    attributes: &wgpu::vertex_attr_array![0 => Float32x2, 1 => Float32x4],
+                }],
+            },
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleStrip,
+                ..wgpu::PrimitiveState::default()
+            },
+            // see-through, so like translucent quads they only check the depth buffer
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: crate::render::DEPTH_FORMAT,
+                depth_write_enabled: false,
+                depth_compare: wgpu::CompareFunction::Less,
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState::default(),
+            }),
+            multisample: wgpu::MultisampleState {
+                count: sample_count,
+                mask: !0,
+                alpha_to_coverage_enabled: false,
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: shader,
+                entry_point: "fs_main",
+                targets: &[wgpu::ColorTargetState {
+                    format,
+                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                    write_mask: wgpu::ColorWrites::ALL,
+                }],
+            }),
+            multiview: None,
+        });
+        Self { pipeline }
+    }
+
+    /// puts all of `strips` in one buffer for a frame, along with where each one is in it, None
+    /// when there's nothing to draw
+    pub fn prepare(
+        &self,
+        device: &wgpu::Device,
+        strips: &[Vec<RibbonVertex>],
+    ) -> Option<(wgpu::Buffer, Vec<Range<u32>>)> {
+        if strips.is_empty() {
+            return None;
+        }
+        let mut ranges = Vec::with_capacity(strips.len());
+        let mut start = 0;
+        for strip in strips {
+            let end = start + strip.len() as u32;
+            ranges.push(start..end);
+            start = end;
+        }
+        let buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("ribbon buffer"),
+            contents: bytemuck::cast_slice(&strips.concat()),
+            usage: wgpu::BufferUsages::VERTEX,
+        });
+        Some((buffer, ranges))
+    }
+
+    /// expects the world's camera and palette to be bound already, and leaves its own vertex
+    /// buffer in the first slot
+    pub fn draw<'a>(
+        &'a self,
+        rpass: &mut wgpu::RenderPass<'a>,
+        (buffer, ranges): &'a (wgpu::Buffer, Vec<Range<u32>>),
+    ) {
+        rpass.set_pipeline(&self.pipeline);
+        rpass.set_vertex_buffer(0, buffer.slice(..));
+        for range in ranges {
+            rpass.draw(range.clone(), 0..1);
+        }
+    }
+}
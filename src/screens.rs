@@ -0,0 +1,554 @@
+use std::path::{Path, PathBuf};
+
+use crate::bindings::{Action, Bindings};
+use crate::console::Console;
+use crate::game_state::GameState;
+use crate::gamepad::{Axis, MenuEntry, Stick};
+use crate::loading::Loading;
+use crate::locale::Strings;
+use crate::paths;
+use crate::profiles::{self, Summary};
+use crate::replay::Replay;
+use crate::replay_viewer::ReplayViewer;
+use crate::session::{Completion, Session};
+use crate::settings::Settings;
+use crate::stats::{LevelStats, Stats};
+use crate::text::Text;
+
+pub const LEVEL_DIRECTORY: &str = "levels";
+
+pub enum Screen {
+    LevelSelect {
+        levels: Vec<PathBuf>,
+        selected: usize,
+    },
+    /// choosing which save slot to play in, `current` is the one being played
+    Profiles {
+        slots: Vec<Summary>,
+        selected: usize,
+        current: usize,
+    },
+    /// a level on its way, shown until it's ready to play
+    Loading(Loading),
+    Playing(Session),
+    LevelComplete {
+        session: Session,
+        completion: Completion,
+    },
+    Watching(ReplayViewer),
+    /// tuning the sticks, with the preview of them showing underneath
+    GamepadSettings {
+        selected: usize,
+    },
+}
+
+impl Screen {
+    pub fn level_select() -> Self {
+        Screen::LevelSelect {
+            levels: find_levels(LEVEL_DIRECTORY),
+            selected: 0,
+        }
+    }
+
+    /// the save slots, starting on the one being played
+    pub fn profiles(current: usize) -> Self {
+        Screen::Profiles {
+            slots: profiles::summaries(),
+            selected: current,
+            current,
+        }
+    }
+
+    /// the session being played or just finished, if there is one
+    pub fn session(&self) -> Option<&Session> {
+        match self {
+            Screen::LevelSelect { .. }
+            | Screen::Profiles { .. }
+            | Screen::Loading(_)
+            | Screen::Watching(_)
+            | Screen::GamepadSettings { .. } => None,
+            Screen::Playing(session) | Screen::LevelComplete { session, .. } => Some(session),
+        }
+    }
+
+    /// what's on screen and the state one update before it, for interpolating between them
+    pub fn world(&self) -> Option<(&GameState, &GameState)> {
+        match self {
+            Screen::Watching(viewer) => Some((&viewer.playback.state, &viewer.playback.last_state)),
+            _ => self
+                .session()
+                .map(|session| (&session.state, &session.last_state)),
+        }
+    }
+
+    /// what gets written on top of the frame
+    pub fn text(&self, stats: &Stats, strings: &Strings, settings: &Settings) -> Vec<Text> {
+        let bindings = &settings.bindings;
+        match self {
+            Screen::LevelSelect { levels, selected } => {
+                let mut text = vec![
+                    Text::new(strings.get("level_select.title"), (40.0, 40.0)).with_size(40.0)
+                ];
+                if levels.is_empty() {
+                    text.push(Text::new(
+                        strings.format("level_select.empty", &[("directory", &LEVEL_DIRECTORY)]),
+                        (40.0, 110.0),
+                    ));
+                }
+                for (index, path) in levels.iter().enumerate() {
+                    let name = crate::level::Level::name_from_path(path);
+                    let marker = if index == *selected { ">" } else { " " };
+                    let color = if index == *selected {
+                        [1.0, 0.9, 0.3, 1.0]
+                    } else {
+                        [1.0, 1.0, 1.0, 1.0]
+                    };
+                    let y = 110.0 + index as f32 * 60.0;
+                    text.push(
+                        Text::new(format!("{} {}", marker, name), (40.0, y)).with_color(color),
+                    );
+                    text.push(
+                        Text::new(describe(&stats.level(&name), strings), (70.0, y + 28.0))
+                            .with_size(18.0)
+                            .with_color([0.7, 0.7, 0.7, 1.0]),
+                    );
+                }
+                text.push(
+                    Text::new(
+                        menu_hint("level_select.hint", strings, bindings),
+                        (40.0, 900.0),
+                    )
+                    .with_size(18.0),
+                );
+                text
+            }
+            Screen::Profiles {
+                slots,
+                selected,
+                current,
+            } => {
+                let mut text =
+                    vec![Text::new(strings.get("profiles.title"), (40.0, 40.0)).with_size(40.0)];
+                for (index, slot) in slots.iter().enumerate() {
+                    let marker = if index == *selected { ">" } else { " " };
+                    let color = if index == *selected {
+                        [1.0, 0.9, 0.3, 1.0]
+                    } else {
+                        [1.0, 1.0, 1.0, 1.0]
+                    };
+                    let key = if slot.slot == *current {
+                        "profiles.slot_current"
+                    } else {
+                        "profiles.slot"
+                    };
+                    let name = strings.format(key, &[("number", &(slot.slot + 1))]);
+                    let y = 110.0 + index as f32 * 60.0;
+                    text.push(
+                        Text::new(format!("{} {}", marker, name), (40.0, y)).with_color(color),
+                    );
+                    text.push(
+                        Text::new(describe_slot(slot, strings), (70.0, y + 28.0))
+                            .with_size(18.0)
+                            .with_color([0.7, 0.7, 0.7, 1.0]),
+                    );
+                }
+                text.push(
+                    Text::new(menu_hint("profiles.hint", strings, bindings), (40.0, 900.0))
+                        .with_size(18.0),
+                );
+                text
+            }
+            Screen::Loading(loading) => {
+                const BAR_LENGTH: usize = 30;
+                let done = (loading.stage.progress() * BAR_LENGTH as f32).round() as usize;
+                vec![
+                    Text::new(
+                        strings.format("loading.title", &[("level", &loading.level_name)]),
+                        (40.0, 40.0),
+                    )
+                    .with_size(40.0),
+                    Text::new(
+                        format!("[{}{}]", "#".repeat(done), "-".repeat(BAR_LENGTH - done)),
+                        (40.0, 110.0),
+                    ),
+                    Text::new(strings.get(loading.stage.key()), (40.0, 145.0))
+                        .with_size(18.0)
+                        .with_color([0.7, 0.7, 0.7, 1.0]),
+                    Text::new(menu_hint("loading.hint", strings, bindings), (40.0, 900.0))
+                        .with_size(18.0),
+                ]
+            }
+            Screen::Playing(session) if session.in_cutscene() => {
+                vec![Text::new(strings.get("intro.skip"), (40.0, 900.0)).with_size(18.0)]
+            }
+            Screen::Playing(session) => {
+                let mut text = hook_hud(&session.state, strings);
+                text.extend(tutorial_prompt(&session.state, strings, bindings));
+                if session.practice.is_some() {
+                    text.push(practice_hint(strings, bindings));
+                }
+                text
+            }
+            Screen::LevelComplete {
+                session,
+                completion,
+            } => {
+                let mut text = vec![
+                    Text::new(strings.get("complete.title"), (40.0, 40.0)).with_size(40.0),
+                    Text::new(
+                        strings.format(
+                            "complete.time",
+                            &[("time", &format!("{:.2}", completion.time))],
+                        ),
+                        (40.0, 110.0),
+                    ),
+                    Text::new(
+                        strings.format(
+                            "complete.hooks",
+                            &[
+                                ("fired", &completion.run.hooks_fired),
+                                ("hit", &completion.run.hooks_attached),
+                            ],
+                        ),
+                        (40.0, 145.0),
+                    ),
+                    Text::new(
+                        strings.format(
+                            "complete.speed",
+                            &[("speed", &format!("{:.1}", completion.run.max_swing_speed))],
+                        ),
+                        (40.0, 180.0),
+                    ),
+                ];
+                if completion.new_best {
+                    text.push(
+                        Text::new(strings.get("complete.new_best"), (40.0, 215.0))
+                            .with_color([1.0, 0.9, 0.3, 1.0]),
+                    );
+                }
+                text.push(
+                    Text::new(
+                        describe(&stats.level(&session.level_name), strings),
+                        (40.0, 270.0),
+                    )
+                    .with_size(18.0)
+                    .with_color([0.7, 0.7, 0.7, 1.0]),
+                );
+                text.push(
+                    Text::new(menu_hint("complete.hint", strings, bindings), (40.0, 900.0))
+                        .with_size(18.0),
+                );
+                text
+            }
+            Screen::Watching(viewer) => viewer.text(strings, bindings),
+            Screen::GamepadSettings { selected } => {
+                let mut text = vec![
+                    Text::new(strings.get("gamepad_settings.title"), (40.0, 40.0)).with_size(40.0),
+                ];
+                for (index, &entry) in MenuEntry::ALL.iter().enumerate() {
+                    let marker = if index == *selected { ">" } else { " " };
+                    let color = if index == *selected {
+                        [1.0, 0.9, 0.3, 1.0]
+                    } else {
+                        [1.0, 1.0, 1.0, 1.0]
+                    };
+                    let line = describe_entry(entry, settings, strings);
+                    text.push(
+                        Text::new(
+                            format!("{} {}", marker, line),
+                            (40.0, 110.0 + index as f32 * 50.0),
+                        )
+                        .with_color(color),
+                    );
+                }
+                text.push(
+                    Text::new(
+                        menu_hint("gamepad_settings.hint", strings, bindings),
+                        (40.0, 900.0),
+                    )
+                    .with_size(18.0),
+                );
+                text
+            }
+        }
+    }
+
+    /// what's focused, said out loud for screen readers whenever it changes
+    pub fn focus(&self, stats: &Stats, strings: &Strings, settings: &Settings) -> Option<String> {
+        match self {
+            Screen::LevelSelect { levels, selected } => {
+                let path = match levels.get(*selected) {
+                    Some(path) => path,
+                    None => {
+                        return Some(
+                            strings.format("focus.no_levels", &[("directory", &LEVEL_DIRECTORY)]),
+                        )
+                    }
+                };
+                let name = crate::level::Level::name_from_path(path);
+                let number = selected + 1;
+                let count = levels.len();
+                Some(match stats.level(&name).best_time {
+                    Some(time) => strings.format(
+                        "focus.level_best",
+                        &[
+                            ("number", &number),
+                            ("count", &count),
+                            ("name", &name),
+                            ("time", &format!("{:.2}", time)),
+                        ],
+                    ),
+                    None => strings.format(
+                        "focus.level",
+                        &[("number", &number), ("count", &count), ("name", &name)],
+                    ),
+                })
+            }
+            Screen::Profiles {
+                slots, selected, ..
+            } => {
+                let slot = slots.get(*selected)?;
+                Some(strings.format(
+                    "focus.profile",
+                    &[
+                        ("number", &(slot.slot + 1)),
+                        ("summary", &describe_slot(slot, strings)),
+                    ],
+                ))
+            }
+            Screen::Loading(loading) => {
+                Some(strings.format("focus.loading", &[("level", &loading.level_name)]))
+            }
+            Screen::Playing(session) if session.in_cutscene() => {
+                Some(strings.format("focus.intro", &[("level", &session.level_name)]))
+            }
+            Screen::Playing(session) => {
+                Some(strings.format("focus.playing", &[("level", &session.level_name)]))
+            }
+            Screen::LevelComplete { completion, .. } => {
+                let key = if completion.new_best {
+                    "focus.complete_best"
+                } else {
+                    "focus.complete"
+                };
+                Some(strings.format(key, &[("time", &format!("{:.2}", completion.time))]))
+            }
+            Screen::Watching(viewer) => {
+                let key = if viewer.paused {
+                    "focus.watching_paused"
+                } else {
+                    "focus.watching"
+                };
+                Some(strings.format(key, &[("level", &viewer.playback.replay().level)]))
+            }
+            Screen::GamepadSettings { selected } => {
+                let entry = *MenuEntry::ALL.get(*selected)?;
+                Some(describe_entry(entry, settings, strings))
+            }
+        }
+    }
+}
+
+/// the console commands for switching screens
+pub fn register_commands(console: &mut Console) {
+    console.register("load", "load <level file>", |args, context| {
+        let path = match args {
+            [path] => Path::new(path),
+            _ => return Err("usage: load <level file>".to_owned()),
+        };
+        // levels can be given by name as well, the way they show up in the level select
+        let path = if path.exists() {
+            path.to_owned()
+        } else {
+            level_path(&path.to_string_lossy())
+        };
+        if !path.exists() {
+            return Err(format!("no level at {}", path.display()));
+        }
+        let shared_ghost = context.shared_ghost.map(Path::to_owned);
+        let message = format!("loading {}", path.display());
+        *context.screen = Screen::Loading(Loading::start(path, shared_ghost, false, None));
+        Ok(message)
+    });
+    console.register("watch", "watch <replay file>", |args, context| {
+        let path = match args {
+            [path] => Path::new(path),
+            _ => return Err("usage: watch <replay file>".to_owned()),
+        };
+        // or the name of a level, for the best run through it
+        let path = if path.exists() {
+            path.to_owned()
+        } else {
+            paths::ghost(&path.to_string_lossy())
+        };
+        let viewer =
+            ReplayViewer::load(&path, context.tick_rate).map_err(|e| format!("{:#}", e))?;
+        *context.screen = Screen::Watching(viewer);
+        Ok(format!("watching {}", path.display()))
+    });
+}
+
+/// the best run through the level at `path`, ready to watch
+pub fn watch_best(path: &Path, tick_rate: f64) -> color_eyre::Result<ReplayViewer> {
+    let level_name = crate::level::Level::name_from_path(path);
+    ReplayViewer::new(Replay::load(paths::ghost(&level_name))?, tick_rate)
+}
+
+/// the file of the level called `name`, the way it shows up in the level select
+pub fn level_path(name: &str) -> PathBuf {
+    Path::new(LEVEL_DIRECTORY).join(name).with_extension("ron")
+}
+
+/// all level files in `directory`, sorted by name
+pub fn find_levels(directory: impl AsRef<Path>) -> Vec<PathBuf> {
+    let mut levels = std::fs::read_dir(directory)
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok().map(|e| e.path()))
+                .filter(|path| path.extension().is_some_and(|e| e == "ron"))
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+    levels.sort();
+    levels
+}
+
+/// a line of the gamepad settings, with what it's set to
+fn describe_entry(entry: MenuEntry, settings: &Settings, strings: &Strings) -> String {
+    let stick = |stick| match stick {
+        Stick::Movement => strings.get("gamepad_settings.movement"),
+        Stick::Aim => strings.get("gamepad_settings.aim"),
+    };
+    let (key, stick) = match entry {
+        MenuEntry::Deadzone(s, Axis::X) => ("gamepad_settings.deadzone_x", stick(s)),
+        MenuEntry::Deadzone(s, Axis::Y) => ("gamepad_settings.deadzone_y", stick(s)),
+        MenuEntry::Curve(s) => ("gamepad_settings.curve", stick(s)),
+        MenuEntry::Sensitivity(s) => ("gamepad_settings.sensitivity", stick(s)),
+        MenuEntry::Rumble => ("gamepad_settings.rumble", ""),
+    };
+    strings.format(
+        key,
+        &[("stick", &stick), ("value", &settings.gamepad.value(entry))],
+    )
+}
+
+fn describe_slot(slot: &Summary, strings: &Strings) -> String {
+    match slot.empty {
+        true => strings.get("profiles.empty").to_owned(),
+        false => strings.format(
+            "profiles.summary",
+            &[("completed", &slot.completed), ("unlocked", &slot.unlocked)],
+        ),
+    }
+}
+
+fn describe(stats: &LevelStats, strings: &Strings) -> String {
+    let best = match stats.best_time {
+        Some(time) => strings.format("stats.best_time", &[("time", &format!("{:.2}", time))]),
+        None => strings.get("stats.no_time").to_owned(),
+    };
+    strings.format(
+        "stats.summary",
+        &[
+            ("best", &best),
+            ("completions", &stats.completions),
+            ("deaths", &stats.deaths),
+            ("hooks", &stats.hooks_fired),
+            ("speed", &format!("{:.1}", stats.max_swing_speed)),
+        ],
+    )
+}
+
+/// what the level's showing tutorial asks of the player, with the buttons that do it
+fn tutorial_prompt(state: &GameState, strings: &Strings, bindings: &Bindings) -> Option<Text> {
+    let tutorial = &state.tutorials[state.tutorial?];
+    let keys = bindings
+        .labels(Action::for_lesson(tutorial.lesson))
+        .join(" / ");
+    Some(
+        Text::new(
+            strings.format(&tutorial.text, &[("keys", &keys)]),
+            (40.0, 80.0),
+        )
+        .with_size(28.0),
+    )
+}
+
+/// the hint at `key` with the buttons for getting around menus filled in
+pub fn menu_hint(key: &str, strings: &Strings, bindings: &Bindings) -> String {
+    let keys = |action| bindings.labels(&[action]).join(" / ");
+    strings.format(
+        key,
+        &[
+            ("up", &keys(Action::MenuUp)),
+            ("down", &keys(Action::MenuDown)),
+            ("confirm", &keys(Action::Confirm)),
+            ("back", &keys(Action::Back)),
+            ("left", &keys(Action::MenuLeft)),
+            ("right", &keys(Action::MenuRight)),
+            ("watch", &keys(Action::WatchBest)),
+            ("coop", &keys(Action::ToggleCoop)),
+            ("slots", &keys(Action::SaveSlots)),
+            ("gamepad", &keys(Action::GamepadSettings)),
+            ("pause", &keys(Action::PlayPause)),
+            ("step_back", &keys(Action::StepBack)),
+            ("step_forward", &keys(Action::StepForward)),
+            ("free_camera", &keys(Action::ToggleFreeCamera)),
+        ],
+    )
+}
+
+/// the buttons for saving and going back, while practicing
+fn practice_hint(strings: &Strings, bindings: &Bindings) -> Text {
+    let keys = |action| bindings.labels(&[action]).join(" / ");
+    Text::new(
+        strings.format(
+            "hud.practice",
+            &[
+                ("save", &keys(Action::SaveState)),
+                ("load", &keys(Action::LoadState)),
+            ],
+        ),
+        (40.0, 900.0),
+    )
+    .with_size(18.0)
+    .with_color([0.7, 0.7, 0.7, 1.0])
+}
+
+/// what's left of the player's hook, for levels that limit it, one line in the corner with
+/// nothing in it for the limits the level doesn't have
+fn hook_hud(state: &GameState, strings: &Strings) -> Vec<Text> {
+    const BAR_LENGTH: usize = 10;
+    let (limits, charge) = match state.hook_charge(state.view_object) {
+        Some(charge) => (state.player_hook_limits(), charge),
+        None => return vec![],
+    };
+    let mut parts = vec![];
+    if limits.cooldown.is_some() {
+        parts.push(match charge.cooldown > 0.0 {
+            true => strings.format(
+                "hud.hook_cooldown",
+                &[("time", &format!("{:.1}", charge.cooldown))],
+            ),
+            false => strings.get("inputs.ready").to_owned(),
+        });
+    }
+    if let (Some(uses), Some(max)) = (charge.uses, limits.uses) {
+        parts.push(strings.format("hud.hook_uses", &[("uses", &uses), ("max", &max)]));
+    }
+    if let (Some(stamina), Some(max)) = (charge.stamina, limits.stamina) {
+        let full = ((stamina / max).clamp(0.0, 1.0) * BAR_LENGTH as f64).round() as usize;
+        let bar = format!("[{}{}]", "#".repeat(full), "-".repeat(BAR_LENGTH - full));
+        parts.push(strings.format("hud.hook_stamina", &[("bar", &bar)]));
+    }
+    if parts.is_empty() {
+        return vec![];
+    }
+    let color = match charge.ready() {
+        true => [1.0, 1.0, 1.0, 1.0],
+        false => [1.0, 0.5, 0.4, 1.0],
+    };
+    vec![Text::new(parts.join("  "), (40.0, 40.0))
+        .with_size(20.0)
+        .with_color(color)]
+}
@@ -0,0 +1,136 @@
+use crate::console::{self, Console};
+use crate::game_state::{GameState, Object};
+use crate::text::Text;
+use crate::tween::Easing;
+
+/// the object picked by right clicking on it, drawn outlined with its fields shown on screen
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Selection {
+    picked: Option<usize>,
+    /// when the picked object was spawned, so a different object ending up in its slot isn't picked
+    spawned_at: u64,
+}
+
+impl Selection {
+    /// picks the object on top at `point`, or clears the selection if there's nothing there
+    pub fn pick(&mut self, state: &GameState, point: cgmath::Point2<f64>) {
+        self.picked = state.object_at(point);
+        if let Some(object) = self.picked.and_then(|index| state.object(index)) {
+            self.spawned_at = object.spawned_at();
+        }
+    }
+
+    /// the index of the picked object, if it's still around
+    pub fn get(&self, state: &GameState) -> Option<usize> {
+        let index = self.picked?;
+        let object = state.object(index)?;
+        (object.spawned_at() == self.spawned_at).then_some(index)
+    }
+
+    /// the fields of the picked object, in the bottom left corner
+    pub fn text(&self, state: &GameState) -> Vec<Text> {
+        let index = match self.get(state) {
+            Some(index) => index,
+            None => return vec![],
+        };
+        let object = match state.object(index) {
+            Some(object) => object,
+            None => return vec![],
+        };
+        let (pos, size, velocity) = (object.get_pos(), object.get_size(), object.get_velocity());
+        [
+            format!("object {}", index),
+            format!("pos      {:.3} {:.3}", pos.x, pos.y),
+            format!("size     {:.3} {:.3}", size.x, size.y),
+            format!("vel      {:.3} {:.3}", velocity.x, velocity.y),
+            format!("friction {:.3}", object.get_surface_friction()),
+            match object.get_max_speed() {
+                Some(max_speed) => format!("maxspeed {:.3}", max_speed),
+                None => "maxspeed level's".to_owned(),
+            },
+            "change with: edit <pos|size|vel|friction|maxspeed> <values>".to_owned(),
+        ]
+        .into_iter()
+        .enumerate()
+        .map(|(i, line)| {
+            Text::new(line, (20.0, 800.0 + i as f32 * 22.0))
+                .with_size(18.0)
+                .with_color([1.0, 0.9, 0.3, 1.0])
+        })
+        .collect()
+    }
+}
+
+/// the console commands for changing the picked object
+pub fn register_commands(console: &mut Console) {
+    const EDIT: &str = "edit <pos|size|vel|friction|maxspeed> <values>";
+    console.register("edit", EDIT, |args, context| {
+        let selection = *context.selection;
+        let session = context.session()?;
+        let index = selection
+            .get(&session.state)
+            .ok_or("nothing is picked, right click on something first")?;
+        let vector = || -> Result<cgmath::Vector2<f64>, String> {
+            Ok(cgmath::vec2(
+                console::number(args, 1, EDIT)?,
+                console::number(args, 2, EDIT)?,
+            ))
+        };
+        let edit: Box<dyn FnOnce(&mut Object)> = match args.first() {
+            Some(&"pos") => {
+                let pos = vector()?;
+                Box::new(move |object| object.set_pos(cgmath::point2(pos.x, pos.y)))
+            }
+            Some(&"size") => {
+                let size = vector()?;
+                if size.x <= 0.0 || size.y <= 0.0 {
+                    return Err("sizes have to be above 0".to_owned());
+                }
+                Box::new(move |object| object.set_size(size))
+            }
+            Some(&"vel") => {
+                let velocity = vector()?;
+                Box::new(move |object| object.set_velocity(velocity))
+            }
+            Some(&"friction") => {
+                let friction = console::number(args, 1, EDIT)?;
+                if friction < 0.0 {
+                    return Err("friction can't go below 0".to_owned());
+                }
+                Box::new(move |object| object.set_surface_friction(friction))
+            }
+            Some(&"maxspeed") => {
+                let max_speed = console::number(args, 1, EDIT)?;
+                // anything at or below 0 takes the limit off again
+                let max_speed = Some(max_speed).filter(|max_speed| *max_speed > 0.0);
+                Box::new(move |object| object.set_max_speed(max_speed))
+            }
+            _ => return Err(format!("usage: {}", EDIT)),
+        };
+        // `get` already checked it's still around
+        if let Some(object) = session.state.object_mut(index) {
+            edit(object);
+        }
+        session.cheated = true;
+        Ok(format!("changed object {}", index))
+    });
+    const MOVE: &str = "move <x> <y> <seconds>";
+    console.register("move", MOVE, |args, context| {
+        let selection = *context.selection;
+        let to = cgmath::point2(
+            console::number(args, 0, MOVE)?,
+            console::number(args, 1, MOVE)?,
+        );
+        let duration = console::number(args, 2, MOVE)?.max(0.0);
+        let session = context.session()?;
+        let index = selection
+            .get(&session.state)
+            .ok_or("nothing is picked, right click on something first")?;
+        session
+            .state
+            .move_object(index, to, duration, Easing::QuadInOut)
+            .ok_or("the picked object is gone")?;
+        session.cheated = true;
+        Ok(format!("moving object {} to {}, {}", index, to.x, to.y))
+    });
+}
@@ -0,0 +1,563 @@
+use std::path::PathBuf;
+
+use crate::animation::Animator;
+use crate::bindings::{Action, ActionEvent, Player};
+use crate::body::Body;
+use crate::console::{self, Console};
+use crate::game_state::{
+    AssistMode, ButtonState, Buttons, Direction, Event, Explosive, GameEvent, GameState, Object,
+    Projectile,
+};
+use crate::heatmap::Heatmap;
+use crate::level::Level;
+use crate::paths;
+use crate::practice::Practice;
+use crate::replay::{Coop, Recorder, Replay};
+use crate::ribbons::Ribbons;
+use crate::sparks::Sparks;
+use crate::stats::RunTracker;
+use crate::telemetry::Telemetry;
+use crate::trail::Trail;
+use crate::tween::{Easing, Tween};
+use crate::world_state::LevelMemory;
+
+/// a level being played, along with everything that gets reset when it restarts
+pub struct Session {
+    pub level: Level,
+    pub level_name: String,
+    pub state: GameState,
+    pub last_state: GameState,
+    pub recorder: Recorder,
+    pub ghost: Option<Replay>,
+    /// a ghost file someone shared, raced against instead of your own best
+    shared_ghost: Option<PathBuf>,
+    pub run: RunTracker,
+    /// the console got used to change the world, so the run can't be replayed and doesn't count
+    pub cheated: bool,
+    /// what the player's sprite is doing
+    pub animator: Animator,
+    /// the player's arms and legs
+    pub body: Body,
+    /// the afterimages behind the player while they're going fast
+    pub trail: Trail,
+    /// what's been left behind by the hook, projectiles and anything else that leaves a ribbon
+    pub ribbons: Ribbons,
+    /// the bits of ropes that just snapped
+    pub sparks: Sparks,
+    /// the movement keys each player is holding, so a key the OS repeats while it's held doesn't
+    /// get handed to the game and written into the replay over and over
+    keys: Buttons<Direction>,
+    partner_keys: Buttons<Direction>,
+    /// how far into the level's intro the camera is, None once it's over or got skipped, the game
+    /// doesn't run until then
+    cutscene_time: Option<f64>,
+    /// the object the second player controls in co-op, tied to the first with a rope
+    pub partner: Option<usize>,
+    /// the physics of every tick being written out, started from the console
+    pub telemetry: Option<Telemetry>,
+    /// where the player has been touching things, turned on from the console
+    pub heatmap: Option<Heatmap>,
+    /// the save for going back over part of the level, turned on from the console
+    pub practice: Option<Practice>,
+    /// what the player has done to the level that it keeps between visits
+    pub memory: LevelMemory,
+    /// the entrance the players came in through, from another level's exit
+    pub entrance: Option<String>,
+    /// the run started out with the level changed from its file, so a replay of it wouldn't play
+    /// back the same
+    altered: bool,
+    /// the help the players are getting, from the settings
+    assist_mode: AssistMode,
+}
+
+/// how a run ended
+#[derive(Clone, Debug)]
+pub enum RunEnd {
+    Completed(Completion),
+    /// the player got crushed, fell out of the level or ran out of health
+    Died,
+}
+
+/// how a run that reached the goal went
+#[derive(Clone, Debug)]
+pub struct Completion {
+    pub time: f64,
+    pub run: RunTracker,
+    pub new_best: bool,
+}
+
+impl Session {
+    /// the camera starts out a bit zoomed out when a level starts and eases in
+    const INTRO_ZOOM: Tween<f64> = Tween {
+        from: 0.6,
+        to: 1.0,
+        start: 0.0,
+        duration: 1.5,
+        easing: Easing::QuadOut,
+    };
+    /// how far apart the players can get in co-op
+    const COOP_ROPE_LENGTH: f64 = 6.0;
+    /// while the level fades in from black
+    const INTRO_FADE: Tween<[f32; 3]> = Tween {
+        from: [0.0; 3],
+        to: [1.0; 3],
+        start: 0.0,
+        duration: 0.8,
+        easing: Easing::QuadIn,
+    };
+
+    /// a fresh start of `level`, without a ghost to race until `load_ghost`
+    pub fn new(level: Level, level_name: String, shared_ghost: Option<PathBuf>) -> Self {
+        let state = GameState::from_level(&level);
+        Self {
+            last_state: state.clone(),
+            state,
+            recorder: Recorder::new(level_name.clone()),
+            ghost: None,
+            shared_ghost,
+            run: RunTracker::default(),
+            cheated: false,
+            animator: Animator::default(),
+            body: Body::default(),
+            trail: Trail::default(),
+            sparks: Sparks::default(),
+            ribbons: Ribbons::default(),
+            keys: Buttons::default(),
+            partner_keys: Buttons::default(),
+            cutscene_time: level.intro.as_ref().map(|_| 0.0),
+            partner: None,
+            telemetry: None,
+            heatmap: None,
+            practice: None,
+            memory: LevelMemory::default(),
+            entrance: None,
+            altered: false,
+            assist_mode: AssistMode::default(),
+            level,
+            level_name,
+        }
+    }
+
+    /// picks the ghost to race, the shared one if it's for this level or else the best run
+    pub fn load_ghost(&mut self) {
+        let shared = self
+            .shared_ghost
+            .as_ref()
+            .and_then(|path| Replay::load(path).ok())
+            .filter(|ghost| ghost.level == self.level_name);
+        self.ghost = shared.or_else(|| Replay::load(paths::ghost(&self.level_name)).ok());
+    }
+
+    /// races the ghost at `path` from a fresh start, instead of the one picked when the level
+    /// loaded
+    pub fn race(&mut self, path: PathBuf) {
+        self.shared_ghost = Some(path);
+        self.restart();
+    }
+
+    /// puts the level back the way the player left it last time, on top of a fresh start
+    pub fn restore(&mut self, memory: LevelMemory) {
+        self.memory = memory;
+        self.set_up_start();
+    }
+
+    /// has the players come in through the entrance called `entrance`, or where the level
+    /// starts for None
+    pub fn enter(&mut self, entrance: Option<String>) {
+        self.entrance = entrance;
+        self.set_up_start();
+    }
+
+    /// changes a fresh start of the level into the one this run gets, with what's remembered
+    /// about it and the players at their entrance
+    fn set_up_start(&mut self) {
+        self.memory.apply(&mut self.state);
+        let entered = match &self.entrance {
+            Some(name) if self.state.spawn_player_at(name) => true,
+            Some(name) => {
+                tracing::warn!("{} has no entrance called {}", self.level_name, name);
+                false
+            }
+            None => false,
+        };
+        self.altered = !self.memory.is_empty() || entered;
+        self.last_state = self.state.clone();
+    }
+
+    /// changes the help the players get, a run it changed partway through wouldn't play back the
+    /// same so it doesn't count
+    pub fn set_assist_mode(&mut self, assist_mode: AssistMode) {
+        let changes_game = |mode: AssistMode| (mode.infinite_hooks, mode.no_death);
+        if self.state.tick > 0 && changes_game(assist_mode) != changes_game(self.assist_mode) {
+            self.altered = true;
+        }
+        self.assist_mode = assist_mode;
+        self.state.set_assist_mode(assist_mode);
+        self.last_state.set_assist_mode(assist_mode);
+        self.recorder.set_assist_mode(assist_mode);
+    }
+
+    pub fn restart(&mut self) {
+        self.state = GameState::from_level(&self.level);
+        self.state.set_assist_mode(self.assist_mode);
+        self.set_up_start();
+        self.recorder = Recorder::new(self.level_name.clone());
+        self.recorder.set_assist_mode(self.assist_mode);
+        self.run = RunTracker::default();
+        self.cheated = self.practice.is_some();
+        self.animator = Animator::default();
+        self.body = Body::default();
+        self.trail = Trail::default();
+        self.sparks = Sparks::default();
+        self.ribbons = Ribbons::default();
+        // keys still held down get pressed again by the next repeat, for the new state to hear
+        self.keys.clear();
+        self.partner_keys.clear();
+        self.load_ghost();
+        if self.partner.is_some() {
+            self.start_coop();
+        }
+    }
+
+    /// adds a second player tied to the first, runs with two players don't count for ghosts
+    pub fn start_coop(&mut self) {
+        self.partner = self.state.start_coop(Self::COOP_ROPE_LENGTH);
+        if let Some(partner) = self.partner {
+            self.recorder.set_coop(Coop {
+                partner,
+                rope_length: Self::COOP_ROPE_LENGTH,
+            });
+        }
+        self.last_state = self.state.clone();
+    }
+
+    /// whether the level's intro is still playing
+    pub fn in_cutscene(&self) -> bool {
+        self.cutscene_time.is_some()
+    }
+
+    /// moves the intro along, ending it once the camera is at the end of the path
+    pub fn advance_cutscene(&mut self, dt: f64) {
+        if let (Some(time), Some(intro)) = (&mut self.cutscene_time, &self.level.intro) {
+            *time += dt;
+            if *time >= intro.length() {
+                self.cutscene_time = None;
+            }
+        }
+    }
+
+    /// ends the intro early, handing the camera back to the player
+    pub fn skip_cutscene(&mut self) {
+        self.cutscene_time = None;
+    }
+
+    /// where the intro has the camera, `extra` seconds after the last time it was moved along
+    pub fn cutscene_camera(&self, extra: f64) -> Option<(cgmath::Point2<f64>, f64)> {
+        self.level
+            .intro
+            .as_ref()?
+            .camera_at(self.cutscene_time? + extra)
+    }
+
+    pub fn submit(&mut self, event: Event) {
+        if let Event::Keyboard { button, state } = event {
+            if !self.keys.set(button, state) {
+                return;
+            }
+        }
+        // in co-op the second player has their own keys, so this only goes to the first
+        self.recorder.submit(&mut self.state, event);
+    }
+
+    /// hands input to the second player in co-op
+    pub fn submit_partner(&mut self, event: Event) {
+        if let Event::Keyboard { button, state } = event {
+            if !self.partner_keys.set(button, state) {
+                return;
+            }
+        }
+        self.recorder.submit_partner(&mut self.state, event);
+    }
+
+    /// hands a movement action to whichever player it's for, and saves or goes back in practice,
+    /// the rest aren't the game's to handle
+    pub fn act(&mut self, player: Player, action: ActionEvent) {
+        let event = match action {
+            ActionEvent::Button(Action::SaveState, ButtonState::Pressed) => {
+                if let Some(practice) = &mut self.practice {
+                    practice.save(&self.state);
+                }
+                return;
+            }
+            ActionEvent::Button(Action::LoadState, ButtonState::Pressed) => {
+                self.load_state();
+                return;
+            }
+            ActionEvent::Button(action, state) => match action.direction() {
+                Some(button) => Event::Keyboard { button, state },
+                None => return,
+            },
+            ActionEvent::Move(push) => Event::Movement { push },
+        };
+        match player {
+            Player::First => self.submit(event),
+            Player::Partner => self.submit_partner(event),
+        }
+    }
+
+    /// goes back to the practice save, if there is one, with whatever keys are held now instead
+    /// of the ones that were held when it got saved
+    fn load_state(&mut self) {
+        let saved = match self.practice.as_ref().and_then(Practice::saved) {
+            Some(saved) => saved.clone(),
+            None => return,
+        };
+        self.state = saved;
+        self.state.set_assist_mode(self.assist_mode);
+        self.last_state = self.state.clone();
+        let player = self.state.view_object;
+        let players = std::iter::once((player, &self.keys))
+            .chain(self.partner.map(|partner| (partner, &self.partner_keys)));
+        for (object, keys) in players {
+            for button in [
+                Direction::Left,
+                Direction::Up,
+                Direction::Right,
+                Direction::Down,
+            ] {
+                let state = match keys.held(button) {
+                    true => ButtonState::Pressed,
+                    false => ButtonState::Released,
+                };
+                self.state
+                    .submit_event_for(object, Event::Keyboard { button, state });
+            }
+        }
+        // the player jumped there, leaving nothing behind on the way
+        self.trail = Trail::default();
+        self.ribbons = Ribbons::default();
+        self.sparks = Sparks::default();
+    }
+
+    /// lets go of every movement key that's held, for when the window stops hearing about keys
+    /// coming back up
+    pub fn release_keys(&mut self) {
+        for button in [
+            Direction::Left,
+            Direction::Up,
+            Direction::Right,
+            Direction::Down,
+        ] {
+            let release = Event::Keyboard {
+                button,
+                state: ButtonState::Released,
+            };
+            self.submit(release);
+            self.submit_partner(release);
+        }
+    }
+
+    /// runs a single update, returning how the run went if it ended on this tick
+    pub fn tick(&mut self, dt: f64) -> Option<RunEnd> {
+        self.state.update(dt);
+        self.animator.tick(&self.state, dt);
+        self.body.tick(&self.state, dt);
+        self.trail.tick(&self.state);
+        self.sparks.tick(dt);
+        self.ribbons.tick(&self.state);
+        self.recorder.end_tick(&self.state);
+        self.run.observe(&self.state);
+        if let Some(heatmap) = &mut self.heatmap {
+            heatmap.record(&self.state);
+        }
+        if let Some(telemetry) = &mut self.telemetry {
+            if let Err(e) = telemetry.record(&self.state) {
+                tracing::warn!("failed to write telemetry, stopping: {}", e);
+                self.telemetry = None;
+            }
+        }
+
+        if self.state.died_at == Some(self.state.tick) {
+            return Some(RunEnd::Died);
+        }
+        let ticks = self.state.completed_at?;
+        if ticks != self.state.tick {
+            return None;
+        }
+        let best = Replay::load(paths::ghost(&self.level_name)).ok();
+        let new_best = !self.cheated
+            && !self.altered
+            && self.partner.is_none()
+            && best.is_none_or(|best| (ticks as usize) < best.path.len());
+        if new_best {
+            if let Err(e) = self.recorder.replay().save(paths::ghost(&self.level_name)) {
+                tracing::warn!("failed to save ghost: {}", e);
+            }
+        }
+        Some(RunEnd::Completed(Completion {
+            time: ticks as f64 * dt,
+            run: self.run.clone(),
+            new_best,
+        }))
+    }
+
+    /// takes the events from the game, counting the ones the run stats care about
+    pub fn drain_events(&mut self) -> Vec<GameEvent> {
+        let events = self.state.drain_events().collect::<Vec<_>>();
+        for event in &events {
+            self.run.on_event(event, self.state.view_object);
+            self.memory.record(
+                event,
+                &self.state,
+                self.level.objects.len(),
+                self.level.player,
+            );
+        }
+        events
+    }
+
+    /// how far the camera is zoomed in and what everything gets tinted with, for the start of
+    /// the level
+    pub fn intro(&self, interpolate: f64) -> (f64, [f32; 3]) {
+        let time = self.last_state.time + (self.state.time - self.last_state.time) * interpolate;
+        (
+            Self::INTRO_ZOOM.value_at(time),
+            Self::INTRO_FADE.value_at(time),
+        )
+    }
+
+    /// where the ghost should be drawn
+    pub fn ghost_position(&self, interpolate: f64) -> Option<[f64; 4]> {
+        self.ghost
+            .as_ref()
+            .and_then(|ghost| ghost.position_at(self.state.tick, interpolate))
+    }
+}
+
+/// the console commands for messing with the level being played
+pub fn register_commands(console: &mut Console) {
+    const SPAWN: &str = "spawn <box|wall|barrel> <x> <y>";
+    console.register("spawn", SPAWN, |args, context| {
+        let (x, y) = (
+            console::number(args, 1, SPAWN)?,
+            console::number(args, 2, SPAWN)?,
+        );
+        let object = match args.first() {
+            Some(&"box") => Object::movable(),
+            Some(&"wall") => Object::fixed(),
+            Some(&"barrel") => Object::movable().explosive(Explosive {
+                radius: 5.0,
+                strength: 30.0,
+                trigger_speed: 10.0,
+            }),
+            _ => return Err(format!("usage: {}", SPAWN)),
+        }
+        .at(x, y)
+        .build();
+        let session = context.session()?;
+        let index = session.state.spawn_object(object);
+        session.cheated = true;
+        Ok(format!("spawned object {}", index))
+    });
+    const TP: &str = "tp <x> <y>, or tp <entrance>";
+    console.register("tp", TP, |args, context| {
+        if let [name] = args {
+            let session = context.session()?;
+            if !session.state.spawn_player_at(name) {
+                let names = session
+                    .state
+                    .entrances
+                    .iter()
+                    .map(|entrance| &*entrance.name);
+                return Err(match names.collect::<Vec<_>>() {
+                    names if names.is_empty() => "this level has no entrances".to_owned(),
+                    names => format!(
+                        "there's no entrance called {}, only {}",
+                        name,
+                        names.join(", ")
+                    ),
+                });
+            }
+            session.cheated = true;
+            return Ok(format!("teleported to entrance {}", name));
+        }
+        let pos = cgmath::point2(console::number(args, 0, TP)?, console::number(args, 1, TP)?);
+        let session = context.session()?;
+        let player = session.state.player().map(|player| player.id());
+        let player = player
+            .and_then(|player| session.state.object_mut(player))
+            .ok_or("there is no player")?;
+        player.teleport(pos);
+        session.cheated = true;
+        Ok(format!("teleported to {}, {}", pos.x, pos.y))
+    });
+    const SET: &str = "set gravity <value>, or set swing \
+        <stiffness|damping|momentum|release_boost|release_bonus|release_window> <value>";
+    console.register("set", SET, |args, context| {
+        let session = context.session()?;
+        let message = match args.first() {
+            Some(&"gravity") => {
+                let value = console::number(args, 1, SET)?;
+                session.state.gravity = value;
+                format!("gravity set to {}", value)
+            }
+            Some(&"swing") => {
+                let value = console::number(args, 2, SET)?;
+                let mut swing = session.state.swing();
+                match args.get(1) {
+                    Some(&"stiffness") => swing.stiffness = value.clamp(0.0, 1.0),
+                    Some(&"damping") => swing.damping = value.clamp(0.0, 1.0),
+                    Some(&"momentum") => swing.momentum = value.clamp(0.0, 1.0),
+                    Some(&"release_boost") => swing.release_boost = value.max(0.0),
+                    Some(&"release_bonus") => swing.release_bonus = value.max(0.0),
+                    Some(&"release_window") => swing.release_window = value.clamp(0.0, 180.0),
+                    _ => return Err(format!("usage: {}", SET)),
+                }
+                session.state.set_swing(swing);
+                format!("swing set to {:?}", swing)
+            }
+            _ => return Err(format!("usage: {}", SET)),
+        };
+        session.cheated = true;
+        Ok(message)
+    });
+    const SHOOT: &str = "shoot <x> <y> <velocity x> <velocity y>";
+    console.register("shoot", SHOOT, |args, context| {
+        let pos = cgmath::point2(
+            console::number(args, 0, SHOOT)?,
+            console::number(args, 1, SHOOT)?,
+        );
+        let velocity = cgmath::vec2(
+            console::number(args, 2, SHOOT)?,
+            console::number(args, 3, SHOOT)?,
+        );
+        let session = context.session()?;
+        let slot = session.state.spawn_projectile(Projectile {
+            pos,
+            velocity,
+            radius: 0.15,
+            gravity_scale: 0.0,
+            damage: 1.0,
+            lifetime: 5.0,
+            owner: None,
+        });
+        session.cheated = true;
+        Ok(format!("fired projectile {}", slot))
+    });
+    const EXPLODE: &str = "explode <x> <y> <radius> <strength>";
+    console.register("explode", EXPLODE, |args, context| {
+        let center = cgmath::point2(
+            console::number(args, 0, EXPLODE)?,
+            console::number(args, 1, EXPLODE)?,
+        );
+        let radius = console::number(args, 2, EXPLODE)?;
+        let strength = console::number(args, 3, EXPLODE)?;
+        if radius <= 0.0 {
+            return Err("the radius has to be above 0".to_owned());
+        }
+        let session = context.session()?;
+        session.state.apply_explosion(center, radius, strength);
+        session.cheated = true;
+        Ok(format!("boom at {}, {}", center.x, center.y))
+    });
+}
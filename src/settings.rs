@@ -0,0 +1,83 @@
+use std::path::Path;
+
+use color_eyre::eyre::WrapErr;
+use serde::{Deserialize, Serialize};
+
+use crate::accessibility::Accessibility;
+use crate::background::BackgroundMode;
+use crate::bindings::Bindings;
+use crate::color_scheme::ColorScheme;
+use crate::game_state::AssistMode;
+use crate::gamepad::GamepadConfig;
+use crate::locale::Strings;
+use crate::online::OnlineConfig;
+
+/// the options that stick around between sessions
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Settings {
+    #[serde(default)]
+    pub color_scheme: ColorScheme,
+    #[serde(default)]
+    pub accessibility: Accessibility,
+    /// help that makes levels easier, runs with any of it on get flagged as assisted
+    #[serde(default)]
+    pub assist_mode: AssistMode,
+    /// the language code of the string table the menus use
+    #[serde(default = "default_locale")]
+    pub locale: String,
+    #[serde(default)]
+    pub gamepad: GamepadConfig,
+    /// what drawing does while the window is in the background
+    #[serde(default)]
+    pub background: BackgroundMode,
+    /// which buttons do what in the game
+    #[serde(default)]
+    pub bindings: Bindings,
+    /// where ghosts get shared, if anywhere
+    #[serde(default)]
+    pub online: OnlineConfig,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            color_scheme: ColorScheme::default(),
+            accessibility: Accessibility::default(),
+            assist_mode: AssistMode::default(),
+            locale: default_locale(),
+            gamepad: GamepadConfig::default(),
+            background: BackgroundMode::default(),
+            bindings: Bindings::default(),
+            online: OnlineConfig::default(),
+        }
+    }
+}
+
+fn default_locale() -> String {
+    Strings::FALLBACK.to_owned()
+}
+
+impl Settings {
+    pub const PATH: &'static str = "settings.ron";
+
+    /// a missing or broken settings file just means the defaults
+    pub fn load(path: impl AsRef<Path>) -> Self {
+        let path = path.as_ref();
+        let mut settings: Self = match std::fs::read_to_string(path) {
+            Ok(text) => ron::from_str(&text).unwrap_or_else(|e| {
+                tracing::warn!("failed to parse settings file {}: {}", path.display(), e);
+                Self::default()
+            }),
+            Err(_) => Self::default(),
+        };
+        settings.gamepad.sanitize();
+        settings
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> color_eyre::Result<()> {
+        let path = path.as_ref();
+        let text = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())?;
+        std::fs::write(path, text)
+            .wrap_err_with(|| format!("failed to write settings file {}", path.display()))
+    }
+}
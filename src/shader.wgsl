@@ -2,10 +2,24 @@ struct VertexInput {
     [[location(0)]] position: vec2<f32>;
     [[location(1)]] offset: vec2<f32>;
     [[location(2)]] size: vec2<f32>;
+    [[location(3)]] color: vec4<f32>;
+    // the scroll offset, then the width of the stripes, 0 for none
+    [[location(4)]] stripes: vec3<f32>;
+    // where in the sprite atlas the current frame is, all 0 for no sprite
+    [[location(5)]] sprite: vec4<f32>;
+    // radians counterclockwise around the middle of the quad
+    [[location(6)]] angle: f32;
 };
 
 struct VertexOutput {
     [[builtin(position)]] position: vec4<f32>;
+    [[location(0)]] color: vec4<f32>;
+    // where in the quad this is, in world units from the bottom left corner
+    [[location(1)]] local: vec2<f32>;
+    [[location(2)]] stripes: vec3<f32>;
+    // where in the quad this is, from 0 to 1 on both axes
+    [[location(3)]] quad: vec2<f32>;
+    [[location(4)]] sprite: vec4<f32>;
 };
 
 struct Camera {
@@ -15,14 +29,116 @@ struct Camera {
 [[group(0), binding(0)]]
 var<uniform> c: Camera;
 
+struct Palette {
+    tint: vec4<f32>;
+    ambient: vec4<f32>;
+};
+
+[[group(1), binding(0)]]
+var<uniform> palette: Palette;
+
+// how much nearer each instance is than the one before it, so with the depth buffer later ones
+// still end up on top of earlier ones, whatever order they get drawn in
+let LAYER_STEP: f32 = 0.00000095367431640625;
+
+// colours are picked in sRGB, but they have to be linear to blend right
+fn to_linear(color: vec3<f32>) -> vec3<f32> {
+    let low = color / 12.92;
+    let high = pow((color + 0.055) / 1.055, vec3<f32>(2.4, 2.4, 2.4));
+    return select(high, low, color <= vec3<f32>(0.04045, 0.04045, 0.04045));
+}
+
+[[stage(vertex)]]
+fn vs_main(in: VertexInput, [[builtin(instance_index)]] instance: u32) -> VertexOutput {
+    var output: VertexOutput;
+    let from_middle = (in.position - vec2<f32>(0.5, 0.5)) * in.size;
+    let turned = vec2<f32>(
+        from_middle.x * cos(in.angle) - from_middle.y * sin(in.angle),
+        from_middle.x * sin(in.angle) + from_middle.y * cos(in.angle),
+    );
+    output.position = c.view_proj * vec4<f32>(in.offset + in.size * 0.5 + turned, 0.0, 1.0);
+    output.position.z = 1.0 - f32(instance + 1u) * LAYER_STEP;
+    output.color = vec4<f32>(to_linear(in.color.rgb), in.color.a);
+    output.local = in.position * in.size;
+    output.stripes = in.stripes;
+    output.quad = in.position;
+    output.sprite = in.sprite;
+    return output;
+}
+
+struct RibbonInput {
+    [[location(0)]] position: vec2<f32>;
+    [[location(1)]] color: vec4<f32>;
+};
+
+// the ribbons things leave behind, a triangle strip in world space that's further back than any
+// instance so it ends up behind everything in the world
 [[stage(vertex)]]
-fn vs_main(in: VertexInput) -> VertexOutput {
+fn vs_ribbon(in: RibbonInput) -> VertexOutput {
     var output: VertexOutput;
-    output.position = c.view_proj * vec4<f32>(in.position * in.size + in.offset, 0.0, 1.0);
+    output.position = c.view_proj * vec4<f32>(in.position, 0.0, 1.0);
+    output.position.z = 1.0 - 0.5 * LAYER_STEP;
+    output.color = vec4<f32>(to_linear(in.color.rgb), in.color.a);
+    output.local = vec2<f32>(0.0, 0.0);
+    output.stripes = vec3<f32>(0.0, 0.0, 0.0);
+    output.quad = vec2<f32>(0.0, 0.0);
+    output.sprite = vec4<f32>(0.0, 0.0, 0.0, 0.0);
     return output;
 }
 
 [[stage(fragment)]]
 fn fs_main(input: VertexOutput) -> [[location(0)]] vec4<f32> {
-    return vec4<f32>(0.0, 0.0, 1.0, 1.0);
+    var color = input.color.rgb;
+    if (input.stripes.z > 0.0) {
+        let p = input.local - input.stripes.xy;
+        if (fract((p.x + p.y) / (2.0 * input.stripes.z)) < 0.5) {
+            color = color * 0.6;
+        }
+    }
+    return vec4<f32>(color * palette.tint.rgb + palette.ambient.rgb, input.color.a);
+}
+
+// the settings of the material being drawn, only bound for objects that have one
+struct Material {
+    params: vec4<f32>;
+    // x is how long the level has been running, in seconds
+    time: vec4<f32>;
+};
+
+[[group(2), binding(0)]]
+var<uniform> material: Material;
+[[group(2), binding(1)]]
+var material_texture: texture_2d<f32>;
+[[group(2), binding(2)]]
+var material_sampler: sampler;
+
+// the texture repeated every params.x world units, in place of the colour of the object
+[[stage(fragment)]]
+fn fs_textured(input: VertexOutput) -> [[location(0)]] vec4<f32> {
+    let repeat = max(material.params.x, 0.001);
+    let uv = vec2<f32>(input.local.x, -input.local.y) / repeat;
+    let color = textureSample(material_texture, material_sampler, uv);
+    return vec4<f32>(color.rgb * palette.tint.rgb + palette.ambient.rgb, color.a * input.color.a);
+}
+
+// the colour of the object pulsing brighter params.x times a second, by up to params.y, tinted by
+// the texture
+[[stage(fragment)]]
+fn fs_glow(input: VertexOutput) -> [[location(0)]] vec4<f32> {
+    let pulse = 0.5 + 0.5 * sin(material.time.x * material.params.x * 6.2831853);
+    let glow = textureSample(material_texture, material_sampler, vec2<f32>(0.5, 0.5)).rgb;
+    let color = input.color.rgb + glow * material.params.y * pulse;
+    return vec4<f32>(color * palette.tint.rgb + palette.ambient.rgb, input.color.a);
+}
+
+// the current frame out of the texture, stretched over the object, which is drawn plain when it
+// has no frame
+[[stage(fragment)]]
+fn fs_sprite(input: VertexOutput) -> [[location(0)]] vec4<f32> {
+    // sampled either way, texture samples have to happen in uniform control flow
+    let uv = input.sprite.xy + vec2<f32>(input.quad.x, 1.0 - input.quad.y) * input.sprite.zw;
+    let frame = textureSample(material_texture, material_sampler, uv);
+    let has_frame = input.sprite.z != 0.0 && input.sprite.w != 0.0;
+    let color = select(input.color, frame, has_frame);
+    return vec4<f32>(color.rgb * palette.tint.rgb + palette.ambient.rgb, color.a);
 }
@@ -0,0 +1,84 @@
+use cgmath::prelude::*;
+
+use crate::game_state::GameEvent;
+use crate::instances::{self, ROPE_COLOR};
+
+/// one bit of a snapped rope flying off
+struct Spark {
+    pos: cgmath::Point2<f64>,
+    velocity: cgmath::Vector2<f64>,
+    /// seconds until it's gone
+    left: f64,
+}
+
+/// the bits ropes break into when they snap, they only look the part and the game never hears of
+/// them
+#[derive(Default)]
+pub struct Sparks {
+    sparks: Vec<Spark>,
+}
+
+impl Sparks {
+    /// how many bits a snapped rope breaks into
+    const COUNT: usize = 16;
+    /// how fast the bits fly off sideways from the rope
+    const SPEED: f64 = 6.0;
+    /// how fast they fall
+    const GRAVITY: f64 = 20.0;
+    /// how long they last, in seconds
+    const LIFETIME: f64 = 0.6;
+    const SIZE: f32 = 0.2;
+
+    pub fn on_event(&mut self, event: &GameEvent) {
+        if let GameEvent::RopeSnapped { from, to, .. } = *event {
+            let along = to - from;
+            let direction = match along.magnitude() {
+                length if length > 0.0 => along / length,
+                _ => cgmath::vec2(1.0, 0.0),
+            };
+            let side = cgmath::vec2(-direction.y, direction.x);
+            // spread out along the rope, flying off to either side of it in turn, the ones
+            // nearer the middle where it broke going faster
+            self.sparks.extend((0..Self::COUNT).map(|i| {
+                let t = (i as f64 + 0.5) / Self::COUNT as f64;
+                let sign = if i % 2 == 0 { 1.0 } else { -1.0 };
+                let speed = Self::SPEED * (1.0 - (2.0 * t - 1.0).abs() * 0.5);
+                Spark {
+                    pos: from + along * t,
+                    velocity: (side * sign + direction * (t - 0.5)) * speed,
+                    left: Self::LIFETIME,
+                }
+            }));
+        }
+    }
+
+    pub fn tick(&mut self, dt: f64) {
+        for spark in &mut self.sparks {
+            spark.velocity.y -= Self::GRAVITY * dt;
+            spark.pos += spark.velocity * dt;
+            spark.left -= dt;
+        }
+        self.sparks.retain(|spark| spark.left > 0.0);
+    }
+
+    /// the quads for the bits, fading out as they go
+    pub fn instances(&self) -> Vec<[f32; 16]> {
+        self.sparks
+            .iter()
+            .map(|spark| {
+                let [r, g, b, _] = ROPE_COLOR;
+                let alpha = (spark.left / Self::LIFETIME) as f32;
+                let half = Self::SIZE / 2.0;
+                instances::instance(
+                    [
+                        spark.pos.x as f32 - half,
+                        spark.pos.y as f32 - half,
+                        Self::SIZE,
+                        Self::SIZE,
+                    ],
+                    [r, g, b, alpha],
+                )
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,53 @@
+// colours all through the game are written in sRGB, the way they'd be picked in a paint program,
+// but blending only looks right in linear space, so they get converted before anything is drawn
+// and turned back into sRGB on the way to the screen, either by the surface itself or by the last
+// pass for surfaces that don't
+
+/// whether what gets written to `format` is taken as linear, with the conversion to what's shown
+/// done on the way in
+pub fn stores_linear(format: wgpu::TextureFormat) -> bool {
+    format.describe().srgb
+        || matches!(
+            format,
+            wgpu::TextureFormat::Rgba16Float | wgpu::TextureFormat::Rgba32Float
+        )
+}
+
+/// what the world gets drawn in for a surface of `format`, the surface's own format when that
+/// stores linear colours and an sRGB one otherwise
+pub fn world_format(format: wgpu::TextureFormat) -> wgpu::TextureFormat {
+    match format {
+        format if stores_linear(format) => format,
+        wgpu::TextureFormat::Bgra8Unorm => wgpu::TextureFormat::Bgra8UnormSrgb,
+        _ => wgpu::TextureFormat::Rgba8UnormSrgb,
+    }
+}
+
+/// one sRGB channel in linear space
+pub fn to_linear(channel: f32) -> f32 {
+    if channel <= 0.04045 {
+        channel / 12.92
+    } else {
+        ((channel + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+/// an sRGB colour in linear space
+pub fn color_to_linear(color: [f32; 3]) -> [f32; 3] {
+    color.map(to_linear)
+}
+
+/// what to clear a target of `format` with for it to show up as the sRGB colour `color`
+pub fn clear_color(color: [f32; 3], format: wgpu::TextureFormat) -> wgpu::Color {
+    let [r, g, b] = if stores_linear(format) {
+        color_to_linear(color)
+    } else {
+        color
+    };
+    wgpu::Color {
+        r: r as f64,
+        g: g as f64,
+        b: b as f64,
+        a: 1.0,
+    }
+}
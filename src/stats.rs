@@ -0,0 +1,103 @@
+use std::{collections::BTreeMap, path::Path};
+
+use color_eyre::eyre::WrapErr;
+use serde::{Deserialize, Serialize};
+
+use crate::game_state::{GameEvent, GameState};
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct LevelStats {
+    /// fastest completion, in seconds
+    pub best_time: Option<f64>,
+    pub completions: u32,
+    /// runs that ended without reaching the goal
+    pub deaths: u32,
+    pub hooks_fired: u32,
+    pub max_swing_speed: f64,
+}
+
+/// stats for every level that has been played, kept in a file between sessions
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Stats {
+    pub levels: BTreeMap<String, LevelStats>,
+}
+
+impl Stats {
+    pub const PATH: &'static str = "stats.ron";
+
+    /// a missing or broken stats file just means starting from scratch
+    pub fn load(path: impl AsRef<Path>) -> Self {
+        let path = path.as_ref();
+        match std::fs::read_to_string(path) {
+            Ok(text) => ron::from_str(&text).unwrap_or_else(|e| {
+                tracing::warn!("failed to parse stats file {}: {}", path.display(), e);
+                Self::default()
+            }),
+            Err(_) => Self::default(),
+        }
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> color_eyre::Result<()> {
+        let path = path.as_ref();
+        let text = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())?;
+        std::fs::write(path, text)
+            .wrap_err_with(|| format!("failed to write stats file {}", path.display()))
+    }
+
+    pub fn level(&self, level: &str) -> LevelStats {
+        self.levels.get(level).cloned().unwrap_or_default()
+    }
+
+    /// adds a finished run to the level's stats, `completion_time` is None if the goal wasn't reached
+    pub fn record_run(&mut self, level: &str, run: &RunTracker, completion_time: Option<f64>) {
+        let stats = self.levels.entry(level.to_owned()).or_default();
+        stats.hooks_fired += run.hooks_fired;
+        stats.max_swing_speed = stats.max_swing_speed.max(run.max_swing_speed);
+        match completion_time {
+            Some(time) => {
+                stats.completions += 1;
+                stats.best_time = Some(stats.best_time.map_or(time, |best| best.min(time)));
+            }
+            None => stats.deaths += 1,
+        }
+    }
+}
+
+/// counts things up during a single run through a level
+#[derive(Clone, Debug, Default)]
+pub struct RunTracker {
+    pub hooks_fired: u32,
+    pub max_swing_speed: f64,
+    /// how many of the fired hooks actually stuck into something
+    pub hooks_attached: u32,
+    /// whether the player has landed on anything after firing the first hook
+    pub touched_ground: bool,
+    /// things knocked out of the world
+    pub objects_destroyed: u32,
+}
+
+impl RunTracker {
+    /// call after every update
+    pub fn observe(&mut self, state: &GameState) {
+        if state.is_swinging() {
+            if let Some(player) = state.player() {
+                let speed = cgmath::InnerSpace::magnitude(player.get_velocity());
+                self.max_swing_speed = self.max_swing_speed.max(speed);
+            }
+        }
+    }
+
+    pub fn on_event(&mut self, event: &GameEvent, player: usize) {
+        match *event {
+            GameEvent::HookFired { object } if object == player => self.hooks_fired += 1,
+            GameEvent::HookAttached { object, .. } if object == player => self.hooks_attached += 1,
+            GameEvent::Landed { object } if object == player && self.hooks_fired > 0 => {
+                self.touched_ground = true
+            }
+            GameEvent::ObjectDestroyed { object } if object != player => {
+                self.objects_destroyed += 1
+            }
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,231 @@
+//! the parts of Steamworks the game uses, through steamworks-rs
+
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use cgmath::prelude::*;
+use steamworks::{CallbackHandle, Client, UserStatsReceived};
+
+use crate::bindings::{Action, ActionEvent};
+use crate::game_state::{ButtonState, GameState};
+
+/// the in game action set from the input configuration, and the names of its actions
+const ACTION_SET: &str = "InGameControls";
+const DIGITAL_ACTIONS: [(&str, Action); 8] = [
+    ("jump", Action::Jump),
+    ("reel", Action::Reel),
+    ("fire_hook", Action::FireHook),
+    ("fire_second_hook", Action::FireSecondHook),
+    ("pause", Action::Pause),
+    ("save_state", Action::SaveState),
+    ("load_state", Action::LoadState),
+    ("restart", Action::Restart),
+];
+const MOVE_ACTION: &str = "move";
+const AIM_ACTION: &str = "aim";
+
+/// the handles for the action set, which Steam only has once it's read the configuration, so
+/// they're looked up until they come back
+struct Handles {
+    set: u64,
+    digital: Vec<(u64, Action)>,
+    movement: u64,
+    aim: u64,
+}
+
+/// a running Steam client, with achievements mirrored to it, the level being played shown to
+/// friends and Steam Input controllers giving the same actions as everything else
+pub struct Steam {
+    client: Client,
+    /// set once Steam has the player's stats, achievements can't be set before that
+    stats_received: Arc<AtomicBool>,
+    _stats_callback: CallbackHandle,
+    /// whether Steam Input started
+    input: bool,
+    handles: Option<Handles>,
+    /// the achievements Steam has taken
+    mirrored: HashSet<String>,
+    /// the achievements Steam wouldn't take, which are still tried again but only warned about
+    /// once
+    refused: HashSet<String>,
+    /// what the rich presence was last set to
+    presence: Option<String>,
+    held: HashSet<Action>,
+    /// how far the move action was pushed sideways, as last handed out
+    movement: f64,
+    /// where the hook was last aimed, None until a controller aims it
+    aim: Option<cgmath::Vector2<f64>>,
+}
+
+impl Steam {
+    /// connects to the Steam client, None when it isn't running or the game wasn't started
+    /// through it
+    pub fn init() -> Option<Self> {
+        let client = Client::init()
+            .map_err(|e| tracing::warn!("failed to connect to steam: {}", e))
+            .ok()?;
+        // Steam loads the stats on its own at startup and says so through a callback
+        let stats_received = Arc::new(AtomicBool::new(false));
+        let user = client.user().steam_id();
+        let received = stats_received.clone();
+        let stats_callback = client.register_callback(move |stats: UserStatsReceived| {
+            if stats.steam_id != user {
+                return;
+            }
+            match stats.result {
+                Ok(()) => received.store(true, Ordering::Relaxed),
+                Err(e) => tracing::warn!("failed to get stats from steam: {}", e),
+            }
+        });
+        let input = client.input().init(false);
+        if !input {
+            tracing::warn!("failed to start steam input");
+        }
+        Some(Self {
+            client,
+            stats_received,
+            _stats_callback: stats_callback,
+            input,
+            handles: None,
+            mirrored: HashSet::new(),
+            refused: HashSet::new(),
+            presence: None,
+            held: HashSet::new(),
+            movement: 0.0,
+            aim: None,
+        })
+    }
+
+    /// call once a frame, for Steam's callbacks to run
+    pub fn run_callbacks(&mut self) {
+        self.client.run_callbacks();
+    }
+
+    /// tells Steam about any of `unlocked` it hasn't taken yet, once it has the player's stats
+    pub fn mirror_achievements<'a>(&mut self, unlocked: impl Iterator<Item = &'a str>) {
+        if !self.stats_received.load(Ordering::Relaxed) {
+            return;
+        }
+        let stats = self.client.user_stats();
+        let mut changed = false;
+        for id in unlocked {
+            if self.mirrored.contains(id) {
+                continue;
+            }
+            match stats.achievement(id).set() {
+                Ok(()) => {
+                    self.mirrored.insert(id.to_owned());
+                    changed = true;
+                }
+                Err(()) => {
+                    if self.refused.insert(id.to_owned()) {
+                        tracing::warn!("steam doesn't know the achievement {}", id);
+                    }
+                }
+            }
+        }
+        if changed && stats.store_stats().is_err() {
+            tracing::warn!("failed to store achievements on steam");
+        }
+    }
+
+    /// shows friends which level is being played, or nothing outside of one
+    pub fn set_level(&mut self, level: Option<&str>) {
+        if self.presence.as_deref() == level {
+            return;
+        }
+        self.presence = level.map(str::to_owned);
+        let friends = self.client.friends();
+        match level {
+            Some(level) => {
+                friends.set_rich_presence("status", Some(&format!("Playing {}", level)));
+            }
+            None => friends.clear_rich_presence(),
+        }
+    }
+
+    /// the actions that started or stopped on Steam Input controllers since last frame, the
+    /// same way `Gamepad::update` hands them out
+    pub fn update_input(&mut self) -> Vec<ActionEvent> {
+        if !self.input {
+            return vec![];
+        }
+        let input = self.client.input();
+        input.run_frame();
+        let handles = match self.handles.take().or_else(|| self.find_handles()) {
+            Some(handles) => handles,
+            None => return vec![],
+        };
+
+        let mut wanted = HashSet::new();
+        let mut movement = 0.0;
+        for controller in input.get_connected_controllers() {
+            input.activate_action_set_handle(controller, handles.set);
+            for &(handle, action) in &handles.digital {
+                let data = input.get_digital_action_data(controller, handle);
+                if data.bActive && data.bState {
+                    wanted.insert(action);
+                }
+            }
+            let data = input.get_analog_action_data(controller, handles.movement);
+            if data.bActive && movement == 0.0 {
+                movement = (data.x as f64).clamp(-1.0, 1.0);
+            }
+            let data = input.get_analog_action_data(controller, handles.aim);
+            let aim = cgmath::vec2(data.x as f64, data.y as f64);
+            if data.bActive && aim.magnitude2() > 0.0 {
+                self.aim = Some(aim);
+            }
+        }
+        self.handles = Some(handles);
+
+        let mut buttons = self
+            .held
+            .difference(&wanted)
+            .map(|&action| (action, ButtonState::Released))
+            .chain(
+                wanted
+                    .difference(&self.held)
+                    .map(|&action| (action, ButtonState::Pressed)),
+            )
+            .collect::<Vec<_>>();
+        buttons.sort_by_key(|&(action, state)| (action as u8, state == ButtonState::Pressed));
+        self.held = wanted;
+        let mut actions = buttons
+            .into_iter()
+            .map(|(action, state)| ActionEvent::Button(action, state))
+            .collect::<Vec<_>>();
+        if movement != self.movement {
+            self.movement = movement;
+            actions.push(ActionEvent::Move(movement));
+        }
+        actions
+    }
+
+    fn find_handles(&self) -> Option<Handles> {
+        let input = self.client.input();
+        let set = input.get_action_set_handle(ACTION_SET);
+        if set == 0 {
+            return None;
+        }
+        Some(Handles {
+            set,
+            digital: DIGITAL_ACTIONS
+                .iter()
+                .map(|&(name, action)| (input.get_digital_action_handle(name), action))
+                .filter(|&(handle, _)| handle != 0)
+                .collect(),
+            movement: input.get_analog_action_handle(MOVE_ACTION),
+            aim: input.get_analog_action_handle(AIM_ACTION),
+        })
+    }
+
+    /// where the hook goes when it's fired from a Steam Input controller, None until one has
+    /// aimed
+    pub fn aim_target(&self, state: &GameState) -> Option<cgmath::Point2<f64>> {
+        let aim = self.aim?;
+        let player = state.object(state.view_object)?;
+        Some(player.center() + aim * GameState::HOOK_RANGE)
+    }
+}
@@ -0,0 +1,81 @@
+use crate::game_state::GameState;
+use crate::text::Text;
+
+/// the debug pause, the game only moves on a tick at a time when asked and gets drawn exactly as
+/// that tick left it, for watching collisions go wrong
+#[derive(Default)]
+pub struct Stepping {
+    pub on: bool,
+    /// ticks asked for since the last frame
+    steps: u32,
+}
+
+impl Stepping {
+    const LINE_HEIGHT: f32 = 20.0;
+    /// contacts past this many get counted instead of listed
+    const SHOWN_CONTACTS: usize = 20;
+
+    pub fn toggle(&mut self) {
+        self.on = !self.on;
+        self.steps = 0;
+    }
+
+    pub fn step(&mut self) {
+        if self.on {
+            self.steps += 1;
+        }
+    }
+
+    /// how much game time passes this frame, `elapsed` when not stepping, otherwise exactly the
+    /// ticks that were asked for
+    pub fn advance(&mut self, elapsed: f64, tick_rate: f64) -> f64 {
+        if !self.on {
+            return elapsed;
+        }
+        std::mem::take(&mut self.steps) as f64 * tick_rate
+    }
+
+    /// how far from the last tick to the current one to draw things, stepping shows the tick
+    /// itself instead of somewhere in between
+    pub fn interpolate(&self, interpolate: f64) -> f64 {
+        match self.on {
+            true => 1.0,
+            false => interpolate,
+        }
+    }
+
+    /// the tick and every contact the solver worked on in it
+    pub fn text(&self, state: &GameState) -> Vec<Text> {
+        let stats = state.physics_stats();
+        let mut lines = vec![
+            format!("stepping, tick {}, . for the next one", state.tick),
+            format!(
+                "{} contacts, {} solver passes, deepest {:.4}",
+                stats.contacts, stats.solver_iterations, stats.max_penetration
+            ),
+        ];
+        let contacts = state.contacts();
+        for contact in contacts.iter().take(Self::SHOWN_CONTACTS) {
+            let (a, b) = contact.objects;
+            lines.push(format!(
+                "{:4} {:4}  normal {:6.3} {:6.3}  impulse {:8.4}",
+                a, b, contact.normal.x, contact.normal.y, contact.impulse
+            ));
+        }
+        if contacts.len() > Self::SHOWN_CONTACTS {
+            lines.push(format!(
+                "and {} more",
+                contacts.len() - Self::SHOWN_CONTACTS
+            ));
+        }
+        lines
+            .into_iter()
+            .enumerate()
+            .map(|(i, line)| {
+                Text::new(line, (40.0, 120.0 + i as f32 * Self::LINE_HEIGHT))
+                    .with_size(16.0)
+                    .with_color([1.0, 0.9, 0.3, 1.0])
+            })
+            .collect()
+    }
+}
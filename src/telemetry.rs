@@ -0,0 +1,94 @@
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use color_eyre::eyre::WrapErr;
+
+use crate::console::Console;
+use crate::game_state::GameState;
+
+/// writes a row of physics numbers for every tick to a csv file, so movement tuning and solver
+/// changes can be compared in a spreadsheet instead of by feel
+pub struct Telemetry {
+    file: BufWriter<File>,
+    pub path: PathBuf,
+}
+
+impl Telemetry {
+    const HEADER: &'static str = "tick,time,velocity_x,velocity_y,speed,player_contacts,contacts,solver_iterations,max_penetration,total_penetration";
+
+    pub fn create(path: impl AsRef<Path>) -> color_eyre::Result<Self> {
+        let path = path.as_ref();
+        let create = || -> std::io::Result<BufWriter<File>> {
+            if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+                std::fs::create_dir_all(dir)?;
+            }
+            let mut file = BufWriter::new(File::create(path)?);
+            writeln!(file, "{}", Self::HEADER)?;
+            Ok(file)
+        };
+        let file = create()
+            .wrap_err_with(|| format!("failed to create telemetry file {}", path.display()))?;
+        Ok(Self {
+            file,
+            path: path.to_owned(),
+        })
+    }
+
+    /// adds the row for the update `state` just had
+    pub fn record(&mut self, state: &GameState) -> std::io::Result<()> {
+        let player = state.object(state.view_object);
+        let velocity = player
+            .as_ref()
+            .map_or(cgmath::vec2(0.0, 0.0), |player| player.get_velocity());
+        let player_contacts = player.map_or(0, |player| player.get_touching().len());
+        let physics = state.physics_stats();
+        writeln!(
+            self.file,
+            "{},{:.4},{:.6},{:.6},{:.6},{},{},{},{:.6},{:.6}",
+            state.tick,
+            state.time,
+            velocity.x,
+            velocity.y,
+            cgmath::InnerSpace::magnitude(velocity),
+            player_contacts,
+            physics.contacts,
+            physics.solver_iterations,
+            physics.max_penetration,
+            physics.total_penetration,
+        )
+    }
+
+    /// writes out whatever is still buffered
+    pub fn finish(mut self) -> std::io::Result<()> {
+        self.file.flush()
+    }
+}
+
+/// the console command for starting and stopping the recording, it keeps going across restarts
+/// of the level
+pub fn register_commands(console: &mut Console) {
+    const USAGE: &str = "telemetry <file.csv|stop>";
+    console.register("telemetry", USAGE, |args, context| {
+        let session = context.session()?;
+        match args {
+            ["stop"] => {
+                let telemetry = session
+                    .telemetry
+                    .take()
+                    .ok_or("telemetry isn't being recorded".to_owned())?;
+                let path = telemetry.path.clone();
+                telemetry.finish().map_err(|e| e.to_string())?;
+                Ok(format!("telemetry written to {}", path.display()))
+            }
+            [path] => {
+                if let Some(telemetry) = session.telemetry.take() {
+                    telemetry.finish().map_err(|e| e.to_string())?;
+                }
+                session.telemetry = Some(Telemetry::create(path).map_err(|e| format!("{:#}", e))?);
+                Ok(format!("recording telemetry to {}", path))
+            }
+            _ => Err(format!("usage: {}", USAGE)),
+        }
+    });
+}
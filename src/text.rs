@@ -0,0 +1,99 @@
+use wgpu_glyph::{ab_glyph, GlyphBrush, GlyphBrushBuilder, Section};
+
+/// a piece of text drawn over everything else, positioned in physical pixels from the top left
+#[derive(Clone, Debug)]
+pub struct Text {
+    pub content: String,
+    pub pos: (f32, f32),
+    pub size: f32,
+    pub color: [f32; 4],
+}
+
+impl Text {
+    pub fn new(content: impl Into<String>, pos: (f32, f32)) -> Self {
+        Self {
+            content: content.into(),
+            pos,
+            size: 24.0,
+            color: [1.0, 1.0, 1.0, 1.0],
+        }
+    }
+
+    pub fn with_size(self, size: f32) -> Self {
+        Self { size, ..self }
+    }
+
+    pub fn with_color(self, color: [f32; 4]) -> Self {
+        Self { color, ..self }
+    }
+}
+
+pub struct TextRenderer {
+    brush: GlyphBrush<()>,
+    /// whether the target takes linear colours, the ones given for text are sRGB
+    linear: bool,
+    staging_belt: wgpu::util::StagingBelt,
+    local_pool: futures::executor::LocalPool,
+}
+
+impl TextRenderer {
+    pub fn new(device: &wgpu::Device, format: wgpu::TextureFormat) -> color_eyre::Result<Self> {
+        let font = ab_glyph::FontArc::try_from_slice(include_bytes!(
+            "../assets/fonts/DejaVuSansMono.ttf"
+        ))?;
+        Ok(Self {
+            brush: GlyphBrushBuilder::using_font(font).build(device, format),
+            linear: crate::srgb::stores_linear(format),
+            staging_belt: wgpu::util::StagingBelt::new(1024),
+            local_pool: futures::executor::LocalPool::new(),
+        })
+    }
+
+    /// records drawing `texts` on top of whatever is already in `target`
+    pub fn draw(
+        &mut self,
+        device: &wgpu::Device,
+        encoder: &mut wgpu::CommandEncoder,
+        target: &wgpu::TextureView,
+        (width, height): (u32, u32),
+        texts: &[Text],
+    ) -> color_eyre::Result<()> {
+        for text in texts {
+            let [r, g, b, a] = text.color;
+            let [r, g, b] = if self.linear {
+                crate::srgb::color_to_linear([r, g, b])
+            } else {
+                [r, g, b]
+            };
+            self.brush.queue(Section {
+                screen_position: text.pos,
+                text: vec![wgpu_glyph::Text::new(&text.content)
+                    .with_scale(text.size)
+                    .with_color([r, g, b, a])],
+                ..Section::default()
+            });
+        }
+        self.brush
+            .draw_queued(
+                device,
+                &mut self.staging_belt,
+                encoder,
+                target,
+                width,
+                height,
+            )
+            .map_err(|e| color_eyre::eyre::eyre!(e))?;
+        self.staging_belt.finish();
+        Ok(())
+    }
+
+    /// call once the frame has been submitted, so the staging buffers can get reused
+    pub fn recall(&mut self) {
+        use futures::task::SpawnExt;
+        let result = self.local_pool.spawner().spawn(self.staging_belt.recall());
+        if let Err(e) = result {
+            tracing::warn!("failed to recall text staging buffers: {}", e);
+        }
+        self.local_pool.run_until_stalled();
+    }
+}
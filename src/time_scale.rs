@@ -0,0 +1,100 @@
+use std::time::Instant;
+
+use crate::text::Text;
+
+/// a short slow down, eased back to full speed at the end
+#[derive(Clone, Copy, Debug)]
+pub struct SlowMotion {
+    /// how fast time goes at the slowest, 1 turns it off
+    pub scale: f64,
+    /// how long it stays at `scale`, in real seconds
+    pub hold: f64,
+    /// how long it takes to get back up to speed afterwards, in real seconds
+    pub ease: f64,
+}
+
+impl Default for SlowMotion {
+    fn default() -> Self {
+        Self {
+            scale: 0.3,
+            hold: 0.1,
+            ease: 0.15,
+        }
+    }
+}
+
+/// how fast game time passes compared to real time, the simulation still steps by the same
+/// amount every update, there are just fewer updates per second
+pub struct TimeScale {
+    /// set from the console, 1 is normal speed
+    pub base: f64,
+    /// set with the debug speed keys, on top of `base`
+    pub debug: f64,
+    /// the assist mode's game speed, on top of the rest
+    pub assist: f64,
+    /// what happens when the hook connects
+    pub on_attach: SlowMotion,
+    /// the slow down going on right now, and when it started
+    dip: Option<(SlowMotion, Instant)>,
+}
+
+impl Default for TimeScale {
+    fn default() -> Self {
+        Self {
+            base: 1.0,
+            debug: 1.0,
+            assist: 1.0,
+            on_attach: SlowMotion::default(),
+            dip: None,
+        }
+    }
+}
+
+impl TimeScale {
+    /// the debug speeds on the number keys, 1 through 5
+    pub const DEBUG_SPEEDS: [f64; 5] = [0.1, 0.5, 1.0, 2.0, 5.0];
+
+    /// slows time down for a bit, starting now
+    pub fn slow_down(&mut self, slow_motion: SlowMotion) {
+        self.dip = Some((slow_motion, Instant::now()));
+    }
+
+    /// whether the console or the debug keys have time going at anything but full speed, which
+    /// runs don't count with
+    pub fn tampered(&self) -> bool {
+        self.base != 1.0 || self.debug != 1.0
+    }
+
+    /// how fast time goes at `now`
+    pub fn at(&mut self, now: Instant) -> f64 {
+        self.base * self.debug * self.assist * self.dip_at(now)
+    }
+
+    fn dip_at(&mut self, now: Instant) -> f64 {
+        let (slow_motion, started) = match self.dip {
+            Some(dip) => dip,
+            None => return 1.0,
+        };
+        let elapsed = now.saturating_duration_since(started).as_secs_f64() - slow_motion.hold;
+        if elapsed >= slow_motion.ease {
+            self.dip = None;
+            return 1.0;
+        }
+        // smoothstep, so it doesn't lurch back into full speed
+        let t = (elapsed / slow_motion.ease).max(0.0);
+        let t = t * t * (3.0 - 2.0 * t);
+        slow_motion.scale + (1.0 - slow_motion.scale) * t
+    }
+
+    /// the debug speed, when it isn't normal
+    pub fn text(&self) -> Option<Text> {
+        if self.debug == 1.0 {
+            return None;
+        }
+        Some(
+            Text::new(format!("speed {}x", self.debug), (40.0, 10.0))
+                .with_size(16.0)
+                .with_color([1.0, 0.9, 0.3, 1.0]),
+        )
+    }
+}
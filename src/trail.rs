@@ -0,0 +1,76 @@
+use std::collections::VecDeque;
+
+use cgmath::prelude::*;
+
+use crate::game_state::GameState;
+use crate::instances;
+
+/// afterimages left behind the player while they're going fast, made from where they were over
+/// the last few ticks
+#[derive(Default)]
+pub struct Trail {
+    /// where the player was on each of the last few ticks as [x, y, width, height], and how fast
+    /// they were going, newest first
+    history: VecDeque<([f64; 4], f64)>,
+}
+
+impl Trail {
+    /// how many ticks back the afterimages go
+    const LENGTH: usize = 8;
+    /// only every this many ticks leaves an afterimage, so they don't blur into one smear
+    const SPACING: usize = 2;
+    /// going slower than this leaves nothing behind
+    const MIN_SPEED: f64 = 18.0;
+    /// how much faster than `MIN_SPEED` it takes for the afterimages to be as strong as they get
+    const FULL_SPEED: f64 = 12.0;
+    /// how see-through the newest afterimage is at full strength
+    const MAX_ALPHA: f32 = 0.35;
+    const COLOR: [f32; 3] = [0.8, 0.9, 1.0];
+    /// moving further than this in one tick can only be a teleport, which shouldn't leave a trail
+    /// across the level
+    const TELEPORT_DISTANCE: f64 = 5.0;
+
+    /// remembers where the player got to on this tick
+    pub fn tick(&mut self, state: &GameState) {
+        let player = match state.object(state.view_object) {
+            Some(player) => player,
+            None => {
+                self.history.clear();
+                return;
+            }
+        };
+        let (pos, size) = (*player.get_pos(), *player.get_size());
+        if let Some(&([x, y, ..], _)) = self.history.front() {
+            if pos.distance(cgmath::point2(x, y)) > Self::TELEPORT_DISTANCE {
+                self.history.clear();
+            }
+        }
+        let speed = player.get_velocity().magnitude();
+        self.history
+            .push_front(([pos.x, pos.y, size.x, size.y], speed));
+        self.history.truncate(Self::LENGTH + 1);
+    }
+
+    /// the quads for the afterimages, the older ones more faded, none at all while slow
+    pub fn instances(&self) -> Vec<[f32; 16]> {
+        self.history
+            .iter()
+            .enumerate()
+            .step_by(Self::SPACING)
+            .skip(1)
+            .filter_map(|(age, &(rect, speed))| {
+                let strength = ((speed - Self::MIN_SPEED) / Self::FULL_SPEED).clamp(0.0, 1.0);
+                if strength == 0.0 {
+                    return None;
+                }
+                let fade = 1.0 - age as f32 / (Self::LENGTH + 1) as f32;
+                let [r, g, b] = Self::COLOR;
+                let alpha = Self::MAX_ALPHA * strength as f32 * fade;
+                Some(instances::instance(
+                    rect.map(|v| v as f32),
+                    [r, g, b, alpha],
+                ))
+            })
+            .collect()
+    }
+}
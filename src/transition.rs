@@ -0,0 +1,154 @@
+use std::time::Instant;
+
+use wgpu::util::DeviceExt;
+
+/// how the screen gets covered up
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Kind {
+    /// all of it darkening at once
+    Fade,
+    /// a circle closing in on the player
+    Wipe,
+}
+
+/// how much of the screen is covered this frame
+#[derive(Clone, Copy, Debug)]
+pub struct Cover {
+    pub kind: Kind,
+    /// from 0 for none of it to 1 for all of it
+    pub amount: f32,
+}
+
+/// the screen starting out covered and coming back into view, after something that would
+/// otherwise just pop in, like a new screen or the player getting put back at the start
+pub struct Transition {
+    kind: Kind,
+    started: Instant,
+}
+
+impl Transition {
+    /// how long it takes to come back into view, in seconds
+    const DURATION: f32 = 0.5;
+
+    pub fn new(kind: Kind, now: Instant) -> Self {
+        Self { kind, started: now }
+    }
+
+    /// how much is covered at `now`, None once it's all showing again
+    pub fn cover(&self, now: Instant) -> Option<Cover> {
+        let progress = (now - self.started).as_secs_f32() / Self::DURATION;
+        (progress < 1.0).then_some(Cover {
+            kind: self.kind,
+            // eased so it opens slowly at first and settles in at the end
+            amount: 1.0 - progress * progress * (3.0 - 2.0 * progress),
+        })
+    }
+}
+
+/// draws the cover over the finished frame, text and all
+pub struct TransitionPass {
+    pipeline: wgpu::RenderPipeline,
+    bind_group: wgpu::BindGroup,
+    cover_buffer: wgpu::Buffer,
+}
+
+impl TransitionPass {
+    pub fn new(device: &wgpu::Device, format: wgpu::TextureFormat) -> Self {
+        let shader = device.create_shader_module(&wgpu::include_wgsl!("transition.wgsl"));
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("transition bind group layout"),
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: (6 * std::mem::size_of::<f32>() as u64).try_into().ok(),
+                },
+                count: None,
+            }],
+        });
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("transition pipeline"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("transition pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: "vs_main",
+                buffers: &[],
+            },
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: "fs_main",
+                targets: &[wgpu::ColorTargetState {
+                    format,
+                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                    write_mask: wgpu::ColorWrites::ALL,
+                }],
+            }),
+            multiview: None,
+        });
+        let cover_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("transition cover buffer"),
+            contents: bytemuck::cast_slice(&[0.0f32; 6]),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("transition bind group"),
+            layout: &bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: cover_buffer.as_entire_binding(),
+            }],
+        });
+        Self {
+            pipeline,
+            bind_group,
+            cover_buffer,
+        }
+    }
+
+    /// records the pass that draws `cover` onto `target`, which is `size` pixels big, a wipe
+    /// closes in on `center`, in pixels from the top left
+    pub fn run(
+        &self,
+        queue: &wgpu::Queue,
+        encoder: &mut wgpu::CommandEncoder,
+        target: &wgpu::TextureView,
+        cover: Cover,
+        center: [f32; 2],
+        size: [f32; 2],
+    ) {
+        let wipe = match cover.kind {
+            Kind::Fade => 0.0,
+            Kind::Wipe => 1.0,
+        };
+        queue.write_buffer(
+            &self.cover_buffer,
+            0,
+            bytemuck::cast_slice(&[cover.amount, wipe, center[0], center[1], size[0], size[1]]),
+        );
+        let mut rpass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("transition pass"),
+            color_attachments: &[wgpu::RenderPassColorAttachment {
+                view: target,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Load,
+                    store: true,
+                },
+            }],
+            depth_stencil_attachment: None,
+        });
+        rpass.set_pipeline(&self.pipeline);
+        rpass.set_bind_group(0, &self.bind_group, &[]);
+        rpass.draw(0..3, 0..1);
+    }
+}
@@ -0,0 +1,30 @@
+struct Cover {
+    // how much is covered, from 0 to 1, and 1 for a circle wipe instead of a fade
+    amount: f32;
+    wipe: f32;
+    // where the wipe closes in on and how big the frame is, in pixels
+    center: vec2<f32>;
+    size: vec2<f32>;
+};
+
+[[group(0), binding(0)]]
+var<uniform> cover: Cover;
+
+// a single triangle covering the whole screen
+[[stage(vertex)]]
+fn vs_main([[builtin(vertex_index)]] index: u32) -> [[builtin(position)]] vec4<f32> {
+    let uv = vec2<f32>(f32((index << 1u) & 2u), f32(index & 2u));
+    return vec4<f32>(uv * 2.0 - 1.0, 0.0, 1.0);
+}
+
+[[stage(fragment)]]
+fn fs_main([[builtin(position)]] position: vec4<f32>) -> [[location(0)]] vec4<f32> {
+    var alpha = cover.amount;
+    if (cover.wipe > 0.5) {
+        // big enough to reach the furthest corner when it's all open
+        let corner = max(cover.center, cover.size - cover.center);
+        let radius = length(corner) * (1.0 - cover.amount);
+        alpha = smoothStep(radius - 2.0, radius + 2.0, distance(position.xy, cover.center));
+    }
+    return vec4<f32>(0.0, 0.0, 0.0, alpha);
+}
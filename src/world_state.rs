@@ -0,0 +1,221 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+
+use color_eyre::eyre::WrapErr;
+use serde::{Deserialize, Serialize};
+
+use crate::console::Console;
+use crate::game_state::{GameEvent, GameState};
+
+/// what the player did to a level that stays done when they come back to it, objects go by their
+/// index in the level file
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct LevelMemory {
+    /// objects that got broken or blown up
+    pub destroyed: BTreeSet<usize>,
+    /// objects that got moved and stayed there, like doors that slid open
+    pub moved: BTreeMap<usize, cgmath::Point2<f64>>,
+}
+
+impl LevelMemory {
+    pub fn is_empty(&self) -> bool {
+        self.destroyed.is_empty() && self.moved.is_empty()
+    }
+
+    /// makes a fresh start of the level look the way it was left
+    pub fn apply(&self, state: &mut GameState) {
+        for &index in &self.destroyed {
+            state.despawn_object(index);
+        }
+        for (&index, &pos) in &self.moved {
+            state.teleport_object(index, pos);
+        }
+    }
+
+    /// keeps track of anything `event` did to the first `level_objects` objects, the ones from
+    /// the level file, apart from `player` who starts where the level says every time
+    pub fn record(
+        &mut self,
+        event: &GameEvent,
+        state: &GameState,
+        level_objects: usize,
+        player: usize,
+    ) {
+        let from_level = |object: usize| object < level_objects && object != player;
+        match *event {
+            GameEvent::ObjectDestroyed { object } if from_level(object) => {
+                self.moved.remove(&object);
+                self.destroyed.insert(object);
+            }
+            GameEvent::TweenFinished { object, .. } if from_level(object) => {
+                if let Some(view) = state.object(object) {
+                    self.moved.insert(object, *view.get_pos());
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// what the player did to every level they've been to, kept in a file between sessions
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct WorldState {
+    pub levels: BTreeMap<String, LevelMemory>,
+}
+
+impl WorldState {
+    pub const PATH: &'static str = "world.ron";
+
+    /// a missing or broken file just means every level starts out the way it was made
+    pub fn load(path: impl AsRef<Path>) -> Self {
+        let path = path.as_ref();
+        match std::fs::read_to_string(path) {
+            Ok(text) => ron::from_str(&text).unwrap_or_else(|e| {
+                tracing::warn!("failed to parse world state file {}: {}", path.display(), e);
+                Self::default()
+            }),
+            Err(_) => Self::default(),
+        }
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> color_eyre::Result<()> {
+        let path = path.as_ref();
+        let text = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())?;
+        std::fs::write(path, text)
+            .wrap_err_with(|| format!("failed to write world state file {}", path.display()))
+    }
+
+    pub fn level(&self, level: &str) -> LevelMemory {
+        self.levels.get(level).cloned().unwrap_or_default()
+    }
+
+    /// stores `memory` for `level`, returning whether that changed anything so it's worth saving
+    pub fn remember(&mut self, level: &str, memory: &LevelMemory) -> bool {
+        if self.level(level) == *memory {
+            return false;
+        }
+        if memory.is_empty() {
+            self.levels.remove(level);
+        } else {
+            self.levels.insert(level.to_owned(), memory.clone());
+        }
+        true
+    }
+}
+
+/// the console command for putting a level back the way it was made
+pub fn register_commands(console: &mut Console) {
+    console.register("forget", "forget", |args, context| {
+        if !args.is_empty() {
+            return Err("usage: forget".to_owned());
+        }
+        let session = context.session()?;
+        session.memory = LevelMemory::default();
+        session.restart();
+        Ok(format!(
+            "{} is back the way it was made",
+            session.level_name
+        ))
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game_state::Object;
+    use crate::level::{Chunking, Level};
+    use crate::tween::Easing;
+
+    const TICK: f64 = 1.0 / 60.0;
+    const DOOR: usize = 2;
+    const CRATE: usize = 3;
+    const BOX: usize = 4;
+
+    /// a long floor with a door near the start, a crate that breaks and a box far off down it,
+    /// split into chunks when `chunked` so the far end starts out of the world
+    fn level(chunked: bool) -> Level {
+        let mut level = Level::parse("(version: 4, player: 0, objects: [])").unwrap();
+        level.objects = vec![
+            Object::movable().at(0.0, 1.0).build(),
+            Object::fixed().at(-10.0, 0.0).size(1000.0, 1.0).build(),
+            Object::fixed().at(3.0, 1.0).size(1.0, 3.0).build(),
+            Object::movable().at(300.0, 1.0).health(1.0).build(),
+            Object::fixed().at(305.0, 1.0).build(),
+        ];
+        level.chunking = chunked.then_some(Chunking {
+            size: 8.0,
+            radius: 16.0,
+        });
+        level
+    }
+
+    fn run(state: &mut GameState, memory: &mut LevelMemory, level_objects: usize, ticks: usize) {
+        for _ in 0..ticks {
+            state.update(TICK);
+            for event in state.drain_events().collect::<Vec<_>>() {
+                memory.record(&event, state, level_objects, 0);
+            }
+        }
+    }
+
+    /// walks the player over to `x`, giving the chunks there time to come back
+    fn go_to(state: &mut GameState, x: f64) {
+        state.teleport_object(0, cgmath::point2(x, 1.0));
+        state.update(TICK);
+    }
+
+    #[test]
+    fn broken_and_moved_things_stay_that_way() {
+        let level = level(false);
+        let mut state = GameState::from_level(&level);
+        let mut memory = LevelMemory::default();
+        state.move_object(DOOR, cgmath::point2(3.0, 4.0), 0.5, Easing::Linear);
+        // the player and things that weren't in the level to begin with are left out
+        state.move_object(0, cgmath::point2(1.0, 1.0), 0.1, Easing::Linear);
+        let spawned = state.spawn_object(Object::fixed().at(10.0, 5.0).build());
+        state.move_object(spawned, cgmath::point2(10.0, 6.0), 0.1, Easing::Linear);
+        state.damage_object(CRATE, 2.0);
+        run(&mut state, &mut memory, level.objects.len(), 60);
+        assert_eq!(memory.destroyed, BTreeSet::from([CRATE]));
+        assert_eq!(
+            memory.moved,
+            BTreeMap::from([(DOOR, cgmath::point2(3.0, 4.0))])
+        );
+
+        let mut state = GameState::from_level(&level);
+        memory.apply(&mut state);
+        assert!(state.object(CRATE).is_none());
+        assert_eq!(
+            *state.object(DOOR).unwrap().get_pos(),
+            cgmath::point2(3.0, 4.0)
+        );
+        assert_eq!(
+            *state.object(0).unwrap().get_pos(),
+            level.objects[0].get_pos().to_owned()
+        );
+    }
+
+    #[test]
+    fn far_chunks_come_back_the_way_they_were_left() {
+        let level = level(true);
+        let memory = LevelMemory {
+            destroyed: BTreeSet::from([CRATE]),
+            moved: BTreeMap::from([(BOX, cgmath::point2(200.0, 1.0))]),
+        };
+        let mut state = GameState::from_level(&level);
+        assert!(state.object(CRATE).is_none() && state.object(BOX).is_none());
+        memory.apply(&mut state);
+        go_to(&mut state, 302.0);
+        assert!(state.object(CRATE).is_none());
+        assert!(state.object(BOX).is_none());
+        go_to(&mut state, 202.0);
+        assert_eq!(
+            *state.object(BOX).unwrap().get_pos(),
+            cgmath::point2(200.0, 1.0)
+        );
+        assert_eq!(
+            state.object_count() + state.stored_object_count(),
+            level.objects.len() - 1
+        );
+    }
+}